@@ -0,0 +1,129 @@
+use crate::{cli::AggArgs, render::column_value};
+use rlog::LogRecord;
+use std::{collections::HashMap, fmt, io::{self, Write}, str::FromStr};
+
+/// A statistic computed over the numbers in a group.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    /// The value below which this percentage of the numbers lie (nearest rank).
+    Percentile(f64),
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    /// Parses `count`, `sum`, `avg` (or `mean`), `min`, `max`, or a percentile such as `p95`
+    /// or `p99.9`.
+    fn from_str(text: &str) -> Result<Op, String> {
+        match text.trim().to_lowercase().as_str() {
+            "count" => Ok(Op::Count),
+            "sum" => Ok(Op::Sum),
+            "avg" | "mean" => Ok(Op::Avg),
+            "min" => Ok(Op::Min),
+            "max" => Ok(Op::Max),
+            op => match op.strip_prefix('p').and_then(|p| p.parse::<f64>().ok()) {
+                Some(percent) if (0.0..=100.0).contains(&percent) => Ok(Op::Percentile(percent)),
+                _ => Err(format!("unknown operation `{}` (expected count, sum, avg, min, max, or a percentile like p95)", text)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Count => write!(f, "count"),
+            Op::Sum => write!(f, "sum"),
+            Op::Avg => write!(f, "avg"),
+            Op::Min => write!(f, "min"),
+            Op::Max => write!(f, "max"),
+            Op::Percentile(percent) => write!(f, "p{}", percent),
+        }
+    }
+}
+
+impl Op {
+    /// The statistic over `values`, which are sorted and not empty.
+    fn apply(self, values: &[f64]) -> f64 {
+        match self {
+            Op::Count => values.len() as f64,
+            Op::Sum => values.iter().sum(),
+            Op::Avg => values.iter().sum::<f64>() / values.len() as f64,
+            Op::Min => values[0],
+            Op::Max => values[values.len() - 1],
+            Op::Percentile(percent) => {
+                let rank = (percent / 100.0 * values.len() as f64).ceil() as usize;
+                values[rank.clamp(1, values.len()) - 1]
+            }
+        }
+    }
+}
+
+/// The numbers of the `--metric` column among the matching records, per `--group-by` value.
+#[derive(Debug, Default)]
+pub struct Aggregates {
+    groups: HashMap<String, Vec<f64>>,
+    /// Matching records counted.
+    total: usize,
+    /// Matching records whose field is missing or not a number.
+    skipped: usize,
+}
+
+impl Aggregates {
+    pub fn add(&mut self, record: &LogRecord, args: &AggArgs) {
+        self.total += 1;
+        let Some(number) = column_value(record, &args.metric).and_then(|value| value.trim().parse::<f64>().ok()).filter(|n| n.is_finite()) else {
+            self.skipped += 1;
+            return;
+        };
+        let group = match &args.group_by {
+            Some(column) => column_value(record, column).unwrap_or_else(|| "(missing)".to_string()),
+            None => "(all)".to_string(),
+        };
+        self.groups.entry(group).or_default().push(number);
+    }
+
+    /// Number of records counted.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Writes a row of statistics per group, largest group first.
+    pub fn print(&mut self, args: &AggArgs) -> io::Result<()> {
+        let mut groups: Vec<(&String, &mut Vec<f64>)> = self.groups.iter_mut().collect();
+        groups.sort_by(|(a, m), (b, n)| n.len().cmp(&m.len()).then_with(|| a.cmp(b)));
+        let header = args.group_by.as_ref().map_or("GROUP", |(name, _)| name.as_str());
+        let width = groups.iter().map(|(group, _)| group.chars().count()).chain([header.len()]).max().unwrap_or(0);
+
+        let mut out = io::stdout().lock();
+        write!(out, "{:width$}", header)?;
+        for op in &args.ops {
+            write!(out, "  {:>12}", op.to_string())?;
+        }
+        writeln!(out)?;
+        for (group, values) in groups {
+            values.sort_by(f64::total_cmp);
+            write!(out, "{:width$}", group)?;
+            for op in &args.ops {
+                write!(out, "  {:>12}", number(op.apply(values)))?;
+            }
+            writeln!(out)?;
+        }
+        if self.skipped > 0 {
+            let noun = if self.skipped == 1 { "entry" } else { "entries" };
+            eprintln!("rlog: {} {} without a number in {} skipped", self.skipped, noun, args.metric.0);
+        }
+        Ok(())
+    }
+}
+
+/// A statistic with at most three decimals and without trailing zeros.
+fn number(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
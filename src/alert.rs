@@ -0,0 +1,422 @@
+use crate::cli::AlertArgs;
+use clap::ValueEnum;
+use notify_rust::Notification;
+use regex::Regex;
+use rlog::{parse_duration, Expr, LogRecord};
+use serde_json::{json, Map, Value};
+use std::{collections::VecDeque, fmt, io::{self, IsTerminal, Write}, mem, process::{Command, Stdio}, str::FromStr, sync::LazyLock, thread, time::{Duration, Instant}};
+
+/// Longest message shown in a desktop notification, in characters.
+const BODY_LENGTH: usize = 200;
+
+/// How long a webhook may take before it is given up.
+const WEBHOOK_TIMEOUT: &str = "10";
+
+/// How `--alert-on` matches are signaled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum AlertWith {
+    /// Ring the terminal bell.
+    Bell,
+    /// Show a desktop notification.
+    Desktop,
+    /// Both.
+    #[default]
+    Both,
+    /// Neither, for alerts that only run --exec or --webhook.
+    None,
+}
+
+/// The body POSTed by `--webhook`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Payload {
+    /// The entry's columns as a JSON object.
+    #[default]
+    Json,
+    /// A Slack incoming webhook message.
+    Slack,
+    /// A Discord webhook message.
+    Discord,
+    /// A PagerDuty Events API v2 trigger; needs --routing-key.
+    Pagerduty,
+}
+
+/// A rule raising an alert when more than a number of entries match an expression within a
+/// sliding window, written `count(EXPR) > N in DURATION`.
+#[derive(Debug, Clone)]
+pub struct Threshold {
+    source: String,
+    expr: Expr,
+    /// Fewest matches within the window that raise the alert.
+    least: usize,
+    window: Duration,
+}
+
+impl FromStr for Threshold {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Threshold, String> {
+        static SYNTAX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*count\s*\((.*)\)\s*(>=|>)\s*(\d+)\s+in\s+(\S+)\s*$").unwrap());
+        let captures = SYNTAX.captures(text).ok_or_else(|| format!("expected a rule such as `count(Level == ERROR) > 50 in 60s`, got `{}`", text))?;
+        let expr = Expr::parse(&captures[1]).map_err(|e| e.to_string())?;
+        let limit: usize = captures[3].parse().map_err(|_| format!("count out of range: {}", &captures[3]))?;
+        let window = parse_duration(&captures[4]).and_then(|window| window.to_std().ok()).filter(|window| !window.is_zero())
+            .ok_or_else(|| format!("expected a duration such as `30s`, `5m`, or `1h`, got `{}`", &captures[4]))?;
+        let least = if &captures[2] == ">" { limit + 1 } else { limit.max(1) };
+        Ok(Threshold { source: text.trim().to_string(), expr, least, window })
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// A `--alert` rule with the times of its recent matches.
+struct Window {
+    threshold: Threshold,
+    times: VecDeque<Instant>,
+    /// When the rule last raised an alert; it stays quiet for a window afterwards.
+    fired: Option<Instant>,
+}
+
+impl Window {
+    /// Counts the record if it matches, and returns whether the rule raises an alert now.
+    fn push(&mut self, record: &LogRecord, now: Instant) -> bool {
+        if !self.threshold.expr.matches(record) {
+            return false;
+        }
+        self.times.push_back(now);
+        while self.times.front().is_some_and(|at| now.duration_since(*at) > self.threshold.window) {
+            self.times.pop_front();
+        }
+        let cooling = self.fired.is_some_and(|fired| now.duration_since(fired) < self.threshold.window);
+        if self.times.len() < self.threshold.least || cooling {
+            return false;
+        }
+        self.fired = Some(now);
+        true
+    }
+}
+
+/// Notices when no matching entry has arrived for `--expect-heartbeat`.
+pub struct Heartbeat {
+    every: Duration,
+    last: Instant,
+    /// Whether the current silence was reported.
+    missed: bool,
+}
+
+impl Heartbeat {
+    pub fn new(every: Duration) -> Self {
+        Heartbeat { every, last: Instant::now(), missed: false }
+    }
+
+    /// Notes that a matching entry arrived. Returns how long the silence before it lasted if
+    /// it was reported.
+    pub fn beat(&mut self) -> Option<Duration> {
+        let silence = self.last.elapsed();
+        self.last = Instant::now();
+        mem::take(&mut self.missed).then_some(silence)
+    }
+
+    /// Time left until the window runs out, or `Duration::MAX` once the silence was reported.
+    pub fn remaining(&self) -> Duration {
+        if self.missed { Duration::MAX } else { self.every.saturating_sub(self.last.elapsed()) }
+    }
+
+    /// Returns how long no matching entry has arrived, once that exceeds the window.
+    pub fn check(&mut self) -> Option<Duration> {
+        let silence = self.last.elapsed();
+        (silence >= self.every && !mem::replace(&mut self.missed, true)).then_some(silence)
+    }
+}
+
+/// Raises alerts while following, so that rlog can be left in a background window: for each
+/// record matching `--alert-on`, whenever a `--alert` threshold is crossed, and for the
+/// [`notice`](Self::notice)s of `--expect-heartbeat` and `--detect-spikes`. After an
+/// `--alert-on` alert, further matches within `--alert-every` are only counted, and that
+/// count is reported with the next alert.
+pub struct Alerts {
+    rule: Option<Expr>,
+    thresholds: Vec<Window>,
+    with: AlertWith,
+    exec: Option<String>,
+    webhook: Option<String>,
+    payload: Payload,
+    routing_key: Option<String>,
+    every: Duration,
+    /// When the last alert was raised.
+    last: Option<Instant>,
+    /// Matches since the last alert that raised none.
+    held: usize,
+    /// Whether a desktop notification could not be shown, so the warning is given once.
+    failed: bool,
+    /// Alerts raised so far.
+    raised: u64,
+}
+
+impl Alerts {
+    pub fn new(args: &AlertArgs) -> Option<Self> {
+        let thresholds: Vec<Window> = args.alert.iter().map(|threshold| Window { threshold: threshold.clone(), times: VecDeque::new(), fired: None }).collect();
+        args.has_rules().then(|| Alerts {
+            rule: args.alert_on.clone(),
+            thresholds,
+            with: args.alert_with,
+            exec: args.exec.clone(),
+            webhook: args.webhook.clone(),
+            payload: args.payload,
+            routing_key: args.routing_key.clone(),
+            every: args.alert_every.to_std().unwrap_or_default(),
+            last: None,
+            held: 0,
+            failed: false,
+            raised: 0,
+        })
+    }
+
+    /// Raises an alert if the record matches `--alert-on` and no alert was raised too
+    /// recently, or if it makes a `--alert` threshold crossed. Returns what the alerts raised
+    /// said.
+    pub fn check(&mut self, record: &LogRecord) -> Vec<String> {
+        if record.malformed {
+            return Vec::new();
+        }
+        let now = Instant::now();
+        let mut raised: Vec<String> = self.thresholds.iter_mut()
+            .filter_map(|window| window.push(record, now).then(|| format!("{} matches of `{}`", window.times.len(), window.threshold)))
+            .collect();
+        if self.rule.as_ref().is_some_and(|rule| rule.matches(record)) {
+            if self.last.is_some_and(|last| now.duration_since(last) < self.every) {
+                self.held += 1;
+            } else {
+                self.last = Some(now);
+                let held = mem::take(&mut self.held);
+                let message = record.get("Message").unwrap_or(&record.raw);
+                let level = record.level().to_uppercase();
+                let mut summary = if level.is_empty() { message.to_string() } else { format!("[{}] {}", level, message) };
+                if held > 0 {
+                    summary.push_str(&format!(" (and {} more since the last alert)", held));
+                }
+                raised.push(summary);
+            }
+        }
+        for summary in &raised {
+            self.raise(record, summary);
+        }
+        raised
+    }
+
+    /// Raises an alert about the matching entries as a whole, such as a missed
+    /// `--expect-heartbeat` or a `--detect-spikes` spike, as if raised by a WARNING entry
+    /// saying `text`.
+    pub fn notice(&mut self, text: &str) {
+        let mut record = LogRecord::new(text);
+        record.push("Level", "WARNING");
+        record.push("Message", text);
+        self.raise(&record, &format!("[WARNING] {}", text));
+    }
+
+    /// Number of alerts raised so far.
+    pub fn raised(&self) -> u64 {
+        self.raised
+    }
+
+    /// Signals an alert described by `summary`, raised by `record`.
+    fn raise(&mut self, record: &LogRecord, summary: &str) {
+        self.raised += 1;
+        let level = record.level().to_uppercase();
+        if matches!(self.with, AlertWith::Bell | AlertWith::Both) {
+            bell();
+        }
+        if matches!(self.with, AlertWith::Desktop | AlertWith::Both) && !self.failed {
+            let title = if level.is_empty() { "rlog".to_string() } else { format!("rlog: {}", level) };
+            let body: String = summary.chars().take(BODY_LENGTH).collect();
+            if let Err(e) = notify(&title, &body) {
+                eprintln!("rlog: cannot show a desktop notification: {}", e);
+                self.failed = true;
+            }
+        }
+        if let Some(exec) = &self.exec {
+            let command = placeholders(exec, record);
+            let mut shell = Command::new(if cfg!(windows) { "cmd" } else { "sh" });
+            shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command);
+            run("--exec", shell, None);
+        }
+        if let Some(url) = &self.webhook {
+            let body = self.payload(record, summary).to_string();
+            let mut curl = Command::new("curl");
+            curl.args(["--silent", "--show-error", "--fail", "--max-time", WEBHOOK_TIMEOUT])
+                .args(["--header", "Content-Type: application/json", "--data-binary", "@-", url]);
+            run("--webhook", curl, Some(body));
+        }
+    }
+
+    /// The `--webhook` body for a record.
+    fn payload(&self, record: &LogRecord, summary: &str) -> Value {
+        match self.payload {
+            Payload::Json => Value::Object(columns(record)),
+            Payload::Slack => json!({ "text": summary }),
+            Payload::Discord => json!({ "content": summary.chars().take(2000).collect::<String>() }),
+            Payload::Pagerduty => json!({
+                "routing_key": self.routing_key.as_deref().unwrap_or_default(),
+                "event_action": "trigger",
+                "payload": {
+                    "summary": summary.chars().take(1024).collect::<String>(),
+                    "source": record.source.as_deref().unwrap_or("rlog"),
+                    "severity": severity(record.level()),
+                    "custom_details": columns(record),
+                },
+            }),
+        }
+    }
+}
+
+/// The record's columns as JSON strings.
+fn columns(record: &LogRecord) -> Map<String, Value> {
+    record.fields().map(|(header, value)| (header.to_string(), Value::String(value.to_string()))).collect()
+}
+
+/// The PagerDuty severity closest to a level.
+fn severity(level: &str) -> &'static str {
+    match rlog::normalize_level(level).as_str() {
+        "CRITICAL" | "ALERT" | "EMERGENCY" => "critical",
+        "ERROR" => "error",
+        "WARNING" => "warning",
+        _ => "info",
+    }
+}
+
+/// Fills in `{json}` with the record as a JSON object and `{Column}` with a column's value,
+/// each quoted for the shell.
+fn placeholders(template: &str, record: &LogRecord) -> String {
+    let mut command = template.replace("{json}", &shell_quote(&Value::Object(columns(record)).to_string()));
+    for (header, value) in record.fields() {
+        command = command.replace(&format!("{{{}}}", header), &shell_quote(value));
+    }
+    command
+}
+
+fn shell_quote(text: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
+    }
+}
+
+/// Starts a command without waiting for it, feeding it `input`, and warns if it fails.
+fn run(option: &'static str, mut command: Command, input: Option<String>) {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let child = command.stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() }).stdout(Stdio::null()).spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return eprintln!("rlog: {}: cannot run {}: {}", option, program, e),
+    };
+    thread::spawn(move || {
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        match child.wait() {
+            Ok(status) if !status.success() => eprintln!("rlog: {}: {} failed ({})", option, program, status),
+            Err(e) => eprintln!("rlog: {}: {}: {}", option, program, e),
+            Ok(_) => {}
+        }
+    });
+}
+
+/// Rings the terminal bell on stderr, which stays on the terminal when the entries are
+/// redirected.
+fn bell() {
+    let mut stderr = io::stderr();
+    if stderr.is_terminal() {
+        let _ = stderr.write_all(b"\x07").and_then(|()| stderr.flush());
+    }
+}
+
+/// Shows a desktop notification: through the notification service over D-Bus on Linux and
+/// the BSDs, Notification Center on macOS, and toast notifications on Windows.
+fn notify(title: &str, body: &str) -> io::Result<()> {
+    Notification::new().appname("rlog").summary(title).body(body).show().map(drop).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: &str, message: &str) -> LogRecord {
+        let mut record = LogRecord::new(format!("{} {}", level, message));
+        record.push("Level", level);
+        record.push("Message", message);
+        record
+    }
+
+    #[test]
+    fn parses_threshold_rules() {
+        let threshold: Threshold = "count(Level == ERROR) > 2 in 10s".parse().unwrap();
+        assert_eq!((threshold.least, threshold.window), (3, Duration::from_secs(10)));
+        assert_eq!(threshold.to_string(), "count(Level == ERROR) > 2 in 10s");
+        let threshold: Threshold = r#" count( Message =~ "timeout" ) >= 0 in 5m "#.parse().unwrap();
+        assert_eq!((threshold.least, threshold.window), (1, Duration::from_secs(300)));
+        assert!("Level == ERROR > 2".parse::<Threshold>().unwrap_err().starts_with("expected a rule such as"));
+        assert_eq!("count(Level == ERROR) > 2 in 0s".parse::<Threshold>().unwrap_err(), "expected a duration such as `30s`, `5m`, or `1h`, got `0s`");
+        assert!("count(Level ==) > 2 in 1m".parse::<Threshold>().is_err());
+    }
+
+    #[test]
+    fn fires_when_the_window_fills_then_cools_down() {
+        let mut window = Window { threshold: "count(Level == ERROR) > 2 in 10s".parse().unwrap(), times: VecDeque::new(), fired: None };
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let (error, info) = (entry("ERROR", "failed"), entry("INFO", "ok"));
+        assert!(!window.push(&error, at(0)));
+        assert!(!window.push(&info, at(1)));
+        assert!(!window.push(&error, at(2)));
+        assert!(window.push(&error, at(3)));
+        // Still over the threshold, but within a window of the alert.
+        assert!(!window.push(&error, at(4)));
+        // Within a window of the alert, though more matched.
+        assert!(!window.push(&error, at(12)));
+        // The matches at 0 to 3 have left the window by now.
+        assert!(window.push(&error, at(14)));
+    }
+
+    #[test]
+    fn reports_a_missed_heartbeat_once() {
+        let mut heartbeat = Heartbeat::new(Duration::ZERO);
+        assert_eq!(heartbeat.beat(), None);
+        assert!(heartbeat.check().is_some());
+        assert_eq!(heartbeat.check(), None);
+        assert_eq!(heartbeat.remaining(), Duration::MAX);
+        assert!(heartbeat.beat().is_some());
+        let mut heartbeat = Heartbeat::new(Duration::from_secs(3600));
+        assert_eq!(heartbeat.check(), None);
+        assert!(heartbeat.remaining() > Duration::from_secs(3500));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn quotes_placeholders_for_the_shell() {
+        let record = entry("ERROR", "it's down");
+        assert_eq!(placeholders("notify {Level} {Message} {Thread}", &record), r#"notify 'ERROR' 'it'\''s down' {Thread}"#);
+        assert_eq!(placeholders("echo {json}", &record), r#"echo '{"Level":"ERROR","Message":"it'\''s down"}'"#);
+    }
+
+    #[test]
+    fn builds_webhook_payloads() {
+        let alerts = |payload| Alerts {
+            rule: None, thresholds: Vec::new(), with: AlertWith::None, exec: None, webhook: None, payload, routing_key: Some("key".into()),
+            every: Duration::ZERO, last: None, held: 0, failed: false, raised: 0,
+        };
+        let mut record = entry("CRITICAL", "disk full");
+        record.source = Some("db.log".into());
+        assert_eq!(alerts(Payload::Json).payload(&record, "[CRITICAL] disk full"), json!({ "Level": "CRITICAL", "Message": "disk full" }));
+        assert_eq!(alerts(Payload::Slack).payload(&record, "[CRITICAL] disk full"), json!({ "text": "[CRITICAL] disk full" }));
+        assert_eq!(alerts(Payload::Discord).payload(&record, &"x".repeat(3000))["content"].as_str().map(str::len), Some(2000));
+        let event = alerts(Payload::Pagerduty).payload(&record, "[CRITICAL] disk full");
+        assert_eq!((&event["routing_key"], &event["event_action"]), (&json!("key"), &json!("trigger")));
+        assert_eq!((&event["payload"]["source"], &event["payload"]["severity"]), (&json!("db.log"), &json!("critical")));
+        assert_eq!(severity("warn"), "warning");
+        assert_eq!(severity("debug"), "info");
+    }
+}
@@ -0,0 +1,28 @@
+use std::{io::{self, Read}, sync::mpsc::{self, Receiver, Sender}};
+
+/// A byte stream made of the lines sent to it from other threads, for inputs that are not
+/// files: it ends once every sender is dropped.
+pub struct Lines {
+    rx: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+    read: usize,
+}
+
+/// Creates a stream and the sender of its lines, each of which ends with `\n`.
+pub fn lines() -> (Sender<Vec<u8>>, Lines) {
+    let (tx, rx) = mpsc::channel();
+    (tx, Lines { rx, buffer: Vec::new(), read: 0 })
+}
+
+impl Read for Lines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.read == self.buffer.len() {
+            let Ok(line) = self.rx.recv() else { return Ok(0) };
+            (self.buffer, self.read) = (line, 0);
+        }
+        let n = buf.len().min(self.buffer.len() - self.read);
+        buf[..n].copy_from_slice(&self.buffer[self.read..self.read + n]);
+        self.read += n;
+        Ok(n)
+    }
+}
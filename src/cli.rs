@@ -0,0 +1,1636 @@
+use clap::{error::ErrorKind, ArgAction, ArgGroup, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use chrono::{format::{Item, StrftimeItems}, DateTime, FixedOffset, TimeDelta};
+use rlog::{build_parser, Bounds, Dedupe, detect_format, load_formats, normalize_level, parse_duration, parser_for, AutoParser, ContextWindow, CustomFormat, DataQuery, Expr, FieldMatch, LogParser, ParserOptions, PatternParser, Quoting, Sample, Template, TextEncoding, TimeBound, Zone};
+use crate::{agg::Op, alert::{AlertWith, Payload, Threshold}, cloudwatch::LogGroup, config::{parse_color, Config}, docker::Container, evtx::EventLog, forward::Target, kubernetes::Pods, listen::{endpoint_address, Endpoint}, output::OutputFormat, remote::Remote, search::Search, spawned::{curl_headers, Spawned}, render::{Fit, RowStyle, TimeDisplay}};
+use crossterm::style::Color;
+use regex::Regex;
+use std::{cell::{Cell, RefCell}, collections::HashMap, env, ffi::OsString, io::{self, IsTerminal}, mem, net::SocketAddr, path::PathBuf, sync::{Arc, LazyLock}, time::Duration};
+
+/// A small log file viewer.
+#[derive(Debug, Parser)]
+#[command(name = "rlog", version, about, after_help = EXIT_STATUS, args_conflicts_with_subcommands = true, args_override_self = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Read settings from this file instead of ~/.config/rlog/config.toml.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Apply the named profile from the config file.
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// When to color the output; `auto` colors only a terminal and respects NO_COLOR.
+    #[arg(long, global = true, value_name = "WHEN", value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+    /// Level colors: dark, light, colorblind, or a theme from the config file.
+    #[arg(long, global = true, value_name = "NAME", default_value = "dark")]
+    pub theme: String,
+    /// Show DateTime values in this time zone (`local`, `UTC`, `Europe/Berlin`, ...), and read
+    /// --since/--until times without an offset in it.
+    #[arg(long, global = true, value_name = "ZONE")]
+    pub tz: Option<Zone>,
+    /// Read DateTime values without an offset as times in this zone rather than local time,
+    /// such as `UTC` for servers that log UTC without saying so; also --since/--until times,
+    /// unless --tz is given.
+    #[arg(long, global = true, value_name = "ZONE")]
+    pub log_tz: Option<Zone>,
+
+    /// Arguments for the default `tail` command.
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether stdout should get color codes.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+        }
+    }
+}
+
+/// The lowest level of unified log entries `rlog oslog` reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OslogLevel {
+    Default,
+    Info,
+    Debug,
+}
+
+/// What makes matches repeats of one another for `--dedupe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DedupeMode {
+    /// The same message.
+    Exact,
+    /// The same message once numbers, ids, and addresses are masked.
+    Template,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Follow a log file, printing matching entries as they are appended (default).
+    Tail(Box<TailArgs>),
+    /// Print matching entries once and exit.
+    Query(QueryArgs),
+    /// Search many files at once, one per core, printing matching entries file by file
+    /// with the file they come from.
+    Grep(GrepArgs),
+    /// Print the number of matching entries per level, or with --bucket a histogram of them
+    /// over time.
+    Stats(StatsArgs),
+    /// Draw a sparkline of how many entries match over time, or a heatmap of hours by day.
+    Timeline(TimelineArgs),
+    /// Group matching messages by template, masking their variable parts, and print how
+    /// often each template occurs.
+    Patterns(PatternsArgs),
+    /// Print the most frequent values of a column among matching entries.
+    Top(TopArgs),
+    /// Compute sums, averages, or percentiles of a numeric column among matching entries,
+    /// optionally per value of another column.
+    Agg(AggArgs),
+    /// Save matching entries to a standalone HTML page with colors and search.
+    Export(ExportArgs),
+    /// Follow a log file on another host through ssh, as `tail` does with local ones.
+    Ssh(Box<SshArgs>),
+    /// Follow the output of Docker containers, as `tail` does with files.
+    Docker(Box<DockerArgs>),
+    /// Follow the logs of Kubernetes pods, as `tail` does with files.
+    K8s(Box<K8sArgs>),
+    /// Follow a stream of log events from an HTTP endpoint: server-sent events, chunked
+    /// NDJSON, or WebSocket messages.
+    Http(Box<HttpArgs>),
+    /// Follow a Kafka topic, as `tail` does with files.
+    Kafka(Box<KafkaArgs>),
+    /// Follow the results of a Grafana Loki query, as `tail` does with files.
+    Loki(Box<LokiArgs>),
+    /// Follow the hits of an Elasticsearch or OpenSearch query, as `tail` does with files.
+    Es(Box<EsArgs>),
+    /// Follow AWS CloudWatch Logs groups, as `tail` does with files.
+    Cloudwatch(Box<CloudwatchArgs>),
+    /// Follow the systemd journal, as `tail` does with files.
+    Journal(Box<JournalArgs>),
+    /// Read Windows event log files (`.evtx`) given as LOG_FILE, as `tail` does with files.
+    Evtx(Box<EvtxArgs>),
+    /// Follow channels of the Windows event log, as `tail` does with files. Windows only.
+    Winlog(Box<WinlogArgs>),
+    /// Follow the macOS unified log through `log stream`, as `tail` does with files, first
+    /// reading the entries from --since with `log show`. macOS only.
+    Oslog(Box<OslogArgs>),
+    /// Accept syslog or GELF messages over the network or on Unix sockets and show them as
+    /// they arrive, as `tail` does with files.
+    Listen(Box<ListenArgs>),
+    /// Follow the inputs without printing them, raising alerts and logging to syslog, as a
+    /// service: systemd `Type=notify` units are told when it is ready.
+    Watch(WatchArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct TailArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    #[command(flatten)]
+    pub display: DisplayArgs,
+    /// Start at the end of the file, first showing only the last N matching entries.
+    #[arg(long, value_name = "N", conflicts_with = "state_file")]
+    pub tail: Option<usize>,
+    /// Start at the end of the file without showing earlier entries (same as `--tail 0`).
+    #[arg(long, conflicts_with_all = ["tail", "state_file"])]
+    pub no_history: bool,
+    /// Print what is in the inputs now and exit instead of waiting for more.
+    #[arg(long, conflicts_with = "alerting")]
+    pub no_follow: bool,
+    /// Keep a status bar below the entries with lines per second, errors in the last minute,
+    /// and matches per level since the start. Only shown on a terminal.
+    #[arg(long, conflicts_with_all = ["no_follow", "out", "count", "quiet"])]
+    pub status: bool,
+    #[command(flatten)]
+    pub alerts: AlertArgs,
+    /// Serve counts of the lines read, parse errors, entries per level, matches, and alerts
+    /// raised at http://ADDR/metrics in the Prometheus text format, e.g. `0.0.0.0:9200`.
+    #[arg(long, value_name = "ADDR", conflicts_with = "no_follow")]
+    pub metrics_listen: Option<SocketAddr>,
+    /// Open an interactive viewer with scrollback, pause, and search.
+    #[arg(long, conflicts_with_all = ["output", "summary", "status", "count", "quiet", "state_file", "alerting", "forward", "metrics_listen"])]
+    pub tui: bool,
+    /// Number of entries the interactive viewer keeps in memory.
+    #[arg(long, value_name = "N", default_value_t = 10_000, requires = "tui")]
+    pub scrollback: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct SshArgs {
+    /// The remote log file. `ssh` must be able to log in to HOST, and `tail -F` must be
+    /// there; with --tail N the last N lines are read, whether they match or not.
+    #[arg(value_name = "[USER@]HOST:PATH")]
+    pub remote: Remote,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DockerArgs {
+    /// Containers to read, by name or id, separated by commas; their output is read
+    /// through the Docker Engine API, from the daemon `DOCKER_HOST` names or the local one.
+    /// Entries take the container's timestamps when they have none.
+    #[arg(value_name = "CONTAINER,...")]
+    pub containers: String,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("pods").required(true).multiple(true)))]
+pub struct K8sArgs {
+    /// What to read, as `kubectl logs` takes it: a pod name, or TYPE/NAME such as
+    /// `deploy/api` for the pods of a deployment, stateful set, daemon set, replica set, job,
+    /// or service. Logs are read through the Kubernetes API, with the cluster and credentials
+    /// of the kubeconfig, again from the last entry whenever the stream ends, e.g. because
+    /// the container restarted.
+    #[arg(value_name = "POD|TYPE/NAME", group = "pods")]
+    pub target: Option<String>,
+    /// Namespace of the pods.
+    #[arg(short = 'n', long, value_name = "NAMESPACE")]
+    pub namespace: Option<String>,
+    /// Read the pods matching a label selector, such as `app=api`; with a target, only
+    /// those of its pods.
+    #[arg(short = 'l', long, value_name = "SELECTOR", group = "pods")]
+    pub selector: Option<String>,
+    /// Read this container of each pod.
+    #[arg(long, value_name = "NAME", conflicts_with = "all_containers")]
+    pub container: Option<String>,
+    /// Read every container of each pod.
+    #[arg(long)]
+    pub all_containers: bool,
+    /// kubeconfig context to use.
+    #[arg(long, value_name = "NAME")]
+    pub kube_context: Option<String>,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct HttpArgs {
+    /// The stream to read, an http(s) URL read through `curl` or a ws(s) URL read through
+    /// `websocat`. Each line of the response, each `data:` line of an event stream, or each
+    /// WebSocket message is an entry. When the stream ends it is read again, with growing
+    /// pauses.
+    #[arg(value_name = "URL", value_parser = stream_url)]
+    pub url: String,
+    /// Send a header with the request, such as `Authorization: Bearer TOKEN`; repeatable.
+    /// Set it in a config profile to keep tokens off the command line.
+    #[arg(short = 'H', long, value_name = "NAME: VALUE", value_parser = header)]
+    pub header: Vec<String>,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct KafkaArgs {
+    /// Brokers to connect to, as HOST:PORT separated by commas. Messages are read through
+    /// `kcat` (formerly kafkacat), one entry each.
+    #[arg(short = 'b', long, value_name = "HOST:PORT,...")]
+    pub brokers: String,
+    /// Topic to read.
+    #[arg(short = 't', long, value_name = "TOPIC")]
+    pub topic: String,
+    /// Read as a member of this consumer group, carrying on from the offsets it committed,
+    /// and sharing the partitions with the other members.
+    #[arg(short = 'g', long, value_name = "GROUP")]
+    pub group: Option<String>,
+    /// Read the messages kept in the topic from the oldest. By default only new ones are
+    /// read, or those after the group's offsets; with --since, those from that time on.
+    #[arg(long, conflicts_with_all = ["start", "tail", "no_history"])]
+    pub from_beginning: bool,
+    /// Set a librdkafka property, such as `security.protocol=SASL_SSL` or
+    /// `sasl.password=...`; repeatable. They are passed on out of sight of other processes.
+    #[arg(short = 'X', long, value_name = "KEY=VALUE", value_parser = property)]
+    pub property: Vec<String>,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct LokiArgs {
+    /// Address of Loki, such as `http://loki:3100`. The query runs through `logcli`, which
+    /// takes credentials from LOKI_USERNAME and LOKI_PASSWORD, or LOKI_BEARER_TOKEN.
+    #[arg(long, value_name = "URL")]
+    pub url: String,
+    /// LogQL query selecting the entries, such as `{app="api"} |= "timeout"`.
+    #[arg(long, value_name = "LOGQL")]
+    pub query: String,
+    /// Tenant to query, for multi-tenant Loki.
+    #[arg(long, value_name = "ID")]
+    pub org_id: Option<String>,
+    /// Wait for new entries once the earlier ones are read, which is the default.
+    #[arg(short = 'f', long, conflicts_with = "no_follow")]
+    pub follow: bool,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct EsArgs {
+    /// Address of the cluster. Requests are sent through `curl`.
+    #[arg(long, value_name = "URL", default_value = "http://localhost:9200")]
+    pub url: String,
+    /// Indices to search, such as `logs-*`.
+    #[arg(long, value_name = "INDEX")]
+    pub index: String,
+    /// Only read the documents matching a query in Lucene syntax, such as
+    /// `level:ERROR AND service:payments`.
+    #[arg(long, value_name = "QUERY")]
+    pub query: Option<String>,
+    /// Field holding the time of each document, which hits are read in the order of and
+    /// --since and --until apply to.
+    #[arg(long, value_name = "FIELD", default_value = "@timestamp")]
+    pub time_field: String,
+    /// Send a header with each request, such as `Authorization: ApiKey KEY`; repeatable.
+    #[arg(short = 'H', long, value_name = "NAME: VALUE", value_parser = header)]
+    pub header: Vec<String>,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct CloudwatchArgs {
+    /// Log group to read, such as `/ecs/api`; repeatable. Events are read through the
+    /// CloudWatch Logs API, with credentials found as the AWS CLI finds them; without
+    /// --since, it starts 10 minutes back. The log stream of each event is the Stream column.
+    #[arg(short = 'g', long, value_name = "GROUP", required = true)]
+    pub group: Vec<String>,
+    /// Only read the log streams whose names start with PREFIX.
+    #[arg(long, value_name = "PREFIX")]
+    pub stream_prefix: Option<String>,
+    /// Only read events matching a CloudWatch filter pattern, such as `ERROR` or
+    /// `{ $.status >= 500 }`, which is applied by AWS before events are sent.
+    #[arg(long, value_name = "PATTERN")]
+    pub filter_pattern: Option<String>,
+    /// AWS profile to use.
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// AWS region to use.
+    #[arg(long, value_name = "REGION")]
+    pub region: Option<String>,
+    /// Wait for new events once the earlier ones are read, which is the default.
+    #[arg(short = 'f', long, conflicts_with = "no_follow")]
+    pub follow: bool,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct JournalArgs {
+    /// Read the entries of this systemd unit, such as `nginx`; repeatable. By default the
+    /// whole journal is read, through `journalctl`.
+    #[arg(short = 'u', long, value_name = "UNIT")]
+    pub unit: Vec<String>,
+    /// Read the journal of the user's own services instead of the system one.
+    #[arg(long)]
+    pub user: bool,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct EvtxArgs {
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct WinlogArgs {
+    /// Read this channel, such as `Application`, `System`, `Security`, or
+    /// `Microsoft-Windows-PowerShell/Operational`; repeatable. Its records are read from the
+    /// file the event log service keeps them in, under `%SystemRoot%\System32\winevt\Logs`;
+    /// Security needs an administrator.
+    #[arg(long, value_name = "CHANNEL", required = true)]
+    pub channel: Vec<String>,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct OslogArgs {
+    /// Read only the entries matching this `log` predicate, e.g. `subsystem == "com.myapp"`
+    /// or `process == "Safari" AND messageType == error`; see `log help predicates`.
+    #[arg(long, value_name = "PREDICATE")]
+    pub predicate: Option<String>,
+    /// Have `log` pass on entries down to this level, as its `--level` option does. Info and
+    /// debug entries are only kept for a while, if at all, so mostly show up while following.
+    #[arg(long, value_name = "LEVEL", value_enum, default_value_t = OslogLevel::Default)]
+    pub log_level: OslogLevel,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("endpoints").required(true).multiple(true)))]
+pub struct ListenArgs {
+    /// Receive syslog datagrams on [ADDR:]PORT, usually 514; repeatable. Ports below 1024
+    /// need privileges.
+    #[arg(long, value_name = "[ADDR:]PORT", value_parser = endpoint_address, group = "endpoints")]
+    pub udp: Vec<SocketAddr>,
+    /// Accept syslog connections on [ADDR:]PORT, usually 601, with messages framed by
+    /// newlines or octet counts; repeatable.
+    #[arg(long, value_name = "[ADDR:]PORT", value_parser = endpoint_address, group = "endpoints")]
+    pub tcp: Vec<SocketAddr>,
+    /// Receive datagrams on a Unix socket created at PATH, as `/dev/log` does; repeatable.
+    /// A socket left behind by an earlier run is replaced, one in use is not.
+    #[arg(long, value_name = "PATH", group = "endpoints")]
+    pub unix: Vec<PathBuf>,
+    /// Accept connections on a Unix stream socket created at PATH; repeatable.
+    #[arg(long, value_name = "PATH", group = "endpoints")]
+    pub unix_stream: Vec<PathBuf>,
+    /// Receive Graylog GELF datagrams on [ADDR:]PORT, usually 12201, compressed with gzip
+    /// or zlib or not, and in chunks or not; repeatable.
+    #[arg(long, value_name = "[ADDR:]PORT", value_parser = endpoint_address, group = "endpoints")]
+    pub gelf_udp: Vec<SocketAddr>,
+    /// Accept GELF connections on [ADDR:]PORT, usually 12201, with messages ended by null
+    /// bytes or newlines; repeatable.
+    #[arg(long, value_name = "[ADDR:]PORT", value_parser = endpoint_address, group = "endpoints")]
+    pub gelf_tcp: Vec<SocketAddr>,
+    #[command(flatten)]
+    pub tail: TailArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    #[command(flatten)]
+    pub alerts: AlertArgs,
+    /// Serve counts of the lines read, parse errors, entries per level, matches, and alerts
+    /// raised at http://ADDR/metrics in the Prometheus text format, e.g. `0.0.0.0:9200`.
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_listen: Option<SocketAddr>,
+    /// Detach from the terminal and keep watching in the background.
+    #[arg(long)]
+    pub daemon: bool,
+}
+
+/// Alert rules and what to do when they fire, while following.
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("alerting").multiple(true)))]
+pub struct AlertArgs {
+    /// Raise an alert when an entry matching EXPR (e.g. `Level >= ERROR`) arrives while
+    /// following, whether or not it is shown: ring the terminal bell, show a desktop
+    /// notification, and run --exec or --webhook.
+    #[arg(long, value_name = "EXPR", value_parser = expr, group = "alerting")]
+    pub alert_on: Option<Expr>,
+    /// Raise an alert when more than N entries matching EXPR arrive within DURATION, e.g.
+    /// `count(Level == ERROR) > 50 in 60s`; repeatable. Each rule then stays quiet for
+    /// DURATION.
+    #[arg(long, value_name = "count(EXPR) > N in DURATION", group = "alerting")]
+    pub alert: Vec<Threshold>,
+    /// Warn, and raise an alert, when no matching entry arrives for DURATION while
+    /// following, e.g. to notice that a service died silently.
+    #[arg(long, value_name = "DURATION", value_parser = duration, group = "alerting")]
+    pub expect_heartbeat: Option<TimeDelta>,
+    /// Warn, and raise an alert, when the number of matching entries in a minute is
+    /// FACTOR times the average of the previous minutes (3 by default), or that many times
+    /// less.
+    #[arg(long, value_name = "FACTOR", value_parser = factor, num_args = 0..=1, require_equals = true, default_missing_value = "3", group = "alerting")]
+    pub detect_spikes: Option<f64>,
+    /// How to signal alerts.
+    #[arg(long, value_name = "HOW", value_enum, default_value_t = AlertWith::Both, requires = "alerting")]
+    pub alert_with: AlertWith,
+    /// Also run COMMAND with the shell on each alert; `{json}` is replaced by the entry
+    /// raising it as a JSON object and `{Column}` by a column's value, both quoted.
+    #[arg(long, value_name = "COMMAND", requires = "alerting")]
+    pub exec: Option<String>,
+    /// Also POST each alert to URL (with `curl`), in the --payload format.
+    #[arg(long, value_name = "URL", requires = "alerting")]
+    pub webhook: Option<String>,
+    /// Body of --webhook requests.
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = Payload::Json, requires = "webhook")]
+    pub payload: Payload,
+    /// PagerDuty integration key for `--payload pagerduty`.
+    #[arg(long, value_name = "KEY", requires = "webhook")]
+    pub routing_key: Option<String>,
+    /// Raise at most one --alert-on alert per DURATION; matches in between are counted and
+    /// reported with the next alert.
+    #[arg(long, value_name = "DURATION", value_parser = duration, default_value = "10s", requires = "alert_on")]
+    pub alert_every: TimeDelta,
+}
+
+#[derive(Debug, Args)]
+pub struct QueryArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    #[command(flatten)]
+    pub display: DisplayArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct GrepArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    #[command(flatten)]
+    pub display: DisplayArgs,
+    /// Search the files under directories, in subdirectories too, that look like logs: text
+    /// and compressed files that are not hidden.
+    #[arg(short = 'r', long)]
+    pub recursive: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    /// Count entries per level in time buckets of this length (`30s`, `5m`, `1h`, `1d`) and
+    /// draw a histogram, to see when a spike happened.
+    #[arg(long, value_name = "DURATION", value_parser = duration)]
+    pub bucket: Option<TimeDelta>,
+    /// Level colors of the active theme.
+    #[arg(skip)]
+    pub colors: HashMap<String, Color>,
+    /// Whether counts are colored, resolved from `--color`.
+    #[arg(skip)]
+    pub colored: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TimelineArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    /// Time each mark of the sparkline covers (`30s`, `5m`, `1h`); by default the whole
+    /// span fits the terminal.
+    #[arg(long, value_name = "DURATION", value_parser = duration, conflicts_with = "heatmap")]
+    pub bucket: Option<TimeDelta>,
+    /// Add a sparkline per level below the one for all entries.
+    #[arg(long, conflicts_with = "heatmap")]
+    pub by_level: bool,
+    /// Draw a grid of days by hour of day instead, to see daily patterns.
+    #[arg(long)]
+    pub heatmap: bool,
+    /// Level colors of the active theme.
+    #[arg(skip)]
+    pub colors: HashMap<String, Color>,
+    /// Whether marks are colored, resolved from `--color`.
+    #[arg(skip)]
+    pub colored: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PatternsArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    /// Number of templates to list; 0 lists them all.
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub limit: usize,
+    /// Share of tokens (0 to 1) a message must have in common with a template to join it;
+    /// lower values merge more messages.
+    #[arg(long, value_name = "RATIO", default_value_t = 0.5, value_parser = ratio)]
+    pub similarity: f64,
+}
+
+#[derive(Debug, Args)]
+pub struct TopArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    /// Column to count the values of, such as `Level` or `Data.endpoint`.
+    #[arg(long, value_name = "COLUMN", value_parser = column)]
+    pub by: (String, Option<DataQuery>),
+    /// Number of values to list; 0 lists them all.
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub limit: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct AggArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    /// Numeric column to aggregate, such as `Data.duration_ms`. (`--field` already filters
+    /// entries by column value.)
+    #[arg(long, value_name = "COLUMN", value_parser = column)]
+    pub metric: (String, Option<DataQuery>),
+    /// Statistics to compute: count, sum, avg, min, max, and percentiles such as p50 or p99.9.
+    #[arg(long = "op", value_name = "OP,...", value_delimiter = ',', action = ArgAction::Set, default_value = "count,avg,p50,p95,max")]
+    pub ops: Vec<Op>,
+    /// Compute the statistics separately for each value of this column, such as `Data.endpoint`.
+    #[arg(long, value_name = "COLUMN", value_parser = column)]
+    pub group_by: Option<(String, Option<DataQuery>)>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    #[command(flatten)]
+    pub display: DisplayArgs,
+    /// Write the page to PATH.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["count", "quiet"])]
+    pub html: PathBuf,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct InputArgs {
+    /// Log files to read; several files are followed at once. `-` reads stdin, which is
+    /// also the default when input is piped; named pipes are read like it. A directory or a quoted pattern such as
+    /// `'logs/*.log'` stands for the files in it.
+    #[arg(value_name = "LOG_FILE", value_parser = existing_file)]
+    pub files: Vec<PathBuf>,
+    /// Interleave entries from several files in DateTime order instead of file by file.
+    #[arg(long)]
+    pub merge: bool,
+    /// Input format; `auto` detects it from the first lines of each input. The container log
+    /// formats `docker` (json-file) and `cri` (kubelet) may name the format of the lines
+    /// they wrap, as in `cri:json`. NAME.toml files in ~/.config/rlog/formats define
+    /// formats of their own: a `regex` whose named groups are the columns, and optionally a
+    /// `time_format`, a `[levels]` table mapping values to levels, `multiline = true`, and
+    /// a `continuation` pattern of lines that continue an entry.
+    #[arg(long, value_name = "FORMAT", default_value = "auto", value_parser = format)]
+    pub format: String,
+    /// Parse lines with a regex whose named groups are the columns, instead of --format.
+    /// Grok-style `%{TIMESTAMP_ISO8601:DateTime}` references are expanded.
+    #[arg(long, value_name = "REGEX", value_parser = pattern)]
+    pub pattern: Option<String>,
+    /// How DateTime values are written, in strftime syntax (e.g. `%d/%b/%Y:%H:%M:%S %z`), for
+    /// --since/--until and --merge. By default ISO 8601, RFC 2822, syslog, and Unix epoch
+    /// times in seconds or milliseconds are recognized.
+    #[arg(long, value_name = "FORMAT", value_parser = time_format)]
+    pub time_format: Option<String>,
+    /// Read COLUMN (DateTime, Level, Message) from KEY in structured formats; repeatable.
+    #[arg(long = "key", value_name = "COLUMN=KEY", value_parser = column_key)]
+    pub keys: Vec<(String, String)>,
+    /// How pipe-delimited logs write a `|` inside a field: `backslash` (`a \| b`), `quotes`
+    /// (`"a | b"`), or `none`.
+    #[arg(long, value_name = "STYLE", default_value = "backslash")]
+    pub quoting: Quoting,
+    /// Treat lines that do not parse, such as stack trace frames, as continuing the entry before
+    /// them: they are added to its Message (or Data) column instead of being dropped.
+    #[arg(long)]
+    pub multiline: bool,
+    /// Add TraceId and SpanId columns with the trace context of each entry: a W3C
+    /// traceparent, or OpenTelemetry-style trace and span ID fields. Filter on them with
+    /// e.g. `--where 'TraceId == "4bf9..."'`.
+    #[arg(long)]
+    pub trace: bool,
+    /// Character encoding of the inputs: `utf8`, `latin1`, `utf16le`, another WHATWG label, or
+    /// `auto` to tell from a byte order mark or the first block of each file. Invalid bytes
+    /// are shown as `�`.
+    #[arg(long, value_name = "ENCODING", default_value = "utf8")]
+    pub encoding: TextEncoding,
+    /// Show lines that cannot be parsed, dimmed, instead of skipping them.
+    #[arg(long)]
+    pub show_raw: bool,
+    /// Stop with an error at the first line that cannot be parsed.
+    #[arg(long, conflicts_with_all = ["show_raw", "multiline"])]
+    pub strict: bool,
+    /// Check files for changes every 500ms instead of using filesystem notifications.
+    #[arg(long)]
+    pub poll: bool,
+    /// Read whole files through a memory map, and skip lines without the --filter word before
+    /// parsing them. Much faster on large files, but they must not be truncated meanwhile.
+    #[arg(long)]
+    pub fast: bool,
+    /// First read rotated copies of each file (app.log.1, app.log.2.gz, ...), oldest first.
+    #[arg(long)]
+    pub include_rotated: bool,
+    /// When a file is rotated, switch to the new file without reading the rest of the old one.
+    #[arg(long)]
+    pub rotate_immediately: bool,
+    /// Read only what was added to the files since the previous run with the same PATH, and
+    /// record in PATH where reading stopped. Rotated files are noticed by their inode.
+    #[arg(long, value_name = "PATH")]
+    pub state_file: Option<PathBuf>,
+    /// Keep looking for files in the directories and patterns given while following: new
+    /// ones are followed from their first line, and removed ones are dropped.
+    #[arg(long)]
+    pub watch_dir: bool,
+    /// The directories and patterns given, with `--watch-dir`.
+    #[arg(skip)]
+    pub patterns: Vec<PathBuf>,
+    /// Files on other hosts, from `rlog ssh`.
+    #[arg(skip)]
+    pub remotes: Vec<Remote>,
+    /// Sockets syslog messages are received on, from `rlog listen`.
+    #[arg(skip)]
+    pub endpoints: Vec<Endpoint>,
+    /// Searches whose hits are read, from `rlog es`.
+    #[arg(skip)]
+    pub searches: Vec<Search>,
+    /// Windows event logs whose records are read, from `rlog evtx` and `rlog winlog`.
+    #[arg(skip)]
+    pub event_logs: Vec<EventLog>,
+    /// Containers whose logs are read, from `rlog docker`.
+    #[arg(skip)]
+    pub containers: Vec<Container>,
+    /// CloudWatch Logs groups whose events are read, from `rlog cloudwatch`.
+    #[arg(skip)]
+    pub log_groups: Vec<LogGroup>,
+    /// Pods whose logs are read, from `rlog k8s`.
+    #[arg(skip)]
+    pub pods: Vec<Pods>,
+    /// Commands whose output is read, from `rlog http` and the like.
+    #[arg(skip)]
+    pub spawned: Vec<Spawned>,
+    /// Zone the DateTime column is converted to, from `--tz`.
+    #[arg(skip)]
+    pub tz: Option<Zone>,
+    /// Zone DateTime values without an offset are in, from `--log-tz`.
+    #[arg(skip)]
+    pub log_tz: Option<Zone>,
+}
+
+#[derive(Debug, Args)]
+pub struct FilterArgs {
+    /// Only show entries containing WORD.
+    #[arg(long, visible_alias = "f", value_name = "WORD")]
+    pub filter: Option<String>,
+    /// Match --filter WORD case-insensitively.
+    #[arg(short = 'i', long)]
+    pub ignore_case: bool,
+    /// Let --filter WORD match despite small typos.
+    #[arg(long, requires = "filter")]
+    pub fuzzy: bool,
+    /// Only show entries matching REGEX; repeatable, all must match.
+    #[arg(long = "match", value_name = "REGEX", value_parser = regex)]
+    pub patterns: Vec<Regex>,
+    /// Hide entries matching REGEX, like `grep -v`; repeatable.
+    #[arg(long = "exclude", value_name = "REGEX", value_parser = regex)]
+    pub excludes: Vec<Regex>,
+    /// Only show entries whose COLUMN equals VALUE (`Column=value`) or matches a regex
+    /// (`Column~regex`); repeatable, all must hold.
+    #[arg(long = "field", value_name = "COLUMN=VALUE|COLUMN~REGEX", value_parser = field)]
+    pub fields: Vec<(String, FieldMatch)>,
+    /// Only show entries with one of these levels.
+    #[arg(long, visible_alias = "l", value_name = "LEVEL,...", value_delimiter = ',', action = ArgAction::Set, value_parser = level)]
+    pub level: Vec<String>,
+    /// Only show entries at least as severe as LEVEL.
+    #[arg(long, value_name = "LEVEL", value_parser = level)]
+    pub min_level: Option<String>,
+    /// Severity ordering for --min-level, least severe first. Defaults to
+    /// DEBUG,INFO,NOTICE,WARNING,ERROR,CRITICAL,ALERT,EMERGENCY.
+    #[arg(long, value_name = "LEVEL,...", value_delimiter = ',', action = ArgAction::Set, value_parser = level)]
+    pub severities: Vec<String>,
+    /// Only show entries at or after TIME: a date such as `2025-03-01 10:00`, or a relative
+    /// time such as `15m`, `2 hours ago`, `today`, or `yesterday`.
+    #[arg(long, visible_aliases = ["s", "since"], value_name = "TIME", value_parser = time_bound)]
+    pub start: Option<String>,
+    /// Only show entries at or before TIME, given like --start (`now`, `1h`, ...).
+    #[arg(long, visible_aliases = ["t", "until"], value_name = "TIME", value_parser = time_bound)]
+    pub to: Option<String>,
+    /// Only show entries matching a boolean expression over columns, e.g.
+    /// `Level == "ERROR" && Message =~ "timeout" && Data.user_id != 42`.
+    #[arg(long = "where", value_name = "EXPR", value_parser = expr)]
+    pub where_: Option<Expr>,
+    /// Only show a share of the matching entries, given as `1/100`, `2%`, or `0.02`, to get a
+    /// feel for a very chatty log. The same entries are picked on every run.
+    #[arg(long, value_name = "RATE", value_parser = sample_rate)]
+    pub sample: Option<f64>,
+    /// Pick other entries for --sample on every run.
+    #[arg(long, requires = "sample")]
+    pub random_sample: bool,
+    /// Leave out the first N matching entries.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub skip: usize,
+    /// Stop after N matching entries (after --skip), e.g. to grab the first 100 errors after
+    /// a given time; also stops following.
+    #[arg(long, visible_alias = "max-lines", value_name = "N")]
+    pub head: Option<usize>,
+    /// Query the JSON Data column jq-style, e.g. `.request.headers["x-request-id"]`. A plain
+    /// path is shown as an extra column; with a test (`.status >= 500`) it filters entries.
+    #[arg(long, value_name = "QUERY", value_parser = data_query)]
+    pub data_query: Vec<DataQuery>,
+    /// Zone for --since/--until times without an offset, from `--tz` or else `--log-tz`.
+    #[arg(skip)]
+    pub tz: Option<Zone>,
+}
+
+#[derive(Debug, Args)]
+pub struct DisplayArgs {
+    /// Column widths for formatted output, by position. By default DateTime is 20 wide,
+    /// Level 10, Message 50, Data 30, and other columns 15.
+    #[arg(long, visible_alias = "w", value_name = "W1,W2,...", value_delimiter = ',', action = ArgAction::Set)]
+    pub width: Vec<usize>,
+    /// Do not print the header row with the column names.
+    #[arg(long)]
+    pub no_header: bool,
+    /// Repeat the header row every N entries.
+    #[arg(long, value_name = "N", conflicts_with = "no_header")]
+    pub header_every: Option<usize>,
+    /// Show the input of each entry as a `Source` column instead of a `[source]` prefix.
+    #[arg(long)]
+    pub source_column: bool,
+    /// Color rows by the input they come from instead of by level.
+    #[arg(long)]
+    pub color_by_source: bool,
+    /// Show DateTime as written (`absolute`), as time elapsed (`ago`), or as the time since the
+    /// previous entry shown (`delta`), which makes stalls stand out.
+    #[arg(long, value_name = "MODE", value_enum, default_value_t = TimeDisplay::Absolute)]
+    pub time_display: TimeDisplay,
+    /// Show the line number of each entry in its input, as a `Line` column.
+    #[arg(long)]
+    pub line_numbers: bool,
+    /// Show the byte offset of each entry in its input, as an `Offset` column.
+    #[arg(long)]
+    pub offsets: bool,
+    /// Wrap long values onto continuation lines under their column instead of overflowing.
+    #[arg(long)]
+    pub wrap: bool,
+    /// Keep the default column widths instead of fitting the table to the terminal.
+    #[arg(long)]
+    pub no_fit: bool,
+    /// Include the Data column.
+    #[arg(long, visible_alias = "v")]
+    pub verbose: bool,
+    /// Include the Data column and pretty-print it as JSON.
+    #[arg(long, visible_alias = "V")]
+    pub detailed: bool,
+    /// Write entries as a table, or as JSON, NDJSON, CSV, or TSV for other programs.
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+    /// Write the output to PATH instead of the terminal.
+    #[arg(long, value_name = "PATH", conflicts_with = "tee")]
+    pub out: Option<PathBuf>,
+    /// Write the output to PATH as well as the terminal.
+    #[arg(long, value_name = "PATH")]
+    pub tee: Option<PathBuf>,
+    /// Also ship matching entries to a log store: `loki=URL`, `es=URL/INDEX`,
+    /// `gelf=udp://HOST:PORT` (or `tcp://`), or an OpenTelemetry collector with
+    /// `otlp=URL` (OTLP/HTTP, usually port 4318); repeatable. HTTP targets are sent to with
+    /// `curl`, in batches.
+    #[arg(long, value_name = "TARGET")]
+    pub forward: Vec<Target>,
+    /// Leave colors and other escape sequences out of the --out/--tee file.
+    #[arg(long)]
+    pub strip_ansi: bool,
+    /// Lay out each entry with a template instead of a table, e.g.
+    /// `'{DateTime} [{Level}] {Message}{?Data.request_id} ({Data.request_id}){/}'`.
+    #[arg(long, value_name = "TEMPLATE", value_parser = template)]
+    pub format_out: Option<Template>,
+    /// Color text matching PATTERN in the Message and Data columns, in COLOR (a name, a
+    /// 256-color index, or `#rrggbb`; yellow by default); repeatable.
+    #[arg(long = "highlight", value_name = "PATTERN[:COLOR]", value_parser = highlight)]
+    pub highlights: Vec<(Regex, Color)>,
+    /// Style rows matching an expression, e.g. `'Data.duration_ms > 1000 => bg:red bold'`.
+    /// Styles combine `fg:COLOR`, `bg:COLOR`, bold, dim, italic, underline, blink, reverse,
+    /// and strike; repeatable, later rules win.
+    #[arg(long = "rule", value_name = "EXPR => STYLE", value_parser = rule)]
+    pub rules: Vec<(Expr, RowStyle)>,
+    /// Show the keys of the JSON Data column as columns of their own instead of one JSON string.
+    #[arg(long)]
+    pub expand_data: bool,
+    /// Columns to show, in order, e.g. `DateTime,Level,Message,Data.user_id`. `Data.KEY`
+    /// picks a key out of the JSON Data column.
+    #[arg(long, value_name = "COLUMN,...", value_delimiter = ',', action = ArgAction::Set, value_parser = column)]
+    pub columns: Vec<(String, Option<DataQuery>)>,
+    /// Show a column under another header, e.g. `Message=Msg`; repeatable.
+    #[arg(long, value_name = "COLUMN=HEADER", value_parser = rename)]
+    pub rename: Vec<(String, String)>,
+    /// Collapse runs of matches with the same level and message into the first one and a
+    /// count of repeats; `--dedupe=template` also collapses messages differing only in
+    /// numbers, ids, and addresses.
+    #[arg(long, value_name = "MODE", value_enum, num_args = 0..=1, require_equals = true, default_missing_value = "exact")]
+    pub dedupe: Option<DedupeMode>,
+    /// Print at most N entries per second (`200/s`), minute (`1000/m`), or hour (`/h`),
+    /// and tell how many were left out, so a burst does not flood the terminal.
+    #[arg(long, value_name = "N/UNIT", value_parser = rate)]
+    pub max_rate: Option<(usize, Duration)>,
+    /// Print only the number of matching entries, like `grep -c`.
+    #[arg(short = 'c', long, conflicts_with = "quiet")]
+    pub count: bool,
+    /// Print no entries and stop at the first match, like `grep -q`; the exit status tells
+    /// whether any entry matched.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// When done (or interrupted), print on stderr how many lines were read, matched, and
+    /// failed to parse, the matches per level, and the time span they cover.
+    #[arg(long)]
+    pub summary: bool,
+    /// Also show N entries before each match, dimmed.
+    #[arg(short = 'B', long, value_name = "N")]
+    pub before: Option<usize>,
+    /// Also show N entries after each match, dimmed.
+    #[arg(short = 'A', long, value_name = "N")]
+    pub after: Option<usize>,
+    /// Also show N entries before and after each match (same as `-B N -A N`).
+    #[arg(short = 'C', long, value_name = "N")]
+    pub context: Option<usize>,
+    /// Input labels; rows are prefixed with their source when there is more than one.
+    #[arg(skip)]
+    pub sources: Vec<Arc<str>>,
+    /// Whether rows are prefixed with their source even with one input: more may turn up
+    /// with `--watch-dir`, and `grep` searches many.
+    #[arg(skip)]
+    pub attribute_rows: bool,
+    /// Level colors of the active theme, including overrides from the config file.
+    #[arg(skip)]
+    pub colors: HashMap<String, Color>,
+    /// Whether rows are colored, resolved from `--color`.
+    #[arg(skip)]
+    pub colored: bool,
+    /// Widths fitted to the terminal, when printing a table there without `--width`.
+    #[arg(skip)]
+    pub fit: Option<RefCell<Fit>>,
+    /// `--data-query` paths shown as extra columns, with their headers.
+    #[arg(skip)]
+    pub data_columns: Vec<(String, DataQuery)>,
+    /// Time of the entry shown last, for `--time-display delta`.
+    #[arg(skip)]
+    pub previous_time: Cell<Option<DateTime<FixedOffset>>>,
+}
+
+impl InputArgs {
+    /// Options passed on to the format parsers.
+    pub fn parser_options(&self) -> ParserOptions {
+        ParserOptions { keys: self.keys.clone(), quoting: self.quoting, formats: custom_formats().clone().unwrap_or_default() }
+    }
+
+    /// Creates a parser for one input in the selected format. With `auto`, the format is
+    /// detected from `sample` (the first lines of a file) or, for streams, from the first line.
+    pub fn parser(&self, label: &str, sample: Option<&[String]>) -> Box<dyn LogParser + Send> {
+        let options = self.parser_options();
+        if self.format != "auto" || self.pattern.is_some() {
+            return build_parser(&self.format, self.pattern.as_deref(), &options).expect("clap validates formats and patterns");
+        }
+        let label = label.to_string();
+        match sample {
+            Some(sample) => {
+                let format = detect_format(sample, &options);
+                eprintln!("rlog: detected {} format for {}", format, label);
+                parser_for(format, &options).expect("detected formats exist")
+            }
+            None => Box::new(AutoParser::new(options).on_detect(move |format| eprintln!("rlog: detected {} format for {}", format, label))),
+        }
+    }
+}
+
+impl Cli {
+    /// Parses the command line on top of the config file defaults and profile, defaulting to
+    /// stdin when no file is given and input is piped.
+    pub fn parse_args() -> Self {
+        let args: Vec<OsString> = env::args_os().collect();
+        // Parse once to find --config/--profile, then again with the config applied.
+        let first = Cli::parse_from(&args);
+        let config = Config::load(first.config.as_deref()).unwrap_or_else(|e| Cli::command().error(ErrorKind::Io, e).exit());
+        let args = config.apply(args, first.profile.as_deref()).unwrap_or_else(|e| Cli::command().error(ErrorKind::InvalidValue, e).exit());
+        let mut cli = Cli::parse_from(args);
+        let colors = config.palette(&cli.theme).unwrap_or_else(|e| Cli::command().error(ErrorKind::InvalidValue, e).exit());
+        match &mut cli.command {
+            Some(Command::Stats(args)) => (args.colors, args.colored) = (colors.clone(), cli.color.enabled()),
+            Some(Command::Timeline(args)) => (args.colors, args.colored) = (colors.clone(), cli.color.enabled()),
+            _ => {}
+        }
+        if let Some(Command::Watch(args)) = &cli.command {
+            if !args.alerts.has_rules() {
+                Cli::command().error(ErrorKind::MissingRequiredArgument, "watch needs a rule: --alert-on, --alert, --expect-heartbeat, or --detect-spikes").exit();
+            }
+        }
+        let recursive = matches!(&cli.command, Some(Command::Grep(args)) if args.recursive);
+        if let Some(Command::Grep(args)) = &cli.command {
+            if args.input.state_file.is_some() {
+                Cli::command().error(ErrorKind::ArgumentConflict, "grep searches files on their own and cannot record them in a --state-file").exit();
+            }
+        }
+        if let Some(Command::Ssh(args)) = &mut cli.command {
+            let remote = Remote { lines: args.tail.history(), ..args.remote.clone() };
+            args.tail.input.remotes.push(remote);
+        }
+        if let Some(Command::Docker(args)) = &mut cli.command {
+            let tail = &mut args.tail;
+            tail.input.containers = args.containers.split(',').filter(|c| !c.is_empty()).map(|container| tail.container(container)).collect();
+        }
+        if let Some(Command::K8s(args)) = &mut cli.command {
+            let pods = args.pods();
+            args.tail.input.pods.push(pods);
+        }
+        if let Some(Command::Http(args)) = &mut cli.command {
+            let spawned = args.stream();
+            args.tail.input.spawned.push(spawned);
+        }
+        if let Some(Command::Kafka(args)) = &mut cli.command {
+            let spawned = args.kcat();
+            args.tail.input.spawned.push(spawned);
+        }
+        if let Some(Command::Loki(args)) = &mut cli.command {
+            if args.tail.no_follow && args.tail.tail.is_some() {
+                Cli::command().error(ErrorKind::ArgumentConflict, "loki cannot read the last N entries with --no-follow; use --since").exit();
+            }
+            let spawned = args.logcli();
+            args.tail.input.spawned.push(spawned);
+        }
+        if let Some(Command::Es(args)) = &mut cli.command {
+            let search = args.search();
+            let input = &mut args.tail.input;
+            input.searches.push(search);
+            if input.format == "auto" && input.pattern.is_none() {
+                input.format = "json".to_string();
+            }
+            if !input.keys.iter().any(|(column, _)| column == "DateTime") {
+                input.keys.push(("DateTime".to_string(), args.time_field.clone()));
+            }
+        }
+        if let Some(Command::Cloudwatch(args)) = &mut cli.command {
+            let groups = args.group.iter().map(|group| args.log_group(group)).collect();
+            args.tail.input.log_groups = groups;
+        }
+        if let Some(Command::Journal(args)) = &mut cli.command {
+            let spawned = args.journalctl();
+            let input = &mut args.tail.input;
+            input.spawned.push(spawned);
+            if input.format == "auto" && input.pattern.is_none() {
+                input.format = "journal".to_string();
+            }
+        }
+        if let Some(Command::Evtx(args)) = &mut cli.command {
+            let input = &mut args.tail.input;
+            let mut files = Vec::new();
+            for path in mem::take(&mut input.files) {
+                match rlog::is_pattern(&path) {
+                    true => files.extend(rlog::expand_pattern(&path).unwrap_or_else(|e| Cli::command().error(ErrorKind::Io, format!("{}: {}", path.display(), e)).exit())),
+                    false => files.push(path),
+                }
+            }
+            if files.is_empty() {
+                Cli::command().error(ErrorKind::MissingRequiredArgument, "evtx needs the .evtx files to read").exit();
+            }
+            input.event_logs = files.iter().map(|file| EventLog::file(file)).collect();
+            if input.format == "auto" && input.pattern.is_none() {
+                input.format = "winlog".to_string();
+            }
+        }
+        if let Some(Command::Winlog(args)) = &mut cli.command {
+            if !cfg!(windows) {
+                Cli::command().error(ErrorKind::InvalidSubcommand, "winlog reads the Windows event log, so only works on Windows; read exported .evtx files with `rlog evtx`").exit();
+            }
+            let logs = args.channel.iter().map(|channel| args.event_log(channel)).collect();
+            let input = &mut args.tail.input;
+            input.event_logs = logs;
+            if input.format == "auto" && input.pattern.is_none() {
+                input.format = "winlog".to_string();
+            }
+        }
+        if let Some(Command::Oslog(args)) = &mut cli.command {
+            if !cfg!(target_os = "macos") {
+                Cli::command().error(ErrorKind::InvalidSubcommand, "oslog reads the macOS unified log, so only works on macOS").exit();
+            }
+            let spawned = args.log();
+            let input = &mut args.tail.input;
+            input.spawned.push(spawned);
+            if input.format == "auto" && input.pattern.is_none() {
+                input.format = "oslog".to_string();
+            }
+        }
+        if let Some(Command::Listen(args)) = &mut cli.command {
+            let input = &mut args.tail.input;
+            input.endpoints = args.udp.iter().map(|addr| Endpoint::Udp(*addr))
+                .chain(args.tcp.iter().map(|addr| Endpoint::Tcp(*addr)))
+                .chain(args.unix.iter().map(|path| Endpoint::Unix(path.clone())))
+                .chain(args.unix_stream.iter().map(|path| Endpoint::UnixStream(path.clone())))
+                .chain(args.gelf_udp.iter().map(|addr| Endpoint::GelfUdp(*addr)))
+                .chain(args.gelf_tcp.iter().map(|addr| Endpoint::GelfTcp(*addr)))
+                .collect();
+            // With both kinds of endpoints, each is detected on its own.
+            let gelf = input.endpoints.iter().filter(|endpoint| endpoint.is_gelf()).count();
+            if input.format == "auto" && input.pattern.is_none() && (gelf == 0 || gelf == input.endpoints.len()) {
+                input.format = if gelf == 0 { "syslog" } else { "gelf" }.to_string();
+            }
+        }
+        let terminal = io::stdout().is_terminal();
+        let (input, filter, display, on_terminal) = match &mut cli.command {
+            None => (&mut cli.tail.input, &mut cli.tail.filter, Some(&mut cli.tail.display), terminal || cli.tail.tui),
+            Some(Command::Tail(args)) => (&mut args.input, &mut args.filter, Some(&mut args.display), terminal || args.tui),
+            Some(Command::Query(args)) => (&mut args.input, &mut args.filter, Some(&mut args.display), terminal),
+            Some(Command::Grep(args)) => (&mut args.input, &mut args.filter, Some(&mut args.display), terminal),
+            Some(Command::Ssh(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Docker(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::K8s(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Http(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Kafka(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Loki(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Es(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Cloudwatch(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Journal(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Evtx(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Winlog(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Oslog(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Listen(args)) => (&mut args.tail.input, &mut args.tail.filter, Some(&mut args.tail.display), terminal || args.tail.tui),
+            Some(Command::Stats(args)) => (&mut args.input, &mut args.filter, None, false),
+            Some(Command::Timeline(args)) => (&mut args.input, &mut args.filter, None, false),
+            Some(Command::Patterns(args)) => (&mut args.input, &mut args.filter, None, false),
+            Some(Command::Top(args)) => (&mut args.input, &mut args.filter, None, false),
+            Some(Command::Agg(args)) => (&mut args.input, &mut args.filter, None, false),
+            Some(Command::Export(args)) => (&mut args.input, &mut args.filter, Some(&mut args.display), false),
+            Some(Command::Watch(args)) => (&mut args.input, &mut args.filter, None, false),
+        };
+        input.tz = cli.tz;
+        input.log_tz = cli.log_tz;
+        filter.tz = cli.tz.or(cli.log_tz);
+        filter.check_levels().unwrap_or_else(|e| Cli::command().error(ErrorKind::InvalidValue, e).exit());
+        if let Some(display) = display {
+            display.colors = colors;
+            display.colored = cli.color.enabled();
+            let table = display.output == OutputFormat::Table && display.format_out.is_none() && !display.detailed;
+            if on_terminal && table && display.width.is_empty() && !display.no_fit && display.out.is_none() {
+                display.fit = Some(RefCell::default());
+            }
+            display.data_columns = filter.data_query.iter().filter(|q| !q.is_test()).map(|q| (q.to_string(), q.clone())).collect();
+        }
+        let mut files = Vec::new();
+        for path in mem::take(&mut input.files) {
+            if !rlog::is_pattern(&path) {
+                files.push(path);
+                continue;
+            }
+            let found = if recursive && path.is_dir() { rlog::walk(&path) } else { rlog::expand_pattern(&path) };
+            let found = found.unwrap_or_else(|e| Cli::command().error(ErrorKind::Io, format!("{}: {}", path.display(), e)).exit());
+            if found.is_empty() && !input.watch_dir {
+                Cli::command().error(ErrorKind::InvalidValue, format!("no files match {}", path.display())).exit();
+            }
+            files.extend(found);
+            if input.watch_dir {
+                input.patterns.push(path);
+            }
+        }
+        input.files = files;
+        if input.files.is_empty() && input.patterns.is_empty() && input.remotes.is_empty() && input.endpoints.is_empty() && input.searches.is_empty() && input.pods.is_empty() && input.spawned.is_empty() {
+            if io::stdin().is_terminal() {
+                Cli::command().error(ErrorKind::MissingRequiredArgument, "no <LOG_FILE> given and stdin is a terminal").exit();
+            }
+            input.files.push(PathBuf::from("-"));
+        }
+        cli
+    }
+}
+
+impl TailArgs {
+    /// How many earlier entries to show before following, or `None` to read from the top.
+    pub fn history(&self) -> Option<usize> {
+        if self.no_history { Some(0) } else { self.tail }
+    }
+
+    /// The logs of `container` as these options ask for them: from --since, only the last
+    /// --tail lines, and following unless --no-follow.
+    fn container(&self, name: &str) -> Container {
+        let since = match self.filter.start.as_deref().map(|text| TimeBound::parse(text, self.filter.tz.unwrap_or_default())) {
+            Some(TimeBound::At(since)) => Some(since),
+            _ => None,
+        };
+        Container { name: name.to_string(), since, last: self.history(), follow: !self.no_follow }
+    }
+}
+
+impl K8sArgs {
+    /// The pods to read as these options ask for them, from --since and only the last --tail
+    /// lines of each container, and following unless --no-follow.
+    fn pods(&self) -> Pods {
+        let Container { since, last, follow, .. } = self.tail.container("");
+        let (target, selector, namespace) = (self.target.clone(), self.selector.clone(), self.namespace.clone());
+        Pods { target, selector, namespace, container: self.container.clone(), all_containers: self.all_containers, context: self.kube_context.clone(), since, last, follow }
+    }
+}
+
+impl HttpArgs {
+    /// The command reading the stream. `curl` is given the headers on stdin, so that they do
+    /// not show in the list of processes; `websocat` cannot take them that way.
+    fn stream(&self) -> Spawned {
+        let resume = (!self.tail.no_follow).then(Vec::new);
+        if self.url.starts_with("ws://") || self.url.starts_with("wss://") {
+            let mut args = vec!["--text".to_string(), "--unidirectional".to_string()];
+            for header in &self.header {
+                args.extend(["--header".to_string(), header.clone()]);
+            }
+            args.push(self.url.clone());
+            return Spawned { resume, ..Spawned::new(&self.url, "websocat", args) };
+        }
+        let args = ["--silent", "--show-error", "--fail", "--no-buffer", "--location", "--config", "-", &self.url].map(str::to_string).to_vec();
+        Spawned { events: true, input: Some(curl_headers(&self.header)), resume, ..Spawned::new(&self.url, "curl", args) }
+    }
+}
+
+impl KafkaArgs {
+    /// The `kcat` consumer reading the topic from where these options say. --tail N reads
+    /// the last N messages of each partition.
+    fn kcat(&self) -> Spawned {
+        let tail = &self.tail;
+        let mut args = ["-C", "-u", "-q", "-b", &self.brokers].map(str::to_string).to_vec();
+        let offset = match tail.filter.start.as_deref().map(|text| TimeBound::parse(text, tail.filter.tz.unwrap_or_default())) {
+            Some(TimeBound::At(since)) => Some(format!("s@{}", since.timestamp_millis())),
+            _ if self.from_beginning => Some("beginning".to_string()),
+            _ => match tail.history() {
+                Some(0) => Some("end".to_string()),
+                Some(lines) => Some(format!("-{}", lines)),
+                None => self.group.is_none().then(|| "end".to_string()),
+            },
+        };
+        if let Some(offset) = offset {
+            args.extend(["-o".to_string(), offset]);
+        }
+        if tail.no_follow {
+            args.push("-e".to_string());
+        }
+        let input = (!self.property.is_empty()).then(|| self.property.iter().map(|property| format!("{}\n", property)).collect());
+        if input.is_some() {
+            args.extend(["-F".to_string(), "/dev/stdin".to_string()]);
+        }
+        match &self.group {
+            Some(group) => args.extend(["-G".to_string(), group.clone(), self.topic.clone()]),
+            None => args.extend(["-t".to_string(), self.topic.clone()]),
+        }
+        Spawned { input, ..Spawned::new(&self.topic, "kcat", args) }
+    }
+}
+
+impl LokiArgs {
+    /// The `logcli` query these options ask for. Following, it tails the query, starting with
+    /// at most `LOKI_TAIL_LIMIT` entries, or --tail N; otherwise it reads every result in
+    /// order. Without --since, Loki looks an hour back.
+    fn logcli(&self) -> Spawned {
+        let tail = &self.tail;
+        let mut args = ["query", "--quiet", "--no-labels", "--timezone=UTC"].map(str::to_string).to_vec();
+        args.push(format!("--addr={}", self.url));
+        if let Some(org_id) = &self.org_id {
+            args.push(format!("--org-id={}", org_id));
+        }
+        let zone = tail.filter.tz.unwrap_or_default();
+        for (option, bound) in [("--from", &tail.filter.start), ("--to", &tail.filter.to)] {
+            if let Some(TimeBound::At(time)) = bound.as_deref().map(|text| TimeBound::parse(text, zone)) {
+                args.push(format!("{}={}", option, time.to_rfc3339()));
+            }
+        }
+        match tail.no_follow {
+            true => args.extend(["--forward".to_string(), "--limit=0".to_string()]),
+            false => args.extend(["--tail".to_string(), format!("--limit={}", tail.history().unwrap_or(LOKI_TAIL_LIMIT))]),
+        }
+        args.push(self.query.clone());
+        Spawned { timestamped: true, ..Spawned::new(&self.query, "logcli", args) }
+    }
+}
+
+impl EsArgs {
+    /// The search these options ask for.
+    fn search(&self) -> Search {
+        let tail = &self.tail;
+        let zone = tail.filter.tz.unwrap_or_default();
+        let bound = |bound: &Option<String>| match bound.as_deref().map(|text| TimeBound::parse(text, zone)) {
+            Some(TimeBound::At(time)) => Some(time),
+            _ => None,
+        };
+        Search {
+            url: self.url.clone(),
+            index: self.index.clone(),
+            query: self.query.clone(),
+            time_field: self.time_field.clone(),
+            headers: self.header.clone(),
+            since: bound(&tail.filter.start),
+            until: bound(&tail.filter.to),
+            last: tail.history(),
+            follow: !tail.no_follow,
+        }
+    }
+}
+
+impl CloudwatchArgs {
+    /// The log group `group`, read as these options ask. Without --since it starts 10
+    /// minutes back, and with --no-history now; --tail N cannot be asked for.
+    fn log_group(&self, group: &str) -> LogGroup {
+        let tail = &self.tail;
+        let now = chrono::Utc::now();
+        let since = match tail.filter.start.as_deref().map(|text| TimeBound::parse(text, tail.filter.tz.unwrap_or_default())) {
+            Some(TimeBound::At(since)) => since.to_utc(),
+            _ if tail.no_history => now,
+            _ => now - TimeDelta::minutes(10),
+        };
+        LogGroup {
+            name: group.to_string(),
+            stream_prefix: self.stream_prefix.clone(),
+            filter_pattern: self.filter_pattern.clone(),
+            profile: self.profile.clone(),
+            region: self.region.clone(),
+            since,
+            follow: !tail.no_follow,
+        }
+    }
+}
+
+impl JournalArgs {
+    /// The `journalctl` command reading the journal as these options ask. --since and
+    /// --until are passed on so that the journal is not read in full to find them.
+    fn journalctl(&self) -> Spawned {
+        let tail = &self.tail;
+        let mut args = vec!["--output=json".to_string(), "--no-pager".to_string()];
+        if self.user {
+            args.push("--user".to_string());
+        }
+        args.extend(self.unit.iter().map(|unit| format!("--unit={}", unit)));
+        if !tail.no_follow {
+            args.push("--follow".to_string());
+        }
+        let zone = tail.filter.tz.unwrap_or_default();
+        for (option, bound) in [("--since", &tail.filter.start), ("--until", &tail.filter.to)] {
+            if let Some(TimeBound::At(time)) = bound.as_deref().map(|text| TimeBound::parse(text, zone)) {
+                args.push(format!("{}=@{}", option, time.timestamp()));
+            }
+        }
+        if let Some(lines) = tail.history() {
+            args.push(format!("--lines={}", lines));
+        }
+        let label = if self.unit.is_empty() { "journal".to_string() } else { self.unit.join(",") };
+        Spawned::new(label, "journalctl", args)
+    }
+}
+
+impl WinlogArgs {
+    /// The log of `channel` as these options ask for it: the records from --since, or the
+    /// last --tail ones (10 when following, all of them otherwise), then, following, the new
+    /// ones.
+    fn event_log(&self, channel: &str) -> EventLog {
+        let tail = &self.tail;
+        let since = match tail.filter.start.as_deref().map(|text| TimeBound::parse(text, tail.filter.tz.unwrap_or_default())) {
+            Some(TimeBound::At(since)) => Some(since),
+            _ => None,
+        };
+        let last = match tail.history() {
+            None if since.is_none() && !tail.no_follow => Some(10),
+            last => last,
+        };
+        EventLog { since, last, follow: !tail.no_follow, ..EventLog::channel(channel) }
+    }
+}
+
+impl OslogArgs {
+    /// The `log` command reading the unified log as these options ask: `log show` with
+    /// --no-follow, otherwise `log stream`, after `log show` from --since if given. Between
+    /// the two, an entry may be missed or read twice.
+    fn log(&self) -> Spawned {
+        let tail = &self.tail;
+        let zone = tail.filter.tz.unwrap_or_default();
+        let [since, until] = [&tail.filter.start, &tail.filter.to].map(|bound| match bound.as_deref().map(|text| TimeBound::parse(text, zone)) {
+            Some(TimeBound::At(time)) => Some(time.format("%Y-%m-%d %H:%M:%S%z").to_string()),
+            _ => None,
+        });
+        let (show_level, stream_level): (&[&str], _) = match self.log_level {
+            OslogLevel::Default => (&[], ["--level", "default"]),
+            OslogLevel::Info => (&["--info"], ["--level", "info"]),
+            OslogLevel::Debug => (&["--info", "--debug"], ["--level", "debug"]),
+        };
+        let label = self.predicate.clone().unwrap_or_else(|| "oslog".to_string());
+        if tail.no_follow {
+            let mut args: Vec<String> = ["show", "--style", "ndjson"].iter().chain(show_level).map(|arg| arg.to_string()).collect();
+            for (option, value) in [("--predicate", &self.predicate), ("--start", &since), ("--end", &until)] {
+                if let Some(value) = value {
+                    args.extend([option.to_string(), value.clone()]);
+                }
+            }
+            return Spawned::new(label, "log", args);
+        }
+        let mut stream: Vec<String> = ["stream", "--style", "ndjson"].iter().chain(&stream_level).map(|arg| arg.to_string()).collect();
+        let Some(since) = since else {
+            if let Some(predicate) = &self.predicate {
+                stream.extend(["--predicate".to_string(), predicate.clone()]);
+            }
+            return Spawned::new(label, "log", stream);
+        };
+        // The predicate and start time are passed as arguments of the script, so that they
+        // need no quoting in it.
+        let predicate = if self.predicate.is_some() { " --predicate \"$2\"" } else { "" };
+        let script = format!(
+            "log show --style ndjson {} --start \"$1\"{} && exec log {}{}",
+            show_level.join(" "), predicate, stream.join(" "), predicate,
+        );
+        let args = vec!["-c".to_string(), script, "sh".to_string(), since, self.predicate.clone().unwrap_or_default()];
+        Spawned::new(label, "sh", args)
+    }
+}
+
+impl AlertArgs {
+    /// Whether any rule raising alerts is given.
+    pub fn has_rules(&self) -> bool {
+        self.alert_on.is_some() || !self.alert.is_empty() || self.expect_heartbeat.is_some() || self.detect_spikes.is_some()
+    }
+}
+
+impl DisplayArgs {
+    /// The bounds of `filter` on the records to print; with `--quiet` the first match is
+    /// enough.
+    pub fn bounds(&self, filter: &FilterArgs) -> Bounds {
+        let mut bounds = filter.bounds();
+        if self.quiet {
+            bounds.head = Some(bounds.head.map_or(1, |head| head.min(1)));
+        }
+        bounds
+    }
+
+    /// The `--dedupe` stage, if enabled.
+    pub fn dedupe(&self) -> Option<Dedupe> {
+        self.dedupe.map(|mode| Dedupe::new(mode == DedupeMode::Template))
+    }
+
+    /// The window selecting matches and their context entries.
+    pub fn context_window(&self) -> ContextWindow {
+        ContextWindow::new(self.before.or(self.context).unwrap_or(0), self.after.or(self.context).unwrap_or(0))
+    }
+}
+
+impl FilterArgs {
+    /// Checks the levels against the severity ordering, normalizing spellings such as `warn`.
+    fn check_levels(&mut self) -> Result<(), String> {
+        let known: Vec<&str> = if self.severities.is_empty() { LEVELS.to_vec() } else { self.severities.iter().map(String::as_str).collect() };
+        let resolve = |level: &str| {
+            if known.contains(&level) {
+                return Ok(level.to_string());
+            }
+            let normalized = normalize_level(level);
+            match known.contains(&normalized.as_str()) {
+                true => Ok(normalized),
+                false => Err(format!("unknown level `{}` (expected one of {})", level, known.join(", "))),
+            }
+        };
+        self.level = self.level.iter().map(|l| resolve(l)).collect::<Result<_, _>>()?;
+        self.min_level = self.min_level.as_deref().map(resolve).transpose()?;
+        Ok(())
+    }
+
+    /// Builds the library filter from the parsed flags.
+    pub fn to_filter(&self) -> rlog::Filter {
+        rlog::Filter {
+            word: self.filter.clone(),
+            ignore_case: self.ignore_case,
+            fuzzy: self.fuzzy,
+            fields: self.fields.clone(),
+            patterns: self.patterns.clone(),
+            excludes: self.excludes.clone(),
+            levels: self.level.clone(),
+            min_level: self.min_level.clone(),
+            severities: self.severities.clone(),
+            from: self.start.as_deref().map(|text| TimeBound::parse(text, self.tz.unwrap_or_default())),
+            to: self.to.as_deref().map(|text| TimeBound::parse(text, self.tz.unwrap_or_default())),
+            expr: self.where_.clone(),
+            data_queries: self.data_query.clone(),
+            sample: self.sample.map(|rate| Sample::new(rate, self.random_sample)),
+        }
+    }
+
+    /// The `--skip`/`--head` bounds on the matching records.
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(self.skip, self.head)
+    }
+}
+
+/// Entries `rlog loki` reads before following, the most Loki returns by default.
+const LOKI_TAIL_LIMIT: usize = 5000;
+
+/// Exit statuses listed under `--help`.
+const EXIT_STATUS: &str = "Exit status:
+  0  at least one entry matched
+  1  no entry matched
+  2  invalid command line or config file
+  3  an input could not be read or the output could not be written";
+
+/// Levels understood by the color palette.
+pub const LEVELS: [&str; 8] = rlog::record::SEVERITIES;
+
+fn time_format(value: &str) -> Result<String, String> {
+    match StrftimeItems::new(value).any(|item| item == Item::Error) {
+        true => Err("invalid strftime format".to_string()),
+        false => Ok(value.to_string()),
+    }
+}
+
+fn time_bound(value: &str) -> Result<String, String> {
+    match value.trim() {
+        "" => Err("empty time".to_string()),
+        value => Ok(value.to_string()),
+    }
+}
+
+fn stream_url(value: &str) -> Result<String, String> {
+    match ["http://", "https://", "ws://", "wss://"].iter().any(|scheme| value.starts_with(scheme)) {
+        true => Ok(value.to_string()),
+        false => Err(format!("expected an http, https, ws, or wss URL, got `{}`", value)),
+    }
+}
+
+fn header(value: &str) -> Result<String, String> {
+    match value.split_once(':') {
+        Some((name, _)) if !name.trim().is_empty() && !value.contains(['\r', '\n']) => Ok(value.to_string()),
+        _ => Err(format!("expected NAME: VALUE, got `{}`", value)),
+    }
+}
+
+fn property(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((key, _)) if !key.trim().is_empty() && !value.contains('\n') => Ok(value.to_string()),
+        _ => Err(format!("expected KEY=VALUE, got `{}`", value)),
+    }
+}
+
+fn duration(value: &str) -> Result<TimeDelta, String> {
+    match parse_duration(value) {
+        Some(duration) if duration > TimeDelta::zero() => Ok(duration),
+        _ => Err(format!("expected a duration such as `30s`, `5m`, or `1h`, got `{}`", value)),
+    }
+}
+
+fn factor(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(factor) if factor > 1.0 => Ok(factor),
+        _ => Err(format!("expected a number greater than 1, got `{}`", value)),
+    }
+}
+
+fn ratio(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err(format!("expected a number from 0 to 1, got `{}`", value)),
+    }
+}
+
+fn sample_rate(value: &str) -> Result<f64, String> {
+    Sample::parse_rate(value)
+}
+
+fn rate(value: &str) -> Result<(usize, Duration), String> {
+    let (count, unit) = value.split_once('/').unwrap_or((value, "s"));
+    let per = match unit.trim() {
+        "s" | "sec" | "second" => Duration::from_secs(1),
+        "m" | "min" | "minute" => Duration::from_secs(60),
+        "h" | "hour" => Duration::from_secs(3600),
+        _ => return Err(format!("unknown unit `{}` (expected s, m, or h)", unit)),
+    };
+    match count.trim().parse::<usize>() {
+        Ok(count) if count > 0 => Ok((count, per)),
+        _ => Err(format!("expected a rate such as `200/s`, got `{}`", value)),
+    }
+}
+
+fn existing_file(value: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(value);
+    if value == "-" || path.is_file() || is_fifo(&path) || rlog::is_pattern(&path) {
+        Ok(path)
+    } else {
+        Err(format!("file not found: {}", value))
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    path.metadata().is_ok_and(|meta| meta.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_: &std::path::Path) -> bool {
+    false
+}
+
+/// The formats defined in the files of [`Config::formats_dir`], loaded once.
+fn custom_formats() -> &'static Result<Vec<Arc<CustomFormat>>, String> {
+    static FORMATS: LazyLock<Result<Vec<Arc<CustomFormat>>, String>> = LazyLock::new(|| Config::formats_dir().map_or(Ok(Vec::new()), |dir| load_formats(&dir)));
+    &FORMATS
+}
+
+fn format(value: &str) -> Result<String, String> {
+    if value == "auto" || parser_for(value, &ParserOptions::default()).is_some() {
+        return Ok(value.to_string());
+    }
+    // The definition files are only read when a format is not built in.
+    let formats = custom_formats().clone()?;
+    let names: Vec<&str> = rlog::parser::FORMATS.iter().copied().chain(formats.iter().map(|format| format.name.as_str())).collect();
+    match parser_for(value, &ParserOptions { formats: formats.clone(), ..ParserOptions::default() }) {
+        Some(_) => Ok(value.to_string()),
+        None => Err(format!("unknown format `{}` (expected auto, {}, docker:FORMAT, or cri:FORMAT)", value, names.join(", "))),
+    }
+}
+
+fn pattern(value: &str) -> Result<String, String> {
+    PatternParser::new(value).map(|_| value.to_string())
+}
+
+fn regex(value: &str) -> Result<Regex, String> {
+    Regex::new(value).map_err(|e| e.to_string())
+}
+
+fn field(value: &str) -> Result<(String, FieldMatch), String> {
+    let split = value.find(['=', '~']).filter(|&at| at > 0).ok_or_else(|| format!("expected COLUMN=VALUE or COLUMN~REGEX, got `{}`", value))?;
+    let (column, rest) = (value[..split].to_string(), &value[split + 1..]);
+    match &value[split..=split] {
+        "=" => Ok((column, FieldMatch::Equals(rest.to_string()))),
+        _ => Ok((column, FieldMatch::Regex(regex(rest)?))),
+    }
+}
+
+fn data_query(value: &str) -> Result<DataQuery, String> {
+    DataQuery::parse(value).map_err(|e| e.to_string())
+}
+
+fn column(value: &str) -> Result<(String, Option<DataQuery>), String> {
+    match value.strip_prefix("Data") {
+        Some(path) if path.starts_with(['.', '[']) => Ok((value.to_string(), Some(data_query(path)?))),
+        _ if value.is_empty() => Err("empty column name".to_string()),
+        _ => Ok((value.to_string(), None)),
+    }
+}
+
+fn highlight(value: &str) -> Result<(Regex, Color), String> {
+    let (pattern, color) = match value.rsplit_once(':').and_then(|(p, c)| Some((p, parse_color(c)?))) {
+        Some((pattern, color)) if !pattern.is_empty() => (pattern, color),
+        _ => (value, Color::Yellow),
+    };
+    Ok((regex(pattern)?, color))
+}
+
+fn rule(value: &str) -> Result<(Expr, RowStyle), String> {
+    let (expr, style) = value.rsplit_once("=>").ok_or_else(|| format!("expected `EXPR => STYLE`, got `{}`", value))?;
+    Ok((self::expr(expr.trim())?, RowStyle::parse(style)?))
+}
+
+fn template(value: &str) -> Result<Template, String> {
+    Template::parse(value)
+}
+
+fn expr(value: &str) -> Result<Expr, String> {
+    Expr::parse(value).map_err(|e| e.to_string())
+}
+
+fn column_key(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((column, key)) if !column.is_empty() && !key.is_empty() => Ok((column.to_string(), key.to_string())),
+        _ => Err(format!("expected COLUMN=KEY, got `{}`", value)),
+    }
+}
+
+fn rename(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((column, header)) if !column.is_empty() && !header.is_empty() => Ok((column.to_string(), header.to_string())),
+        _ => Err(format!("expected COLUMN=HEADER, got `{}`", value)),
+    }
+}
+
+fn level(value: &str) -> Result<String, String> {
+    match value.trim() {
+        "" => Err("empty level".to_string()),
+        level => Ok(level.to_uppercase()),
+    }
+}
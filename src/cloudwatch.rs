@@ -0,0 +1,141 @@
+use crate::channel::{self, Lines};
+use aws_config::{timeout::TimeoutConfig, BehaviorVersion, Region};
+use aws_sdk_cloudwatchlogs::{error::SdkError, Client};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::{collections::HashSet, error::Error, fmt::{self, Debug}, io, sync::mpsc::Sender, thread, time::Duration};
+use tokio::runtime::{Builder, Runtime};
+
+/// Pause between two reads of the events that came in meanwhile.
+const POLL: Duration = Duration::from_secs(5);
+
+/// Longest wait for the answer to one request, after which it is made again.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest pause before a log group that could not be read is read again.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A CloudWatch Logs group whose events are read through the CloudWatch Logs API, from
+/// `rlog cloudwatch`: each event as a line starting with its timestamp and the name of its
+/// log stream, as `aws logs tail --format detailed` writes them. Credentials and the region
+/// are found as the AWS CLI finds them, through the environment, the shared config and
+/// credentials files, or the instance or task role.
+///
+/// Following, the events after the last one read are asked for every few seconds.
+#[derive(Debug, Clone)]
+pub struct LogGroup {
+    /// Name of the group, such as `/ecs/api`.
+    pub name: String,
+    /// Only read the log streams whose names start with this.
+    pub stream_prefix: Option<String>,
+    /// CloudWatch filter pattern the events must match.
+    pub filter_pattern: Option<String>,
+    pub profile: Option<String>,
+    pub region: Option<String>,
+    /// Time of the first events to read.
+    pub since: DateTime<Utc>,
+    pub follow: bool,
+}
+
+impl fmt::Display for LogGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl LogGroup {
+    /// Checks that the group can be read, and reads its events on a background thread from
+    /// then on.
+    pub fn open(&self) -> io::Result<Lines> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let timeout = TimeoutConfig::builder().operation_attempt_timeout(TIMEOUT).build();
+        let mut config = aws_config::defaults(BehaviorVersion::latest()).timeout_config(timeout);
+        if let Some(profile) = &self.profile {
+            config = config.profile_name(profile);
+        }
+        if let Some(region) = &self.region {
+            config = config.region(Region::new(region.clone()));
+        }
+        let client = Client::new(&runtime.block_on(config.load()));
+        runtime.block_on(client.filter_log_events().log_group_name(&self.name).limit(1).send())
+            .map_err(|e| io::Error::other(format!("cannot read log group {}: {}", self, describe(e))))?;
+        let (tx, lines) = channel::lines();
+        let group = self.clone();
+        thread::spawn(move || group.run(runtime, client, tx));
+        Ok(lines)
+    }
+
+    fn run(self, runtime: Runtime, client: Client, tx: Sender<Vec<u8>>) {
+        let mut backoff = Duration::from_secs(1);
+        let (mut start, mut seen) = (self.since.timestamp_millis(), HashSet::new());
+        loop {
+            let pause = match runtime.block_on(self.read(&client, &mut start, &mut seen, &tx)) {
+                Ok(()) if self.follow => {
+                    backoff = Duration::from_secs(1);
+                    POLL
+                }
+                Ok(()) => return,
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return,
+                Err(e) if !self.follow => return eprintln!("rlog: {}: {}", self, e),
+                Err(e) => {
+                    eprintln!("rlog: {}: {}, reading it again in {}s", self, e, backoff.as_secs());
+                    let pause = backoff;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    pause
+                }
+            };
+            thread::sleep(pause);
+        }
+    }
+
+    /// Sends the events from `start` (in milliseconds) on, page by page, leaving out those
+    /// `seen` before, and moves `start` to the time of the last one. The API takes the start
+    /// inclusively, so the events at that time come again the next time: `seen` holds their
+    /// ids. Fails with `BrokenPipe` once nothing reads the lines any more.
+    async fn read(&self, client: &Client, start: &mut i64, seen: &mut HashSet<String>, tx: &Sender<Vec<u8>>) -> io::Result<()> {
+        let (from, mut token) = (*start, None);
+        loop {
+            let page = client.filter_log_events()
+                .log_group_name(&self.name)
+                .set_log_stream_name_prefix(self.stream_prefix.clone())
+                .set_filter_pattern(self.filter_pattern.clone())
+                .start_time(from)
+                .set_next_token(token)
+                .send().await
+                .map_err(|e| io::Error::other(describe(e)))?;
+            for event in page.events() {
+                let (Some(time), Some(id)) = (event.timestamp(), event.event_id()) else { continue };
+                if time < *start || seen.contains(id) {
+                    continue;
+                }
+                if time > *start {
+                    (*start, *seen) = (time, HashSet::new());
+                }
+                seen.insert(id.to_string());
+                let stamp = DateTime::from_timestamp_millis(time).unwrap_or_default().to_rfc3339_opts(SecondsFormat::Millis, true);
+                let message = event.message().unwrap_or_default().trim_end_matches(['\r', '\n']);
+                let line = format!("{} {} {}\n", stamp, event.log_stream_name().unwrap_or("-"), message);
+                tx.send(line.into_bytes()).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+            }
+            match page.next_token() {
+                Some(next) => token = Some(next.to_string()),
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+/// The error AWS answered with, or else what kept the request from being answered with
+/// the errors that caused it, as the SDK's own message is only `dispatch failure` or the
+/// like.
+fn describe<E: Error + 'static, R: Debug>(e: SdkError<E, R>) -> String {
+    if let Some(e) = e.as_service_error() {
+        return e.to_string();
+    }
+    let mut text = e.to_string();
+    let mut source = e.source();
+    while let Some(e) = source {
+        text = format!("{}: {}", text, e);
+        source = e.source();
+    }
+    text
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+/// Placeholder for the variable parts of a message template.
+pub const WILDCARD: &str = "<*>";
+
+/// A group of messages sharing a template, such as `Failed to connect to <*> after <*> ms`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cluster {
+    tokens: Vec<String>,
+    /// Number of messages added to the cluster.
+    pub count: usize,
+}
+
+impl Cluster {
+    /// The template, with variable tokens shown as [`WILDCARD`].
+    pub fn template(&self) -> String {
+        self.tokens.join(" ")
+    }
+
+    /// Share of positions where `tokens` has the template's token, counting a wildcard as
+    /// matching anything.
+    fn similarity(&self, tokens: &[String]) -> f64 {
+        let same = self.tokens.iter().zip(tokens).filter(|(a, b)| *a == WILDCARD || a == b).count();
+        same as f64 / tokens.len().max(1) as f64
+    }
+}
+
+/// Groups messages by template the way Drain does: messages are split into tokens, tokens
+/// that look variable (numbers, ids, addresses) are masked right away, and a message joins
+/// the most similar cluster with the same number of tokens and the same first token if at
+/// least `threshold` of its tokens agree. Tokens that then differ become wildcards.
+#[derive(Debug, Clone)]
+pub struct Drain {
+    threshold: f64,
+    clusters: Vec<Cluster>,
+    /// Clusters by token count and first token.
+    groups: HashMap<(usize, String), Vec<usize>>,
+}
+
+impl Drain {
+    /// A miner joining messages when at least `threshold` (0 to 1) of their tokens agree
+    /// with a template; 0.5 suits most logs.
+    pub fn new(threshold: f64) -> Self {
+        Drain { threshold, clusters: Vec::new(), groups: HashMap::new() }
+    }
+
+    /// Adds a message, returning the index of the cluster it joined.
+    pub fn add(&mut self, message: &str) -> usize {
+        let tokens = tokenize(message);
+        let key = (tokens.len(), tokens.first().cloned().unwrap_or_default());
+        let candidates = self.groups.entry(key).or_default();
+        let best = candidates.iter()
+            .map(|&idx| (idx, self.clusters[idx].similarity(&tokens)))
+            .filter(|(_, similarity)| *similarity >= self.threshold)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        match best {
+            Some((idx, _)) => {
+                let cluster = &mut self.clusters[idx];
+                for (template, token) in cluster.tokens.iter_mut().zip(tokens) {
+                    if *template != token {
+                        *template = WILDCARD.to_string();
+                    }
+                }
+                cluster.count += 1;
+                idx
+            }
+            None => {
+                candidates.push(self.clusters.len());
+                self.clusters.push(Cluster { tokens, count: 1 });
+                self.clusters.len() - 1
+            }
+        }
+    }
+
+    /// The clusters found so far, in the order they were first seen.
+    pub fn clusters(&self) -> &[Cluster] {
+        &self.clusters
+    }
+}
+
+/// The template of a single message: its tokens, with those that look variable masked.
+pub fn mask(message: &str) -> String {
+    tokenize(message).join(" ")
+}
+
+/// Splits a message at whitespace, masking tokens with digits (numbers, times, ids, IP
+/// addresses, hex hashes) apart from the punctuation around them.
+fn tokenize(message: &str) -> Vec<String> {
+    message.split_whitespace().map(|token| {
+        let core = token.trim_matches(|c: char| !c.is_alphanumeric());
+        match core.chars().any(|c| c.is_ascii_digit()) {
+            true => token.replacen(core, WILDCARD, 1),
+            false => token.to_string(),
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates(drain: &Drain) -> Vec<(String, usize)> {
+        drain.clusters().iter().map(|cluster| (cluster.template(), cluster.count)).collect()
+    }
+
+    #[test]
+    fn masks_tokens_with_digits() {
+        assert_eq!(mask("Connected to 10.0.0.7:5432 in 35ms (attempt #2)"), "Connected to <*> in <*> (attempt #<*>)");
+        assert_eq!(mask("  user   bob logged in "), "user bob logged in");
+    }
+
+    #[test]
+    fn groups_messages_by_template() {
+        let mut drain = Drain::new(0.5);
+        let joined: Vec<usize> = [
+            "Failed to connect to db1 after 300 ms",
+            "Failed to connect to db2 after 450 ms",
+            "User alice logged in",
+            "User bob logged in",
+            "Failed to connect to cache after 12 ms",
+            "Disk full",
+        ].into_iter().map(|message| drain.add(message)).collect();
+        assert_eq!(joined, [0, 0, 1, 1, 0, 2]);
+        assert_eq!(templates(&drain), [
+            ("Failed to connect to <*> after <*> ms".to_string(), 3),
+            ("User <*> logged in".to_string(), 2),
+            ("Disk full".to_string(), 1),
+        ]);
+    }
+
+    #[test]
+    fn keeps_dissimilar_messages_apart() {
+        let mut drain = Drain::new(0.9);
+        drain.add("User alice logged in");
+        drain.add("User bob logged in");
+        drain.add("User carol logged out now");
+        assert_eq!(templates(&drain).len(), 3);
+    }
+}
@@ -0,0 +1,182 @@
+use clap::{Command as ClapCommand, CommandFactory};
+use crate::render;
+use crossterm::style::Color;
+use serde_derive::Deserialize;
+use std::{collections::HashMap, env, ffi::OsString, fs, path::{Path, PathBuf}};
+use toml::{Table, Value};
+
+/// Settings read from `~/.config/rlog/config.toml`.
+///
+/// Top-level keys are defaults for the command-line options of the same name (`format`,
+/// `width`, `verbose`, ...), and each `[profiles.NAME]` table holds further options applied
+/// with `--profile NAME`. Options given on the command line win over both.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// Level name to color, e.g. `INFO = "green"`, applied on top of the theme.
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
+    /// Custom themes: `[themes.NAME]` tables of level colors, based on the `dark` theme.
+    #[serde(default)]
+    pub themes: HashMap<String, HashMap<String, String>>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Table>,
+    #[serde(flatten)]
+    pub defaults: Table,
+}
+
+impl Config {
+    /// The default config path: `$XDG_CONFIG_HOME/rlog/config.toml` or `~/.config/rlog/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(config_dir()?.join("config.toml"))
+    }
+
+    /// The directory of format definition files: `~/.config/rlog/formats`.
+    pub fn formats_dir() -> Option<PathBuf> {
+        Some(config_dir()?.join("formats"))
+    }
+
+    /// Loads `path`, or the default path if `None`. A missing default file is an empty config.
+    pub fn load(path: Option<&Path>) -> Result<Config, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(_) if !required => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// The level colors of `theme`, a built-in or `[themes.NAME]` theme, with the `[colors]`
+    /// overrides applied.
+    pub fn palette(&self, theme: &str) -> Result<HashMap<String, Color>, String> {
+        let (base, custom) = match (render::theme(theme), self.themes.get(theme)) {
+            (_, Some(custom)) => (render::theme("dark"), Some(custom)),
+            (Some(base), None) => (Some(base), None),
+            (None, None) => {
+                let mut names: Vec<&str> = render::THEMES.to_vec();
+                names.extend(self.themes.keys().map(String::as_str));
+                return Err(format!("unknown theme `{}` (expected one of {})", theme, names.join(", ")));
+            }
+        };
+        let mut palette: HashMap<String, Color> = base.into_iter().flatten().map(|(level, color)| (level.to_string(), color)).collect();
+        for (level, name) in custom.into_iter().flatten().chain(&self.colors) {
+            let color = parse_color(name).ok_or_else(|| format!("unknown color `{}` for level {}", name, level))?;
+            palette.insert(level.to_uppercase(), color);
+        }
+        Ok(palette)
+    }
+
+    /// Rewrites `args` so the config defaults, then the chosen profile, come before the
+    /// options given on the command line (which therefore override them).
+    ///
+    /// Options the selected subcommand does not accept are skipped, so shared defaults such
+    /// as `width` do not break commands without a table view.
+    pub fn apply(&self, args: Vec<OsString>, profile: Option<&str>) -> Result<Vec<OsString>, String> {
+        let mut tables = vec![&self.defaults];
+        if let Some(name) = profile {
+            tables.push(self.profiles.get(name).ok_or_else(|| format!("unknown profile `{}`", name))?);
+        }
+
+        let root = crate::cli::Cli::command();
+        let sub = args.get(1).and_then(|a| a.to_str()).and_then(|a| root.find_subcommand(a)).cloned();
+        let split = if sub.is_some() { 2 } else { 1 };
+        let target = sub.as_ref().unwrap_or(&root);
+
+        let mut injected = Vec::new();
+        for table in tables {
+            for (key, value) in table {
+                if key == "colors" || key == "profiles" || key == "themes" {
+                    continue;
+                }
+                if !accepts(&root, key) {
+                    return Err(format!("unknown option `{}` in config", key));
+                }
+                // Global options such as `theme` are only declared on the root command.
+                let mut args = target.get_arguments().chain(root.get_arguments().filter(|arg| arg.is_global_set()));
+                if let Some(arg) = args.find(|arg| arg.get_long() == Some(key)) {
+                    injected.extend(option_args(key, value, arg.get_value_delimiter())?);
+                }
+            }
+        }
+
+        let mut rewritten: Vec<OsString> = args[..split.min(args.len())].to_vec();
+        rewritten.extend(injected);
+        rewritten.extend(args.into_iter().skip(split));
+        Ok(rewritten)
+    }
+}
+
+/// Parses a color name (`red`, `dark_blue`, ...), a 256-color index (`208`), or an RGB
+/// color (`#ff8800` or `rgb(255,136,0)`).
+pub fn parse_color(text: &str) -> Option<Color> {
+    let text = text.trim();
+    if let Ok(index) = text.parse::<u8>() {
+        return Some(Color::AnsiValue(index));
+    }
+    let rgb = if let Some(hex) = text.strip_prefix('#').filter(|hex| hex.len() == 6) {
+        let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+        (channel(0)?, channel(2)?, channel(4)?)
+    } else if let Some(args) = text.strip_prefix("rgb(").and_then(|rest| rest.strip_suffix(')')) {
+        let channels: Vec<u8> = args.split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()?;
+        match channels[..] {
+            [r, g, b] => (r, g, b),
+            _ => return None,
+        }
+    } else {
+        return Color::try_from(text).ok();
+    };
+    Some(Color::Rgb { r: rgb.0, g: rgb.1, b: rgb.2 })
+}
+
+/// Whether any command accepts the long option `name`.
+fn accepts(root: &ClapCommand, name: &str) -> bool {
+    accepts_here(root, name) || root.get_subcommands().any(|sub| accepts_here(sub, name))
+}
+
+fn accepts_here(command: &ClapCommand, name: &str) -> bool {
+    command.get_arguments().any(|arg| arg.get_long() == Some(name))
+}
+
+/// Turns one config entry into command-line arguments. Arrays become one delimited value
+/// for options that take lists, and repeated options otherwise.
+fn option_args(key: &str, value: &Value, delimiter: Option<char>) -> Result<Vec<OsString>, String> {
+    let flag = OsString::from(format!("--{}", key));
+    Ok(match value {
+        Value::Boolean(true) => vec![flag],
+        Value::Boolean(false) => Vec::new(),
+        Value::Array(items) if delimiter.is_some() => {
+            let items = items.iter().map(|item| scalar(key, item).map(|s| s.to_string_lossy().into_owned())).collect::<Result<Vec<_>, _>>()?;
+            vec![flag, items.join(&delimiter.unwrap().to_string()).into()]
+        }
+        Value::Array(items) => {
+            let mut args = Vec::new();
+            for item in items {
+                args.push(flag.clone());
+                args.push(scalar(key, item)?);
+            }
+            args
+        }
+        other => vec![flag, scalar(key, other)?],
+    })
+}
+
+fn scalar(key: &str, value: &Value) -> Result<OsString, String> {
+    match value {
+        Value::String(s) => Ok(s.into()),
+        Value::Integer(i) => Ok(i.to_string().into()),
+        Value::Float(f) => Ok(f.to_string().into()),
+        _ => Err(format!("unsupported value for `{}` in config", key)),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/rlog` or `~/.config/rlog`.
+fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("rlog"))
+}
@@ -0,0 +1,103 @@
+use crate::record::LogRecord;
+use std::collections::VecDeque;
+
+/// A record picked for display by a [`ContextWindow`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shown {
+    /// A record that matched the filter.
+    Match(LogRecord),
+    /// A non-matching neighbor of a match.
+    Context(LogRecord),
+    /// Records were skipped between the previous shown record and the next one.
+    Break,
+    /// The match before was repeated this many more times, see [`Dedupe`](crate::Dedupe).
+    Repeated(usize),
+}
+
+/// Selects matching records plus up to `before` and `after` neighbors of each, like
+/// `grep -B/-A`. Works on an unbounded stream by keeping only the last `before` records.
+#[derive(Debug, Clone, Default)]
+pub struct ContextWindow {
+    before: usize,
+    after: usize,
+    recent: VecDeque<LogRecord>,
+    /// Neighbors still to show after the last match.
+    after_left: usize,
+    /// Whether anything was shown yet, and whether records were dropped since.
+    shown_any: bool,
+    skipped: bool,
+}
+
+impl ContextWindow {
+    pub fn new(before: usize, after: usize) -> Self {
+        ContextWindow { before, after, ..Default::default() }
+    }
+
+    /// Whether neighbors are shown at all.
+    pub fn is_enabled(&self) -> bool {
+        self.before > 0 || self.after > 0
+    }
+
+    /// Feeds the next record and whether it matched, returning what to show in order.
+    pub fn push(&mut self, record: LogRecord, matched: bool) -> Vec<Shown> {
+        let mut shown = Vec::new();
+        if matched {
+            if self.shown_any && self.skipped && self.is_enabled() {
+                shown.push(Shown::Break);
+            }
+            shown.extend(self.recent.drain(..).map(Shown::Context));
+            shown.push(Shown::Match(record));
+            self.after_left = self.after;
+            self.shown_any = true;
+            self.skipped = false;
+        } else if self.after_left > 0 {
+            self.after_left -= 1;
+            shown.push(Shown::Context(record));
+        } else if self.before > 0 {
+            if self.recent.len() == self.before {
+                self.recent.pop_front();
+                self.skipped = true;
+            }
+            self.recent.push_back(record);
+        } else {
+            self.skipped = true;
+        }
+        shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds lines `0..count`, matching those in `matches`, and shows what comes out: `=N`
+    /// for a match, `N` for context, and `--` for a break.
+    fn show(window: &mut ContextWindow, count: usize, matches: &[usize]) -> Vec<String> {
+        (0..count).flat_map(|n| window.push(LogRecord::new(n.to_string()), matches.contains(&n))).map(|shown| match shown {
+            Shown::Match(record) => format!("={}", record.raw),
+            Shown::Context(record) => record.raw,
+            Shown::Break => "--".to_string(),
+            Shown::Repeated(count) => format!("x{}", count),
+        }).collect()
+    }
+
+    #[test]
+    fn shows_neighbors_before_and_after() {
+        assert_eq!(show(&mut ContextWindow::new(2, 1), 10, &[3, 8]), ["1", "2", "=3", "4", "--", "6", "7", "=8", "9"]);
+        assert_eq!(show(&mut ContextWindow::new(0, 2), 6, &[0]), ["=0", "1", "2"]);
+    }
+
+    #[test]
+    fn breaks_only_between_separate_groups() {
+        assert_eq!(show(&mut ContextWindow::new(1, 1), 6, &[1, 3]), ["0", "=1", "2", "=3", "4"]);
+        assert_eq!(show(&mut ContextWindow::new(1, 0), 6, &[2, 5]), ["1", "=2", "--", "4", "=5"]);
+        assert_eq!(show(&mut ContextWindow::new(1, 1), 4, &[1, 2]), ["0", "=1", "=2", "3"]);
+    }
+
+    #[test]
+    fn shows_only_matches_when_disabled() {
+        let mut window = ContextWindow::new(0, 0);
+        assert!(!window.is_enabled());
+        assert_eq!(show(&mut window, 6, &[1, 4]), ["=1", "=4"]);
+    }
+}
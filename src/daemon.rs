@@ -0,0 +1,91 @@
+use std::{env, io, process::{Command, Stdio}, time::Duration};
+
+/// Syslog facility of the messages `rlog watch` logs (daemon).
+#[cfg(unix)]
+const FACILITY: u8 = 3;
+
+/// Severity of a logged message, as numbered by syslog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error = 3,
+    Warning = 4,
+    Info = 6,
+}
+
+/// Where `rlog watch` reports what it does: syslog through `/dev/log`, which journald
+/// reads too, or stderr when there is no syslog socket.
+pub struct Log {
+    #[cfg(unix)]
+    socket: Option<std::os::unix::net::UnixDatagram>,
+}
+
+impl Log {
+    pub fn open() -> Self {
+        #[cfg(unix)]
+        {
+            let socket = std::os::unix::net::UnixDatagram::unbound().and_then(|socket| socket.connect("/dev/log").map(|()| socket)).ok();
+            Log { socket }
+        }
+        #[cfg(not(unix))]
+        Log {}
+    }
+
+    pub fn write(&self, severity: Severity, text: &str) {
+        #[cfg(unix)]
+        if let Some(socket) = &self.socket {
+            let message = format!("<{}>rlog[{}]: {}", FACILITY * 8 + severity as u8, std::process::id(), text);
+            if socket.send(message.as_bytes()).is_ok() {
+                return;
+            }
+        }
+        let _ = severity;
+        eprintln!("rlog: {}", text);
+    }
+}
+
+/// Tells systemd about the state of the service (`READY=1`, `STOPPING=1`, `WATCHDOG=1`,
+/// `STATUS=...`) when it runs as a `Type=notify` unit, through `$NOTIFY_SOCKET`. Does
+/// nothing otherwise.
+pub fn notify(state: &str) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::net::UnixDatagram;
+        let Some(path) = env::var_os("NOTIFY_SOCKET") else { return };
+        let Ok(socket) = UnixDatagram::unbound() else { return };
+        let bytes = path.as_encoded_bytes();
+        let sent = match bytes.strip_prefix(b"@") {
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+                SocketAddr::from_abstract_name(name).and_then(|addr| socket.send_to_addr(state.as_bytes(), &addr))
+            }
+            _ => socket.send_to(state.as_bytes(), &path),
+        };
+        let _ = sent;
+    }
+    #[cfg(not(unix))]
+    let _ = state;
+}
+
+/// How often systemd expects `WATCHDOG=1`, if it watches this process: half of
+/// `$WATCHDOG_USEC`, to leave some slack.
+pub fn watchdog_interval() -> Option<Duration> {
+    let pid = env::var("WATCHDOG_PID").ok();
+    if pid.is_some_and(|pid| pid != std::process::id().to_string()) {
+        return None;
+    }
+    let usec: u64 = env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(usec / 2)).filter(|interval| !interval.is_zero())
+}
+
+/// Starts this program again with the same arguments but `--daemon`, detached from the
+/// terminal: without stdin, stdout, and stderr, and in a process group of its own so that
+/// Ctrl+C and the terminal closing leave it running. Returns its process id.
+pub fn detach() -> io::Result<u32> {
+    let args: Vec<_> = env::args_os().skip(1).filter(|arg| arg != "--daemon").collect();
+    let mut command = Command::new(env::current_exe()?);
+    command.args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    Ok(command.spawn()?.id())
+}
@@ -0,0 +1,267 @@
+use crate::record::LogRecord;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::{cmp::Ordering, str::FromStr};
+
+/// Timestamp layouts with a UTC offset, tried in order.
+const ZONED_FORMATS: [&str; 4] = ["%Y-%m-%dT%H:%M:%S%.f%:z", "%Y-%m-%d %H:%M:%S%.f%:z", "%Y-%m-%dT%H:%M:%S%.f%z", "%d/%b/%Y:%H:%M:%S %z"];
+
+/// Timestamp layouts without an offset, read as local time.
+const NAIVE_FORMATS: [&str; 6] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M", "%Y/%m/%d %H:%M:%S%.f", "%Y/%m/%d %H:%M"];
+
+/// Parses a timestamp in one of the common log layouts: RFC 3339 / ISO 8601 (`T` or space
+/// separated, optional fraction and offset), RFC 2822, Apache's `01/Mar/2025:10:00:00 +0000`,
+/// syslog's `Mar  1 10:00:00` (in the current year), a slash-separated date and time, a
+/// plain date, or Unix epoch seconds, milliseconds, microseconds, or nanoseconds. Times
+/// without an offset are local.
+pub fn parse_datetime(text: &str) -> Option<DateTime<FixedOffset>> {
+    parse_datetime_in(text, Zone::Local)
+}
+
+/// Parses a timestamp like [`parse_datetime`], reading times without an offset in `zone`.
+pub fn parse_datetime_in(text: &str, zone: Zone) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+    if let Some(time) = DateTime::parse_from_rfc2822(text).ok().or_else(|| epoch(text)) {
+        return Some(time);
+    }
+    // `,123` fractions (log4j, Python logging) and a `Z` suffix are written the ISO way.
+    let text = match text.rfind(',') {
+        Some(comma) if text[comma + 1..].bytes().all(|b| b.is_ascii_digit()) => format!("{}.{}", &text[..comma], &text[comma + 1..]),
+        _ => text.to_string(),
+    };
+    let text = match text.strip_suffix(['Z', 'z']) {
+        Some(rest) => format!("{}+00:00", rest),
+        None => text,
+    };
+    if let Some(time) = ZONED_FORMATS.iter().find_map(|format| DateTime::parse_from_str(&text, format).ok()) {
+        return Some(time);
+    }
+    let naive = NAIVE_FORMATS.iter().find_map(|format| NaiveDateTime::parse_from_str(&text, format).ok())
+        .or_else(|| ["%Y-%m-%d", "%Y/%m/%d"].iter().find_map(|format| NaiveDate::parse_from_str(&text, format).ok()?.and_hms_opt(0, 0, 0)))
+        .or_else(|| without_year(&text))?;
+    zone.resolve(naive)
+}
+
+/// Parses a timestamp written in a strftime-style `format` such as `%d/%b/%Y:%H:%M:%S %z`.
+/// Times without an offset are local, and dates without a time of day are midnight.
+pub fn parse_with_format(text: &str, format: &str) -> Option<DateTime<FixedOffset>> {
+    parse_with_format_in(text, format, Zone::Local)
+}
+
+/// Parses a timestamp like [`parse_with_format`], reading times without an offset in `zone`.
+pub fn parse_with_format_in(text: &str, format: &str, zone: Zone) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+    DateTime::parse_from_str(text, format).ok()
+        .or_else(|| zone.resolve(NaiveDateTime::parse_from_str(text, format).ok()?))
+        .or_else(|| zone.resolve(NaiveDate::parse_from_str(text, format).ok()?.and_hms_opt(0, 0, 0)?))
+}
+
+/// The parsed DateTime of a record: the time set when it was read, or else the DateTime
+/// column read by [`parse_datetime`].
+pub fn record_time(record: &LogRecord) -> Option<DateTime<FixedOffset>> {
+    record.time.or_else(|| parse_datetime(record.datetime()))
+}
+
+/// Reads a Unix timestamp, telling the unit from the number of digits (10 for seconds up to
+/// 19 for nanoseconds). Seconds may have a fraction.
+fn epoch(text: &str) -> Option<DateTime<FixedOffset>> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) || (!fraction.is_empty() && whole.len() > 10) {
+        return None;
+    }
+    let number: i64 = whole.parse().ok()?;
+    let time = match whole.len() {
+        9 | 10 => {
+            let nanos = format!("{:0<9}", fraction).get(..9)?.parse().ok()?;
+            DateTime::from_timestamp(number, nanos)
+        }
+        12 | 13 => DateTime::from_timestamp_millis(number),
+        15 | 16 => DateTime::from_timestamp_micros(number),
+        18 | 19 => Some(DateTime::<Utc>::from_timestamp_nanos(number)),
+        _ => None,
+    };
+    Some(time?.fixed_offset())
+}
+
+/// Reads a syslog timestamp, taking the most recent year in which it is not in the future.
+fn without_year(text: &str) -> Option<NaiveDateTime> {
+    let now = Local::now().naive_local();
+    let parse = |year: i32| NaiveDateTime::parse_from_str(&format!("{} {}", year, text), "%Y %b %e %H:%M:%S%.f").ok();
+    let time = parse(now.year())?;
+    if time > now + Duration::days(1) { parse(now.year() - 1) } else { Some(time) }
+}
+
+/// A time zone chosen with `--tz`: the system's local zone or an IANA zone such as
+/// `Europe/Berlin`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zone {
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    /// Parses `local`, `UTC`, or an IANA zone name.
+    fn from_str(text: &str) -> Result<Zone, String> {
+        match text {
+            _ if text.eq_ignore_ascii_case("local") => Ok(Zone::Local),
+            _ if text.eq_ignore_ascii_case("utc") => Ok(Zone::Named(Tz::UTC)),
+            _ => text.parse::<Tz>().map(Zone::Named).map_err(|_| format!("unknown time zone `{}`", text)),
+        }
+    }
+}
+
+impl Zone {
+    /// The instant a wall-clock time in this zone denotes; the earlier one when clocks go back.
+    pub fn resolve(self, naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Zone::Local => Some(Local.from_local_datetime(&naive).earliest()?.fixed_offset()),
+            Zone::Named(tz) => Some(tz.from_local_datetime(&naive).earliest()?.fixed_offset()),
+        }
+    }
+
+    /// The wall-clock time in this zone at `time`.
+    pub fn convert(self, time: &DateTime<FixedOffset>) -> NaiveDateTime {
+        match self {
+            Zone::Local => time.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => time.with_timezone(&tz).naive_local(),
+        }
+    }
+}
+
+/// Parses a point in time relative to `now`: `now`, `today`, `yesterday`, or an amount of
+/// seconds, minutes, hours, days, or weeks ago (`15m`, `2h`, `2 hours ago`). Days start at
+/// midnight in `zone`.
+pub fn parse_relative(text: &str, now: DateTime<FixedOffset>, zone: Zone) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim().to_lowercase();
+    let midnight = |days: i64| zone.resolve((zone.convert(&now).date() - Duration::days(days)).and_hms_opt(0, 0, 0)?);
+    match text.as_str() {
+        "now" => return Some(now),
+        "today" => return midnight(0),
+        "yesterday" => return midnight(1),
+        _ => {}
+    }
+    let text = text.strip_suffix("ago").unwrap_or(&text).trim_end();
+    now.checked_sub_signed(parse_duration(text)?)
+}
+
+/// Parses an amount of seconds, minutes, hours, days, or weeks such as `30s`, `5m`, or
+/// `2 hours`.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim().to_lowercase();
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let amount: i64 = text[..split].parse().ok()?;
+    let unit = match text[split..].trim_start() {
+        "s" | "sec" | "secs" | "second" | "seconds" => Duration::seconds(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::minutes(1),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Duration::hours(1),
+        "d" | "day" | "days" => Duration::days(1),
+        "w" | "week" | "weeks" => Duration::weeks(1),
+        _ => return None,
+    };
+    unit.checked_mul(i32::try_from(amount).ok()?)
+}
+
+/// A `--since`/`--until` limit on the DateTime column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeBound {
+    /// A point in time; records whose DateTime cannot be parsed are outside the bound.
+    At(DateTime<FixedOffset>),
+    /// Text that is not a recognized date, compared with the DateTime column as a string.
+    Text(String),
+}
+
+impl TimeBound {
+    /// Parses a relative or absolute time, reading times without an offset in `zone`, and
+    /// falls back to plain text.
+    pub fn parse(text: &str, zone: Zone) -> TimeBound {
+        let now = Local::now().fixed_offset();
+        match parse_relative(text, now, zone).or_else(|| parse_datetime_in(text, zone)) {
+            Some(time) => TimeBound::At(time),
+            None => TimeBound::Text(text.to_string()),
+        }
+    }
+
+    /// How a record's DateTime, given as text and as parsed by [`parse_datetime`], compares
+    /// with the bound.
+    pub fn compare(&self, text: &str, time: Option<&DateTime<FixedOffset>>) -> Option<Ordering> {
+        match self {
+            TimeBound::At(bound) => time.map(|time| time.cmp(bound)),
+            TimeBound::Text(bound) => Some(text.cmp(bound.as_str())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    const UTC: Zone = Zone::Named(Tz::UTC);
+
+    fn utc(text: &str) -> Option<String> {
+        parse_datetime_in(text, UTC).map(|time| time.to_rfc3339())
+    }
+
+    #[test]
+    fn tells_epoch_units_from_digit_counts() {
+        assert_eq!(utc("1740823200").as_deref(), Some("2025-03-01T10:00:00+00:00"));
+        assert_eq!(utc("1740823200.25").as_deref(), Some("2025-03-01T10:00:00.250+00:00"));
+        assert_eq!(utc("1740823200123").as_deref(), Some("2025-03-01T10:00:00.123+00:00"));
+        assert_eq!(utc("1740823200123456").as_deref(), Some("2025-03-01T10:00:00.123456+00:00"));
+        assert_eq!(utc("1740823200123456789").as_deref(), Some("2025-03-01T10:00:00.123456789+00:00"));
+        assert_eq!(utc("999999999").as_deref(), Some("2001-09-09T01:46:39+00:00"));
+        // Other lengths are more likely ids or counts than times.
+        assert_eq!(utc("12345"), None);
+        assert_eq!(utc("17408232001"), None);
+        assert_eq!(utc("1740823200123.5"), None);
+    }
+
+    #[test]
+    fn reads_common_layouts() {
+        for text in ["2025-03-01T10:00:00Z", "2025-03-01 10:00:00", "2025-03-01T11:00:00+01:00", "2025-03-01 10:00:00,000", "01/Mar/2025:10:00:00 +0000", "Sat, 01 Mar 2025 10:00:00 +0000", "2025/03/01 10:00:00"] {
+            assert_eq!(parse_datetime_in(text, UTC).map(|time| time.timestamp()), Some(1740823200), "{}", text);
+        }
+        assert_eq!(utc("2025-03-01").as_deref(), Some("2025-03-01T00:00:00+00:00"));
+        assert_eq!(parse_datetime_in("2025-03-01 11:00", "Europe/Berlin".parse().unwrap()).map(|time| time.timestamp()), Some(1740823200));
+        assert_eq!(utc("not a time"), None);
+    }
+
+    #[test]
+    fn yearless_times_are_not_in_the_future() {
+        let now = Local::now().naive_local();
+        let past = now - Duration::days(2);
+        let text = past.format("%b %e %H:%M:%S").to_string();
+        assert_eq!(parse_datetime(&text).map(|time| time.naive_local()), past.with_nanosecond(0));
+        // Days ahead are taken for last year's.
+        let ahead = now + Duration::days(3);
+        let time = parse_datetime(&ahead.format("%b %e %H:%M:%S").to_string()).unwrap();
+        assert_eq!(time.year(), ahead.year() - 1);
+        assert_eq!(parse_datetime_in("Mar  1 10:00:00.250", UTC).map(|time| time.format("%m-%d %H:%M:%S%.3f").to_string()).as_deref(), Some("03-01 10:00:00.250"));
+    }
+
+    #[test]
+    fn reads_relative_times() {
+        let now = DateTime::parse_from_rfc3339("2025-03-01T10:30:00+01:00").unwrap();
+        let relative = |text: &str| parse_relative(text, now, UTC).map(|time| time.to_rfc3339());
+        assert_eq!(relative("now").as_deref(), Some("2025-03-01T10:30:00+01:00"));
+        assert_eq!(relative("15m").as_deref(), Some("2025-03-01T10:15:00+01:00"));
+        assert_eq!(relative("2 hours ago").as_deref(), Some("2025-03-01T08:30:00+01:00"));
+        assert_eq!(relative("1w").as_deref(), Some("2025-02-22T10:30:00+01:00"));
+        assert_eq!(relative("Today").as_deref(), Some("2025-03-01T00:00:00+00:00"));
+        assert_eq!(relative("yesterday").as_deref(), Some("2025-02-28T00:00:00+00:00"));
+        assert_eq!(relative("5 fortnights"), None);
+        assert_eq!(relative("2025-03-01"), None);
+    }
+
+    #[test]
+    fn bounds_fall_back_to_text() {
+        assert_eq!(TimeBound::parse("2025-03-01T10:00:00Z", UTC), TimeBound::At(DateTime::parse_from_rfc3339("2025-03-01T10:00:00Z").unwrap()));
+        let bound = TimeBound::parse("build-17", UTC);
+        assert_eq!(bound, TimeBound::Text("build-17".into()));
+        assert_eq!(bound.compare("build-18", None), Some(Ordering::Greater));
+        assert_eq!(TimeBound::parse("1h", UTC).compare("", None), None);
+    }
+}
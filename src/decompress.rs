@@ -0,0 +1,54 @@
+use std::{fs::File, io::{self, Read, Seek, SeekFrom}, path::Path};
+
+/// Compression formats that inputs are transparently decompressed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Identifies the compression of `file` from its magic bytes, falling back to the
+    /// extension of `path` for files too short to tell. Leaves the file at its start.
+    pub fn detect(path: &Path, file: &mut File) -> io::Result<Option<Compression>> {
+        let mut magic = [0; 4];
+        let mut read = 0;
+        while read < magic.len() {
+            match file.read(&mut magic[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        file.seek(SeekFrom::Start(0))?;
+
+        if read >= 2 && magic[..2] == [0x1f, 0x8b] {
+            return Ok(Some(Compression::Gzip));
+        }
+        if read == 4 && magic == [0x28, 0xb5, 0x2f, 0xfd] {
+            return Ok(Some(Compression::Zstd));
+        }
+        if read >= 3 && &magic[..3] == b"BZh" {
+            return Ok(Some(Compression::Bzip2));
+        }
+        if read == 4 {
+            return Ok(None);
+        }
+        Ok(match path.extension().and_then(|e| e.to_str()) {
+            Some("gz") => Some(Compression::Gzip),
+            Some("zst" | "zstd") => Some(Compression::Zstd),
+            Some("bz2") => Some(Compression::Bzip2),
+            _ => None,
+        })
+    }
+
+    /// Wraps `file` in a streaming decoder.
+    pub fn decoder(self, file: File) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            // Multi-member so concatenated archives (`cat a.gz b.gz`) are read completely.
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+        })
+    }
+}
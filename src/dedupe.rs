@@ -0,0 +1,97 @@
+use crate::{cluster::mask, context::Shown, record::LogRecord};
+
+/// Collapses runs of matches with the same message into the first of them followed by a
+/// [`Shown::Repeated`] count, like syslogd's "last message repeated N times". Matches only
+/// repeat one another if they also share their level and input.
+#[derive(Debug, Clone, Default)]
+pub struct Dedupe {
+    /// Compare messages by template (see [`mask`]) rather than exactly, so that retries
+    /// differing only in numbers or ids collapse too.
+    template: bool,
+    /// What the last match shown is compared by.
+    last: Option<(Option<String>, String, String)>,
+    /// Matches swallowed since the last one shown.
+    repeated: usize,
+}
+
+impl Dedupe {
+    pub fn new(template: bool) -> Self {
+        Dedupe { template, ..Default::default() }
+    }
+
+    /// Feeds the next entry to show, returning what to show instead, in order.
+    pub fn push(&mut self, shown: Shown) -> Vec<Shown> {
+        let key = match &shown {
+            Shown::Match(record) if !record.malformed => Some(self.key(record)),
+            _ => None,
+        };
+        if key.is_some() && key == self.last {
+            self.repeated += 1;
+            return Vec::new();
+        }
+        let mut out: Vec<Shown> = self.finish().into_iter().collect();
+        self.last = key;
+        out.push(shown);
+        out
+    }
+
+    /// The count of repeats still held back, once no more entries follow.
+    pub fn finish(&mut self) -> Option<Shown> {
+        let repeated = std::mem::take(&mut self.repeated);
+        (repeated > 0).then_some(Shown::Repeated(repeated))
+    }
+
+    fn key(&self, record: &LogRecord) -> (Option<String>, String, String) {
+        let message = record.get("Message").unwrap_or(&record.raw);
+        let message = if self.template { mask(message) } else { message.to_string() };
+        (record.source.as_deref().map(str::to_string), record.level().to_string(), message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(source: &str, level: &str, message: &str) -> Shown {
+        let mut record = LogRecord::new(message);
+        record.push("Level", level);
+        record.push("Message", message);
+        record.source = Some(source.into());
+        Shown::Match(record)
+    }
+
+    fn messages(shown: &[Shown]) -> Vec<String> {
+        shown.iter().map(|shown| match shown {
+            Shown::Match(record) | Shown::Context(record) => record.get("Message").unwrap_or_default().to_string(),
+            Shown::Break => "--".to_string(),
+            Shown::Repeated(count) => format!("x{}", count),
+        }).collect()
+    }
+
+    fn run(dedupe: &mut Dedupe, entries: Vec<Shown>) -> Vec<String> {
+        let mut shown: Vec<Shown> = entries.into_iter().flat_map(|entry| dedupe.push(entry)).collect();
+        shown.extend(dedupe.finish());
+        messages(&shown)
+    }
+
+    #[test]
+    fn collapses_runs_of_the_same_message() {
+        let entries = vec![entry("a", "ERROR", "timeout"), entry("a", "ERROR", "timeout"), entry("a", "ERROR", "timeout"), entry("a", "INFO", "ok"), entry("a", "ERROR", "timeout")];
+        assert_eq!(run(&mut Dedupe::new(false), entries), ["timeout", "x2", "ok", "timeout"]);
+    }
+
+    #[test]
+    fn keeps_levels_inputs_and_context_apart() {
+        let mut context = LogRecord::new("timeout");
+        context.push("Message", "timeout");
+        let entries = vec![entry("a", "ERROR", "timeout"), entry("a", "WARNING", "timeout"), entry("b", "WARNING", "timeout"), Shown::Context(context), entry("b", "WARNING", "timeout")];
+        assert_eq!(run(&mut Dedupe::new(false), entries), ["timeout", "timeout", "timeout", "timeout", "timeout"]);
+    }
+
+    #[test]
+    fn compares_by_template_when_asked() {
+        let entries = || vec![entry("a", "WARNING", "retry 1 of 5 after 200ms"), entry("a", "WARNING", "retry 2 of 5 after 400ms"), entry("a", "WARNING", "retry 3 of 5 after 800ms")];
+        assert_eq!(run(&mut Dedupe::new(true), entries()), ["retry 1 of 5 after 200ms", "x2"]);
+        assert_eq!(run(&mut Dedupe::new(false), entries()).len(), 3);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::channel::{self, Lines};
+use bollard::{container::LogOutput, query_parameters::{LogsOptions, LogsOptionsBuilder}, Docker};
+use chrono::{DateTime, FixedOffset};
+use futures_util::StreamExt;
+use rlog::parse_datetime;
+use std::{fmt, io, sync::mpsc::Sender, thread, time::Duration};
+use tokio::runtime::{Builder, Runtime};
+
+/// Longest pause before the logs of a stopped container are asked for again.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A container whose output is read through the Docker Engine API, from `rlog docker`: its
+/// stdout and stderr lines, each starting with its timestamp as `docker logs --timestamps`
+/// writes them. The daemon is found as the `docker` CLI finds it, through `DOCKER_HOST` or
+/// else the local socket (named pipe on Windows).
+///
+/// Following, the log stream ends when the container stops; it is asked for again, with
+/// growing pauses, from the last line read on.
+#[derive(Debug, Clone)]
+pub struct Container {
+    /// Name or id of the container.
+    pub name: String,
+    pub since: Option<DateTime<FixedOffset>>,
+    /// Start with only the last N lines instead of all of them.
+    pub last: Option<usize>,
+    pub follow: bool,
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl Container {
+    /// Checks that the container exists, and reads its logs on a background thread from
+    /// then on.
+    pub fn open(&self) -> io::Result<Lines> {
+        let failed = |e: bollard::errors::Error| io::Error::other(format!("cannot read the logs of container {}: {}", self, e));
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let docker = {
+            let _context = runtime.enter();
+            Docker::connect_with_defaults().map_err(failed)?
+        };
+        runtime.block_on(docker.inspect_container(&self.name, None)).map_err(failed)?;
+        let (tx, lines) = channel::lines();
+        let container = self.clone();
+        thread::spawn(move || container.run(runtime, docker, tx));
+        Ok(lines)
+    }
+
+    fn run(self, runtime: Runtime, docker: Docker, tx: Sender<Vec<u8>>) {
+        let (mut backoff, mut last) = (Duration::from_secs(1), None);
+        let (mut since, mut tail) = (self.since.map_or(0, |since| since.timestamp()), self.last.map_or_else(|| "all".to_string(), |n| n.to_string()));
+        loop {
+            let options = LogsOptionsBuilder::new().stdout(true).stderr(true).timestamps(true).follow(self.follow)
+                .since(since.try_into().unwrap_or(i32::MAX)).tail(&tail).build();
+            let (read, failure) = match runtime.block_on(self.pump(&docker, options, &mut last, &tx)) {
+                Ok(read) => (read, String::new()),
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return,
+                Err(e) => (0, format!(" ({})", e)),
+            };
+            if !self.follow {
+                if !failure.is_empty() {
+                    eprintln!("rlog: {}: log stream ended{}", self, failure);
+                }
+                return;
+            }
+            if read > 0 {
+                backoff = Duration::from_secs(1);
+            }
+            eprintln!("rlog: {}: log stream ended{}, reading it again in {}s", self, failure, backoff.as_secs());
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            // The API takes whole seconds, so lines up to the last one read come again.
+            if let Some(time) = last {
+                (since, tail) = (time.timestamp(), "all".to_string());
+            }
+        }
+    }
+
+    /// Sends the lines of one log stream until it ends, leaving out those not after `last`,
+    /// the time of the last line sent before, and keeping it up to date. Returns how many
+    /// lines were sent, or a `BrokenPipe` error once nothing reads them any more.
+    async fn pump(&self, docker: &Docker, options: LogsOptions, last: &mut Option<DateTime<FixedOffset>>, tx: &Sender<Vec<u8>>) -> io::Result<usize> {
+        let mut logs = docker.logs(&self.name, Some(options));
+        // Output of containers with a terminal comes in arbitrary pieces, not line by line.
+        let (mut partial, mut sent) = (Vec::new(), 0);
+        while let Some(output) = logs.next().await {
+            let output = output.map_err(io::Error::other)?;
+            let message = match &output {
+                LogOutput::StdOut { message } | LogOutput::StdErr { message } | LogOutput::Console { message } => message,
+                LogOutput::StdIn { .. } => continue,
+            };
+            partial.extend_from_slice(message);
+            while let Some(end) = memchr::memchr(b'\n', &partial) {
+                let line: Vec<u8> = partial.drain(..=end).collect();
+                let time = line.split(|&b| b == b' ').next().and_then(|stamp| parse_datetime(&String::from_utf8_lossy(stamp)));
+                if time.is_some_and(|time| last.is_some_and(|last| time <= last)) {
+                    continue;
+                }
+                *last = time.or(*last);
+                tx.send(line).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+                sent += 1;
+            }
+        }
+        if !partial.is_empty() {
+            partial.push(b'\n');
+            tx.send(partial).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+            sent += 1;
+        }
+        Ok(sent)
+    }
+}
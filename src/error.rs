@@ -0,0 +1,61 @@
+use std::{fmt, io, path::PathBuf, process::ExitCode};
+
+/// Exit status when the output could be written but no entry matched.
+pub const NO_MATCH: u8 = 1;
+
+/// Exit status when an input cannot be read or the output cannot be written. Invalid
+/// command lines and config files exit with 2, as clap does.
+pub const IO_FAILURE: u8 = 3;
+
+/// Why a command stopped before finishing.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading an input failed; the message names the input.
+    Input(io::Error),
+    /// Writing the output failed, to `path` or else to stdout.
+    Output { path: Option<PathBuf>, source: io::Error },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// An output error for the file at `path`, or stdout.
+    pub fn output(path: Option<&PathBuf>, source: io::Error) -> Self {
+        Error::Output { path: path.cloned(), source }
+    }
+
+    /// Whether the reader of stdout went away, as with `rlog query app.log | head`; that is
+    /// not worth a message.
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, Error::Output { path: None, source } if source.kind() == io::ErrorKind::BrokenPipe)
+    }
+
+    /// The documented exit status for this error.
+    pub fn exit_code(&self) -> ExitCode {
+        if self.is_broken_pipe() { ExitCode::SUCCESS } else { ExitCode::from(IO_FAILURE) }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Input(source) => write!(f, "{}", source),
+            Error::Output { path: Some(path), source } => write!(f, "cannot write {}: {}", path.display(), source),
+            Error::Output { path: None, source } => write!(f, "cannot write output: {}", source),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Input(source) | Error::Output { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Error::Input(source)
+    }
+}
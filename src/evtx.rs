@@ -0,0 +1,127 @@
+use crate::channel::{self, Lines};
+use chrono::{DateTime, FixedOffset};
+use evtx::EvtxParser;
+use std::{env, fmt, fs, io, path::{Path, PathBuf}, sync::mpsc::Sender, thread, time::Duration};
+
+/// Pause between two reads of a followed event log.
+const POLL: Duration = Duration::from_secs(1);
+
+/// Longest pause before an event log that could not be read is read again.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// One event record: its record ID, its time in milliseconds, and its JSON line.
+type Record = (u64, i64, Vec<u8>);
+
+/// A Windows event log file (`.evtx`) read with the evtx crate: the files given to `rlog
+/// evtx`, and those behind the channels `rlog winlog` reads. Each record becomes a JSON line
+/// as `evtx_dump -o jsonl` writes it, `{"Event": {"System": {...}, "EventData": {...}}}`,
+/// for [`rlog::WinlogParser`].
+///
+/// Event logs are circular, so records are read in the order of their record IDs. Following,
+/// the file is read again every second for the records after the last one read, and from
+/// the start once it has been cleared.
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    /// What the log is called: the path of the file, or the name of the channel.
+    pub label: String,
+    pub path: PathBuf,
+    /// Leave out the records before this.
+    pub since: Option<DateTime<FixedOffset>>,
+    /// Start with only the last N records instead of all of them.
+    pub last: Option<usize>,
+    pub follow: bool,
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+impl EventLog {
+    /// The `.evtx` file at `path`, read once in full.
+    pub fn file(path: &Path) -> Self {
+        EventLog { label: path.display().to_string(), path: path.to_path_buf(), since: None, last: None, follow: false }
+    }
+
+    /// The file the event log service keeps `channel` in, such as `System` or
+    /// `Microsoft-Windows-PowerShell/Operational`, read in full.
+    pub fn channel(channel: &str) -> Self {
+        let root = env::var_os("SystemRoot").map_or_else(|| PathBuf::from(r"C:\Windows"), PathBuf::from);
+        let path = root.join(r"System32\winevt\Logs").join(format!("{}.evtx", channel.replace('/', "%4")));
+        EventLog { label: channel.to_string(), path, since: None, last: None, follow: false }
+    }
+
+    /// Reads the records asked for, failing if the file is not an event log, and sends them
+    /// on a background thread, from where the new ones are sent when following.
+    pub fn open(&self) -> io::Result<Lines> {
+        let (mut records, broken) = self.records()?;
+        if broken > 0 {
+            eprintln!("rlog: {}: {} record{} could not be read", self, broken, if broken == 1 { "" } else { "s" });
+        }
+        let newest = records.last().map_or(0, |record| record.0);
+        if let Some(since) = self.since {
+            records.retain(|record| record.1 >= since.timestamp_millis());
+        }
+        if let Some(last) = self.last {
+            records.drain(..records.len().saturating_sub(last));
+        }
+        let (tx, lines) = channel::lines();
+        let log = self.clone();
+        thread::spawn(move || log.run(records, newest, tx));
+        Ok(lines)
+    }
+
+    fn run(self, mut records: Vec<Record>, mut newest: u64, tx: Sender<Vec<u8>>) {
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            for (_, _, line) in records.drain(..) {
+                if tx.send(line).is_err() {
+                    return;
+                }
+            }
+            if !self.follow {
+                return;
+            }
+            thread::sleep(POLL);
+            match self.records() {
+                Ok((read, _)) => {
+                    backoff = Duration::from_secs(1);
+                    // Record IDs start again from 1 when the log is cleared.
+                    if read.last().is_some_and(|record| record.0 < newest) {
+                        newest = 0;
+                    }
+                    records = read.into_iter().filter(|record| record.0 > newest).collect();
+                    newest = records.last().map_or(newest, |record| record.0);
+                }
+                Err(e) => {
+                    eprintln!("rlog: {}: {}, reading it again in {}s", self, e, backoff.as_secs());
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /// Reads every record of the file, by record ID, and counts those that cannot be read,
+    /// which are left out.
+    fn records(&self) -> io::Result<(Vec<Record>, usize)> {
+        let failed = |e: &dyn fmt::Display| format!("cannot read {}: {}", self.path.display(), e);
+        // Read at once, so that the service writing to a live log cannot change it midway.
+        let file = fs::read(&self.path).map_err(|e| io::Error::new(e.kind(), failed(&e)))?;
+        let mut parser = EvtxParser::from_buffer(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, failed(&e)))?;
+        let (mut records, mut broken) = (Vec::new(), 0);
+        for record in parser.records_json_value() {
+            match record {
+                Ok(record) => {
+                    let mut line = serde_json::to_vec(&record.data)?;
+                    line.push(b'\n');
+                    records.push((record.event_record_id, record.timestamp.as_millisecond(), line));
+                }
+                Err(_) => broken += 1,
+            }
+        }
+        records.sort_by_key(|record| record.0);
+        Ok((records, broken))
+    }
+}
@@ -0,0 +1,613 @@
+use crate::record::{severity_rank, LogRecord};
+use regex::Regex;
+use serde_json::Value;
+use std::{borrow::Cow, cell::OnceCell, cmp::Ordering, fmt};
+
+/// A boolean filter expression over a record's columns, e.g.
+/// `Level == "ERROR" && Message =~ "timeout" && Data.user_id != 42`.
+///
+/// Operands are column names, `Data.key.path` (with `[0]` or `["key"]` steps) into the
+/// JSON Data column, string and number literals, `true`, `false`, and `null`. Operators
+/// are `==`, `!=`, `<`, `<=`, `>`, `>=`, `=~` and `!~` (regex match), combined with `&&`,
+/// `||`, `!` (or `and`, `or`, `not`) and parentheses.
+///
+/// Values compare as numbers when both sides look numeric, levels compare by severity when
+/// both sides are levels, and otherwise as strings.
+#[derive(Debug, Clone)]
+pub struct Expr {
+    source: String,
+    node: Node,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Operand, Op, Operand),
+    Match(Operand, Regex, bool),
+    Truthy(Operand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A value reference inside an expression.
+#[derive(Debug, Clone)]
+pub(crate) enum Operand {
+    Literal(Val<'static>),
+    Column(String),
+    Data(Vec<PathStep>),
+}
+
+/// One step of a path into JSON data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathStep {
+    Key(String),
+    Index(usize),
+}
+
+/// A value produced while evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Val<'a> {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(Cow<'a, str>),
+}
+
+/// An error in an expression, with the byte offset where it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExprError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+impl Expr {
+    /// Parses an expression.
+    pub fn parse(source: &str) -> Result<Expr, ExprError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0, end: source.len() };
+        let node = parser.or()?;
+        if let Some((token, at)) = parser.tokens.get(parser.pos) {
+            return Err(ExprError { position: *at, message: format!("unexpected {}", token) });
+        }
+        Ok(Expr { source: source.to_string(), node })
+    }
+
+    /// The expression as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the expression against a record.
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        let ctx = Context::new(record);
+        eval(&self.node, &ctx)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// A jq-style query into the JSON Data column, such as `.request.headers["x-request-id"]`,
+/// optionally followed by a test like `.status >= 500` or `.path =~ "^/api"`.
+#[derive(Debug, Clone)]
+pub struct DataQuery {
+    source: String,
+    path: Vec<PathStep>,
+    test: Option<Node>,
+}
+
+impl DataQuery {
+    /// Parses a query.
+    pub fn parse(source: &str) -> Result<DataQuery, ExprError> {
+        let mut tokens = tokenize(source)?;
+        match tokens.first() {
+            Some((Token::Dot, _)) => {
+                // `.["key"]` and a lone `.` start without a key.
+                if matches!(tokens.get(1), None | Some((Token::LBracket | Token::Op(_), _))) {
+                    tokens.remove(0);
+                }
+            }
+            Some((Token::LBracket, _)) => {}
+            _ => return Err(ExprError { position: 0, message: "a query starts with `.`".into() }),
+        }
+        let mut parser = Parser { tokens, pos: 0, end: source.len() };
+        let path = parser.path()?;
+        let test = match parser.peek() {
+            None => None,
+            Some(_) => Some(parser.comparison_with(Operand::Data(path.clone()))?),
+        };
+        if let Some((token, at)) = parser.tokens.get(parser.pos) {
+            return Err(ExprError { position: *at, message: format!("unexpected {}", token) });
+        }
+        Ok(DataQuery { source: source.to_string(), path, test })
+    }
+
+    /// The query as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the query tests records rather than only extracting a value.
+    pub fn is_test(&self) -> bool {
+        self.test.is_some()
+    }
+
+    /// The extracted value as column text, or `None` if the record has no such value.
+    pub fn value(&self, record: &LogRecord) -> Option<String> {
+        self.value_in(Context::new(record).data()?)
+    }
+
+    /// Like [`value`](Self::value), for a Data column that is already decoded.
+    pub fn value_in(&self, data: &Value) -> Option<String> {
+        let value = lookup(data, &self.path)?;
+        (!value.is_null()).then(|| crate::parser::scalar_text(value))
+    }
+
+    /// Whether the record passes the test, or has the value if the query has no test.
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        match &self.test {
+            Some(test) => eval(test, &Context::new(record)),
+            None => self.value(record).is_some(),
+        }
+    }
+}
+
+impl fmt::Display for DataQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// A record being evaluated, with its Data column decoded at most once.
+pub(crate) struct Context<'a> {
+    pub record: &'a LogRecord,
+    data: OnceCell<Option<Value>>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(record: &'a LogRecord) -> Self {
+        Context { record, data: OnceCell::new() }
+    }
+
+    /// The Data column decoded as JSON, if it is valid JSON.
+    pub fn data(&self) -> Option<&Value> {
+        self.data.get_or_init(|| self.record.get("Data").and_then(|d| serde_json::from_str(d).ok())).as_ref()
+    }
+
+    pub fn value(&self, operand: &Operand) -> Val<'_> {
+        match operand {
+            Operand::Literal(v) => v.clone(),
+            Operand::Column(name) => match self.record.get(name) {
+                Some(value) => Val::Str(Cow::Borrowed(value)),
+                // Bare level names such as `ERROR` stand for themselves.
+                None if severity_rank(name).is_some() => Val::Str(Cow::Owned(name.clone())),
+                None => Val::Null,
+            },
+            Operand::Data(path) => match self.data().and_then(|data| lookup(data, path)) {
+                Some(value) => Val::from_json(value),
+                None => Val::Null,
+            },
+        }
+    }
+}
+
+/// Follows `path` through nested JSON objects and arrays.
+pub fn lookup<'v>(value: &'v Value, path: &[PathStep]) -> Option<&'v Value> {
+    path.iter().try_fold(value, |value, step| match step {
+        PathStep::Key(key) => value.get(key),
+        PathStep::Index(idx) => value.get(idx),
+    })
+}
+
+impl<'a> Val<'a> {
+    fn from_json(value: &'a Value) -> Self {
+        match value {
+            Value::Null => Val::Null,
+            Value::Bool(b) => Val::Bool(*b),
+            Value::Number(n) => n.as_f64().map_or(Val::Null, Val::Num),
+            Value::String(s) => Val::Str(Cow::Borrowed(s)),
+            other => Val::Str(Cow::Owned(other.to_string())),
+        }
+    }
+
+    pub fn as_num(&self) -> Option<f64> {
+        match self {
+            Val::Num(n) => Some(*n),
+            Val::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            Val::Null => Cow::Borrowed(""),
+            Val::Bool(b) => Cow::Owned(b.to_string()),
+            Val::Num(n) => Cow::Owned(n.to_string()),
+            Val::Str(s) => Cow::Borrowed(s),
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Val::Null => false,
+            Val::Bool(b) => *b,
+            Val::Num(n) => *n != 0.0,
+            Val::Str(s) => !s.is_empty(),
+        }
+    }
+}
+
+/// Orders two values: numerically, then (for ordering operators) by severity, then as text.
+/// `None` if either is null.
+fn compare(a: &Val, b: &Val, by_severity: bool) -> Option<Ordering> {
+    if matches!(a, Val::Null) || matches!(b, Val::Null) {
+        return None;
+    }
+    if let (Some(x), Some(y)) = (a.as_num(), b.as_num()) {
+        return x.partial_cmp(&y);
+    }
+    if let (Val::Bool(x), Val::Bool(y)) = (a, b) {
+        return Some(x.cmp(y));
+    }
+    let (x, y) = (a.text(), b.text());
+    if let (true, Some(x), Some(y)) = (by_severity, severity_rank(&x), severity_rank(&y)) {
+        return Some(x.cmp(&y));
+    }
+    Some(x.cmp(&y))
+}
+
+fn eval(node: &Node, ctx: &Context) -> bool {
+    match node {
+        Node::Or(a, b) => eval(a, ctx) || eval(b, ctx),
+        Node::And(a, b) => eval(a, ctx) && eval(b, ctx),
+        Node::Not(a) => !eval(a, ctx),
+        Node::Truthy(a) => ctx.value(a).truthy(),
+        Node::Match(a, regex, negate) => {
+            let value = ctx.value(a);
+            let matched = !matches!(value, Val::Null) && regex.is_match(&value.text());
+            matched != *negate
+        }
+        Node::Compare(a, op, b) => {
+            let (a, b) = (ctx.value(a), ctx.value(b));
+            let is_null = |v: &Val| matches!(v, Val::Null);
+            match (op, compare(&a, &b, !matches!(op, Op::Eq | Op::Ne))) {
+                (Op::Eq, None) => is_null(&a) && is_null(&b),
+                (Op::Ne, None) => !(is_null(&a) && is_null(&b)),
+                (_, None) => false,
+                (Op::Eq, Some(o)) => o == Ordering::Equal,
+                (Op::Ne, Some(o)) => o != Ordering::Equal,
+                (Op::Lt, Some(o)) => o == Ordering::Less,
+                (Op::Le, Some(o)) => o != Ordering::Greater,
+                (Op::Gt, Some(o)) => o == Ordering::Greater,
+                (Op::Ge, Some(o)) => o != Ordering::Less,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "`{}`", s),
+            Token::Str(s) => write!(f, "string {:?}", s),
+            Token::Num(n) => write!(f, "number {}", n),
+            Token::Op(op) => write!(f, "`{}`", op),
+            Token::LParen => f.write_str("`(`"),
+            Token::RParen => f.write_str("`)`"),
+            Token::LBracket => f.write_str("`[`"),
+            Token::RBracket => f.write_str("`]`"),
+            Token::Dot => f.write_str("`.`"),
+        }
+    }
+}
+
+const OPERATORS: [&str; 13] = ["&&", "||", "==", "!=", "<=", ">=", "=~", "!~", "<", ">", "!", "and", "or"];
+
+pub(crate) fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ExprError> {
+    let mut tokens = Vec::new();
+    let bytes = source.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = match c {
+            '(' => { i += 1; Token::LParen }
+            ')' => { i += 1; Token::RParen }
+            '[' => { i += 1; Token::LBracket }
+            ']' => { i += 1; Token::RBracket }
+            '.' if !bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => { i += 1; Token::Dot }
+            '"' | '\'' => {
+                let quote = c;
+                let mut value = String::new();
+                let mut chars = source[i + 1..].char_indices();
+                loop {
+                    match chars.next() {
+                        Some((j, ch)) if ch == quote => { i += j + 2; break; }
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, 'n')) => value.push('\n'),
+                            Some((_, 't')) => value.push('\t'),
+                            Some((_, ch)) => value.push(ch),
+                            None => return Err(ExprError { position: start, message: "unterminated string".into() }),
+                        },
+                        Some((_, ch)) => value.push(ch),
+                        None => return Err(ExprError { position: start, message: "unterminated string".into() }),
+                    }
+                }
+                Token::Str(value)
+            }
+            c if c.is_ascii_digit() || c == '.' || (c == '-' && bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit() || *b == b'.')) => {
+                i += 1;
+                while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.' || bytes[i] == b'e' || bytes[i] == b'E') {
+                    i += 1;
+                }
+                let text = &source[start..i];
+                Token::Num(text.parse().map_err(|_| ExprError { position: start, message: format!("invalid number `{}`", text) })?)
+            }
+            c if c.is_alphabetic() || c == '_' || c == '@' => {
+                while i < bytes.len() && ((bytes[i] as char).is_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'-' || bytes[i] == b'@' || bytes[i] >= 0x80) {
+                    i += 1;
+                }
+                match &source[start..i] {
+                    "and" => Token::Op("&&"),
+                    "or" => Token::Op("||"),
+                    "not" => Token::Op("!"),
+                    word => Token::Ident(word.to_string()),
+                }
+            }
+            _ => {
+                let op = OPERATORS.iter().find(|op| source[i..].starts_with(**op))
+                    .ok_or_else(|| ExprError { position: start, message: format!("unexpected character `{}`", c) })?;
+                i += op.len();
+                Token::Op(op)
+            }
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, at)| *at)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ExprError {
+        ExprError { position: self.position(), message: message.into() }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.and()?;
+        while self.eat(&Token::Op("||")) {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.unary()?;
+        while self.eat(&Token::Op("&&")) {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, ExprError> {
+        if self.eat(&Token::Op("!")) {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::LParen) {
+            let node = self.or()?;
+            if !self.eat(&Token::RParen) {
+                return Err(self.error("expected `)`"));
+            }
+            return Ok(node);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Node, ExprError> {
+        let lhs = self.operand()?;
+        self.comparison_with(lhs)
+    }
+
+    fn comparison_with(&mut self, lhs: Operand) -> Result<Node, ExprError> {
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Ok(Node::Truthy(lhs)),
+        };
+        let op = match op {
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "=~" | "!~" => {
+                self.pos += 1;
+                let at = self.position();
+                let Some(Token::Str(pattern)) = self.peek().cloned() else { return Err(self.error("expected a regex string")) };
+                self.pos += 1;
+                let regex = Regex::new(&pattern).map_err(|e| ExprError { position: at, message: format!("invalid regex: {}", e) })?;
+                return Ok(Node::Match(lhs, regex, op == "!~"));
+            }
+            _ => return Ok(Node::Truthy(lhs)),
+        };
+        self.pos += 1;
+        let rhs = self.operand()?;
+        Ok(Node::Compare(lhs, op, rhs))
+    }
+
+    pub(crate) fn operand(&mut self) -> Result<Operand, ExprError> {
+        let Some(token) = self.peek().cloned() else { return Err(self.error("expected a value")) };
+        self.pos += 1;
+        Ok(match token {
+            Token::Str(s) => Operand::Literal(Val::Str(Cow::Owned(s))),
+            Token::Num(n) => Operand::Literal(Val::Num(n)),
+            Token::Ident(name) if name == "true" => Operand::Literal(Val::Bool(true)),
+            Token::Ident(name) if name == "false" => Operand::Literal(Val::Bool(false)),
+            Token::Ident(name) if name == "null" => Operand::Literal(Val::Null),
+            Token::Ident(name) if name == "Data" && matches!(self.peek(), Some(Token::Dot | Token::LBracket)) => {
+                Operand::Data(self.path()?)
+            }
+            Token::Ident(name) => Operand::Column(name),
+            other => {
+                self.pos -= 1;
+                return Err(self.error(format!("expected a value, found {}", other)));
+            }
+        })
+    }
+
+    /// Parses `.key`, `["key"]`, and `[0]` steps.
+    fn path(&mut self) -> Result<Vec<PathStep>, ExprError> {
+        let mut path = Vec::new();
+        loop {
+            if self.eat(&Token::Dot) {
+                match self.peek().cloned() {
+                    Some(Token::Ident(key)) => { self.pos += 1; path.push(PathStep::Key(key)); }
+                    Some(Token::Str(key)) => { self.pos += 1; path.push(PathStep::Key(key)); }
+                    _ => return Err(self.error("expected a key after `.`")),
+                }
+            } else if self.eat(&Token::LBracket) {
+                match self.peek().cloned() {
+                    Some(Token::Str(key)) => path.push(PathStep::Key(key)),
+                    Some(Token::Num(n)) if n >= 0.0 && n.fract() == 0.0 => path.push(PathStep::Index(n as usize)),
+                    _ => return Err(self.error("expected a key or index inside `[]`")),
+                }
+                self.pos += 1;
+                if !self.eat(&Token::RBracket) {
+                    return Err(self.error("expected `]`"));
+                }
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> LogRecord {
+        let mut record = LogRecord::new("");
+        record.push("DateTime", "2025-03-01 10:00:00");
+        record.push("Level", "WARNING");
+        record.push("Message", "request timeout after 30s");
+        record.push("Data", r#"{"user_id":42,"tags":["slow","api"],"ms":"1500","meta":{"retry":true},"gone":null}"#);
+        record
+    }
+
+    fn matches(source: &str) -> bool {
+        Expr::parse(source).unwrap_or_else(|e| panic!("{}: {}", source, e)).matches(&record())
+    }
+
+    fn error(source: &str) -> String {
+        Expr::parse(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert!(matches("Level == 'ERROR' && false || Data.user_id == 42"));
+        assert!(matches("Data.user_id == 42 || Level == 'ERROR' && false"));
+        assert!(!matches("(Data.user_id == 42 || Level == 'ERROR') && false"));
+        assert!(matches("not Level == 'ERROR' and Message =~ 'time(out)?'"));
+        assert!(!matches("!(Level == 'WARNING')"));
+    }
+
+    #[test]
+    fn compares_numbers_as_numbers() {
+        assert!(matches("Data.user_id > 9"));
+        assert!(matches("Data.ms >= 1000"));
+        assert!(matches("Data.user_id == 42.0"));
+        assert!(matches("Message > 'request'"));
+    }
+
+    #[test]
+    fn orders_levels_by_severity() {
+        assert!(matches("Level >= 'warn'"));
+        assert!(matches("Level < 'ERROR'"));
+        assert!(matches("Level > 'INFO'"));
+        assert!(!matches("Level >= 'CRITICAL'"));
+        // Equality is exact: spellings are not normalized.
+        assert!(!matches("Level == 'warn'"));
+    }
+
+    #[test]
+    fn follows_paths_into_data() {
+        assert!(matches("Data.tags[1] == 'api'"));
+        assert!(matches(r#"Data["meta"].retry"#));
+        assert!(matches("Data.gone == null && Data.missing == null"));
+        assert!(!matches("Data.missing != null || Data.missing > 0"));
+        assert!(matches("Data.meta.retry == true && Missing !~ 'x'"));
+    }
+
+    #[test]
+    fn reports_where_errors_are() {
+        assert_eq!(error("Level == 'ERROR"), "unterminated string at position 9");
+        assert_eq!(error("(Level == 'ERROR'"), "expected `)` at position 17");
+        assert_eq!(error("Level =="), "expected a value at position 8");
+        assert_eq!(error("Level == )"), "expected a value, found `)` at position 9");
+        assert_eq!(error("Message =~ 42"), "expected a regex string at position 11");
+        assert!(error("Message =~ '('").starts_with("invalid regex: "));
+        assert_eq!(error("Level # 1"), "unexpected character `#` at position 6");
+        assert_eq!(error("Level 'x'"), "unexpected string \"x\" at position 6");
+        assert_eq!(error("Data.tags[-1]"), "expected a key or index inside `[]` at position 10");
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{datetime::{record_time, TimeBound}, expr::{DataQuery, Expr}, record::{normalize_level, severity_rank, LogRecord}};
+use regex::Regex;
+use std::{cmp::Ordering, collections::hash_map::{DefaultHasher, RandomState}, hash::{BuildHasher, Hash, Hasher}};
+
+/// A condition on a single column.
+#[derive(Debug, Clone)]
+pub enum FieldMatch {
+    /// The column equals this value exactly.
+    Equals(String),
+    /// The column matches this regex.
+    Regex(Regex),
+}
+
+impl FieldMatch {
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            FieldMatch::Equals(expected) => value == expected,
+            FieldMatch::Regex(re) => re.is_match(value),
+        }
+    }
+}
+
+/// Keeps a share of the records that pass the other criteria. Records are picked by a hash
+/// of where they were read and what they say, so the choice does not depend on the order
+/// they are checked in.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// Share of records kept, from 0 to 1.
+    pub rate: f64,
+    /// Random hash keys, so each run picks other records; `None` picks the same ones every run.
+    keys: Option<RandomState>,
+}
+
+impl Sample {
+    pub fn new(rate: f64, random: bool) -> Self {
+        Sample { rate, keys: random.then(RandomState::new) }
+    }
+
+    /// Parses a rate written as a fraction (`1/100`), a percentage (`2%`), or a number from
+    /// 0 to 1 (`0.02`).
+    pub fn parse_rate(text: &str) -> Result<f64, String> {
+        let text = text.trim();
+        let rate = match (text.split_once('/'), text.strip_suffix('%')) {
+            (Some((n, d)), _) => n.trim().parse::<f64>().ok().zip(d.trim().parse::<f64>().ok()).filter(|(_, d)| *d > 0.0).map(|(n, d)| n / d),
+            (None, Some(percent)) => percent.trim().parse::<f64>().ok().map(|p| p / 100.0),
+            (None, None) => text.parse::<f64>().ok(),
+        };
+        rate.filter(|rate| (0.0..=1.0).contains(rate)).ok_or_else(|| format!("expected a rate such as `1/100`, `2%`, or `0.02`, got `{}`", text))
+    }
+
+    /// Whether the record is in the sample.
+    pub fn keeps(&self, record: &LogRecord) -> bool {
+        let mut hasher = match &self.keys {
+            Some(keys) => keys.build_hasher(),
+            None => DefaultHasher::new(),
+        };
+        (&record.source, record.offset, &record.raw).hash(&mut hasher);
+        (hasher.finish() as f64) < self.rate * u64::MAX as f64
+    }
+}
+
+/// Which of the matching records to keep by position: those after the first `skip`, up to
+/// `head` of them.
+#[derive(Debug, Clone, Default)]
+pub struct Bounds {
+    pub skip: usize,
+    pub head: Option<usize>,
+    /// Matches counted so far.
+    seen: usize,
+}
+
+impl Bounds {
+    pub fn new(skip: usize, head: Option<usize>) -> Self {
+        Bounds { skip, head, seen: 0 }
+    }
+
+    /// Counts a match and returns whether it is kept.
+    pub fn admit(&mut self) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        self.seen += 1;
+        self.seen > self.skip
+    }
+
+    /// Whether `head` matches have been kept, so that reading can stop.
+    pub fn is_full(&self) -> bool {
+        self.head.is_some_and(|head| self.seen >= self.skip + head)
+    }
+}
+
+/// Criteria a record must satisfy to be displayed. Unset criteria match everything.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Substring that must appear somewhere in the raw line.
+    pub word: Option<String>,
+    /// Compare `word` case-insensitively.
+    pub ignore_case: bool,
+    /// Let `word` match with a few typos (roughly one edit per four characters).
+    pub fuzzy: bool,
+    /// Regexes that must all match the raw line.
+    pub patterns: Vec<Regex>,
+    /// Regexes none of which may match the raw line.
+    pub excludes: Vec<Regex>,
+    /// Column conditions that must all hold; records without the column do not match.
+    pub fields: Vec<(String, FieldMatch)>,
+    /// Levels to show, compared case-insensitively; empty shows every level.
+    pub levels: Vec<String>,
+    /// Least severe level to show.
+    pub min_level: Option<String>,
+    /// Levels from least to most severe, used by `min_level`; empty means
+    /// [`SEVERITIES`](crate::record::SEVERITIES).
+    pub severities: Vec<String>,
+    /// Inclusive lower bound on the `DateTime` column.
+    pub from: Option<TimeBound>,
+    /// Inclusive upper bound on the `DateTime` column.
+    pub to: Option<TimeBound>,
+    /// Boolean expression over the record's columns.
+    pub expr: Option<Expr>,
+    /// Tests on values inside the JSON Data column; queries without a test are ignored.
+    pub data_queries: Vec<DataQuery>,
+    /// Share of the otherwise matching records to keep.
+    pub sample: Option<Sample>,
+}
+
+impl Filter {
+    /// Returns whether the record passes every configured criterion.
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        let datetime = record.datetime();
+        let time = (self.from.is_some() || self.to.is_some()).then(|| record_time(record)).flatten();
+        let date_ok = self.from.as_ref().is_none_or(|from| from.compare(datetime, time.as_ref()).is_some_and(Ordering::is_ge))
+            && self.to.as_ref().is_none_or(|to| to.compare(datetime, time.as_ref()).is_some_and(Ordering::is_le));
+        let level = record.level();
+        let level_ok = (self.levels.is_empty() || self.levels.iter().any(|lvl| level.eq_ignore_ascii_case(lvl)))
+            && self.min_level.as_deref().is_none_or(|min| {
+                matches!((self.rank(level), self.rank(min)), (Some(rank), Some(min)) if rank >= min)
+            });
+        let word_ok = self.word.as_deref().is_none_or(|word| self.contains_word(&record.raw, word));
+        let fields_ok = self.fields.iter().all(|(column, cond)| record.get(column).is_some_and(|v| cond.matches(v)));
+        let patterns_ok = self.patterns.iter().all(|re| re.is_match(&record.raw))
+            && !self.excludes.iter().any(|re| re.is_match(&record.raw));
+        let data_ok = self.data_queries.iter().filter(|q| q.is_test()).all(|q| q.matches(record));
+        date_ok && level_ok && word_ok && fields_ok && patterns_ok && data_ok && self.expr.as_ref().is_none_or(|expr| expr.matches(record))
+            && self.sample.as_ref().is_none_or(|sample| sample.keeps(record))
+    }
+
+    fn contains_word(&self, text: &str, word: &str) -> bool {
+        if self.fuzzy {
+            let word: Vec<char> = word.to_lowercase().chars().collect();
+            let text: Vec<char> = text.to_lowercase().chars().collect();
+            fuzzy_distance(&text, &word) <= word.len().div_ceil(4).max(1)
+        } else if self.ignore_case {
+            text.to_lowercase().contains(&word.to_lowercase())
+        } else {
+            text.contains(word)
+        }
+    }
+
+    /// Position of `level` in the severity ordering, or `None` if it is not part of it.
+    pub fn rank(&self, level: &str) -> Option<usize> {
+        if self.severities.is_empty() {
+            return severity_rank(level);
+        }
+        let position = |name: &str| self.severities.iter().position(|s| s.eq_ignore_ascii_case(name));
+        position(level.trim()).or_else(|| position(&normalize_level(level)))
+    }
+}
+
+/// The fewest edits (insertions, deletions, substitutions) turning `word` into some
+/// substring of `text`.
+fn fuzzy_distance(text: &[char], word: &[char]) -> usize {
+    // Column of the edit-distance table where matching may start anywhere in `text`.
+    let mut prev: Vec<usize> = (0..=word.len()).collect();
+    let mut best = prev[word.len()];
+    for &c in text {
+        let mut row = vec![0; word.len() + 1];
+        for (j, &w) in word.iter().enumerate() {
+            let substitute = prev[j] + usize::from(c != w);
+            row[j + 1] = substitute.min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        best = best.min(row[word.len()]);
+        prev = row;
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(count: u64) -> Vec<LogRecord> {
+        (0..count).map(|n| {
+            let mut record = LogRecord::new(format!("2025-03-01 10:00:00|INFO|request {}", n));
+            record.offset = Some(n * 80);
+            record
+        }).collect()
+    }
+
+    #[test]
+    fn bounds_skip_then_keep_head() {
+        let mut bounds = Bounds::new(2, Some(3));
+        let kept: Vec<bool> = (0..7).map(|_| bounds.admit()).collect();
+        assert_eq!(kept, [false, false, true, true, true, false, false]);
+        assert!(bounds.is_full());
+    }
+
+    #[test]
+    fn bounds_without_head_never_fill() {
+        let mut bounds = Bounds::new(1, None);
+        assert!(!bounds.admit());
+        assert!((0..1000).all(|_| bounds.admit()));
+        assert!(!bounds.is_full());
+        assert!(Bounds::new(0, Some(0)).is_full());
+    }
+
+    #[test]
+    fn sample_keeps_about_the_rate() {
+        let records = records(10_000);
+        let kept = |sample: &Sample| records.iter().filter(|record| sample.keeps(record)).count();
+        assert!((900..1100).contains(&kept(&Sample::new(0.1, false))));
+        assert!((800..1200).contains(&kept(&Sample::new(0.1, true))));
+        assert_eq!(kept(&Sample::new(0.0, false)), 0);
+        assert_eq!(kept(&Sample::new(1.0, false)), records.len());
+    }
+
+    #[test]
+    fn sample_picks_the_same_records_in_any_order() {
+        let records = records(1000);
+        let sample = Sample::new(0.5, true);
+        let forwards: Vec<bool> = records.iter().map(|record| sample.keeps(record)).collect();
+        let mut backwards: Vec<bool> = records.iter().rev().map(|record| sample.keeps(record)).collect();
+        backwards.reverse();
+        assert_eq!(forwards, backwards);
+        let fixed = Sample::new(0.5, false);
+        assert!(records.iter().all(|record| fixed.keeps(record) == Sample::new(0.5, false).keeps(record)));
+    }
+
+    #[test]
+    fn parses_rates() {
+        assert_eq!(Sample::parse_rate("1/100"), Ok(0.01));
+        assert_eq!(Sample::parse_rate("2%"), Ok(0.02));
+        assert_eq!(Sample::parse_rate(" 0.5 "), Ok(0.5));
+        assert_eq!(Sample::parse_rate("3/2"), Err("expected a rate such as `1/100`, `2%`, or `0.02`, got `3/2`".into()));
+        assert!(Sample::parse_rate("1/0").is_err());
+        assert!(Sample::parse_rate("half").is_err());
+    }
+}
@@ -0,0 +1,1118 @@
+use crate::decompress::Compression;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use memmap2::Mmap;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+use std::{collections::VecDeque, fs::{metadata, File, Metadata}, io::{self, BufRead, BufReader, Read, Seek, SeekFrom}, path::{Path, PathBuf}, str::FromStr, sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError}, thread, time::Duration};
+
+/// Follows a growing file like `tail -f`, yielding lines as they are appended.
+///
+/// If the file shrinks below the current position it is assumed to be truncated and is
+/// read again from the top. If the path starts pointing at a different file (by inode), it
+/// is assumed to have been rotated: the old file is read to its end, unless disabled with
+/// [`set_drain_rotated`](Self::set_drain_rotated), and the new one is followed from its
+/// first line. Non-seekable inputs such as stdin are read on a background
+/// thread instead, and are finished once they reach end of file.
+#[derive(Debug)]
+pub struct Follower {
+    path: PathBuf,
+    position: u64,
+    /// Number of lines before `position`, unknown after skipping ahead with [`tail`](Self::tail).
+    line: Option<u64>,
+    drain_rotated: bool,
+    count_lines: bool,
+    /// Encoding of the bytes read from `input`. Files are split into lines in it; streams in
+    /// encodings other than ASCII-compatible ones are transcoded to UTF-8 first.
+    encoding: &'static Encoding,
+    input: Input,
+    /// Older, already rotated files to read completely before this one, oldest first.
+    backlog: VecDeque<Follower>,
+}
+
+/// A line read from an input, with where it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub text: String,
+    /// 1-based line number, if known.
+    pub number: Option<u64>,
+    /// Byte offset of the line, counted in the decompressed data for compressed inputs.
+    pub offset: u64,
+}
+
+/// Where a file was read up to, so that a later run can carry on from there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Device and inode of the file, or its creation time where there are no inodes.
+    pub file: (u64, u64),
+    /// Byte offset of the next unread line.
+    pub position: u64,
+}
+
+#[derive(Debug)]
+enum Input {
+    File { reader: BufReader<File>, id: Option<FileId> },
+    Stream { lines: Receiver<io::Result<Vec<u8>>>, finished: bool },
+}
+
+/// How the bytes of an input are decoded. Invalid sequences are replaced with U+FFFD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// Told from a byte order mark, or for files also from their first block: UTF-16 if
+    /// it is full of NUL bytes, else UTF-8 if it is valid, and Windows-1252 otherwise.
+    Auto,
+    Known(&'static Encoding),
+}
+
+impl Default for TextEncoding {
+    fn default() -> Self {
+        TextEncoding::Known(UTF_8)
+    }
+}
+
+impl FromStr for TextEncoding {
+    type Err = String;
+
+    /// Parses `auto`, `utf16le`, `utf16be`, or a WHATWG label such as `utf-8`, `latin1`, or
+    /// `shift_jis`.
+    fn from_str(text: &str) -> Result<TextEncoding, String> {
+        match text.to_lowercase().as_str() {
+            "auto" => Ok(TextEncoding::Auto),
+            "utf8" => Ok(TextEncoding::Known(UTF_8)),
+            "utf16le" => Ok(TextEncoding::Known(UTF_16LE)),
+            "utf16be" => Ok(TextEncoding::Known(UTF_16BE)),
+            label => Encoding::for_label(label.as_bytes()).map(TextEncoding::Known)
+                .ok_or_else(|| format!("unknown encoding `{}` (expected auto, utf8, latin1, utf16le, or another WHATWG label)", text)),
+        }
+    }
+}
+
+impl Follower {
+    /// Opens `path` for following, starting at its first line. The path `-` means stdin.
+    /// Named pipes are read like stdin, and finished once their writers have closed them.
+    ///
+    /// Compressed files (gzip, zstd, bzip2) are decompressed on the fly and, like streams,
+    /// are finished once fully read.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_waking(path.as_ref(), None, TextEncoding::default())
+    }
+
+    /// Opens `path` like [`open`](Self::open), decoding it as `encoding`. UTF-16 files are
+    /// followed like any other, split at their two-byte line endings; files in other
+    /// encodings that are not ASCII-compatible, such as ISO-2022-JP, are transcoded on the fly
+    /// and, like compressed files, finished once fully read.
+    pub fn open_encoded(path: impl AsRef<Path>, encoding: TextEncoding) -> io::Result<Self> {
+        Self::open_waking(path.as_ref(), None, encoding)
+    }
+
+    fn open_waking(path: &Path, wake: Option<Sender<()>>, encoding: TextEncoding) -> io::Result<Self> {
+        if path.as_os_str() == "-" {
+            return Ok(Self::spawn_decoding(path.to_path_buf(), io::stdin(), wake, encoding));
+        }
+        #[cfg(unix)]
+        if std::os::unix::fs::FileTypeExt::is_fifo(&metadata(path)?.file_type()) {
+            return Ok(Self::spawn_decoding(path.to_path_buf(), Fifo { path: path.to_path_buf(), file: None }, wake, encoding));
+        }
+        let mut file = File::open(path)?;
+        if let Some(compression) = Compression::detect(path, &mut file)? {
+            return Ok(Self::spawn_decoding(path.to_path_buf(), compression.decoder(file)?, wake, encoding));
+        }
+        let encoding = match encoding {
+            TextEncoding::Auto => sniff(&mut file)?,
+            TextEncoding::Known(encoding) => encoding,
+        };
+        if !encoding.is_ascii_compatible() && encoding != UTF_16LE && encoding != UTF_16BE {
+            return Ok(Self::spawn_decoding(path.to_path_buf(), file, wake, TextEncoding::Known(encoding)));
+        }
+        let id = file_id(&file.metadata()?);
+        let input = Input::File { reader: BufReader::new(file), id };
+        Ok(Follower { path: path.to_path_buf(), position: 0, line: Some(0), drain_rotated: true, count_lines: false, encoding, input, backlog: VecDeque::new() })
+    }
+
+    /// Follows standard input.
+    pub fn stdin() -> Self {
+        Self::from_reader("-", io::stdin())
+    }
+
+    /// Follows an arbitrary non-seekable byte stream, labelled `name`.
+    pub fn from_reader(name: impl Into<PathBuf>, reader: impl Read + Send + 'static) -> Self {
+        Self::spawn_reader(name.into(), reader, None)
+    }
+
+    /// Reads `reader` as text in `encoding` on a background thread. Encodings that are not
+    /// ASCII-compatible are transcoded to UTF-8 so lines can be split at `\n` bytes.
+    fn spawn_decoding(name: PathBuf, reader: impl Read + Send + 'static, wake: Option<Sender<()>>, encoding: TextEncoding) -> Self {
+        let (reader, encoding): (Box<dyn Read + Send>, _) = match encoding {
+            TextEncoding::Known(encoding) if encoding.is_ascii_compatible() => (Box::new(reader), encoding),
+            TextEncoding::Known(encoding) => (Box::new(DecodeReaderBytesBuilder::new().encoding(Some(encoding)).build(reader)), UTF_8),
+            // Streams cannot be sampled up front, so only a byte order mark is looked for.
+            TextEncoding::Auto => (Box::new(DecodeReaderBytesBuilder::new().strip_bom(true).build(reader)), UTF_8),
+        };
+        let mut follower = Self::spawn_reader(name, reader, wake);
+        follower.encoding = encoding;
+        follower
+    }
+
+    /// Reads `reader` on a background thread, signalling `wake` whenever a line arrives.
+    fn spawn_reader(name: PathBuf, reader: impl Read + Send + 'static, wake: Option<Sender<()>>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            loop {
+                let mut line = Vec::new();
+                let result = match reader.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(_) => Ok(line),
+                    Err(e) => Err(e),
+                };
+                let failed = result.is_err();
+                if tx.send(result).is_err() || failed {
+                    break;
+                }
+                if let Some(wake) = &wake {
+                    let _ = wake.send(());
+                }
+            }
+            if let Some(wake) = &wake {
+                let _ = wake.send(());
+            }
+        });
+        let input = Input::Stream { lines: rx, finished: false };
+        Follower { path: name, position: 0, line: Some(0), drain_rotated: false, count_lines: false, encoding: UTF_8, input, backlog: VecDeque::new() }
+    }
+
+    /// The file being followed.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Byte offset of the next unread line.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Where the file has been read up to. `None` for streams, compressed files, and files
+    /// that cannot be told apart from a replacement.
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        match &self.input {
+            Input::File { id: Some(file), .. } => Some(Checkpoint { file: *file, position: self.position }),
+            _ => None,
+        }
+    }
+
+    /// Carries on from a checkpoint of an earlier run. If the path still names the same file,
+    /// reading moves ahead to where it stopped, unless the file has been truncated since. If
+    /// the file has been rotated since, the rest of it is read first when it is found under a
+    /// rotated name and not compressed yet, then the new file from its first line.
+    ///
+    /// Line numbers are unknown afterwards.
+    pub fn resume(&mut self, checkpoint: Checkpoint) -> io::Result<()> {
+        self.resume_waking(checkpoint, None)
+    }
+
+    fn resume_waking(&mut self, checkpoint: Checkpoint, wake: Option<Sender<()>>) -> io::Result<()> {
+        let Input::File { reader, id } = &mut self.input else { return Ok(()) };
+        if *id == Some(checkpoint.file) {
+            if reader.get_ref().metadata()?.len() >= checkpoint.position {
+                (self.position, self.line) = (checkpoint.position, None);
+            }
+            return Ok(());
+        }
+        for sibling in rotated_siblings(&self.path)? {
+            let mut file = File::open(&sibling)?;
+            if file_id(&file.metadata()?) != Some(checkpoint.file) || Compression::detect(&sibling, &mut file)?.is_some() {
+                continue;
+            }
+            file.seek(SeekFrom::Start(checkpoint.position))?;
+            let mut rest = Self::spawn_decoding(sibling, file, wake, TextEncoding::Known(self.encoding));
+            (rest.position, rest.line) = (checkpoint.position, None);
+            self.backlog.push_front(rest);
+            break;
+        }
+        Ok(())
+    }
+
+    /// Whether the input supports seeking (regular files do, pipes and stdin do not).
+    pub fn is_seekable(&self) -> bool {
+        matches!(self.input, Input::File { .. })
+    }
+
+    /// Sets whether a rotated-away file is read to its end before switching to the new one.
+    pub fn set_drain_rotated(&mut self, drain: bool) {
+        self.drain_rotated = drain;
+    }
+
+    /// Sets whether [`tail`](Self::tail) counts the lines it skips, so that line numbers stay
+    /// known. This reads the whole file once.
+    pub fn set_count_lines(&mut self, count: bool) {
+        self.count_lines = count;
+    }
+
+    /// Whether the input has ended for good. Files never finish; they may still grow.
+    pub fn is_finished(&self) -> bool {
+        self.backlog.is_empty() && matches!(self.input, Input::Stream { finished: true, .. })
+    }
+
+    /// Reads the rotated-away siblings of this file (`app.log.1`, `app.log.2.gz`,
+    /// `app.log-20250301`, ...) oldest first before the file itself. Returns how many were found.
+    pub fn include_rotated(&mut self) -> io::Result<usize> {
+        self.include_rotated_waking(None)
+    }
+
+    fn include_rotated_waking(&mut self, wake: Option<Sender<()>>) -> io::Result<usize> {
+        if !self.is_seekable() {
+            return Ok(0);
+        }
+        for sibling in rotated_siblings(&self.path)? {
+            let mut file = File::open(&sibling)?;
+            let reader: Box<dyn Read + Send> = match Compression::detect(&sibling, &mut file)? {
+                Some(compression) => compression.decoder(file)?,
+                None => Box::new(file),
+            };
+            self.backlog.push_back(Self::spawn_decoding(sibling, reader, wake.clone(), TextEncoding::Known(self.encoding)));
+        }
+        Ok(self.backlog.len())
+    }
+
+    /// Reads the first line of the file without moving the follow position, e.g. to learn its
+    /// headers before skipping ahead. Streams have no separate first line and return `None`.
+    pub fn first_line(&mut self) -> io::Result<Option<String>> {
+        Ok(self.head(1)?.and_then(|lines| lines.into_iter().next()))
+    }
+
+    /// Reads up to `count` lines from the start of the file without moving the follow
+    /// position. Streams cannot be re-read and return `None`.
+    pub fn head(&mut self, count: usize) -> io::Result<Option<Vec<String>>> {
+        let Input::File { reader, .. } = &mut self.input else { return Ok(None) };
+        reader.seek(SeekFrom::Start(0))?;
+        let mut lines = Vec::new();
+        let mut line = Vec::new();
+        while lines.len() < count && read_line(reader, self.encoding, &mut line)? > 0 {
+            lines.push(decode(&line, self.encoding));
+            line.clear();
+        }
+        Ok(Some(lines))
+    }
+
+    /// Moves the follow position back from the end of the file to the start of the
+    /// `count`-th last line accepted by `wanted`, and returns the end: the lines up to there,
+    /// read on as usual, hold the last `count` accepted ones. Lines `wanted` returns `false`
+    /// or `None` for, such as the continuation lines of an entry, are passed over. A last line
+    /// without its newline yet is left out, to be read once it is complete.
+    ///
+    /// The file is scanned backwards in blocks, so only as much of it is read as is needed
+    /// to find the lines. Streams cannot seek and are left untouched; their end is where they
+    /// are.
+    ///
+    /// Line numbers are only known with [`set_count_lines`](Self::set_count_lines).
+    pub fn tail(&mut self, count: usize, mut wanted: impl FnMut(&str) -> Option<bool>) -> io::Result<u64> {
+        let Input::File { reader, .. } = &mut self.input else { return Ok(self.position) };
+        self.backlog.clear();
+        let len = reader.get_ref().metadata()?.len();
+        let end = line_start(reader.get_mut(), len, self.encoding)?;
+        let (mut start, mut found) = (end, 0);
+        if count > 0 {
+            scan_backwards(reader.get_mut(), end, self.encoding, |offset, text| {
+                if wanted(text) == Some(true) {
+                    (start, found) = (offset, found + 1);
+                }
+                found < count
+            })?;
+        }
+        self.line = None;
+        if self.count_lines {
+            let (numbers, total) = count_lines(reader.get_mut(), end, &[start], self.encoding)?;
+            self.line = Some(numbers.first().map_or(total, |number| number - 1));
+        }
+        self.position = start;
+        Ok(end)
+    }
+
+    /// Moves the follow position ahead to shortly before the first line `before` returns
+    /// `false` for, bisecting the file on the assumption that its lines are in order. Lines
+    /// `before` returns `None` for, such as ones without a timestamp, are skipped over.
+    ///
+    /// Line numbers are lost unless [`set_count_lines`](Self::set_count_lines) is on, which
+    /// reads the skipped part after all. Streams and files with rotated copies still to read
+    /// are left untouched.
+    pub fn seek_to(&mut self, mut before: impl FnMut(&str) -> Option<bool>) -> io::Result<()> {
+        let Input::File { reader, .. } = &mut self.input else { return Ok(()) };
+        if !self.backlog.is_empty() {
+            return Ok(());
+        }
+        let (mut low, mut high) = (self.position, reader.get_ref().metadata()?.len());
+        let mut text = Vec::new();
+        while high - low > SCAN_BLOCK {
+            let mid = low + (high - low) / 2;
+            let mid = mid - mid % newline(self.encoding).len() as u64;
+            reader.seek(SeekFrom::Start(mid))?;
+            // The line `mid` falls into started before it.
+            let mut offset = mid + read_line(reader, self.encoding, &mut text)? as u64;
+            let mut placed = None;
+            while offset < high && placed.is_none() {
+                text.clear();
+                let read = read_line(reader, self.encoding, &mut text)?;
+                if read == 0 {
+                    break;
+                }
+                placed = before(&decode(&text, self.encoding)).map(|before| (before, offset));
+                offset += read as u64;
+            }
+            match placed {
+                Some((true, start)) => low = start,
+                _ => high = mid,
+            }
+            text.clear();
+        }
+        if low > self.position {
+            self.line = match self.count_lines {
+                true => Some(count_lines(reader.get_mut(), low, &[], self.encoding)?.1),
+                false => None,
+            };
+            self.position = low;
+        }
+        Ok(())
+    }
+
+    /// Reads every line appended since the last call.
+    pub fn poll(&mut self) -> io::Result<Vec<Line>> {
+        self.read(false, u64::MAX)
+    }
+
+    /// Reads everything available, waiting for streams to reach end of file.
+    pub fn read_to_end(&mut self) -> io::Result<Vec<Line>> {
+        self.read(true, u64::MAX)
+    }
+
+    /// Reads on like [`read_to_end`](Self::read_to_end), but stops at the end of the line
+    /// that takes it past `limit` bytes, so that large files can be worked through a part at
+    /// a time. `None` once everything has been read.
+    pub fn read_chunk(&mut self, limit: u64) -> io::Result<Option<Vec<Line>>> {
+        let lines = self.read(true, limit)?;
+        Ok((!lines.is_empty()).then_some(lines))
+    }
+
+    /// Reads on like [`read_chunk`](Self::read_chunk), but maps the file into memory and
+    /// splits it with SIMD-accelerated searches, only copying out the lines `keep` accepts.
+    /// Much faster on large files; streams, rotated copies, and UTF-16 files are read as usual.
+    ///
+    /// The file must not be truncated while it is mapped.
+    pub fn read_mapped(&mut self, limit: u64, mut keep: impl FnMut(&[u8]) -> bool) -> io::Result<Option<Vec<Line>>> {
+        let file = match &self.input {
+            Input::File { reader, .. } if self.backlog.is_empty() && self.encoding.is_ascii_compatible() && reader.get_ref().metadata()?.len() >= self.position => reader.get_ref(),
+            _ => return Ok(self.read_chunk(limit)?.map(|lines| lines.into_iter().filter(|line| keep(line.text.as_bytes())).collect())),
+        };
+        // SAFETY: the map is only read, and log files are only appended to while rlog runs.
+        let map = unsafe { Mmap::map(file)? };
+        let data = map.get(self.position as usize..).unwrap_or_default();
+        if data.is_empty() {
+            return Ok(None);
+        }
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        let data = match data.get(limit..) {
+            Some(rest) => &data[..memchr::memchr(b'\n', rest).map_or(data.len(), |idx| limit + idx + 1)],
+            None => data,
+        };
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while start < data.len() {
+            let end = memchr::memchr(b'\n', &data[start..]).map_or(data.len(), |idx| start + idx + 1);
+            self.line = self.line.map(|n| n + 1);
+            if keep(&data[start..end]) {
+                let text = decode(&data[start..end], self.encoding);
+                lines.push(Line { text, number: self.line, offset: self.position + start as u64 });
+            }
+            start = end;
+        }
+        self.position += data.len() as u64;
+        Ok(Some(lines))
+    }
+
+    /// Reads what is available, waiting for streams to end if `wait`, and stopping at the end
+    /// of the line that takes it past `limit` bytes.
+    fn read(&mut self, wait: bool, limit: u64) -> io::Result<Vec<Line>> {
+        let mut lines = Vec::new();
+        while let Some(older) = self.backlog.front_mut() {
+            lines.extend(older.read(wait, limit)?);
+            if !older.is_finished() {
+                // Keep chronological order: nothing newer until the older file is done.
+                return Ok(lines);
+            }
+            self.backlog.pop_front();
+        }
+        match &mut self.input {
+            Input::File { reader, id } => {
+                // While the path is missing (renamed, not yet recreated) keep reading the old file.
+                let rotated = metadata(&self.path).is_ok_and(|meta| file_id(&meta) != *id);
+                if rotated {
+                    // The old file is not written to anymore, so its last line is complete.
+                    if self.drain_rotated && !read_lines(reader, self.encoding, &mut self.position, &mut self.line, &mut lines, true, limit)? {
+                        return Ok(lines);
+                    }
+                    let file = File::open(&self.path)?;
+                    *id = file_id(&file.metadata()?);
+                    *reader = BufReader::new(file);
+                    self.position = 0;
+                    self.line = Some(0);
+                }
+
+                let len = reader.get_ref().metadata()?.len();
+                if len < self.position {
+                    self.position = 0;
+                    self.line = Some(0);
+                }
+                if len > self.position {
+                    read_lines(reader, self.encoding, &mut self.position, &mut self.line, &mut lines, wait, limit)?;
+                }
+            }
+            Input::Stream { lines: rx, finished } => {
+                let mut read = 0;
+                while !*finished && read < limit {
+                    let next = if wait { rx.recv().map_err(|_| TryRecvError::Disconnected) } else { rx.try_recv() };
+                    match next {
+                        Ok(bytes) => {
+                            let bytes = bytes?;
+                            read += bytes.len() as u64;
+                            lines.push(next_line(&bytes, self.encoding, &mut self.position, &mut self.line));
+                        }
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => *finished = true,
+                    }
+                }
+            }
+        }
+        Ok(lines)
+    }
+}
+
+/// A named pipe, opened on the first read: opening blocks until a writer opens it too,
+/// which must not hold up the thread following the other inputs.
+struct Fifo {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl Read for Fifo {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.file {
+            Some(file) => file.read(buf),
+            None => self.file.insert(File::open(&self.path)?).read(buf),
+        }
+    }
+}
+
+/// Reads the lines from `position` to the end of the file, advancing `position` and `line`,
+/// or until more than `limit` bytes have been read. Returns whether it got to the end.
+///
+/// A last line without a newline may still be being written, and is left for the next read
+/// unless `complete`, when the file is read for the last time.
+fn read_lines(reader: &mut BufReader<File>, encoding: &'static Encoding, position: &mut u64, line: &mut Option<u64>, lines: &mut Vec<Line>, complete: bool, limit: u64) -> io::Result<bool> {
+    reader.seek(SeekFrom::Start(*position))?;
+    let start = *position;
+    let mut bytes = Vec::new();
+    while read_line(reader, encoding, &mut bytes)? > 0 {
+        if !complete && !ends_line(&bytes, encoding) {
+            break;
+        }
+        lines.push(next_line(&bytes, encoding, position, line));
+        bytes.clear();
+        if *position - start >= limit {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Turns the bytes of a line read at `position` into a [`Line`], advancing `position` and
+/// `line` past it.
+fn next_line(bytes: &[u8], encoding: &'static Encoding, position: &mut u64, line: &mut Option<u64>) -> Line {
+    let offset = *position;
+    *position += bytes.len() as u64;
+    *line = line.map(|n| n + 1);
+    Line { text: decode(bytes, encoding), number: *line, offset }
+}
+
+/// Decodes the bytes of one line, replacing invalid sequences and dropping the line ending
+/// and any byte order mark.
+fn decode(bytes: &[u8], encoding: &'static Encoding) -> String {
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    text.trim_start_matches('\u{feff}').trim_end_matches(['\r', '\n']).to_string()
+}
+
+/// The bytes of a line ending in `encoding`.
+fn newline(encoding: &'static Encoding) -> &'static [u8] {
+    match encoding {
+        _ if encoding == UTF_16LE => b"\n\0",
+        _ if encoding == UTF_16BE => b"\0\n",
+        _ => b"\n",
+    }
+}
+
+/// Whether `bytes`, starting at the start of a character, end with a line ending.
+fn ends_line(bytes: &[u8], encoding: &'static Encoding) -> bool {
+    let newline = newline(encoding);
+    bytes.len().is_multiple_of(newline.len()) && bytes.ends_with(newline)
+}
+
+/// The offsets just past the line endings in `block`, which starts at the start of a
+/// character. In UTF-16, a `\n` byte is only a line ending as the right half of a character.
+fn newline_ends<'a>(block: &'a [u8], encoding: &'static Encoding) -> impl DoubleEndedIterator<Item = usize> + 'a {
+    let newline = newline(encoding);
+    let at = newline.iter().position(|&b| b == b'\n').unwrap_or(0);
+    memchr::memchr_iter(b'\n', block).filter_map(move |idx| {
+        let start = idx.checked_sub(at)?;
+        (start.is_multiple_of(newline.len()) && block.get(start..start + newline.len()) == Some(newline)).then_some(start + newline.len())
+    })
+}
+
+/// Reads a line into `bytes` like `read_until`, including its line ending, which in UTF-16
+/// takes the byte after a `\n` too. Returns how many bytes were read.
+fn read_line(reader: &mut impl BufRead, encoding: &'static Encoding, bytes: &mut Vec<u8>) -> io::Result<usize> {
+    let (start, width) = (bytes.len(), newline(encoding).len());
+    while reader.read_until(b'\n', bytes)? > 0 {
+        // Complete the character the `\n` byte is part of.
+        while !(bytes.len() - start).is_multiple_of(width) {
+            let Some(&byte) = reader.fill_buf()?.first() else { break };
+            bytes.push(byte);
+            reader.consume(1);
+        }
+        if ends_line(&bytes[start..], encoding) {
+            break;
+        }
+    }
+    Ok(bytes.len() - start)
+}
+
+/// Tells the encoding of a file from its byte order mark or first block, see
+/// [`TextEncoding::Auto`].
+fn sniff(file: &mut File) -> io::Result<&'static Encoding> {
+    let mut sample = Vec::new();
+    file.by_ref().take(SCAN_BLOCK).read_to_end(&mut sample)?;
+    file.seek(SeekFrom::Start(0))?;
+    if let Some((encoding, _)) = Encoding::for_bom(&sample) {
+        return Ok(encoding);
+    }
+    // ASCII text in UTF-16 has every other byte zero.
+    let zeros = |parity: usize| sample.iter().skip(parity).step_by(2).filter(|&&b| b == 0).count();
+    let (even, odd) = (zeros(0), zeros(1));
+    Ok(match std::str::from_utf8(&sample) {
+        _ if odd > sample.len() / 4 && even == 0 => UTF_16LE,
+        _ if even > sample.len() / 4 && odd == 0 => UTF_16BE,
+        // A character cut off at the end of the sample does not count.
+        Err(e) if e.error_len().is_some() => WINDOWS_1252,
+        _ => UTF_8,
+    })
+}
+
+/// Counts the lines before `end`, also returning the number of the line starting at each of
+/// the ascending `offsets`.
+fn count_lines(file: &mut File, end: u64, offsets: &[u64], encoding: &'static Encoding) -> io::Result<(Vec<u64>, u64)> {
+    let newlines = |bytes: &[u8]| newline_ends(bytes, encoding).count() as u64;
+    file.seek(SeekFrom::Start(0))?;
+    let mut numbers = Vec::with_capacity(offsets.len());
+    let mut block = vec![0; SCAN_BLOCK as usize];
+    let (mut start, mut total, mut terminated) = (0, 0, true);
+    while start < end {
+        let len = SCAN_BLOCK.min(end - start) as usize;
+        file.read_exact(&mut block[..len])?;
+        while let Some(&offset) = offsets.get(numbers.len()).filter(|&&offset| offset < start + len as u64) {
+            numbers.push(total + newlines(&block[..(offset - start) as usize]) + 1);
+        }
+        total += newlines(&block[..len]);
+        terminated = ends_line(&block[..len], encoding);
+        start += len as u64;
+    }
+    // An unterminated last line has been read as well.
+    Ok((numbers, total + !terminated as u64))
+}
+
+/// Size of the blocks read when scanning a whole file or its end.
+const SCAN_BLOCK: u64 = 64 * 1024;
+
+/// Finds where the line that `end` falls into starts: `end` itself if a line ending comes
+/// right before it.
+fn line_start(file: &mut File, end: u64, encoding: &'static Encoding) -> io::Result<u64> {
+    let mut block = vec![0; SCAN_BLOCK as usize];
+    // Half a UTF-16 character may have been written so far.
+    let mut block_end = end - end % newline(encoding).len() as u64;
+    while block_end > 0 {
+        let len = SCAN_BLOCK.min(block_end);
+        let block_start = block_end - len;
+        file.seek(SeekFrom::Start(block_start))?;
+        file.read_exact(&mut block[..len as usize])?;
+        if let Some(start) = newline_ends(&block[..len as usize], encoding).next_back() {
+            return Ok(block_start + start as u64);
+        }
+        block_end = block_start;
+    }
+    Ok(0)
+}
+
+/// Calls `on_line` with the offset and text of each line before `end`, newest first, until it
+/// returns `false`.
+fn scan_backwards(file: &mut File, end: u64, encoding: &'static Encoding, mut on_line: impl FnMut(u64, &str) -> bool) -> io::Result<()> {
+    let mut block_start = end;
+    // Bytes of the line that straddles the boundary of the block read next.
+    let mut carry: Vec<u8> = Vec::new();
+
+    while block_start > 0 {
+        let len = SCAN_BLOCK.min(block_start);
+        block_start -= len;
+        let mut block = vec![0; len as usize];
+        file.seek(SeekFrom::Start(block_start))?;
+        file.read_exact(&mut block)?;
+        block.extend_from_slice(&carry);
+
+        let mut line_end = block.len();
+        // A line ending at the very end terminates the last line rather than starting an
+        // empty one.
+        for start in newline_ends(&block, encoding).rev().filter(|&start| start < block.len()) {
+            if !on_line(block_start + start as u64, &decode(&block[start..line_end], encoding)) {
+                return Ok(());
+            }
+            line_end = start;
+        }
+        carry = block[..line_end].to_vec();
+    }
+    if !carry.is_empty() {
+        on_line(0, &decode(&carry, encoding));
+    }
+    Ok(())
+}
+
+/// Finds the rotated copies of `path` in its directory, ordered oldest first.
+///
+/// Numbered copies (`app.log.1`, `app.log.2.gz`) get older as the number grows; dated
+/// copies (`app.log-20250301`, `app.log.20250301.gz`) sort by date and count as older
+/// than the numbered ones.
+pub fn rotated_siblings(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else { return Ok(Vec::new()) };
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let pattern = Regex::new(&format!(r"^{}[.-](\d+)(?:\.(?:gz|zst|zstd|bz2))?$", regex::escape(name))).unwrap();
+
+    let mut found: Vec<((bool, i64), PathBuf)> = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(caps) = file_name.to_str().and_then(|n| pattern.captures(n)) else { continue };
+        let Ok(number) = caps[1].parse::<i64>() else { continue };
+        let dated = caps[1].len() >= 8;
+        found.push(((!dated, if dated { number } else { -number }), dir.join(&file_name)));
+    }
+    found.sort();
+    Ok(found.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Whether `path` stands for several files: a directory, or a file name with `*`, `?`, or
+/// `[...]` wildcards.
+pub fn is_pattern(path: &Path) -> bool {
+    path.is_dir() || path.file_name().and_then(|n| n.to_str()).is_some_and(|name| name.contains(['*', '?', '[']))
+}
+
+/// The files `pattern` stands for, in name order: the files in a directory, leaving out
+/// hidden files, rotated copies, and compressed files; or the files whose name matches a
+/// wildcard pattern, where only a pattern starting with `.` matches hidden files.
+pub fn expand_pattern(pattern: &Path) -> io::Result<Vec<PathBuf>> {
+    let (dir, matches): (&Path, Regex) = if pattern.is_dir() {
+        (pattern, Regex::new(r"^[^.].*$").unwrap())
+    } else {
+        let Some(name) = pattern.file_name().and_then(|n| n.to_str()) else { return Ok(Vec::new()) };
+        let mut regex = String::from("^");
+        let mut chars = name.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' => regex.push_str(".*"),
+                '?' => regex.push('.'),
+                '[' => {
+                    regex.push('[');
+                    if chars.next_if(|c| *c == '!').is_some() {
+                        regex.push('^');
+                    }
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                        regex.push_str(&regex::escape(&c.to_string()));
+                    }
+                    regex.push(']');
+                }
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+        let regex = Regex::new(&regex).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        (pattern.parent().unwrap_or(Path::new("")), regex)
+    };
+    let hidden = pattern.file_name().and_then(|n| n.to_str()).is_some_and(|name| name.starts_with('.'));
+    let rotated = Regex::new(r"[.-]\d+(?:\.(?:gz|zst|zstd|bz2))?$|\.(?:gz|zst|zstd|bz2)$").unwrap();
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(if dir.as_os_str().is_empty() { Path::new(".") } else { dir })? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else { continue };
+        if !matches.is_match(&name) || (name.starts_with('.') && !hidden) || (pattern.is_dir() && rotated.is_match(&name)) {
+            continue;
+        }
+        let path = dir.join(&name);
+        if path.is_file() {
+            found.push(path);
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// The files under `dir` and its subdirectories that look like logs, in path order: text
+/// and compressed files, leaving out hidden files and directories, and files with NUL bytes
+/// in their first block, such as binaries. Subdirectories that cannot be read are skipped.
+pub fn walk(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = vec![std::fs::read_dir(dir)?];
+    while let Some(entries) = pending.pop() {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => {
+                    pending.extend(std::fs::read_dir(&path));
+                }
+                Ok(kind) if kind.is_file() && is_text(&path).unwrap_or(false) => found.push(path),
+                _ => {}
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// Whether the file at `path` is compressed, or has no NUL bytes in its first block unless
+/// a UTF-16 byte order mark explains them.
+fn is_text(path: &Path) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    if Compression::detect(path, &mut file)?.is_some() {
+        return Ok(true);
+    }
+    let mut block = Vec::new();
+    file.take(SCAN_BLOCK).read_to_end(&mut block)?;
+    Ok(block.starts_with(&[0xff, 0xfe]) || block.starts_with(&[0xfe, 0xff]) || !block.contains(&0))
+}
+
+/// Identifies a file independently of its path, to notice when a path is recreated.
+type FileId = (u64, u64);
+
+/// The identity of the file at `path` that [`Checkpoint::file`] records: its device and
+/// inode numbers, or its creation time where there are no inodes.
+pub fn file_identity(path: &Path) -> io::Result<Option<(u64, u64)>> {
+    Ok(file_id(&metadata(path)?))
+}
+
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(meta: &Metadata) -> Option<FileId> {
+    // Without inode numbers, a different creation time is the best sign of a new file.
+    let created = meta.created().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some((created.as_secs(), created.subsec_nanos() as u64))
+}
+
+/// Follows several files at once, tagging each line with the index of the file it came from.
+///
+/// [`wait`](Self::wait) blocks until one of the inputs may have new data: streams signal
+/// each line they receive, and files are watched with filesystem notifications once
+/// [`watch`](Self::watch) succeeds. Without notifications, waiting simply sleeps.
+#[derive(Debug)]
+pub struct MultiFollower {
+    followers: Vec<Follower>,
+    wake_tx: Sender<()>,
+    wake_rx: Receiver<()>,
+    watcher: Option<RecommendedWatcher>,
+    /// Directories whose every change wakes [`wait`](Self::wait), see [`watch_dirs`](Self::watch_dirs).
+    dirs: Vec<PathBuf>,
+}
+
+impl MultiFollower {
+    /// Opens every path for following, as [`Follower::open`] does.
+    pub fn open<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> io::Result<Self> {
+        Self::open_encoded(paths, TextEncoding::default())
+    }
+
+    /// Opens every path for following, as [`Follower::open_encoded`] does.
+    pub fn open_encoded<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>, encoding: TextEncoding) -> io::Result<Self> {
+        let (wake_tx, wake_rx) = mpsc::channel();
+        let followers = paths.into_iter()
+            .map(|path| Follower::open_waking(path.as_ref(), Some(wake_tx.clone()), encoding).map_err(|e| naming(path.as_ref(), e)))
+            .collect::<io::Result<_>>()?;
+        Ok(MultiFollower { followers, wake_tx, wake_rx, watcher: None, dirs: Vec::new() })
+    }
+
+    /// Adds a non-seekable byte stream as another input, returning its index.
+    pub fn add_reader(&mut self, name: impl Into<PathBuf>, reader: impl Read + Send + 'static) -> usize {
+        self.followers.push(Follower::spawn_reader(name.into(), reader, Some(self.wake_tx.clone())));
+        self.followers.len() - 1
+    }
+
+    /// Starts following the file at `path` from its first line, as another input. Returns
+    /// its index.
+    pub fn add(&mut self, path: &Path, encoding: TextEncoding) -> io::Result<usize> {
+        let follower = Follower::open_waking(path, Some(self.wake_tx.clone()), encoding).map_err(|e| naming(path, e))?;
+        self.followers.push(follower);
+        Ok(self.followers.len() - 1)
+    }
+
+    /// Stops following one of the inputs; those after it move down one index.
+    pub fn remove(&mut self, idx: usize) -> Follower {
+        self.followers.remove(idx)
+    }
+
+    /// Makes [`wait`](Self::wait) also return when files are created, changed, or removed in
+    /// `dirs`, once [`watch`](Self::watch) succeeds, so that new files can be looked for.
+    pub fn watch_dirs(&mut self, dirs: impl IntoIterator<Item = PathBuf>) {
+        self.dirs.extend(dirs.into_iter().filter_map(|dir| dir.canonicalize().ok()));
+    }
+
+    /// Starts watching the followed files for changes (inotify, kqueue, ReadDirectoryChanges).
+    ///
+    /// Returns `false` if notifications are unavailable, in which case [`wait`](Self::wait)
+    /// keeps sleeping for its full timeout.
+    pub fn watch(&mut self) -> bool {
+        let files: Vec<PathBuf> = self.followers.iter().filter(|f| f.is_seekable()).filter_map(|f| absolute(f.path())).collect();
+        let wake = self.wake_tx.clone();
+        let watched = files.clone();
+        let watched_dirs = self.dirs.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let wanted = |path: &PathBuf| watched.contains(path) || path.parent().is_some_and(|dir| watched_dirs.iter().any(|d| d == dir));
+            if event.is_ok_and(|event| event.paths.iter().any(wanted)) {
+                let _ = wake.send(());
+            }
+        });
+        let Ok(mut watcher) = watcher else { return false };
+
+        // Watch directories rather than the files themselves so renames and re-creations are seen.
+        let mut dirs: Vec<&Path> = files.iter().filter_map(|f| f.parent()).chain(self.dirs.iter().map(PathBuf::as_path)).collect();
+        dirs.sort();
+        dirs.dedup();
+        if dirs.iter().any(|dir| watcher.watch(dir, RecursiveMode::NonRecursive).is_err()) {
+            return false;
+        }
+        self.watcher = Some(watcher);
+        true
+    }
+
+    /// Blocks until an input signals new data or `timeout` passes; returns whether it was signalled.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let woken = match self.wake_rx.recv_timeout(timeout) {
+            Ok(()) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => unreachable!("MultiFollower holds a sender"),
+        };
+        while self.wake_rx.try_recv().is_ok() {}
+        woken
+    }
+
+    /// A handle that makes a pending [`wait`](Self::wait) return early when sent to, e.g. from
+    /// a signal handler.
+    pub fn waker(&self) -> Sender<()> {
+        self.wake_tx.clone()
+    }
+
+    /// The individual followers, in the order they were opened.
+    pub fn followers(&self) -> &[Follower] {
+        &self.followers
+    }
+
+    /// Queues the rotated siblings of every followed file, see [`Follower::include_rotated`].
+    pub fn include_rotated(&mut self) -> io::Result<usize> {
+        let mut found = 0;
+        for follower in &mut self.followers {
+            found += follower.include_rotated_waking(Some(self.wake_tx.clone())).map_err(|e| naming(&follower.path, e))?;
+        }
+        Ok(found)
+    }
+
+    /// Carries on following one of the files from a checkpoint, see [`Follower::resume`].
+    pub fn resume(&mut self, idx: usize, checkpoint: Checkpoint) -> io::Result<()> {
+        let follower = &mut self.followers[idx];
+        follower.resume_waking(checkpoint, Some(self.wake_tx.clone())).map_err(|e| naming(&follower.path, e))
+    }
+
+    /// Mutable access to the individual followers, e.g. to configure rotation handling.
+    pub fn followers_mut(&mut self) -> &mut [Follower] {
+        &mut self.followers
+    }
+
+    /// Whether every input has ended for good.
+    pub fn is_finished(&self) -> bool {
+        self.followers.iter().all(Follower::is_finished)
+    }
+
+    /// Reads every line appended to any file since the last call, file by file.
+    pub fn poll(&mut self) -> io::Result<Vec<(usize, Line)>> {
+        self.read(Follower::poll)
+    }
+
+    /// Reads everything available from every input, waiting for streams to end.
+    pub fn read_to_end(&mut self) -> io::Result<Vec<(usize, Line)>> {
+        self.read(Follower::read_to_end)
+    }
+
+    /// Reads the next part of one of the inputs, see [`Follower::read_chunk`].
+    pub fn read_chunk(&mut self, idx: usize, limit: u64) -> io::Result<Option<Vec<Line>>> {
+        let follower = &mut self.followers[idx];
+        follower.read_chunk(limit).map_err(|e| naming(&follower.path, e))
+    }
+
+    /// Reads the next part of one of the inputs through [`Follower::read_mapped`].
+    pub fn read_mapped(&mut self, idx: usize, limit: u64, keep: impl FnMut(&[u8]) -> bool) -> io::Result<Option<Vec<Line>>> {
+        let follower = &mut self.followers[idx];
+        follower.read_mapped(limit, keep).map_err(|e| naming(&follower.path, e))
+    }
+
+    fn read(&mut self, mut read: impl FnMut(&mut Follower) -> io::Result<Vec<Line>>) -> io::Result<Vec<(usize, Line)>> {
+        let mut lines = Vec::new();
+        for (idx, follower) in self.followers.iter_mut().enumerate() {
+            let read = read(follower).map_err(|e| naming(&follower.path, e))?;
+            lines.extend(read.into_iter().map(|line| (idx, line)));
+        }
+        Ok(lines)
+    }
+}
+
+/// `error` with the input it happened on in front of its message, so that it can be told
+/// apart when several inputs are followed.
+fn naming(path: &Path, error: io::Error) -> io::Error {
+    let name = if path.as_os_str() == "-" { "stdin".into() } else { path.display().to_string() };
+    io::Error::new(error.kind(), format!("{}: {}", name, error))
+}
+
+/// Absolute form of `path` that stays valid after the file itself is removed.
+fn absolute(path: &Path) -> Option<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    fn append(path: &Path, text: &[u8]) {
+        OpenOptions::new().create(true).append(true).open(path).unwrap().write_all(text).unwrap();
+    }
+
+    fn texts(lines: Vec<Line>) -> Vec<String> {
+        lines.into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn reads_lines_as_they_are_completed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        append(&path, b"one\ntwo\nthr");
+        let mut follower = Follower::open(&path).unwrap();
+        let lines = follower.poll().unwrap();
+        assert_eq!(lines.iter().map(|line| (line.text.as_str(), line.number, line.offset)).collect::<Vec<_>>(), [("one", Some(1), 0), ("two", Some(2), 4)]);
+        assert!(follower.poll().unwrap().is_empty());
+        append(&path, b"ee\r\n");
+        assert_eq!(texts(follower.poll().unwrap()), ["three"]);
+        assert_eq!(follower.position(), 15);
+        assert!(!follower.is_finished());
+    }
+
+    #[test]
+    fn starts_over_when_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        append(&path, b"one\ntwo\nthree\n");
+        let mut follower = Follower::open(&path).unwrap();
+        assert_eq!(follower.poll().unwrap().len(), 3);
+        fs::write(&path, b"four\n").unwrap();
+        let lines = follower.poll().unwrap();
+        assert_eq!(lines.iter().map(|line| (line.text.as_str(), line.number, line.offset)).collect::<Vec<_>>(), [("four", Some(1), 0)]);
+    }
+
+    #[test]
+    fn drains_a_rotated_file_before_the_new_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        append(&path, b"one\n");
+        let mut follower = Follower::open(&path).unwrap();
+        assert_eq!(texts(follower.poll().unwrap()), ["one"]);
+        fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        append(&dir.path().join("app.log.1"), b"two\nlast words");
+        // Until the new file shows up, the old one is read on.
+        assert_eq!(texts(follower.poll().unwrap()), ["two"]);
+        append(&path, b"three\n");
+        assert_eq!(texts(follower.poll().unwrap()), ["last words", "three"]);
+        assert_eq!(follower.checkpoint().map(|checkpoint| checkpoint.position), Some(6));
+    }
+
+    #[test]
+    fn can_skip_the_rest_of_a_rotated_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        append(&path, b"one\n");
+        let mut follower = Follower::open(&path).unwrap();
+        follower.set_drain_rotated(false);
+        follower.poll().unwrap();
+        fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        append(&dir.path().join("app.log.1"), b"two\n");
+        append(&path, b"three\n");
+        assert_eq!(texts(follower.poll().unwrap()), ["three"]);
+    }
+
+    #[test]
+    fn reads_rotated_siblings_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        for (name, text) in [("app.log.2", "oldest\n"), ("app.log.1", "older\n"), ("app.log", "current\n"), ("other.log.1", "unrelated\n")] {
+            append(&dir.path().join(name), text.as_bytes());
+        }
+        let mut follower = Follower::open(&path).unwrap();
+        assert_eq!(follower.include_rotated().unwrap(), 2);
+        assert_eq!(texts(follower.read_to_end().unwrap()), ["oldest", "older", "current"]);
+    }
+
+    #[test]
+    fn tails_the_last_wanted_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let text: String = (1..=5000).map(|n| format!("{} {}\n", if n % 2 == 0 { "entry" } else { "  at frame" }, n)).collect();
+        append(&path, format!("{}partial", text).as_bytes());
+        let mut follower = Follower::open(&path).unwrap();
+        follower.set_count_lines(true);
+        let end = follower.tail(2, |line| Some(line.starts_with("entry"))).unwrap();
+        assert_eq!(end, text.len() as u64);
+        let lines = follower.read_chunk(end - follower.position()).unwrap().unwrap();
+        assert_eq!(lines.iter().map(|line| (line.text.as_str(), line.number)).collect::<Vec<_>>(), [("entry 4998", Some(4998)), ("  at frame 4999", Some(4999)), ("entry 5000", Some(5000))]);
+    }
+
+    #[test]
+    fn follows_utf16_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let utf16 = |text: &str| text.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>();
+        // U+0A0A has a `\n` byte in both halves.
+        append(&path, &utf16("\u{feff}one \u{a0a}\ntwo\n"));
+        let mut follower = Follower::open_encoded(&path, TextEncoding::Auto).unwrap();
+        assert_eq!(texts(follower.poll().unwrap()), ["one \u{a0a}", "two"]);
+        let three = utf16("three\n");
+        append(&path, &three[..5]);
+        assert!(follower.poll().unwrap().is_empty());
+        append(&path, &three[5..]);
+        assert_eq!(texts(follower.poll().unwrap()), ["three"]);
+        assert_eq!(follower.tail(1, |_| Some(true)).unwrap(), 34);
+        assert_eq!(follower.position(), 22);
+    }
+}
@@ -0,0 +1,372 @@
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use rlog::{record_time, severity_rank, trace_context, LogRecord};
+use serde_json::{json, Map, Value};
+use std::{collections::BTreeMap, fmt, io::{self, Write}, net::{TcpStream, UdpSocket}, process::{Command, Stdio}, str::FromStr, sync::mpsc::{self, RecvTimeoutError, Sender}, thread::{self, JoinHandle}, time::{Duration, Instant}};
+
+/// Most records shipped in one request.
+const BATCH_SIZE: usize = 500;
+
+/// Longest a record waits to be shipped with others.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a request may take before it is given up.
+const REQUEST_TIMEOUT: &str = "10";
+
+/// Largest GELF datagram sent in one piece; longer messages are chunked.
+const GELF_CHUNK: usize = 8192;
+
+/// Where `--forward` ships matching records: `loki=URL`, `es=URL/INDEX`,
+/// `gelf=udp://HOST:PORT` (or `tcp://`), or `otlp=URL`.
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// Grafana Loki, through its push API.
+    Loki(String),
+    /// An Elasticsearch or OpenSearch index or data stream, through the bulk API.
+    Es { url: String, index: String },
+    /// A Graylog GELF input.
+    Gelf { address: String, tcp: bool },
+    /// An OpenTelemetry collector, through OTLP/HTTP with JSON encoding.
+    Otlp(String),
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Target, String> {
+        let usage = || format!("expected loki=URL, es=URL/INDEX, gelf=udp://HOST:PORT, or otlp=URL, got `{}`", text);
+        let (kind, value) = text.split_once('=').ok_or_else(usage)?;
+        let http = value.starts_with("http://") || value.starts_with("https://");
+        match kind {
+            "loki" if http => Ok(Target::Loki(value.trim_end_matches('/').to_string())),
+            "otlp" if http => Ok(Target::Otlp(value.trim_end_matches('/').trim_end_matches("/v1/logs").to_string())),
+            "es" if http => {
+                let (scheme, rest) = value.split_once("://").expect("checked above");
+                match rest.split_once('/').filter(|(_, index)| !index.is_empty() && !index.contains('/')) {
+                    Some((host, index)) => Ok(Target::Es { url: format!("{}://{}", scheme, host), index: index.to_string() }),
+                    None => Err(format!("expected es=URL/INDEX such as es=http://localhost:9200/logs-rlog, got `{}`", text)),
+                }
+            }
+            "gelf" => match value.split_once("://") {
+                Some(("udp", address)) => Ok(Target::Gelf { address: address.to_string(), tcp: false }),
+                Some(("tcp", address)) => Ok(Target::Gelf { address: address.to_string(), tcp: true }),
+                _ => Err(usage()),
+            },
+            _ => Err(usage()),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Loki(url) => write!(f, "loki={}", url),
+            Target::Es { url, index } => write!(f, "es={}/{}", url, index),
+            Target::Gelf { address, tcp } => write!(f, "gelf={}://{}", if *tcp { "tcp" } else { "udp" }, address),
+            Target::Otlp(url) => write!(f, "otlp={}", url),
+        }
+    }
+}
+
+/// Ships records to a target on a background thread, in batches, so that a slow or
+/// unreachable target does not hold up the output. Records that cannot be shipped are
+/// dropped with a warning.
+pub struct Forwarder {
+    tx: Sender<LogRecord>,
+    thread: JoinHandle<()>,
+}
+
+impl Forwarder {
+    pub fn start(target: Target) -> Forwarder {
+        let (tx, rx) = mpsc::channel::<LogRecord>();
+        let thread = thread::spawn(move || {
+            let mut sink = Sink { target, tcp: None };
+            let mut batch = Vec::new();
+            let mut deadline = Instant::now() + FLUSH_INTERVAL;
+            loop {
+                let ended = match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(record) => {
+                        batch.push(record);
+                        false
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => true,
+                };
+                if ended || batch.len() >= BATCH_SIZE || Instant::now() >= deadline {
+                    if !batch.is_empty() {
+                        if let Err(e) = sink.ship(&batch) {
+                            let entries = if batch.len() == 1 { "entry" } else { "entries" };
+                            eprintln!("rlog: cannot forward {} {} to {}: {}", batch.len(), entries, sink.target, e);
+                        }
+                        batch.clear();
+                    }
+                    deadline = Instant::now() + FLUSH_INTERVAL;
+                }
+                if ended {
+                    return;
+                }
+            }
+        });
+        Forwarder { tx, thread }
+    }
+
+    pub fn send(&self, record: &LogRecord) {
+        let _ = self.tx.send(record.clone());
+    }
+
+    /// Ships the records still waiting, and returns once they are.
+    pub fn finish(self) {
+        drop(self.tx);
+        let _ = self.thread.join();
+    }
+}
+
+struct Sink {
+    target: Target,
+    /// The connection to a GELF TCP input, kept between batches.
+    tcp: Option<TcpStream>,
+}
+
+impl Sink {
+    fn ship(&mut self, batch: &[LogRecord]) -> io::Result<()> {
+        match &self.target {
+            Target::Loki(url) => {
+                let mut streams: BTreeMap<(String, String), Vec<Value>> = BTreeMap::new();
+                for record in batch {
+                    let labels = (rlog::normalize_level(record.level()).to_lowercase(), record.source.as_deref().unwrap_or("").to_string());
+                    let nanos = time(record).timestamp_nanos_opt().unwrap_or_default();
+                    streams.entry(labels).or_default().push(json!([nanos.to_string(), Value::Object(document(record)).to_string()]));
+                }
+                let streams: Vec<Value> = streams.into_iter().map(|((level, source), values)| {
+                    let mut labels = json!({ "job": "rlog" });
+                    if !level.is_empty() {
+                        labels["level"] = level.into();
+                    }
+                    if !source.is_empty() {
+                        labels["source"] = source.into();
+                    }
+                    json!({ "stream": labels, "values": values })
+                }).collect();
+                post(&format!("{}/loki/api/v1/push", url), "application/json", json!({ "streams": streams }).to_string()).map(drop)
+            }
+            Target::Es { url, index } => {
+                let mut body = String::new();
+                for record in batch {
+                    let mut document = document(record);
+                    document.insert("@timestamp".to_string(), time(record).to_rfc3339_opts(SecondsFormat::AutoSi, true).into());
+                    body.push_str(&json!({ "create": { "_index": index } }).to_string());
+                    body.push('\n');
+                    body.push_str(&Value::Object(document).to_string());
+                    body.push('\n');
+                }
+                let response: Value = serde_json::from_slice(&post(&format!("{}/_bulk", url), "application/x-ndjson", body)?).unwrap_or_default();
+                if response["errors"].as_bool() != Some(true) {
+                    return Ok(());
+                }
+                let items = response["items"].as_array().map_or(&[][..], Vec::as_slice);
+                let failed = items.iter().filter(|item| item.pointer("/create/error").is_some()).count();
+                let reason = items.iter().find_map(|item| item.pointer("/create/error/reason")).and_then(Value::as_str).unwrap_or("unknown error");
+                Err(io::Error::other(format!("{} rejected: {}", failed, reason)))
+            }
+            Target::Otlp(url) => {
+                let records: Vec<Value> = batch.iter().map(otlp).collect();
+                let body = json!({
+                    "resourceLogs": [{
+                        "resource": { "attributes": [attribute("service.name", "rlog")] },
+                        "scopeLogs": [{ "scope": { "name": "rlog" }, "logRecords": records }],
+                    }],
+                });
+                post(&format!("{}/v1/logs", url), "application/json", body.to_string()).map(drop)
+            }
+            Target::Gelf { address, tcp: false } => {
+                let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+                socket.connect(address)?;
+                for record in batch {
+                    for chunk in chunks(gelf(record).to_string().into_bytes()) {
+                        socket.send(&chunk)?;
+                    }
+                }
+                Ok(())
+            }
+            Target::Gelf { address, tcp: true } => {
+                let mut frames = Vec::new();
+                for record in batch {
+                    frames.extend(gelf(record).to_string().into_bytes());
+                    frames.push(0);
+                }
+                if self.tcp.is_none() {
+                    self.tcp = Some(TcpStream::connect(address.as_str())?);
+                }
+                let result = self.tcp.as_mut().expect("connected").write_all(&frames);
+                if result.is_err() {
+                    self.tcp = None;
+                }
+                result
+            }
+        }
+    }
+}
+
+/// POSTs `body` through `curl`, returning the response.
+fn post(url: &str, content_type: &str, body: String) -> io::Result<Vec<u8>> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", REQUEST_TIMEOUT, "--header"])
+        .arg(format!("Content-Type: {}", content_type))
+        .args(["--data-binary", "@-", url])
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot run curl: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    match output.status.success() {
+        true => Ok(output.stdout),
+        false => Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().trim_start_matches("curl: ").to_string())),
+    }
+}
+
+/// When a record happened, or now if it does not tell.
+fn time(record: &LogRecord) -> DateTime<FixedOffset> {
+    record_time(record).unwrap_or_else(|| Utc::now().fixed_offset())
+}
+
+/// The record's columns as a JSON object, with the Data column kept structured.
+fn document(record: &LogRecord) -> Map<String, Value> {
+    record.fields().map(|(header, value)| {
+        let value = match serde_json::from_str(value) {
+            Ok(json @ Value::Object(_)) if header == "Data" => json,
+            _ => Value::String(value.to_string()),
+        };
+        (header.to_string(), value)
+    }).collect()
+}
+
+/// The record as a GELF 1.1 message: Data fields and the other columns become additional
+/// fields.
+fn gelf(record: &LogRecord) -> Value {
+    let message = record.get("Message").filter(|m| !m.is_empty()).unwrap_or(&record.raw);
+    let host = record.get("Host").filter(|h| !h.is_empty()).map_or_else(hostname, str::to_string);
+    let time = time(record);
+    let mut gelf = json!({
+        "version": "1.1",
+        "host": host,
+        "short_message": message,
+        "timestamp": time.timestamp() as f64 + f64::from(time.timestamp_subsec_millis()) / 1000.0,
+    });
+    // Syslog numbers severities from 0 for the most severe.
+    if let Some(rank) = severity_rank(record.level()) {
+        gelf["level"] = (rlog::record::SEVERITIES.len() - 1 - rank).into();
+    }
+    let mut fields = Map::new();
+    if let Some(source) = &record.source {
+        fields.insert("source".to_string(), source.to_string().into());
+    }
+    for (header, value) in document(record) {
+        match (header.as_str(), value) {
+            ("DateTime" | "Level" | "Message" | "Host", _) => {}
+            ("Data", Value::Object(data)) => fields.extend(data),
+            (_, value) => {
+                fields.insert(header, value);
+            }
+        }
+    }
+    for (key, value) in fields {
+        let key: String = key.chars().map(|c| if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') { c } else { '_' }).collect();
+        if key == "id" {
+            continue;
+        }
+        let value = match value {
+            Value::String(_) | Value::Number(_) => value,
+            other => Value::String(other.to_string()),
+        };
+        gelf[format!("_{}", key)] = value;
+    }
+    gelf
+}
+
+/// The record as an OTLP log record, with its trace context and the other columns and
+/// Data fields as attributes.
+fn otlp(record: &LogRecord) -> Value {
+    let message = record.get("Message").filter(|m| !m.is_empty()).unwrap_or(&record.raw);
+    let nanos = time(record).timestamp_nanos_opt().unwrap_or_default();
+    let level = rlog::normalize_level(record.level());
+    let mut attributes = Vec::new();
+    if let Some(source) = &record.source {
+        attributes.push(attribute("log.file.path", source));
+    }
+    for (header, value) in document(record) {
+        match (header.as_str(), value) {
+            ("DateTime" | "Level" | "Message" | "TraceId" | "SpanId", _) => {}
+            ("Data", Value::Object(data)) => attributes.extend(data.into_iter().map(|(key, value)| attribute(&key, &text(value)))),
+            (_, value) => attributes.push(attribute(&header, &text(value))),
+        }
+    }
+    let mut log = json!({
+        "timeUnixNano": nanos.to_string(),
+        "observedTimeUnixNano": Utc::now().timestamp_nanos_opt().unwrap_or_default().to_string(),
+        "severityText": level,
+        "body": { "stringValue": message },
+        "attributes": attributes,
+    });
+    if let Some(number) = severity_number(&level) {
+        log["severityNumber"] = number.into();
+    }
+    if let Some(context) = trace_context(record) {
+        log["traceId"] = context.trace_id.into();
+        if !context.span_id.is_empty() {
+            log["spanId"] = context.span_id.into();
+        }
+    }
+    log
+}
+
+/// An OTLP string attribute.
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// A JSON value as attribute text, without quotes around strings.
+fn text(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+/// The OpenTelemetry severity number of a canonical level.
+fn severity_number(level: &str) -> Option<u8> {
+    Some(match level {
+        "DEBUG" => 5,
+        "INFO" => 9,
+        "NOTICE" => 10,
+        "WARNING" => 13,
+        "ERROR" => 17,
+        "CRITICAL" => 21,
+        "ALERT" => 22,
+        "EMERGENCY" => 23,
+        _ => return None,
+    })
+}
+
+/// The name of this host, for records that do not name theirs.
+fn hostname() -> String {
+    std::fs::read_to_string("/etc/hostname").ok().map(|name| name.trim().to_string())
+        .or_else(|| std::env::var("HOSTNAME").or_else(|_| std::env::var("COMPUTERNAME")).ok())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "rlog".to_string())
+}
+
+/// Splits a GELF message into UDP datagrams, chunked as GELF asks when it is too long.
+fn chunks(message: Vec<u8>) -> Vec<Vec<u8>> {
+    if message.len() <= GELF_CHUNK {
+        return vec![message];
+    }
+    let id = Utc::now().timestamp_nanos_opt().unwrap_or_default().to_be_bytes();
+    let pieces: Vec<&[u8]> = message.chunks(GELF_CHUNK - 12).collect();
+    pieces.iter().enumerate().map(|(idx, piece)| {
+        let mut chunk = vec![0x1e, 0x0f];
+        chunk.extend(id);
+        chunk.extend([idx as u8, pieces.len() as u8]);
+        chunk.extend(*piece);
+        chunk
+    }).collect()
+}
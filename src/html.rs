@@ -0,0 +1,127 @@
+use crate::{cli::DisplayArgs, render::{label, record_color, repeated, visible_cells}};
+use crossterm::style::Color;
+use rlog::{record_time, Shown};
+use std::{fs::File, io::{self, BufWriter, Write}, path::Path};
+
+const STYLE: &str = "body { background: #1e1e1e; color: #d4d4d4; font: 13px monospace; margin: 0; }
+#search { position: sticky; top: 0; width: 100%; box-sizing: border-box; padding: 6px; font: inherit; background: #252526; color: inherit; border: 0; border-bottom: 1px solid #444; }
+table { border-collapse: collapse; width: 100%; }
+th { position: sticky; top: 29px; background: #333; text-align: left; }
+th, td { padding: 2px 8px; white-space: pre-wrap; vertical-align: top; }
+tr.context, tr.raw { opacity: 0.5; }
+tr.break td { border-top: 1px dashed #555; padding: 0; }";
+
+const SCRIPT: &str = "document.getElementById('search').addEventListener('input', function () {
+  var query = this.value.toLowerCase();
+  document.querySelectorAll('tbody tr').forEach(function (row) {
+    row.style.display = row.textContent.toLowerCase().includes(query) ? '' : 'none';
+  });
+});";
+
+/// Writes the shown records as a standalone HTML page with level colors, a sticky header
+/// row, and a search box.
+pub fn export(path: &Path, shown: &[Shown], display: &DisplayArgs) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>", escape(&title(display)))?;
+    writeln!(out, "<style>\n{}\n</style>\n</head>\n<body>", STYLE)?;
+    writeln!(out, "<input id=\"search\" placeholder=\"Search\" autofocus>\n<table>")?;
+
+    let first = shown.iter().find_map(|s| match s {
+        Shown::Match(record) | Shown::Context(record) if !record.malformed => Some(record),
+        _ => None,
+    });
+    let sources = display.sources.len() > 1 && !display.source_column;
+    if let Some(record) = first {
+        write!(out, "<thead><tr>")?;
+        if sources {
+            write!(out, "<th>Source</th>")?;
+        }
+        for (header, _, _) in visible_cells(record, display) {
+            write!(out, "<th>{}</th>", escape(label(&header, display)))?;
+        }
+        writeln!(out, "</tr></thead>")?;
+    }
+
+    writeln!(out, "<tbody>")?;
+    for entry in shown {
+        let (record, class) = match entry {
+            Shown::Match(record) => (record, ""),
+            Shown::Context(record) => (record, " class=\"context\""),
+            Shown::Break => {
+                writeln!(out, "<tr class=\"break\"><td colspan=\"100\"></td></tr>")?;
+                continue;
+            }
+            Shown::Repeated(count) => {
+                writeln!(out, "<tr class=\"raw\"><td colspan=\"100\">{}</td></tr>", escape(&repeated(*count)))?;
+                continue;
+            }
+        };
+        if record.malformed {
+            writeln!(out, "<tr class=\"raw\"><td colspan=\"100\">{}</td></tr>", escape(&record.raw))?;
+            continue;
+        }
+        write!(out, "<tr{} style=\"color: {}\">", class, css_color(record_color(record, display)))?;
+        if sources {
+            write!(out, "<td>{}</td>", escape(record.source.as_deref().unwrap_or("")))?;
+        }
+        for (_, value, _) in visible_cells(record, display) {
+            write!(out, "<td>{}</td>", escape(&value))?;
+        }
+        writeln!(out, "</tr>")?;
+        display.previous_time.set(record_time(record));
+    }
+    writeln!(out, "</tbody>\n</table>\n<script>\n{}\n</script>\n</body>\n</html>", SCRIPT)?;
+    out.flush()
+}
+
+fn title(display: &DisplayArgs) -> String {
+    match display.sources.as_slice() {
+        [] => "rlog".to_string(),
+        sources => format!("rlog: {}", sources.join(", ")),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The CSS equivalent of a terminal color, approximating the usual xterm palette.
+fn css_color(color: Color) -> String {
+    const BASIC: [&str; 16] = [
+        "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+        "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+    ];
+    let index = match color {
+        Color::Reset => return "inherit".to_string(),
+        Color::Rgb { r, g, b } => return format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+        Color::AnsiValue(n) => n,
+    };
+    match index {
+        0..=15 => BASIC[index as usize].to_string(),
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = index - 16;
+            format!("#{:02x}{:02x}{:02x}", level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let grey = 8 + (index - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", grey, grey, grey)
+        }
+    }
+}
@@ -0,0 +1,534 @@
+use crate::{cli::InputArgs, error::{self, Error}, state::State, POLL_INTERVAL};
+use chrono::{DateTime, FixedOffset};
+use memchr::memmem::Finder;
+use rayon::prelude::*;
+use rlog::{expand_pattern, TimestampedParser, file_identity, merge_by_datetime, parse_datetime_in, parse_with_format_in, record_time, trace_context, Filter, Line, LogParser, LogRecord, MultiFollower, TimeBound, Zone};
+use std::{collections::{HashSet, VecDeque}, io::{self, ErrorKind}, path::{Path, PathBuf}, sync::{mpsc::Sender, Arc}, time::Duration};
+
+/// How often watched inputs are re-checked without a notification.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Number of lines parsed together by one thread.
+const PARALLEL_CHUNK: usize = 16 * 1024;
+
+/// Number of bytes of an input read and parsed at once when reading it through.
+const READ_CHUNK: u64 = 16 * 1024 * 1024;
+
+/// Number of lines read from the top of a file to detect its format.
+const FORMAT_SAMPLE: usize = 10;
+
+/// The inputs named on the command line, each with its own parser state.
+pub struct Inputs {
+    follower: MultiFollower,
+    parsers: Vec<Box<dyn LogParser + Send>>,
+    labels: Vec<Arc<str>>,
+    time_format: Option<String>,
+    tz: Option<Zone>,
+    /// Zone of DateTime values without an offset, with `--log-tz`.
+    log_tz: Zone,
+    merge: bool,
+    /// Whether lines that are not records continue the record before them, with `--multiline`.
+    multiline: bool,
+    /// Whether records get TraceId and SpanId columns, with `--trace`.
+    trace: bool,
+    /// The last record read from each input, which a continuation at the start of the next
+    /// poll belongs to.
+    previous: Vec<Option<LogRecord>>,
+    /// Whether malformed lines become records of their own, with `--show-raw`.
+    show_raw: bool,
+    /// Whether a malformed line is an error, with `--strict`.
+    strict: bool,
+    /// Number of lines parsed so far.
+    read: u64,
+    /// Number of lines that could not be parsed so far.
+    malformed: u64,
+    /// Whether whole files are read through a memory map, with `--fast`.
+    fast: bool,
+    /// Substring lines must contain to be parsed at all, with `--fast`.
+    prefilter: Option<Finder<'static>>,
+    /// Whether filesystem notifications are active, so waiting need not poll as often.
+    watching: bool,
+    /// The `--state-file` and what it holds.
+    state: Option<(PathBuf, State)>,
+    /// Whether each input carries on from the state file.
+    resumed: Vec<bool>,
+    /// What new files are looked for in, with `--watch-dir`.
+    discovery: Option<Discovery>,
+    /// The last record of each input read by [`read_chunk`](Self::read_chunk), held back in
+    /// case the next part starts with lines that continue it.
+    held: Vec<Option<LogRecord>>,
+    /// Records of each input read by [`read_chunk`](Self::read_chunk) but not returned yet,
+    /// and whether the input has been read to its end.
+    queued: Vec<(VecDeque<LogRecord>, bool)>,
+}
+
+/// The directories and patterns files are looked for in while following, with `--watch-dir`.
+struct Discovery {
+    /// The input options, to open and parse new files as the others.
+    args: InputArgs,
+    /// Files already considered, by path and inode, so that each is only followed once.
+    known: HashSet<(PathBuf, (u64, u64))>,
+}
+
+impl Inputs {
+    /// Opens every input for following.
+    pub fn open(args: &InputArgs) -> io::Result<Self> {
+        let mut follower = MultiFollower::open_encoded(&args.files, args.encoding)?;
+        for f in follower.followers_mut() {
+            f.set_drain_rotated(!args.rotate_immediately);
+        }
+        if args.include_rotated {
+            follower.include_rotated()?;
+        }
+        follower.watch_dirs(args.patterns.iter().map(|pattern| if pattern.is_dir() { pattern.clone() } else { directory(pattern) }));
+        for remote in &args.remotes {
+            follower.add_reader(remote.to_string(), remote.reader());
+        }
+        for endpoint in &args.endpoints {
+            follower.add_reader(endpoint.to_string(), endpoint.open()?);
+        }
+        for search in &args.searches {
+            follower.add_reader(search.to_string(), search.open()?);
+        }
+        for log in &args.event_logs {
+            follower.add_reader(log.to_string(), log.open()?);
+        }
+        for container in &args.containers {
+            follower.add_reader(container.to_string(), container.open()?);
+        }
+        for group in &args.log_groups {
+            follower.add_reader(group.to_string(), group.open()?);
+        }
+        for pods in &args.pods {
+            follower.add_reader(pods.to_string(), pods.open()?);
+        }
+        for spawned in &args.spawned {
+            follower.add_reader(&spawned.label, spawned.open()?);
+        }
+        let watching = !args.poll && follower.watch();
+        let labels: Vec<Arc<str>> = args.files.iter()
+            .map(|path| if path.as_os_str() == "-" { Arc::from("stdin") } else { Arc::from(path.display().to_string()) })
+            .chain(args.remotes.iter().map(|remote| Arc::from(remote.to_string())))
+            .chain(args.endpoints.iter().map(|endpoint| Arc::from(endpoint.to_string())))
+            .chain(args.searches.iter().map(|search| Arc::from(search.to_string())))
+            .chain(args.event_logs.iter().map(|log| Arc::from(log.to_string())))
+            .chain(args.containers.iter().map(|container| Arc::from(container.to_string())))
+            .chain(args.log_groups.iter().map(|group| Arc::from(group.to_string())))
+            .chain(args.pods.iter().map(|pods| Arc::from(pods.to_string())))
+            .chain(args.spawned.iter().map(|spawned| Arc::from(spawned.label.as_str())))
+            .collect();
+        // Whether the lines of each input start with a timestamp, and then the log stream.
+        let stamped: Vec<(bool, bool)> = vec![(false, false); labels.len() - args.containers.len() - args.log_groups.len() - args.pods.len() - args.spawned.len()].into_iter()
+            .chain(args.containers.iter().map(|_| (true, false)))
+            .chain(args.log_groups.iter().map(|_| (true, true)))
+            .chain(args.pods.iter().map(|_| (true, false)))
+            .chain(args.spawned.iter().map(|spawned| (spawned.timestamped, false)))
+            .collect();
+        let mut parsers = Vec::new();
+        for ((follower, label), stamped) in follower.followers_mut().iter_mut().zip(&labels).zip(stamped) {
+            let parser = args.parser(label, follower.head(FORMAT_SAMPLE)?.as_deref());
+            parsers.push(match stamped {
+                (true, true) => Box::new(TimestampedParser::new(parser).with_stream()),
+                (true, false) => Box::new(TimestampedParser::new(parser)),
+                (false, _) => parser,
+            });
+        }
+        let state = args.state_file.as_ref().map(|path| State::load(path).map(|state| (path.clone(), state))).transpose()?;
+        let mut resumed = vec![false; parsers.len()];
+        for (idx, file) in args.files.iter().enumerate() {
+            if let Some(checkpoint) = state.as_ref().and_then(|(_, state)| state.get(file)) {
+                resumed[idx] = true;
+                // Reading carries on past the header line, so the parser learns it here.
+                if let Some(header) = follower.followers_mut()[idx].first_line()? {
+                    parsers[idx].parse(&header);
+                }
+                follower.resume(idx, checkpoint)?;
+            }
+        }
+        let previous = vec![None; parsers.len()];
+        let discovery = (!args.patterns.is_empty()).then(|| Discovery {
+            args: args.clone(),
+            known: follower.followers().iter().filter_map(|f| Some((f.path().to_path_buf(), f.checkpoint()?.file))).collect(),
+        });
+        let mut inputs = Inputs {
+            follower, parsers, labels, time_format: args.time_format.clone(), tz: args.tz, log_tz: args.log_tz.unwrap_or_default(), merge: args.merge,
+            multiline: args.multiline, trace: args.trace, previous, show_raw: args.show_raw, strict: args.strict, read: 0, malformed: 0,
+            fast: args.fast, prefilter: None, watching, state, resumed, discovery, held: Vec::new(), queued: Vec::new(),
+        };
+        if args.strict {
+            // So the error can tell where the line is.
+            inputs.count_lines();
+        }
+        Ok(inputs)
+    }
+
+    /// Labels identifying each input, in command-line order.
+    pub fn labels(&self) -> &[Arc<str>] {
+        &self.labels
+    }
+
+    /// Keeps line numbers known when skipping to the end, at the cost of reading whole files.
+    pub fn count_lines(&mut self) {
+        self.follower.followers_mut().iter_mut().for_each(|f| f.set_count_lines(true));
+    }
+
+    /// Number of lines read and parsed so far, and how many of them could not be parsed.
+    pub fn line_counts(&self) -> (u64, u64) {
+        (self.read, self.malformed)
+    }
+
+    /// Moves to the end of the files that do not carry on from the `--state-file`, so that
+    /// only entries added from now on are read from them.
+    pub fn skip_to_end(&mut self) -> io::Result<()> {
+        for (idx, follower) in self.follower.followers_mut().iter_mut().enumerate() {
+            if self.resumed[idx] {
+                continue;
+            }
+            if let Some(header) = follower.first_line()? {
+                self.parsers[idx].parse(&header);
+            }
+            follower.tail(0, |_| Some(false))?;
+        }
+        Ok(())
+    }
+
+    /// Records in the `--state-file` where each file was read up to.
+    pub fn save_state(&mut self) -> error::Result<()> {
+        let Some((path, state)) = &mut self.state else { return Ok(()) };
+        for follower in self.follower.followers() {
+            if let Some(checkpoint) = follower.checkpoint() {
+                state.set(follower.path(), checkpoint);
+            }
+        }
+        state.save(path).map_err(|e| Error::output(Some(path), e))
+    }
+
+    /// Tells on stderr how many lines could not be parsed, if any.
+    pub fn report(&self) {
+        report(self.malformed, self.show_raw);
+    }
+
+    /// Blocks until new data may be available, or `limit` has passed. Inputs are still
+    /// re-checked periodically in case a notification was missed.
+    pub fn wait(&self, limit: Duration) {
+        self.follower.wait(limit.min(if self.watching { WATCH_INTERVAL } else { POLL_INTERVAL }));
+    }
+
+    /// A handle that interrupts [`wait`](Self::wait) when sent to.
+    pub fn waker(&self) -> Sender<()> {
+        self.follower.waker()
+    }
+
+    /// Whether new data was signalled since the last call, without blocking.
+    pub fn changed(&self) -> bool {
+        self.follower.wait(Duration::ZERO)
+    }
+
+    /// Whether every input has ended, so following can stop. With `--watch-dir`, more may
+    /// still appear.
+    pub fn is_finished(&self) -> bool {
+        self.discovery.is_none() && self.follower.is_finished()
+    }
+
+    /// Reads and parses everything appended to any input since the last call. With
+    /// `--watch-dir`, new files are followed from then on, and removed ones are dropped
+    /// once read to their end.
+    pub fn poll(&mut self) -> io::Result<Vec<LogRecord>> {
+        self.discover()?;
+        let lines = self.follower.poll()?;
+        let records = self.parse(lines)?;
+        self.drop_removed();
+        Ok(records)
+    }
+
+    /// Starts following the files that appeared in the `--watch-dir` directories and
+    /// patterns. A file that is already followed under another name, such as a rotated copy,
+    /// is left to the follower that has it.
+    fn discover(&mut self) -> io::Result<()> {
+        let Some(discovery) = &mut self.discovery else { return Ok(()) };
+        for pattern in &discovery.args.patterns {
+            // The directory may be gone for now; it is looked in again on the next poll.
+            for path in expand_pattern(pattern).unwrap_or_default() {
+                let Ok(Some(id)) = file_identity(&path) else { continue };
+                if !discovery.known.insert((path.clone(), id)) || self.follower.followers().iter().any(|f| f.checkpoint().is_some_and(|c| c.file == id)) {
+                    continue;
+                }
+                let idx = self.follower.add(&path, discovery.args.encoding)?;
+                let label: Arc<str> = Arc::from(path.display().to_string());
+                eprintln!("rlog: following new file {}", label);
+                self.parsers.push(discovery.args.parser(&label, self.follower.followers_mut()[idx].head(FORMAT_SAMPLE)?.as_deref()));
+                self.labels.push(label);
+                self.previous.push(None);
+                self.resumed.push(false);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops following the `--watch-dir` files whose path is gone, which were just read to
+    /// their end.
+    fn drop_removed(&mut self) {
+        let Some(discovery) = &mut self.discovery else { return };
+        for idx in (0..self.labels.len()).rev() {
+            let follower = &self.follower.followers()[idx];
+            if !follower.is_seekable() || follower.path().exists() {
+                continue;
+            }
+            discovery.known.retain(|(path, _)| path != follower.path());
+            self.follower.remove(idx);
+            self.parsers.remove(idx);
+            self.previous.remove(idx);
+            self.resumed.remove(idx);
+            eprintln!("rlog: {} was removed", self.labels.remove(idx));
+        }
+    }
+
+    /// Reads and parses the next part of the inputs, waiting for piped input, so that they
+    /// can be worked through without holding all of them in memory. Inputs are read one after
+    /// the other, or with `--merge` side by side, their records interleaved by DateTime.
+    /// `None` once everything has been read.
+    pub fn read_chunk(&mut self) -> io::Result<Option<Vec<LogRecord>>> {
+        self.held.resize(self.parsers.len(), None);
+        self.queued.resize(self.parsers.len(), (VecDeque::new(), false));
+        if !self.merge {
+            let Some(idx) = self.queued.iter().position(|(_, done)| !done) else { return Ok(None) };
+            return Ok(Some(match self.read_input(idx)? {
+                Some(records) => records,
+                None => {
+                    self.queued[idx].1 = true;
+                    self.held[idx].take().into_iter().collect()
+                }
+            }));
+        }
+        // A record can only be passed on once every input that may still have an earlier one
+        // has been read past it.
+        for idx in 0..self.parsers.len() {
+            while self.queued[idx].0.is_empty() && !self.queued[idx].1 {
+                match self.read_input(idx)? {
+                    Some(records) => self.queued[idx].0.extend(records),
+                    None => {
+                        let held = self.held[idx].take();
+                        self.queued[idx] = (held.into_iter().collect(), true);
+                    }
+                }
+            }
+        }
+        let mut merged = Vec::new();
+        while self.queued.iter().all(|(queue, done)| *done || !queue.is_empty()) {
+            let next = self.queued.iter().enumerate()
+                .filter_map(|(idx, (queue, _))| Some((idx, record_time(queue.front()?))))
+                .min_by(|(_, a), (_, b)| a.cmp(b));
+            let Some((idx, _)) = next else { break };
+            merged.extend(self.queued[idx].0.pop_front());
+        }
+        Ok((!merged.is_empty()).then_some(merged))
+    }
+
+    /// Reads and parses the next part of one input, holding back its last record if the
+    /// lines after it may still continue it.
+    fn read_input(&mut self, idx: usize) -> io::Result<Option<Vec<LogRecord>>> {
+        let lines = match (self.fast, &self.prefilter) {
+            (true, Some(finder)) => self.follower.read_mapped(idx, READ_CHUNK, |line| finder.find(line).is_some())?,
+            (true, None) => self.follower.read_mapped(idx, READ_CHUNK, |_| true)?,
+            (false, _) => self.follower.read_chunk(idx, READ_CHUNK)?,
+        };
+        let Some(lines) = lines else { return Ok(None) };
+        self.read += lines.len() as u64;
+        let held = self.held[idx].take();
+        let mut records = self.parse_batch(idx, &lines, held)?;
+        if self.multiline || self.parsers[idx].multiline() {
+            self.held[idx] = records.pop();
+        }
+        Ok(Some(records))
+    }
+
+    /// With `--fast`, lets [`read_chunk`](Self::read_chunk) drop lines that cannot contain
+    /// the `--filter` word before they are parsed. Only valid when records outside `filter`
+    /// are not shown as context.
+    pub fn prefilter(&mut self, filter: &Filter) -> io::Result<()> {
+        let word = filter.word.as_deref().filter(|_| self.fast && !filter.ignore_case && !filter.fuzzy);
+        self.prefilter = word.map(|word| Finder::new(word.as_bytes()).into_owned());
+        if self.prefilter.is_some() {
+            // Header lines would be dropped too, so let the parsers see them first.
+            for (follower, parser) in self.follower.followers_mut().iter_mut().zip(&mut self.parsers) {
+                if let Some(header) = follower.first_line()? {
+                    parser.parse(&header);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips to the end of every input, returning the last `count` matching records of each.
+    ///
+    /// Each file is looked through backwards by a copy of its parser, to find where those
+    /// records start, and read forward from there as usual, so that the parser itself sees
+    /// the lines in order. Parsers that cannot be copied keep state from line to line, which
+    /// cannot be known going backwards, so their files are read from the start instead.
+    pub fn tail(&mut self, count: usize, filter: &Filter) -> io::Result<Vec<LogRecord>> {
+        let mut batches = Vec::new();
+        for idx in 0..self.parsers.len() {
+            let end = {
+                let (follower, parser) = (&mut self.follower.followers_mut()[idx], &mut self.parsers[idx]);
+                if let Some(header) = follower.first_line()? {
+                    parser.parse(&header);
+                }
+                let (format, zone) = (self.time_format.as_deref(), self.log_tz);
+                match parser.fork() {
+                    Some(mut probe) => follower.tail(count, |text| probe.parse(text).map(|mut r| {
+                        r.time = parse_time(&r, format, zone);
+                        filter.matches(&r)
+                    }))?,
+                    None if follower.is_seekable() => u64::MAX,
+                    None => follower.position(),
+                }
+            };
+            let multiline = self.multiline || self.parsers[idx].multiline();
+            let (mut records, mut held) = (VecDeque::new(), None);
+            while let Some(left) = end.checked_sub(self.follower.followers()[idx].position()).filter(|&left| left > 0) {
+                let Some(lines) = self.follower.read_chunk(idx, left.min(READ_CHUNK))? else { break };
+                self.read += lines.len() as u64;
+                let mut parsed = self.parse_batch(idx, &lines, held.take())?;
+                if multiline {
+                    held = parsed.pop();
+                }
+                records.extend(parsed.into_iter().filter(|record| filter.matches(record)));
+                records.drain(..records.len().saturating_sub(count));
+            }
+            records.extend(held.filter(|record| filter.matches(record)));
+            records.drain(..records.len().saturating_sub(count));
+            batches.push(Vec::from(records));
+        }
+        Ok(if self.merge { merge_by_datetime(batches) } else { batches.concat() })
+    }
+
+    /// Skips ahead in each file to shortly before the `--since` time of `filter`, see
+    /// [`Follower::seek_to`](rlog::Follower::seek_to). Entries must be in time order.
+    pub fn seek(&mut self, filter: &Filter) -> io::Result<()> {
+        let Some(TimeBound::At(start)) = filter.from else { return Ok(()) };
+        for (idx, follower) in self.follower.followers_mut().iter_mut().enumerate() {
+            let parser = &mut self.parsers[idx];
+            if let Some(header) = follower.first_line()? {
+                parser.parse(&header);
+            }
+            let (format, zone) = (self.time_format.as_deref(), self.log_tz);
+            follower.seek_to(|text| Some(parse_time(&parser.parse(text)?, format, zone)? < start))?;
+        }
+        Ok(())
+    }
+
+    /// Parses a batch of lines. With `--merge` the records are interleaved by DateTime;
+    /// otherwise they come file by file.
+    fn parse(&mut self, lines: Vec<(usize, Line)>) -> io::Result<Vec<LogRecord>> {
+        self.read += lines.len() as u64;
+        let mut batches = vec![Vec::new(); self.parsers.len()];
+        for (idx, line) in lines {
+            batches[idx].push(line);
+        }
+        let batches = batches.into_iter().enumerate().map(|(idx, lines)| self.parse_batch(idx, &lines, None)).collect::<io::Result<_>>()?;
+        Ok(if self.merge { merge_by_datetime(batches) } else { batches.concat() })
+    }
+
+    /// Parses the lines of one input. Large batches are split into chunks, and once the first
+    /// chunk has shown the parser any header line, the others are parsed in parallel by copies
+    /// of it. Continuation lines at the start belong to `held`, the record read before them,
+    /// if it has not been passed on yet.
+    fn parse_batch<'a>(&mut self, idx: usize, lines: &'a [Line], held: Option<LogRecord>) -> io::Result<Vec<LogRecord>> {
+        let (label, format, tz, log_tz, trace) = (&self.labels[idx], self.time_format.as_deref(), self.tz, self.log_tz, self.trace);
+        // Lines that are not records are kept as `Err`, except blank and header lines.
+        let parse = |parser: &mut Box<dyn LogParser + Send>, line: &'a Line| {
+            let Some(mut record) = parser.parse(&line.text) else {
+                return (!line.text.trim().is_empty() && !parser.is_header(&line.text)).then_some(Err(line));
+            };
+            record.source = Some(label.clone());
+            record.line = line.number;
+            record.offset = Some(line.offset);
+            record.time = parse_time(&record, format, log_tz);
+            if let (Some(zone), Some(time)) = (tz, record.time) {
+                record.set("DateTime", zone.convert(&time).format("%Y-%m-%d %H:%M:%S%.f").to_string());
+            }
+            if trace {
+                let context = trace_context(&record).unwrap_or_default();
+                record.set("TraceId", context.trace_id);
+                record.set("SpanId", context.span_id);
+            }
+            Some(Ok(record))
+        };
+        let parser = &mut self.parsers[idx];
+        let mut chunks = lines.chunks(PARALLEL_CHUNK);
+        let mut parsed: Vec<Result<LogRecord, &'a Line>> = chunks.next().unwrap_or_default().iter().filter_map(|line| parse(parser, line)).collect();
+        let rest: Vec<&[Line]> = chunks.collect();
+        match rest.iter().map(|_| parser.fork()).collect::<Option<Vec<_>>>() {
+            Some(forks) => parsed.par_extend(rest.into_par_iter().zip(forks).flat_map_iter(|(chunk, mut fork)| {
+                chunk.iter().filter_map(|line| parse(&mut fork, line)).collect::<Vec<_>>()
+            })),
+            None => parsed.extend(rest.into_iter().flatten().filter_map(|line| parse(parser, line))),
+        }
+        let multiline = self.multiline || self.parsers[idx].multiline();
+        let mut records: Vec<LogRecord> = Vec::with_capacity(parsed.len() + 1);
+        records.extend(held);
+        for item in parsed {
+            match item {
+                Ok(record) => records.push(record),
+                Err(line) if !multiline => {
+                    self.malformed += 1;
+                    if self.strict {
+                        let place = line.number.map_or_else(|| format!(" at byte {}", line.offset), |number| format!(":{}", number));
+                        return Err(io::Error::new(ErrorKind::InvalidData, format!("{}{}: cannot parse line: {}", label, place, line.text)));
+                    }
+                    if self.show_raw {
+                        let mut record = LogRecord::new(line.text.as_str());
+                        record.malformed = true;
+                        record.source = Some(label.clone());
+                        record.line = line.number;
+                        record.offset = Some(line.offset);
+                        records.push(record);
+                    }
+                }
+                Err(line) => {
+                    if records.is_empty() {
+                        // The record this continues was read earlier, so its lines come out
+                        // as a record of their own with the same DateTime and Level.
+                        let Some(mut record) = self.previous[idx].clone() else { continue };
+                        record.raw.clear();
+                        record.set("Message", "");
+                        record.line = line.number;
+                        record.offset = Some(line.offset);
+                        records.push(record);
+                    }
+                    self.parsers[idx].continue_record(records.last_mut().unwrap(), &line.text);
+                }
+            }
+        }
+        if let (true, Some(last)) = (multiline, records.last()) {
+            self.previous[idx] = Some(last.clone());
+        }
+        Ok(records)
+    }
+}
+
+/// Tells on stderr that `malformed` lines could not be parsed, if any.
+pub fn report(malformed: u64, show_raw: bool) {
+    if malformed > 0 {
+        let plural = if malformed == 1 { "" } else { "s" };
+        let hint = if show_raw { "" } else { " (--show-raw shows them)" };
+        eprintln!("rlog: {} line{} could not be parsed{}", malformed, plural, hint);
+    }
+}
+
+/// The directory a wildcard pattern looks in.
+fn directory(pattern: &Path) -> PathBuf {
+    match pattern.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Parses the DateTime column with `--time-format`, or by recognizing common formats, reading
+/// times without an offset in `zone`.
+fn parse_time(record: &LogRecord, format: Option<&str>, zone: Zone) -> Option<DateTime<FixedOffset>> {
+    match format {
+        Some(format) => parse_with_format_in(record.datetime(), format, zone),
+        None => parse_datetime_in(record.datetime(), zone),
+    }
+}
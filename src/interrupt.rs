@@ -0,0 +1,50 @@
+use crossterm::{cursor::Show, execute, style::{Attribute, ResetColor, SetAttribute}, terminal::{disable_raw_mode, LeaveAlternateScreen}};
+use std::{io::{self, IsTerminal, Write}, process, sync::{atomic::{AtomicBool, Ordering}, mpsc::Sender, Arc, LazyLock}};
+
+/// Set once Ctrl+C (or SIGTERM) arrives.
+static INTERRUPTED: LazyLock<Arc<AtomicBool>> = LazyLock::new(Arc::default);
+
+/// Exit status after a second interrupt, as shells report a process killed by SIGINT.
+const FORCED_EXIT: i32 = 130;
+
+/// Catches Ctrl+C and SIGTERM so that commands stop after the record being printed, finish
+/// their output, and report as usual. `wake` is signalled so that a follow waiting for new
+/// lines notices at once. A second interrupt restores the terminal and exits immediately.
+///
+/// If the handler cannot be installed, interrupts keep their default behavior.
+#[cfg(unix)]
+pub fn install(wake: Sender<()>) {
+    use signal_hook::{consts::{SIGINT, SIGTERM}, iterator::Signals};
+    let Ok(mut signals) = Signals::new([SIGINT, SIGTERM]) else { return };
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            if INTERRUPTED.swap(true, Ordering::SeqCst) {
+                force_exit();
+            }
+            let _ = wake.send(());
+        }
+    });
+}
+
+/// Catches Ctrl+C; without a signal thread, a follow notices at its next periodic check.
+#[cfg(not(unix))]
+pub fn install(_wake: Sender<()>) {
+    let _ = signal_hook::flag::register(signal_hook::consts::SIGINT, INTERRUPTED.clone());
+}
+
+/// Whether the user asked rlog to stop.
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Leaves the terminal as it was before rlog started, even if a row was cut off in the
+/// middle of its colors, then exits.
+fn force_exit() -> ! {
+    let mut stdout = io::stdout();
+    if stdout.is_terminal() {
+        let _ = disable_raw_mode();
+        let _ = execute!(stdout, ResetColor, SetAttribute(Attribute::Reset), LeaveAlternateScreen, Show);
+        let _ = writeln!(stdout);
+    }
+    process::exit(FORCED_EXIT);
+}
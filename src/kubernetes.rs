@@ -0,0 +1,306 @@
+use crate::channel::{self, Lines};
+use chrono::{DateTime, FixedOffset};
+use futures_util::{io::AsyncBufReadExt, StreamExt};
+use k8s_openapi::{api::{apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet}, batch::v1::Job, core::v1::{Pod, Service}}, apimachinery::pkg::apis::meta::v1::LabelSelector, jiff::Timestamp, NamespaceResourceScope};
+use kube::{api::{ListParams, LogParams}, config::KubeConfigOptions, runtime::{watcher::{self, watcher, Event}, WatchStreamExt}, Api, Client, Config, Resource};
+use rlog::parse_datetime;
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, error::Error, fmt::{self, Debug}, io, sync::mpsc::Sender, thread, time::Duration};
+use tokio::{runtime::Builder, task::JoinHandle};
+
+/// Longest pause before the log of a container is asked for again.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Annotation naming the container `kubectl logs` reads when none is given.
+const DEFAULT_CONTAINER: &str = "kubectl.kubernetes.io/default-container";
+
+/// Pods whose containers' logs are read through the Kubernetes API, from `rlog k8s`: each
+/// line starting with `[pod/NAME/CONTAINER]` and its timestamp, as `kubectl logs --prefix
+/// --timestamps` writes them. The cluster and the credentials are found as kubectl finds
+/// them, through the kubeconfig (`KUBECONFIG` or `~/.kube/config`) with its `exec`
+/// credential plugins, or else the service account of the pod rlog runs in.
+///
+/// A TYPE/NAME target such as `deploy/api` stands for the pods its selector selects. Those,
+/// and the pods of a label selector, are watched while following: pods that come up are
+/// read from their first line, and those that go away are dropped. The log stream of a
+/// container ends when it exits; it is asked for again, with growing pauses, from the last
+/// line read on, so that a restarted container is read on.
+#[derive(Debug, Clone)]
+pub struct Pods {
+    /// A pod name, or TYPE/NAME.
+    pub target: Option<String>,
+    /// A label selector, such as `app=api`, the pods must match.
+    pub selector: Option<String>,
+    /// Namespace of the pods; the one of the kubeconfig context if not given.
+    pub namespace: Option<String>,
+    /// Read this container of each pod, instead of its default one.
+    pub container: Option<String>,
+    pub all_containers: bool,
+    /// kubeconfig context to use, instead of the current one.
+    pub context: Option<String>,
+    pub since: Option<DateTime<FixedOffset>>,
+    /// Start with only the last N lines of each container instead of all of them.
+    pub last: Option<usize>,
+    pub follow: bool,
+}
+
+impl fmt::Display for Pods {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.target.as_deref().or(self.selector.as_deref()).unwrap_or_default())
+    }
+}
+
+/// The pods read, as label and field selectors.
+#[derive(Debug, Clone, Default)]
+struct Selection {
+    labels: Option<String>,
+    fields: Option<String>,
+}
+
+impl Selection {
+    fn list(&self) -> ListParams {
+        ListParams { label_selector: self.labels.clone(), field_selector: self.fields.clone(), ..ListParams::default() }
+    }
+
+    fn watch(&self) -> watcher::Config {
+        watcher::Config { label_selector: self.labels.clone(), field_selector: self.fields.clone(), ..watcher::Config::default() }
+    }
+}
+
+impl Pods {
+    /// Connects to the cluster and checks that the pods can be found, and reads their logs
+    /// on a background thread from then on.
+    pub fn open(&self) -> io::Result<Lines> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let (api, selection) = runtime.block_on(self.connect()).map_err(|e| io::Error::other(format!("cannot read the logs of {}: {}", self, e)))?;
+        let (tx, lines) = channel::lines();
+        let pods = self.clone();
+        thread::spawn(move || runtime.block_on(pods.run(api, selection, tx)));
+        Ok(lines)
+    }
+
+    /// Loads the kubeconfig and resolves the target into the pods it stands for.
+    async fn connect(&self) -> Result<(Api<Pod>, Selection), String> {
+        let config = match &self.context {
+            Some(context) => Config::from_kubeconfig(&KubeConfigOptions { context: Some(context.clone()), ..KubeConfigOptions::default() }).await.map_err(|e| chain(&e))?,
+            None => Config::infer().await.map_err(|e| chain(&e))?,
+        };
+        let namespace = self.namespace.clone().unwrap_or_else(|| config.default_namespace.clone());
+        let client = Client::try_from(config).map_err(describe)?;
+        let api: Api<Pod> = Api::namespaced(client.clone(), &namespace);
+        let target = self.target.as_deref().map(|target| target.split_once('/').unwrap_or(("pod", target)));
+        let selector = match target.map(|(kind, name)| (kind.to_lowercase(), name)) {
+            None => None,
+            Some((kind, name)) if matches!(kind.as_str(), "pod" | "pods" | "po") => {
+                let pod = api.get(name).await.map_err(describe)?;
+                if let Some(container) = self.container.as_ref().filter(|container| !containers(&pod).contains(container)) {
+                    return Err(format!("pod {} has no container {}", name, container));
+                }
+                let selection = Selection { labels: self.selector.clone(), fields: Some(format!("metadata.name={}", name)) };
+                return Ok((api, selection));
+            }
+            Some((kind, name)) => Some(match kind.as_str() {
+                "deployment" | "deployments" | "deploy" => label_selector(&get::<Deployment>(&client, &namespace, name).await?.spec.unwrap_or_default().selector),
+                "statefulset" | "statefulsets" | "sts" => label_selector(&get::<StatefulSet>(&client, &namespace, name).await?.spec.unwrap_or_default().selector),
+                "daemonset" | "daemonsets" | "ds" => label_selector(&get::<DaemonSet>(&client, &namespace, name).await?.spec.unwrap_or_default().selector),
+                "replicaset" | "replicasets" | "rs" => label_selector(&get::<ReplicaSet>(&client, &namespace, name).await?.spec.unwrap_or_default().selector),
+                "job" | "jobs" => label_selector(&get::<Job>(&client, &namespace, name).await?.spec.and_then(|spec| spec.selector).unwrap_or_default()),
+                "service" | "services" | "svc" => {
+                    let labels = get::<Service>(&client, &namespace, name).await?.spec.and_then(|spec| spec.selector).unwrap_or_default();
+                    labels.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join(",")
+                }
+                _ => return Err(format!("unknown resource type `{}` (expected pod, deployment, statefulset, daemonset, replicaset, job, or service)", kind)),
+            }),
+        };
+        // An empty selector would select every pod in the namespace.
+        if selector.as_ref().is_some_and(String::is_empty) {
+            return Err("it selects no pods".to_string());
+        }
+        let labels = [selector, self.selector.clone()].into_iter().flatten().collect::<Vec<_>>().join(",");
+        let selection = Selection { labels: Some(labels), fields: None };
+        api.list_metadata(&selection.list().limit(1)).await.map_err(describe)?;
+        Ok((api, selection))
+    }
+
+    /// Reads the containers of the selected pods, each on a task of its own. Following, the
+    /// pods are watched for new ones to read and deleted ones to drop.
+    async fn run(self, api: Api<Pod>, selection: Selection, tx: Sender<Vec<u8>>) {
+        if !self.follow {
+            let pods = match api.list(&selection.list()).await {
+                Ok(pods) => pods,
+                Err(e) => return eprintln!("rlog: {}: {}", self, describe(e)),
+            };
+            let mut tasks = Vec::new();
+            for pod in pods {
+                let name = pod.metadata.name.clone().unwrap_or_default();
+                for container in self.containers(&pod) {
+                    tasks.push(tokio::spawn(self.clone().read(api.clone(), name.clone(), container, true, tx.clone())));
+                }
+            }
+            for task in tasks {
+                let _ = task.await;
+            }
+            return;
+        }
+        let mut reading: HashMap<(String, String), JoinHandle<()>> = HashMap::new();
+        // Pods listed at the start are read from --since or --tail on, later ones in full.
+        let mut listed = false;
+        let mut events = watcher(api.clone(), selection.watch()).default_backoff().boxed();
+        while let Some(event) = events.next().await {
+            match event {
+                Ok(Event::Apply(pod) | Event::InitApply(pod)) => {
+                    let name = pod.metadata.name.clone().unwrap_or_default();
+                    for container in self.containers(&pod) {
+                        let key = (name.clone(), container.clone());
+                        if reading.get(&key).is_none_or(JoinHandle::is_finished) {
+                            reading.insert(key, tokio::spawn(self.clone().read(api.clone(), name.clone(), container, !listed, tx.clone())));
+                        }
+                    }
+                }
+                Ok(Event::Delete(pod)) => {
+                    let name = pod.metadata.name.unwrap_or_default();
+                    reading.retain(|(pod, _), task| {
+                        if *pod == name {
+                            task.abort();
+                        }
+                        *pod != name
+                    });
+                }
+                Ok(Event::InitDone) => listed = true,
+                Ok(Event::Init) => {}
+                Err(e) => eprintln!("rlog: {}: {}", self, chain(&e)),
+            }
+        }
+    }
+
+    /// The containers of `pod` to read: the one asked for if the pod has it, all of them, or
+    /// else its default one.
+    fn containers(&self, pod: &Pod) -> Vec<String> {
+        let names = containers(pod);
+        if let Some(container) = &self.container {
+            return names.into_iter().filter(|name| name == container).collect();
+        }
+        if self.all_containers {
+            return names;
+        }
+        let default = pod.metadata.annotations.as_ref().and_then(|annotations| annotations.get(DEFAULT_CONTAINER)).filter(|name| names.contains(name));
+        default.cloned().or_else(|| names.into_iter().next()).into_iter().collect()
+    }
+
+    /// Reads the log of one container, and when following, again from the last line read
+    /// whenever it ends, until its pod is gone or has stopped for good. `initial` reads only
+    /// from --since or the last --tail lines on.
+    async fn read(self, api: Api<Pod>, pod: String, container: String, initial: bool, tx: Sender<Vec<u8>>) {
+        let label = format!("pod/{}/{}", pod, container);
+        let mut params = LogParams { container: Some(container), follow: self.follow, timestamps: true, ..LogParams::default() };
+        if initial {
+            params.since_time = self.since.and_then(|since| Timestamp::from_millisecond(since.timestamp_millis()).ok());
+            params.tail_lines = self.last.map(|last| last as i64);
+        }
+        let (mut backoff, mut last) = (Duration::from_secs(1), None);
+        loop {
+            let (read, failure) = match pump(&api, &pod, &params, &label, &mut last, &tx).await {
+                Ok(read) => (read, String::new()),
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return,
+                Err(e) => (0, format!(" ({})", e)),
+            };
+            if !self.follow {
+                if !failure.is_empty() {
+                    eprintln!("rlog: {}: log stream ended{}", label, failure);
+                }
+                return;
+            }
+            match api.get_opt(&pod).await {
+                Ok(None) => return,
+                Ok(Some(pod)) if pod.spec.as_ref().and_then(|spec| spec.restart_policy.as_deref()) == Some("Never") || finished(&pod) => return,
+                _ => {}
+            }
+            if read > 0 {
+                backoff = Duration::from_secs(1);
+            }
+            eprintln!("rlog: {}: log stream ended{}, reading it again in {}s", label, failure, backoff.as_secs());
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            // The API takes whole seconds, so lines up to the last one read come again.
+            params.since_time = last.and_then(|time: DateTime<FixedOffset>| Timestamp::from_millisecond(time.timestamp_millis()).ok()).or(params.since_time);
+            params.tail_lines = None;
+        }
+    }
+}
+
+/// Sends the lines of one log stream until it ends, as `[LABEL] TIMESTAMP TEXT`, leaving out
+/// those not after `last`, the time of the last line sent before, and keeping it up to date.
+/// Returns how many lines were sent, or a `BrokenPipe` error once nothing reads them any more.
+async fn pump(api: &Api<Pod>, pod: &str, params: &LogParams, label: &str, last: &mut Option<DateTime<FixedOffset>>, tx: &Sender<Vec<u8>>) -> io::Result<usize> {
+    let mut stream = Box::pin(api.log_stream(pod, params).await.map_err(|e| io::Error::other(describe(e)))?);
+    let (mut line, mut sent) = (Vec::new(), 0);
+    while stream.read_until(b'\n', &mut line).await? > 0 {
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\r', '\n']);
+        let time = text.split(' ').next().and_then(parse_datetime);
+        if !time.is_some_and(|time| last.is_some_and(|last| time <= last)) {
+            *last = time.or(*last);
+            tx.send(format!("[{}] {}\n", label, text).into_bytes()).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+            sent += 1;
+        }
+        line.clear();
+    }
+    Ok(sent)
+}
+
+/// The names of the containers of `pod`.
+fn containers(pod: &Pod) -> Vec<String> {
+    pod.spec.iter().flat_map(|spec| &spec.containers).map(|container| container.name.clone()).collect()
+}
+
+/// Whether `pod` has stopped for good: it ran to completion or failed, and its containers
+/// are not restarted.
+fn finished(pod: &Pod) -> bool {
+    matches!(pod.status.as_ref().and_then(|status| status.phase.as_deref()), Some("Succeeded" | "Failed"))
+}
+
+/// Reads the object `name` of kind `K`.
+async fn get<K>(client: &Client, namespace: &str, name: &str) -> Result<K, String>
+where
+    K: Resource<Scope = NamespaceResourceScope, DynamicType = ()> + Clone + DeserializeOwned + Debug,
+{
+    Api::<K>::namespaced(client.clone(), namespace).get(name).await.map_err(describe)
+}
+
+/// A label selector as written on the command line, such as `app=api,tier in (web,edge)`.
+fn label_selector(selector: &LabelSelector) -> String {
+    let labels = selector.match_labels.iter().flatten().map(|(key, value)| format!("{}={}", key, value));
+    let expressions = selector.match_expressions.iter().flatten().map(|expression| {
+        let values = || expression.values.clone().unwrap_or_default().join(",");
+        match expression.operator.as_str() {
+            "In" => format!("{} in ({})", expression.key, values()),
+            "NotIn" => format!("{} notin ({})", expression.key, values()),
+            "Exists" => expression.key.clone(),
+            _ => format!("!{}", expression.key),
+        }
+    });
+    labels.chain(expressions).collect::<Vec<_>>().join(",")
+}
+
+/// The message the API server answered with, or else what kept the request from being
+/// answered, as kube's own message repeats the whole answer.
+fn describe(e: kube::Error) -> String {
+    match e {
+        kube::Error::Api(status) => status.message,
+        e => chain(&e),
+    }
+}
+
+/// An error with the errors that caused it, but those kube's message already tells.
+fn chain(e: &dyn Error) -> String {
+    let mut text = e.to_string();
+    let mut source = e.source();
+    while let Some(e) = source {
+        let cause = e.to_string();
+        if !text.contains(&cause) {
+            text = format!("{}: {}", text, cause);
+        }
+        source = e.source();
+    }
+    text
+}
@@ -0,0 +1,33 @@
+//! Parsing, filtering, and following engine behind the `rlog` log viewer.
+//!
+//! The binary is a thin layer over these types: a [`Follower`] (or a [`MultiFollower`] for
+//! several inputs) yields raw lines from a growing file, a [`LogParser`] turns them into [`LogRecord`]s, and a [`Filter`] decides
+//! which records are shown.
+
+pub mod cluster;
+pub mod context;
+pub mod datetime;
+pub mod decompress;
+pub mod dedupe;
+pub mod expr;
+pub mod filter;
+pub mod follow;
+pub mod merge;
+pub mod parser;
+pub mod record;
+pub mod template;
+pub mod trace;
+
+pub use cluster::{mask, Cluster, Drain};
+pub use context::{ContextWindow, Shown};
+pub use datetime::{parse_datetime, parse_datetime_in, parse_duration, parse_with_format, parse_with_format_in, record_time, TimeBound, Zone};
+pub use decompress::Compression;
+pub use dedupe::Dedupe;
+pub use expr::{DataQuery, Expr, ExprError};
+pub use filter::{Bounds, FieldMatch, Filter, Sample};
+pub use follow::{expand_pattern, file_identity, is_pattern, rotated_siblings, walk, Checkpoint, Follower, Line, MultiFollower, TextEncoding};
+pub use merge::{merge_by, merge_by_datetime};
+pub use parser::{build_parser, detect_format, load_formats, parser_for, AccessLogParser, AutoParser, CefParser, CloudFrontParser, ContainerLog, ContainerParser, CustomFormat, CustomParser, DelimitedParser, ElbParser, FormatDefinition, GelfParser, HerokuParser, JournalParser, JsonParser, LeefParser, LogParser, LogfmtParser, MysqlSlowParser, OslogParser, ParserOptions, PatternParser, PostgresParser, Quoting, S3AccessParser, SyslogParser, TimestampedParser, WinlogParser};
+pub use record::{normalize_level, severity_rank, LogRecord};
+pub use template::Template;
+pub use trace::{trace_context, TraceContext};
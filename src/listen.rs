@@ -0,0 +1,255 @@
+use crate::channel::{self, Lines};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde_json::Value;
+use std::{collections::HashMap, fmt, io::{self, BufRead, BufReader, Read}, mem, net::{Ipv4Addr, SocketAddr, TcpListener, UdpSocket}, path::PathBuf, sync::mpsc::{self, Sender}, thread, time::{Duration, Instant}};
+
+/// Largest syslog datagram accepted.
+const MAX_DATAGRAM: usize = 64 * 1024;
+
+/// Marks a datagram as one chunk of a GELF message.
+const GELF_CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+
+/// Most chunks a GELF message may be sent in.
+const GELF_MAX_CHUNKS: usize = 128;
+
+/// How long the chunks of a GELF message are kept waiting for the rest, as Graylog does.
+const GELF_CHUNK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where `rlog listen` accepts syslog or GELF messages.
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    Udp(SocketAddr),
+    Tcp(SocketAddr),
+    /// A Unix datagram socket, like `/dev/log`.
+    Unix(PathBuf),
+    /// A Unix stream socket.
+    UnixStream(PathBuf),
+    /// GELF datagrams, possibly compressed and chunked.
+    GelfUdp(SocketAddr),
+    /// GELF messages over TCP, each ended by a null byte or a newline.
+    GelfTcp(SocketAddr),
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Endpoint::Udp(addr) => write!(f, "udp:{}", addr),
+            Endpoint::Tcp(addr) => write!(f, "tcp:{}", addr),
+            Endpoint::Unix(path) | Endpoint::UnixStream(path) => write!(f, "unix:{}", path.display()),
+            Endpoint::GelfUdp(addr) => write!(f, "gelf-udp:{}", addr),
+            Endpoint::GelfTcp(addr) => write!(f, "gelf-tcp:{}", addr),
+        }
+    }
+}
+
+impl Endpoint {
+    /// Whether GELF messages arrive here rather than syslog ones.
+    pub fn is_gelf(&self) -> bool {
+        matches!(self, Endpoint::GelfUdp(_) | Endpoint::GelfTcp(_))
+    }
+
+    /// Binds the socket, and receives on background threads from then on, one message per
+    /// line of the stream returned.
+    pub fn open(&self) -> io::Result<Lines> {
+        let (tx, lines) = channel::lines();
+        let failed = |e: io::Error| io::Error::new(e.kind(), format!("cannot listen on {}: {}", self, e));
+        match self {
+            Endpoint::Udp(addr) => {
+                let socket = UdpSocket::bind(addr).map_err(failed)?;
+                thread::spawn(move || receive_datagrams(|buf| socket.recv(buf), tx));
+            }
+            Endpoint::Tcp(addr) => {
+                let listener = TcpListener::bind(addr).map_err(failed)?;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        let tx = tx.clone();
+                        thread::spawn(move || receive_stream(stream, tx));
+                    }
+                });
+            }
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                use std::os::unix::net::UnixDatagram;
+                let socket = unlink_stale(path).and_then(|()| UnixDatagram::bind(path)).map_err(failed)?;
+                thread::spawn(move || receive_datagrams(|buf| socket.recv(buf), tx));
+            }
+            #[cfg(unix)]
+            Endpoint::UnixStream(path) => {
+                use std::os::unix::net::UnixListener;
+                let listener = unlink_stale(path).and_then(|()| UnixListener::bind(path)).map_err(failed)?;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        let tx = tx.clone();
+                        thread::spawn(move || receive_stream(stream, tx));
+                    }
+                });
+            }
+            Endpoint::GelfUdp(addr) => {
+                let socket = UdpSocket::bind(addr).map_err(failed)?;
+                thread::spawn(move || receive_gelf_datagrams(socket, tx));
+            }
+            Endpoint::GelfTcp(addr) => {
+                let listener = TcpListener::bind(addr).map_err(failed)?;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        let tx = tx.clone();
+                        thread::spawn(move || receive_gelf_stream(stream, tx));
+                    }
+                });
+            }
+            #[cfg(not(unix))]
+            Endpoint::Unix(_) | Endpoint::UnixStream(_) => return Err(failed(io::ErrorKind::Unsupported.into())),
+        }
+        Ok(lines)
+    }
+}
+
+/// Removes a socket file left behind by a process that is gone, so it can be bound again.
+/// One that something still listens on is left alone: it is in use.
+#[cfg(unix)]
+fn unlink_stale(path: &std::path::Path) -> io::Result<()> {
+    use std::os::unix::{fs::FileTypeExt, net::{UnixDatagram, UnixStream}};
+    let Ok(meta) = std::fs::symlink_metadata(path) else { return Ok(()) };
+    if !meta.file_type().is_socket() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "not a socket"));
+    }
+    let listening = UnixStream::connect(path).is_ok() || UnixDatagram::unbound().and_then(|socket| socket.connect(path)).is_ok();
+    if listening {
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, "in use by another process"));
+    }
+    std::fs::remove_file(path)
+}
+
+/// Parses `[ADDR:]PORT`; a bare port listens on every interface.
+pub fn endpoint_address(value: &str) -> Result<SocketAddr, String> {
+    match value.parse::<u16>() {
+        Ok(port) => Ok(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))),
+        Err(_) => value.parse().map_err(|_| format!("expected [ADDR:]PORT, got `{}`", value)),
+    }
+}
+
+/// Passes on each line of each datagram, as senders may batch several messages in one.
+fn receive_datagrams(mut recv: impl FnMut(&mut [u8]) -> io::Result<usize>, tx: Sender<Vec<u8>>) {
+    let mut datagram = vec![0; MAX_DATAGRAM];
+    while let Ok(len) = recv(&mut datagram) {
+        for line in datagram[..len].split(|b| *b == b'\n') {
+            if send(line, &tx).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Passes on the messages of one connection, framed by octet counting (`LEN MESSAGE`) or
+/// by newlines, as RFC 6587 allows.
+fn receive_stream(stream: impl Read, tx: Sender<Vec<u8>>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let counted = match reader.fill_buf()?.first() {
+            None => return Ok(()),
+            Some(byte) => byte.is_ascii_digit(),
+        };
+        let mut message = Vec::new();
+        if counted {
+            reader.read_until(b' ', &mut message)?;
+            let len: usize = std::str::from_utf8(&message).ok().and_then(|len| len.trim().parse().ok())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad octet count"))?;
+            message = vec![0; len];
+            reader.read_exact(&mut message)?;
+        } else {
+            reader.read_until(b'\n', &mut message)?;
+        }
+        for line in message.split(|b| *b == b'\n') {
+            send(line, &tx).map_err(|_| io::ErrorKind::BrokenPipe)?;
+        }
+    }
+}
+
+/// Sends one message as a line of its own, leaving out empty ones.
+fn send(line: &[u8], tx: &Sender<Vec<u8>>) -> Result<(), mpsc::SendError<Vec<u8>>> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(());
+    }
+    let mut message = line.to_vec();
+    message.push(b'\n');
+    tx.send(message)
+}
+
+/// Passes on the GELF message in each datagram, once all of its chunks have arrived if it
+/// was sent in several.
+fn receive_gelf_datagrams(socket: UdpSocket, tx: Sender<Vec<u8>>) {
+    let mut datagram = vec![0; MAX_DATAGRAM];
+    let mut pending: HashMap<[u8; 8], Chunks> = HashMap::new();
+    while let Ok(len) = socket.recv(&mut datagram) {
+        let datagram = &datagram[..len];
+        let message = match datagram.strip_prefix(&GELF_CHUNK_MAGIC) {
+            Some(chunk) if chunk.len() >= 10 => {
+                pending.retain(|_, chunks| chunks.started.elapsed() < GELF_CHUNK_TIMEOUT);
+                let (id, sequence, count) = (chunk[..8].try_into().unwrap(), chunk[8] as usize, chunk[9] as usize);
+                if count == 0 || count > GELF_MAX_CHUNKS || sequence >= count {
+                    continue;
+                }
+                let chunks = pending.entry(id).or_insert_with(|| Chunks { parts: vec![None; count], started: Instant::now() });
+                if let Some(part) = chunks.parts.get_mut(sequence) {
+                    *part = Some(chunk[10..].to_vec());
+                }
+                if chunks.parts.iter().any(Option::is_none) {
+                    continue;
+                }
+                pending.remove(&id).into_iter().flat_map(|chunks| chunks.parts).flatten().flatten().collect()
+            }
+            Some(_) => continue,
+            None => datagram.to_vec(),
+        };
+        if send_gelf(&message, &tx).is_err() {
+            return;
+        }
+    }
+}
+
+/// The chunks of a GELF message received so far.
+struct Chunks {
+    parts: Vec<Option<Vec<u8>>>,
+    started: Instant,
+}
+
+/// Passes on the GELF messages of one connection, ended by null bytes as Graylog expects, or
+/// by newlines.
+fn receive_gelf_stream(stream: impl Read, tx: Sender<Vec<u8>>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut message = Vec::new();
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return send_gelf(&message, &tx).map_err(|_| io::ErrorKind::BrokenPipe.into());
+        }
+        match buf.iter().position(|b| matches!(b, b'\0' | b'\n')) {
+            Some(end) => {
+                message.extend_from_slice(&buf[..end]);
+                reader.consume(end + 1);
+                send_gelf(&mem::take(&mut message), &tx).map_err(|_| io::ErrorKind::BrokenPipe)?;
+            }
+            None => {
+                let len = buf.len();
+                message.extend_from_slice(buf);
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+/// Sends a GELF message, decompressed if gzip or zlib compressed it, as one line of compact
+/// JSON. Anything else is sent as it is, to show up as a malformed line.
+fn send_gelf(message: &[u8], tx: &Sender<Vec<u8>>) -> Result<(), mpsc::SendError<Vec<u8>>> {
+    let mut inflated = Vec::new();
+    let message = match message {
+        [0x1f, 0x8b, ..] if GzDecoder::new(message).read_to_end(&mut inflated).is_ok() => &inflated[..],
+        [0x78, ..] if ZlibDecoder::new(message).read_to_end(&mut inflated).is_ok() => &inflated[..],
+        _ => message,
+    };
+    match serde_json::from_slice::<Value>(message) {
+        Ok(object @ Value::Object(_)) => send(object.to_string().as_bytes(), tx),
+        _ => message.split(|b| *b == b'\n').try_for_each(|line| send(line, tx)),
+    }
+}
@@ -1,138 +1,547 @@
-use crossterm::{execute, style::{Color, SetForegroundColor}};
-use regex::Regex;
-use serde_json::Value;
-use std::{collections::HashMap, env, fs::{File, metadata}, io::{BufRead, BufReader, Seek, SeekFrom}, path::Path, thread, time::Duration};
-
-/// Returns terminal color based on log level.
-fn get_color(level: &str) -> Color {
-    match level {
-        "DEBUG" => Color::White,
-        "INFO" => Color::Blue,
-        "NOTICE" => Color::Cyan,
-        "WARNING" => Color::Yellow,
-        "ERROR" => Color::Red,
-        "CRITICAL" => Color::Magenta,
-        "ALERT" => Color::DarkRed,
-        "EMERGENCY" => Color::DarkMagenta,
-        _ => Color::Reset,
-    }
-}
-
-/// Parses a log line using regex and associates captured groups with headers.
-fn parse_line<'a>(line: &'a str, regex: &Regex, headers: &[&'a str]) -> Option<HashMap<&'a str, &'a str>> {
-    regex.captures(line).map(|caps| {
-        headers.iter().enumerate()
-            .filter_map(|(i, &header)| Some((header, caps.get(i + 1)?.as_str())))
-            .collect()
-    })
+mod agg;
+mod alert;
+mod channel;
+mod cli;
+mod cloudwatch;
+mod config;
+mod daemon;
+mod docker;
+mod error;
+mod evtx;
+mod forward;
+mod html;
+mod input;
+mod interrupt;
+mod kubernetes;
+mod listen;
+mod metrics;
+mod output;
+mod patterns;
+mod remote;
+mod render;
+mod search;
+mod spawned;
+mod spikes;
+mod state;
+mod stats;
+mod status;
+mod summary;
+mod timeline;
+mod top;
+mod tui;
+
+use agg::Aggregates;
+use alert::{Alerts, Heartbeat};
+use cli::{AggArgs, Cli, Command, DisplayArgs, ExportArgs, GrepArgs, InputArgs, PatternsArgs, QueryArgs, StatsArgs, TailArgs, TimelineArgs, TopArgs, WatchArgs};
+use daemon::{Log, Severity};
+use error::{Error, Result};
+use output::Printer;
+use patterns::Patterns;
+use spikes::Spikes;
+use stats::LevelCounts;
+use status::StatusLine;
+use timeline::Timeline;
+use top::Frequencies;
+use input::Inputs;
+use metrics::Metrics;
+use rayon::prelude::*;
+use summary::Summary;
+use rlog::{Bounds, ContextWindow, Dedupe, Filter, LogRecord, Shown};
+use chrono::TimeDelta;
+use std::{io::{self, IsTerminal}, process::ExitCode, sync::Arc, time::{Duration, Instant}};
+
+/// How often inputs are checked for new data when filesystem notifications are unavailable.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A duration as shown in warnings, such as `2m05s`.
+fn elapsed(duration: Duration) -> String {
+    render::elapsed(TimeDelta::from_std(duration).unwrap_or(TimeDelta::MAX), false)
 }
 
-/// Entry point of the log viewer program.
+/// Opens the inputs and stops reading them on Ctrl+C.
+fn open(input: &InputArgs) -> Result<Inputs> {
+    let inputs = Inputs::open(input)?;
+    interrupt::install(inputs.waker());
+    Ok(inputs)
+}
+
+/// Opens the inputs and lets the display know how to label their rows.
+fn open_inputs(input: &InputArgs, display: &mut DisplayArgs) -> Result<Inputs> {
+    let mut inputs = open(input)?;
+    if display.line_numbers {
+        inputs.count_lines();
+    }
+    display.sources = inputs.labels().to_vec();
+    display.attribute_rows = !input.patterns.is_empty();
+    Ok(inputs)
+}
+
+/// Reads everything currently in the inputs and passes each matching record within `bounds`,
+/// with the context entries `window` selects around it, to `on_shown`. Returns whether any
+/// record matched.
 ///
-/// Command-line arguments:
-/// - `--filter` or `--f`: Filters log entries containing a specific word.
-/// - `--level` or `--l`: Filters log entries by log level.
-/// - `--start` or `--s`: Filters log entries from a specific start date.
-/// - `--to` or `--t`: Filters log entries up to a specific end date.
-/// - `--width` or `--w`: Sets individual column widths for formatted output (comma-separated).
-/// - `--verbose` or `--v`: Includes the Data field in the output.
-/// - `--detailed` or `--V`: Includes and pretty-prints the Data field as JSON.
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: log_viewer <log_file> [--filter WORD|--f WORD] [--level LEVEL|--l LEVEL] [--start DATE|--s DATE] [--to DATE|--t DATE] [--width W1,W2,...|--w W1,W2,...] [--verbose|--v] [--detailed|--V]");
-        return;
-    }
-
-    let log_file = &args[1];
-    let path = Path::new(log_file);
-    if !path.exists() {
-        eprintln!("File not found: {}", log_file);
-        return;
-    }
-
-    let verbose = args.contains(&"--verbose".to_string()) || args.contains(&"--v".to_string());
-    let detailed = args.contains(&"--detailed".to_string()) || args.contains(&"--V".to_string());
-
-    let mut filter_word = None;
-    let mut filter_level = None;
-    let mut from_date = None;
-    let mut to_date = None;
-    let mut col_widths: Vec<usize> = vec![20, 10, 50, 30];
-
-    let mut i = 2;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--filter" | "--f" => { filter_word = args.get(i + 1); i += 1; },
-            "--level" | "--l" => { filter_level = args.get(i + 1).map(|l| l.to_uppercase()); i += 1; },
-            "--start" | "--s" => { from_date = args.get(i + 1); i += 1; },
-            "--to" | "--t" => { to_date = args.get(i + 1); i += 1; },
-            "--width" | "--w" => {
-                if let Some(width_str) = args.get(i + 1) {
-                    col_widths = width_str.split(',').filter_map(|w| w.parse().ok()).collect();
+/// The inputs are read a part at a time, each part checked on all cores while the next one
+/// is read.
+fn scan(inputs: &mut Inputs, filter: &Filter, bounds: &mut Bounds, window: &mut ContextWindow, mut on_shown: impl FnMut(&Shown) -> Result<()>) -> Result<bool> {
+    inputs.seek(filter)?;
+    if !window.is_enabled() {
+        inputs.prefilter(filter)?;
+    }
+    // The context window needs the unmatched records too.
+    let keep_unmatched = window.is_enabled();
+    let check = |records: Vec<LogRecord>| -> Vec<(LogRecord, bool)> {
+        records.into_par_iter()
+            .map(|record| {
+                let matched = filter.matches(&record);
+                (record, matched)
+            })
+            .filter(|(_, matched)| *matched || keep_unmatched)
+            .collect()
+    };
+    let mut matched = false;
+    let mut next = inputs.read_chunk()?;
+    while let Some(records) = next {
+        let (read, records) = rayon::join(|| inputs.read_chunk(), || check(records));
+        for (record, is_match) in records {
+            if interrupt::requested() || bounds.is_full() {
+                return Ok(matched);
+            }
+            let is_match = is_match && bounds.admit();
+            matched |= is_match;
+            for shown in window.push(record, is_match) {
+                on_shown(&shown)?;
+            }
+        }
+        next = read?;
+    }
+    Ok(matched)
+}
+
+/// Records where reading stopped, tells on stderr about unparsed lines, and with
+/// `--summary` what the run showed.
+fn finish(inputs: &mut Inputs, summary: &Summary, display: &DisplayArgs) -> Result<()> {
+    inputs.save_state()?;
+    inputs.report();
+    if display.summary {
+        summary.print(inputs.line_counts());
+    }
+    Ok(())
+}
+
+/// Tells on stderr about something unusual in the followed entries as a whole, and raises an
+/// alert about it.
+fn warn(text: &str, status: &mut Option<StatusLine>, alerts: &mut Option<Alerts>) -> Result<()> {
+    if let Some(status) = status {
+        status.clear().map_err(|e| Error::output(None, e))?;
+    }
+    eprintln!("rlog: {}", text);
+    if let Some(alerts) = alerts {
+        alerts.notice(text);
+    }
+    Ok(())
+}
+
+/// Follows the inputs until they end or Ctrl+C is pressed, printing matching records as they arrive. Returns
+/// whether any record matched.
+fn tail(mut args: TailArgs) -> Result<bool> {
+    let mut inputs = open_inputs(&args.input, &mut args.display)?;
+    if args.tui {
+        return Ok(tui::run(&args, inputs)?);
+    }
+
+    let filter = args.filter.to_filter();
+    let mut bounds = args.display.bounds(&args.filter);
+    let mut window = args.display.context_window();
+    let mut printer = Printer::new(&args.display)?;
+    let mut summary = Summary::default();
+    let mut matched = false;
+    if let Some(count) = args.history() {
+        for record in inputs.tail(count, &filter)? {
+            if !bounds.admit() {
+                continue;
+            }
+            matched = true;
+            let shown = Shown::Match(record);
+            summary.add(&shown);
+            printer.print(&shown)?;
+        }
+    }
+    if args.no_follow {
+        matched |= scan(&mut inputs, &filter, &mut bounds, &mut window, |shown| {
+            summary.add(shown);
+            printer.print(shown)
+        })?;
+        printer.finish()?;
+        finish(&mut inputs, &summary, &args.display)?;
+        return Ok(matched);
+    }
+    if args.history().is_none() {
+        inputs.seek(&filter)?;
+    }
+    let mut status = (args.status && io::stdout().is_terminal()).then(StatusLine::new);
+    let mut alerts = Alerts::new(&args.alerts);
+    let mut heartbeat = args.alerts.expect_heartbeat.and_then(|every| every.to_std().ok()).map(Heartbeat::new);
+    let mut spikes = args.alerts.detect_spikes.map(Spikes::new);
+    let metrics = args.metrics_listen.map(Metrics::serve).transpose()?;
+    let mut read = inputs.line_counts().0;
+    loop {
+        let records = inputs.poll()?;
+        let quiet = records.is_empty();
+        if let (Some(status), true) = (&mut status, !quiet || printer.suppressing()) {
+            status.clear().map_err(|e| Error::output(None, e))?;
+        }
+        for record in records {
+            if interrupt::requested() || bounds.is_full() {
+                break;
+            }
+            if let Some(alerts) = &mut alerts {
+                alerts.check(&record);
+            }
+            let is_match = filter.matches(&record) && bounds.admit();
+            matched |= is_match;
+            if let Some(metrics) = &metrics {
+                metrics.add(&record, is_match);
+            }
+            if let Some(silence) = heartbeat.as_mut().filter(|_| is_match).and_then(Heartbeat::beat) {
+                eprintln!("rlog: matching entries resumed after {}", elapsed(silence));
+            }
+            if let Some(spike) = spikes.as_mut().filter(|_| is_match).and_then(Spikes::add) {
+                warn(&spike, &mut status, &mut alerts)?;
+            }
+            for shown in window.push(record, is_match) {
+                if let (Some(status), Shown::Match(record)) = (&mut status, &shown) {
+                    status.add(record);
                 }
-                i += 1;
-            },
-            _ => {}
+                summary.add(&shown);
+                printer.print(&shown)?;
+            }
+        }
+        if quiet {
+            printer.idle()?;
+        } else {
+            inputs.save_state()?;
+        }
+        if let Some(silence) = heartbeat.as_mut().and_then(Heartbeat::check) {
+            warn(&format!("no matching entries for {}", elapsed(silence)), &mut status, &mut alerts)?;
+        }
+        if let Some(dip) = spikes.as_mut().and_then(Spikes::tick) {
+            warn(&dip, &mut status, &mut alerts)?;
+        }
+        if let Some(metrics) = &metrics {
+            metrics.update(inputs.line_counts(), alerts.as_ref().map_or(0, Alerts::raised));
+        }
+        if let Some(status) = &mut status {
+            let now = inputs.line_counts().0;
+            status.draw(now - read).map_err(|e| Error::output(None, e))?;
+            read = now;
+        }
+        if inputs.is_finished() || interrupt::requested() || bounds.is_full() {
+            if let Some(status) = &mut status {
+                status.clear().map_err(|e| Error::output(None, e))?;
+            }
+            printer.finish()?;
+            finish(&mut inputs, &summary, &args.display)?;
+            return Ok(matched);
+        }
+        inputs.wait(heartbeat.as_ref().map_or(Duration::MAX, Heartbeat::remaining));
+    }
+}
+
+/// Prints the matching records currently in the inputs and returns whether there were any.
+fn query(mut args: QueryArgs) -> Result<bool> {
+    let mut inputs = open_inputs(&args.input, &mut args.display)?;
+    let mut window = args.display.context_window();
+    let mut printer = Printer::new(&args.display)?;
+    let mut summary = Summary::default();
+    let matched = scan(&mut inputs, &args.filter.to_filter(), &mut args.display.bounds(&args.filter), &mut window, |shown| {
+        summary.add(shown);
+        printer.print(shown)
+    })?;
+    printer.finish()?;
+    finish(&mut inputs, &summary, &args.display)?;
+    Ok(matched)
+}
+
+/// Searches each file on its own, on all cores, and prints what matched in them file by
+/// file. Files that cannot be read are reported and skipped.
+fn grep(mut args: GrepArgs) -> Result<bool> {
+    args.display.sources = args.input.files.iter().map(|path| Arc::from(path.display().to_string())).collect();
+    args.display.attribute_rows = true;
+    let filter = args.filter.to_filter();
+    let window = args.display.context_window();
+    let searched: Vec<_> = args.input.files.par_iter().map(|file| -> Result<_> {
+        let mut inputs = Inputs::open(&InputArgs { files: vec![file.clone()], ..args.input.clone() })?;
+        let mut shown = Vec::new();
+        scan(&mut inputs, &filter, &mut Bounds::new(0, None), &mut window.clone(), |s| {
+            shown.push(s.clone());
+            Ok(())
+        })?;
+        Ok((shown, inputs.line_counts()))
+    }).collect();
+
+    let mut printer = Printer::new(&args.display)?;
+    let mut summary = Summary::default();
+    let mut bounds = args.display.bounds(&args.filter);
+    let (mut matched, mut read, mut malformed) = (false, 0, 0);
+    for result in searched {
+        let (shown, counts) = match result {
+            Ok(found) => found,
+            Err(e) => {
+                eprintln!("rlog: {}", e);
+                continue;
+            }
+        };
+        (read, malformed) = (read + counts.0, malformed + counts.1);
+        for shown in shown {
+            if interrupt::requested() || bounds.is_full() {
+                break;
+            }
+            if matches!(shown, Shown::Match(_)) {
+                if !bounds.admit() {
+                    continue;
+                }
+                matched = true;
+            }
+            summary.add(&shown);
+            printer.print(&shown)?;
         }
-        i += 1;
     }
+    printer.finish()?;
+    input::report(malformed, args.input.show_raw);
+    if args.display.summary {
+        summary.print((read, malformed));
+    }
+    Ok(matched)
+}
+
+/// Prints how many matching records there are per level, or per level and time bucket.
+fn stats(args: &StatsArgs) -> Result<bool> {
+    let mut counts = LevelCounts::new(args);
+    let mut inputs = open(&args.input)?;
+    scan(&mut inputs, &args.filter.to_filter(), &mut args.filter.bounds(), &mut ContextWindow::default(), |shown| {
+        match shown {
+            Shown::Match(record) if !record.malformed => counts.add(record),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    counts.print(args).map_err(|e| Error::output(None, e))?;
+    inputs.save_state()?;
+    inputs.report();
+    Ok(counts.total() > 0)
+}
 
-    let file = File::open(path).expect("Failed to open file");
-    let mut reader = BufReader::new(file);
+/// Draws how many records match over time.
+fn timeline(args: &TimelineArgs) -> Result<bool> {
+    let mut timeline = Timeline::default();
+    let mut inputs = open(&args.input)?;
+    scan(&mut inputs, &args.filter.to_filter(), &mut args.filter.bounds(), &mut ContextWindow::default(), |shown| {
+        match shown {
+            Shown::Match(record) if !record.malformed => timeline.add(record, args),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    timeline.print(args).map_err(|e| Error::output(None, e))?;
+    inputs.save_state()?;
+    inputs.report();
+    Ok(timeline.total() > 0)
+}
 
-    let mut header_line = String::new();
-    reader.read_line(&mut header_line).expect("Failed to read header");
-    let headers: Vec<&str> = header_line.trim().split('|').collect();
+/// Prints the templates of the matching messages by how often they occur.
+fn patterns(args: &PatternsArgs) -> Result<bool> {
+    let mut patterns = Patterns::new(args);
+    let mut inputs = open(&args.input)?;
+    scan(&mut inputs, &args.filter.to_filter(), &mut args.filter.bounds(), &mut ContextWindow::default(), |shown| {
+        match shown {
+            Shown::Match(record) if !record.malformed => patterns.add(record),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    patterns.print(args).map_err(|e| Error::output(None, e))?;
+    inputs.save_state()?;
+    inputs.report();
+    Ok(patterns.total() > 0)
+}
 
-    let regex_pattern = headers.iter().map(|_| "(.*?)").collect::<Vec<&str>>().join("\\|");
-    let regex = Regex::new(&format!("^{}$", regex_pattern)).expect("Invalid regex");
+/// Prints the most frequent values of a column among the matching records.
+fn top(args: &TopArgs) -> Result<bool> {
+    let mut frequencies = Frequencies::default();
+    let mut inputs = open(&args.input)?;
+    scan(&mut inputs, &args.filter.to_filter(), &mut args.filter.bounds(), &mut ContextWindow::default(), |shown| {
+        match shown {
+            Shown::Match(record) if !record.malformed => frequencies.add(record, args),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    frequencies.print(args).map_err(|e| Error::output(None, e))?;
+    inputs.save_state()?;
+    inputs.report();
+    Ok(frequencies.total() > 0)
+}
 
-    let mut position = reader.stream_position().unwrap();
+/// Prints statistics of a numeric column among the matching records.
+fn agg(args: &AggArgs) -> Result<bool> {
+    let mut aggregates = Aggregates::default();
+    let mut inputs = open(&args.input)?;
+    scan(&mut inputs, &args.filter.to_filter(), &mut args.filter.bounds(), &mut ContextWindow::default(), |shown| {
+        match shown {
+            Shown::Match(record) if !record.malformed => aggregates.add(record, args),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    aggregates.print(args).map_err(|e| Error::output(None, e))?;
+    inputs.save_state()?;
+    inputs.report();
+    Ok(aggregates.total() > 0)
+}
 
+/// Writes the matching records to an HTML page and returns whether there were any.
+fn export(mut args: ExportArgs) -> Result<bool> {
+    let mut inputs = open_inputs(&args.input, &mut args.display)?;
+    let mut shown = Vec::new();
+    let mut summary = Summary::default();
+    let mut dedupe = args.display.dedupe();
+    let matched = scan(&mut inputs, &args.filter.to_filter(), &mut args.filter.bounds(), &mut args.display.context_window(), |s| {
+        summary.add(s);
+        match &mut dedupe {
+            Some(dedupe) => shown.extend(dedupe.push(s.clone())),
+            None => shown.push(s.clone()),
+        }
+        Ok(())
+    })?;
+    shown.extend(dedupe.as_mut().and_then(Dedupe::finish));
+    html::export(&args.html, &shown, &args.display).map_err(|e| Error::output(Some(&args.html), e))?;
+    finish(&mut inputs, &summary, &args.display)?;
+    Ok(matched)
+}
+
+/// Follows the inputs as a service, raising alerts without printing entries, and logs what it
+/// does to syslog. With `--daemon`, starts itself in the background instead.
+fn watch(args: &WatchArgs) -> Result<bool> {
+    if args.daemon {
+        let pid = daemon::detach()?;
+        eprintln!("rlog: watching in the background (process {})", pid);
+        return Ok(true);
+    }
+    let log = Log::open();
+    let result = watching(args, &log);
+    daemon::notify("STOPPING=1");
+    match &result {
+        Ok(_) => log.write(Severity::Info, "stopped"),
+        Err(e) => log.write(Severity::Error, &e.to_string()),
+    }
+    result
+}
+
+fn watching(args: &WatchArgs, log: &Log) -> Result<bool> {
+    let mut inputs = open(&args.input)?;
+    inputs.skip_to_end()?;
+    let filter = args.filter.to_filter();
+    let mut alerts = Alerts::new(&args.alerts);
+    let mut heartbeat = args.alerts.expect_heartbeat.and_then(|every| every.to_std().ok()).map(Heartbeat::new);
+    let mut spikes = args.alerts.detect_spikes.map(Spikes::new);
+    let metrics = args.metrics_listen.map(Metrics::serve).transpose()?;
+    let watchdog = daemon::watchdog_interval();
+    let mut petted = Instant::now();
+    let notice = |text: &str, alerts: &mut Option<Alerts>| {
+        log.write(Severity::Warning, text);
+        if let Some(alerts) = alerts {
+            alerts.notice(text);
+        }
+    };
+    log.write(Severity::Info, &format!("watching {}", inputs.labels().join(", ")));
+    daemon::notify("READY=1");
     loop {
-        if metadata(path).unwrap().len() < position {
-            position = 0;
-            reader.seek(SeekFrom::Start(0)).unwrap();
-        }
-
-        if metadata(path).unwrap().len() > position {
-            reader.seek(SeekFrom::Start(position)).unwrap();
-            let mut line = String::new();
-
-            while reader.read_line(&mut line).unwrap() > 0 {
-                position += line.len() as u64;
-
-                if let Some(columns) = parse_line(&line.trim(), &regex, &headers) {
-                    let date_ok = from_date.map_or(true, |fd| columns["DateTime"] >= fd)
-                        && to_date.map_or(true, |td| columns["DateTime"] <= td);
-
-                    let level_ok = filter_level.as_ref().map_or(true, |lvl| columns["Level"].to_uppercase() == *lvl);
-                    let word_ok = filter_word.map_or(true, |word| line.contains(word));
-
-                    if date_ok && level_ok && word_ok {
-                        let color = get_color(columns["Level"].to_uppercase().as_str());
-                        execute!(std::io::stdout(), SetForegroundColor(color)).unwrap();
-
-                        for (idx, &header) in headers.iter().enumerate() {
-                            if header == "Data" && detailed {
-                                if let Ok(json) = serde_json::from_str::<Value>(columns["Data"]) {
-                                    println!("{}", serde_json::to_string_pretty(&json).unwrap());
-                                } else {
-                                    println!("{}", columns["Data"]);
-                                }
-                            } else if header != "Data" || verbose {
-                                print!("{:width$} | ", columns[header], width = col_widths.get(idx).unwrap_or(&15));
-                            }
-                        }
-
-                        execute!(std::io::stdout(), SetForegroundColor(Color::Reset)).unwrap();
-                        println!();
-                    }
-                }
-                line.clear();
+        let records = inputs.poll()?;
+        let quiet = records.is_empty();
+        for record in records {
+            if interrupt::requested() {
+                break;
+            }
+            for summary in alerts.as_mut().map(|alerts| alerts.check(&record)).unwrap_or_default() {
+                log.write(Severity::Warning, &format!("alert: {}", summary));
+            }
+            let is_match = filter.matches(&record);
+            if let Some(metrics) = &metrics {
+                metrics.add(&record, is_match);
+            }
+            if !is_match {
+                continue;
+            }
+            if let Some(silence) = heartbeat.as_mut().and_then(Heartbeat::beat) {
+                log.write(Severity::Info, &format!("matching entries resumed after {}", elapsed(silence)));
+            }
+            if let Some(spike) = spikes.as_mut().and_then(Spikes::add) {
+                notice(&spike, &mut alerts);
             }
         }
-        thread::sleep(Duration::from_millis(500));
+        if !quiet {
+            inputs.save_state()?;
+        }
+        if let Some(silence) = heartbeat.as_mut().and_then(Heartbeat::check) {
+            notice(&format!("no matching entries for {}", elapsed(silence)), &mut alerts);
+        }
+        if let Some(dip) = spikes.as_mut().and_then(Spikes::tick) {
+            notice(&dip, &mut alerts);
+        }
+        if let Some(metrics) = &metrics {
+            metrics.update(inputs.line_counts(), alerts.as_ref().map_or(0, Alerts::raised));
+        }
+        if watchdog.is_some_and(|interval| petted.elapsed() >= interval) {
+            daemon::notify("WATCHDOG=1");
+            petted = Instant::now();
+        }
+        if inputs.is_finished() || interrupt::requested() {
+            inputs.save_state()?;
+            return Ok(true);
+        }
+        let until_watchdog = watchdog.map_or(Duration::MAX, |interval| interval.saturating_sub(petted.elapsed()));
+        inputs.wait(heartbeat.as_ref().map_or(Duration::MAX, Heartbeat::remaining).min(until_watchdog));
     }
-}
\ No newline at end of file
+}
+
+/// Entry point of the log viewer program; see `rlog --help` for the command-line interface
+/// and its exit statuses.
+fn main() -> ExitCode {
+    let cli = Cli::parse_args();
+    let result = match cli.command {
+        None => tail(cli.tail),
+        Some(Command::Tail(args)) => tail(*args),
+        Some(Command::Query(args)) => query(args),
+        Some(Command::Grep(args)) => grep(args),
+        Some(Command::Stats(args)) => stats(&args),
+        Some(Command::Timeline(args)) => timeline(&args),
+        Some(Command::Patterns(args)) => patterns(&args),
+        Some(Command::Top(args)) => top(&args),
+        Some(Command::Agg(args)) => agg(&args),
+        Some(Command::Export(args)) => export(args),
+        Some(Command::Ssh(args)) => tail(args.tail),
+        Some(Command::Docker(args)) => tail(args.tail),
+        Some(Command::K8s(args)) => tail(args.tail),
+        Some(Command::Http(args)) => tail(args.tail),
+        Some(Command::Kafka(args)) => tail(args.tail),
+        Some(Command::Loki(args)) => tail(args.tail),
+        Some(Command::Es(args)) => tail(args.tail),
+        Some(Command::Cloudwatch(args)) => tail(args.tail),
+        Some(Command::Journal(args)) => tail(args.tail),
+        Some(Command::Evtx(args)) => tail(args.tail),
+        Some(Command::Winlog(args)) => tail(args.tail),
+        Some(Command::Oslog(args)) => tail(args.tail),
+        Some(Command::Listen(args)) => tail(args.tail),
+        Some(Command::Watch(args)) => watch(&args),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(error::NO_MATCH),
+        Err(e) => {
+            if !e.is_broken_pipe() {
+                eprintln!("rlog: {}", e);
+            }
+            e.exit_code()
+        }
+    }
+}
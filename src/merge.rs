@@ -0,0 +1,65 @@
+use crate::{datetime::record_time, record::LogRecord};
+use std::collections::VecDeque;
+
+/// Interleaves per-source batches of records into one sequence ordered by `key`.
+///
+/// Each batch is assumed to already be in order (as lines in a log file are), so this
+/// is a k-way merge: the relative order of records from the same source is preserved,
+/// and ties between sources go to the earlier batch.
+pub fn merge_by<K: Ord>(batches: Vec<Vec<LogRecord>>, key: impl Fn(&LogRecord) -> K) -> Vec<LogRecord> {
+    let total = batches.iter().map(Vec::len).sum();
+    let mut queues: Vec<VecDeque<LogRecord>> = batches.into_iter().map(VecDeque::from).collect();
+    let mut merged = Vec::with_capacity(total);
+
+    while let Some(next) = queues.iter().enumerate()
+        .filter_map(|(idx, queue)| Some((idx, key(queue.front()?))))
+        .min_by(|(_, a), (_, b)| a.cmp(b))
+        .map(|(idx, _)| idx)
+    {
+        merged.extend(queues[next].pop_front());
+    }
+    merged
+}
+
+/// Interleaves per-source batches of records by time, see [`record_time`]. Records without
+/// a recognizable time come out as soon as they reach the front of their batch, staying
+/// after the record before them.
+pub fn merge_by_datetime(batches: Vec<Vec<LogRecord>>) -> Vec<LogRecord> {
+    merge_by(batches, record_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(source: &str, times: &[&str]) -> Vec<LogRecord> {
+        times.iter().map(|time| {
+            let mut record = LogRecord::new(format!("{} {}", source, time));
+            record.push("DateTime", *time);
+            record
+        }).collect()
+    }
+
+    fn raw(records: Vec<LogRecord>) -> Vec<String> {
+        records.into_iter().map(|record| record.raw).collect()
+    }
+
+    #[test]
+    fn interleaves_batches_by_time() {
+        let merged = merge_by_datetime(vec![
+            batch("a", &["2025-03-01 10:00:00", "2025-03-01 10:00:02", "2025-03-01 10:00:04"]),
+            batch("b", &["2025-03-01T10:00:01Z", "2025-03-01T10:00:03Z"]),
+        ]);
+        assert_eq!(raw(merged), ["a 2025-03-01 10:00:00", "b 2025-03-01T10:00:01Z", "a 2025-03-01 10:00:02", "b 2025-03-01T10:00:03Z", "a 2025-03-01 10:00:04"]);
+    }
+
+    #[test]
+    fn keeps_ties_and_untimed_records_in_place() {
+        let merged = merge_by_datetime(vec![
+            batch("a", &["2025-03-01 10:00:05", "continued", "2025-03-01 10:00:05"]),
+            batch("b", &["2025-03-01 10:00:05", "2025-03-01 10:00:06"]),
+        ]);
+        assert_eq!(raw(merged), ["a 2025-03-01 10:00:05", "a continued", "a 2025-03-01 10:00:05", "b 2025-03-01 10:00:05", "b 2025-03-01 10:00:06"]);
+        assert!(merge_by(Vec::new(), |record| record.raw.clone()).is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use rlog::LogRecord;
+use std::{collections::BTreeMap, fmt::Write as _, io::{self, BufRead, BufReader, Write}, net::{SocketAddr, TcpListener, TcpStream}, sync::{Arc, Mutex}, thread, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+/// How long a scrape may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Counters of what a followed log has brought, served at `/metrics` in the Prometheus text
+/// format by `--metrics-listen`, so that a long-running `rlog watch` can be scraped like any
+/// other exporter.
+#[derive(Clone)]
+pub struct Metrics {
+    counts: Arc<Mutex<Counts>>,
+}
+
+#[derive(Default)]
+struct Counts {
+    lines: u64,
+    malformed: u64,
+    matched: u64,
+    /// Entries read per level, in upper case.
+    levels: BTreeMap<String, u64>,
+    alerts: u64,
+    /// When rlog started, in seconds since the epoch.
+    started: f64,
+}
+
+impl Metrics {
+    /// Binds `addr`, and answers scrapes on a background thread from then on.
+    pub fn serve(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot serve metrics on {}: {}", addr, e)))?;
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |d| d.as_secs_f64());
+        let metrics = Metrics { counts: Arc::new(Mutex::new(Counts { started, ..Counts::default() })) };
+        let serving = metrics.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A client that goes away is nothing to report.
+                let _ = serving.answer(stream);
+            }
+        });
+        Ok(metrics)
+    }
+
+    /// Counts an entry read, and whether it matched.
+    pub fn add(&self, record: &LogRecord, matched: bool) {
+        let mut counts = self.counts.lock().unwrap();
+        if !record.malformed {
+            *counts.levels.entry(record.level().to_uppercase()).or_default() += 1;
+        }
+        counts.matched += u64::from(matched);
+    }
+
+    /// Records the lines read and unparsed so far, as `Inputs::line_counts` gives them, and
+    /// the alerts raised.
+    pub fn update(&self, (lines, malformed): (u64, u64), alerts: u64) {
+        let mut counts = self.counts.lock().unwrap();
+        (counts.lines, counts.malformed, counts.alerts) = (lines, malformed, alerts);
+    }
+
+    /// Reads a request and answers it: the metrics for `GET /metrics`, not found otherwise.
+    fn answer(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        let mut reader = BufReader::new(&stream);
+        let mut request = String::new();
+        reader.read_line(&mut request)?;
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+            header.clear();
+        }
+        let mut parts = request.split_whitespace();
+        let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+        let (status, body) = match (method, path.split('?').next().unwrap_or_default()) {
+            ("GET" | "HEAD", "/metrics") => ("200 OK", self.exposition()),
+            ("GET" | "HEAD", _) => ("404 Not Found", "metrics are at /metrics\n".to_string()),
+            _ => ("405 Method Not Allowed", String::new()),
+        };
+        let mut writer = &stream;
+        write!(writer, "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len())?;
+        if method != "HEAD" {
+            writer.write_all(body.as_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// The counters in the Prometheus text exposition format.
+    fn exposition(&self) -> String {
+        let counts = self.counts.lock().unwrap();
+        let mut text = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(text, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(text, "{}{} {}", name, labels, value);
+            }
+        };
+        let plain = |value: u64| vec![(String::new(), value.to_string())];
+        metric("rlog_lines_read_total", "counter", "Lines read from the inputs.", &plain(counts.lines));
+        metric("rlog_parse_errors_total", "counter", "Lines that could not be parsed.", &plain(counts.malformed));
+        let levels: Vec<_> = counts.levels.iter().map(|(level, n)| (format!("{{level=\"{}\"}}", escape(level)), n.to_string())).collect();
+        metric("rlog_entries_total", "counter", "Entries read, by level.", &levels);
+        metric("rlog_matches_total", "counter", "Entries matching the filter.", &plain(counts.matched));
+        metric("rlog_alerts_total", "counter", "Alerts raised.", &plain(counts.alerts));
+        metric("rlog_start_time_seconds", "gauge", "When rlog started, in seconds since the epoch.", &[(String::new(), format!("{:.3}", counts.started))]);
+        text
+    }
+}
+
+/// A label value with its backslashes, quotes, and line breaks escaped.
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"").replace('\n', r"\n")
+}
@@ -0,0 +1,250 @@
+use crate::{cli::DisplayArgs, error::{Error, Result}, forward::Forwarder, render::{label, suppressed, visible_cells, write_header, write_shown, write_suppressed}};
+use clap::ValueEnum;
+use rlog::{record_time, Dedupe, LogRecord, Shown};
+use serde_json::{Map, Value};
+use regex::bytes::Regex;
+use std::{borrow::Cow, fs::File, io::{self, Write}, mem, time::{Duration, Instant}};
+
+/// How records are written to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored columns for reading in a terminal.
+    #[default]
+    Table,
+    /// One JSON array of objects, closed when the input ends.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Tab-separated values with a header row.
+    Tsv,
+}
+
+/// Writes the records picked for display in the selected output format, to the terminal
+/// and/or the `--out`/`--tee` file.
+pub struct Printer<'a> {
+    display: &'a DisplayArgs,
+    file: Option<File>,
+    /// Matches escape sequences to leave out of the file, with `--strip-ansi`.
+    ansi: Option<Regex>,
+    /// Records written so far, for separators and header rows.
+    written: usize,
+    /// Holds back repeated matches, with `--dedupe`.
+    dedupe: Option<Dedupe>,
+    /// Drops entries beyond `--max-rate`.
+    throttle: Option<Throttle>,
+    /// Matches counted instead of printed, with `--count`.
+    matches: usize,
+    /// Ships matches to the `--forward` targets.
+    forwarders: Vec<Forwarder>,
+}
+
+/// Limits how many entries are written per interval, counting the ones left out.
+struct Throttle {
+    limit: usize,
+    per: Duration,
+    start: Instant,
+    /// Entries written and left out in the current interval.
+    written: usize,
+    suppressed: usize,
+}
+
+impl Throttle {
+    /// Whether an entry may be written now, and how many were left out in an interval that
+    /// just ended.
+    fn admit(&mut self) -> (bool, usize) {
+        let now = Instant::now();
+        let mut ended = 0;
+        if now.duration_since(self.start) >= self.per {
+            (self.start, self.written) = (now, 0);
+            ended = mem::take(&mut self.suppressed);
+        }
+        if self.written < self.limit {
+            self.written += 1;
+            (true, ended)
+        } else {
+            self.suppressed += 1;
+            (false, ended)
+        }
+    }
+}
+
+impl<'a> Printer<'a> {
+    pub fn new(display: &'a DisplayArgs) -> Result<Self> {
+        let path = display.out.as_ref().or(display.tee.as_ref());
+        let file = path.map(File::create).transpose().map_err(|e| Error::output(path, e))?;
+        let ansi = display.strip_ansi.then(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap());
+        let throttle = display.max_rate.map(|(limit, per)| Throttle { limit, per, start: Instant::now(), written: 0, suppressed: 0 });
+        let forwarders = display.forward.iter().cloned().map(Forwarder::start).collect();
+        Ok(Printer { display, file, ansi, written: 0, dedupe: display.dedupe(), throttle, matches: 0, forwarders })
+    }
+
+    pub fn print(&mut self, shown: &Shown) -> Result<()> {
+        if let Shown::Match(record) = shown {
+            self.forwarders.iter().for_each(|forwarder| forwarder.send(record));
+        }
+        if self.display.count || self.display.quiet {
+            self.matches += usize::from(matches!(shown, Shown::Match(_)));
+            return Ok(());
+        }
+        match &mut self.dedupe {
+            Some(dedupe) => dedupe.push(shown.clone()).iter().try_for_each(|shown| self.write(shown)),
+            None => self.write(shown),
+        }
+    }
+
+    /// Whether `--max-rate` has left out entries not yet reported.
+    pub fn suppressing(&self) -> bool {
+        self.throttle.as_ref().is_some_and(|throttle| throttle.suppressed > 0)
+    }
+
+    /// Tells how many entries `--max-rate` left out while no new ones were printed, so that
+    /// the count shows up before following goes quiet.
+    pub fn idle(&mut self) -> Result<()> {
+        match self.throttle.as_mut().map(|throttle| mem::take(&mut throttle.suppressed)) {
+            Some(suppressed) if suppressed > 0 => self.write_suppressed(suppressed),
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&mut self, shown: &Shown) -> Result<()> {
+        if let (Some(throttle), Shown::Match(_) | Shown::Context(_)) = (&mut self.throttle, shown) {
+            let (allowed, suppressed) = throttle.admit();
+            if suppressed > 0 {
+                self.write_suppressed(suppressed)?;
+            }
+            if !allowed {
+                return Ok(());
+            }
+        }
+        let mut buf = Vec::new();
+        self.format(&mut buf, shown).expect("writing to a Vec cannot fail");
+        self.emit(&buf)?;
+        if let Shown::Match(record) | Shown::Context(record) = shown {
+            self.display.previous_time.set(record_time(record));
+        }
+        Ok(())
+    }
+
+    /// Writes the marker for entries left out by `--max-rate`: a dimmed row in a table, or
+    /// a note on stderr so that structured output stays valid.
+    fn write_suppressed(&mut self, count: usize) -> Result<()> {
+        if self.display.output != OutputFormat::Table {
+            eprintln!("rlog: {}", suppressed(count));
+            return Ok(());
+        }
+        let mut buf = Vec::new();
+        write_suppressed(&mut buf, count, self.display).expect("writing to a Vec cannot fail");
+        self.emit(&buf)
+    }
+
+    /// Completes the output once no more records will follow.
+    pub fn finish(&mut self) -> Result<()> {
+        mem::take(&mut self.forwarders).into_iter().for_each(Forwarder::finish);
+        if self.display.count {
+            return self.emit(format!("{}\n", self.matches).as_bytes());
+        }
+        if self.display.quiet {
+            return Ok(());
+        }
+        self.idle()?;
+        if let Some(repeated) = self.dedupe.as_mut().and_then(Dedupe::finish) {
+            self.write(&repeated)?;
+        }
+        if self.display.output == OutputFormat::Json {
+            let end = if self.written == 0 { "[]\n" } else { "\n]\n" };
+            self.emit(end.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn format(&mut self, out: &mut Vec<u8>, shown: &Shown) -> io::Result<()> {
+        let record = match (self.display.output, shown) {
+            // Unparsed lines from --show-raw have no columns to put under a header.
+            (OutputFormat::Table, Shown::Match(record) | Shown::Context(record)) if record.malformed => return write_shown(out, shown, self.display),
+            (_, Shown::Match(record) | Shown::Context(record)) if record.malformed => return Ok(()),
+            (OutputFormat::Table, Shown::Match(record) | Shown::Context(record)) => {
+                let every = self.display.header_every.unwrap_or(0);
+                let due = self.written == 0 || (every > 0 && self.written.is_multiple_of(every));
+                if due && !self.display.no_header && self.display.format_out.is_none() {
+                    write_header(out, record, self.display)?;
+                }
+                self.written += 1;
+                return write_shown(out, shown, self.display);
+            }
+            (OutputFormat::Table, shown) => return write_shown(out, shown, self.display),
+            (_, Shown::Match(record) | Shown::Context(record)) => record,
+            (_, Shown::Break | Shown::Repeated(_)) => return Ok(()),
+        };
+        let cells = self.cells(record);
+        match self.display.output {
+            OutputFormat::Table => unreachable!(),
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                let object: Map<String, Value> = cells.into_iter().map(|(header, value)| (header, json_value(&value))).collect();
+                let text = Value::Object(object).to_string();
+                match (self.display.output, self.written) {
+                    (OutputFormat::Json, 0) => write!(out, "[\n{}", text)?,
+                    (OutputFormat::Json, _) => write!(out, ",\n{}", text)?,
+                    _ => writeln!(out, "{}", text)?,
+                }
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let escape = if self.display.output == OutputFormat::Csv { csv_field } else { tsv_field };
+                let separator = if self.display.output == OutputFormat::Csv { "," } else { "\t" };
+                if self.written == 0 {
+                    writeln!(out, "{}", cells.iter().map(|(header, _)| escape(header)).collect::<Vec<_>>().join(separator))?;
+                }
+                writeln!(out, "{}", cells.iter().map(|(_, value)| escape(value)).collect::<Vec<_>>().join(separator))?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Sends formatted output to the terminal unless `--out` is used, and to the file.
+    fn emit(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.display.out.is_none() {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(bytes).and_then(|()| stdout.flush()).map_err(|e| Error::output(None, e))?;
+        }
+        if let Some(file) = &mut self.file {
+            let bytes = match &self.ansi {
+                Some(ansi) => ansi.replace_all(bytes, &b""[..]),
+                None => Cow::Borrowed(bytes),
+            };
+            let path = self.display.out.as_ref().or(self.display.tee.as_ref());
+            file.write_all(&bytes).map_err(|e| Error::output(path, e))?;
+        }
+        Ok(())
+    }
+
+    /// The selected columns of a record under their `--rename`d headers.
+    fn cells(&self, record: &LogRecord) -> Vec<(String, String)> {
+        visible_cells(record, self.display).into_iter()
+            .map(|(header, value, _)| (label(&header, self.display).to_string(), value.into_owned()))
+            .collect()
+    }
+}
+
+/// Keeps JSON objects and arrays (such as the Data column) structured; other values are strings.
+fn json_value(value: &str) -> Value {
+    match serde_json::from_str(value) {
+        Ok(json @ (Value::Object(_) | Value::Array(_))) => json,
+        _ => Value::String(value.to_string()),
+    }
+}
+
+/// Quotes a CSV field when it contains separators, quotes, or line breaks.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes tabs, line breaks, and backslashes in a TSV field.
+fn tsv_field(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
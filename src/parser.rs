@@ -0,0 +1,366 @@
+mod access;
+mod auto;
+mod aws;
+mod cef;
+mod container;
+mod custom;
+mod gelf;
+mod heroku;
+mod journal;
+mod json;
+mod logfmt;
+mod mysql;
+mod oslog;
+mod pattern;
+mod postgres;
+mod syslog;
+mod timestamped;
+mod winlog;
+
+pub use access::{status_level, AccessLogParser};
+pub use auto::{detect_format, AutoParser, DETECTABLE};
+pub use aws::{CloudFrontParser, ElbParser, S3AccessParser};
+pub use cef::{CefParser, LeefParser};
+pub use container::{ContainerLog, ContainerParser};
+pub use custom::{load_formats, CustomFormat, CustomParser, FormatDefinition};
+pub use gelf::GelfParser;
+pub use heroku::HerokuParser;
+pub use journal::JournalParser;
+pub use json::{scalar_text, JsonParser};
+pub use logfmt::LogfmtParser;
+pub use mysql::MysqlSlowParser;
+pub use oslog::OslogParser;
+pub use pattern::PatternParser;
+pub use postgres::PostgresParser;
+pub use syslog::{decode_pri, SyslogParser};
+pub use timestamped::TimestampedParser;
+pub use winlog::WinlogParser;
+
+use crate::record::LogRecord;
+use std::{str::FromStr, sync::Arc};
+
+/// Names of the built-in formats accepted by [`parser_for`].
+pub const FORMATS: &[&str] = &["pipe", "json", "journal", "gelf", "winlog", "oslog", "logfmt", "syslog", "cef", "leef", "heroku", "clf", "combined", "elb", "cloudfront", "s3", "mysql-slow", "postgres", "docker", "cri"];
+
+/// Settings that format-specific parsers pick up, e.g. from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct ParserOptions {
+    /// `(column, key)` pairs overriding which input key fills a standard column.
+    pub keys: Vec<(String, String)>,
+    /// How pipe-delimited logs write a `|` inside a field.
+    pub quoting: Quoting,
+    /// Formats defined in files, selected by name like the built-in ones.
+    pub formats: Vec<Arc<CustomFormat>>,
+}
+
+/// Creates the parser for the given options: a [`PatternParser`] if `pattern` is set,
+/// otherwise the parser for `format`.
+pub fn build_parser(format: &str, pattern: Option<&str>, options: &ParserOptions) -> Result<Box<dyn LogParser + Send>, String> {
+    match pattern {
+        Some(pattern) => Ok(Box::new(PatternParser::new(pattern)?)),
+        None => parser_for(format, options).ok_or_else(|| format!("unknown format `{}`", format)),
+    }
+}
+
+/// Creates a parser for one of the [`FORMATS`], or one of the `formats` of the options,
+/// which the built-in ones take precedence over. The container log formats `docker` and `cri`
+/// detect the format of the lines they wrap, or read it with the one named after a colon,
+/// as in `cri:json`.
+pub fn parser_for(format: &str, options: &ParserOptions) -> Option<Box<dyn LogParser + Send>> {
+    if let Some((layout, inner)) = format.split_once(':') {
+        let layout = match layout {
+            "docker" => ContainerLog::Docker,
+            "cri" => ContainerLog::Cri,
+            _ => return None,
+        };
+        return match inner {
+            "docker" | "cri" => None,
+            inner => Some(Box::new(ContainerParser::new(layout, parser_for(inner, options)?))),
+        };
+    }
+    match format {
+        "pipe" => Some(Box::new(DelimitedParser::new().quoting(options.quoting))),
+        "json" => Some(Box::new(options.keys.iter().fold(JsonParser::new(), |p, (c, k)| p.map_key(c, k)))),
+        "journal" => Some(Box::new(JournalParser::new())),
+        "gelf" => Some(Box::new(GelfParser::new())),
+        "winlog" => Some(Box::new(WinlogParser::new())),
+        "oslog" => Some(Box::new(OslogParser::new())),
+        "logfmt" => Some(Box::new(options.keys.iter().fold(LogfmtParser::new(), |p, (c, k)| p.map_key(c, k)))),
+        "syslog" => Some(Box::new(SyslogParser::new())),
+        "cef" => Some(Box::new(CefParser::new())),
+        "leef" => Some(Box::new(LeefParser::new())),
+        "heroku" => Some(Box::new(HerokuParser::new())),
+        "clf" => Some(Box::new(AccessLogParser::common())),
+        "combined" => Some(Box::new(AccessLogParser::combined())),
+        "elb" => Some(Box::new(ElbParser::new())),
+        "cloudfront" => Some(Box::new(CloudFrontParser::new())),
+        "s3" => Some(Box::new(S3AccessParser::new())),
+        "mysql-slow" => Some(Box::new(MysqlSlowParser::new())),
+        "postgres" => Some(Box::new(PostgresParser::new())),
+        "docker" => Some(Box::new(ContainerParser::detecting(ContainerLog::Docker, options.clone()))),
+        "cri" => Some(Box::new(ContainerParser::detecting(ContainerLog::Cri, options.clone()))),
+        _ => options.formats.iter().find(|custom| custom.name == format).map(|custom| Box::new(CustomParser::new(custom.clone())) as Box<dyn LogParser + Send>),
+    }
+}
+
+/// Turns raw log lines into [`LogRecord`]s.
+pub trait LogParser {
+    /// Parses one line, returning `None` for lines that are not records (headers, garbage).
+    fn parse(&mut self, line: &str) -> Option<LogRecord>;
+
+    /// A copy of the parser in its current state, such as the headers it has seen, so that
+    /// further parts of the input can be parsed in parallel. `None` if it cannot be copied.
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        None
+    }
+
+    /// Whether `line`, which did not parse into a record, is a header line that is skipped on
+    /// purpose rather than a malformed one.
+    fn is_header(&self, _line: &str) -> bool {
+        false
+    }
+
+    /// Whether entries span several lines, so that lines that do not parse continue the
+    /// record before them, as with `--multiline`.
+    fn multiline(&self) -> bool {
+        false
+    }
+
+    /// Adds `line`, which continues `record`, to it: by default to its Message.
+    fn continue_record(&self, record: &mut LogRecord, line: &str) {
+        record.append_line(line);
+    }
+}
+
+/// Parser for pipe-delimited logs whose first line names the columns, e.g. `DateTime|Level|Message|Data`.
+///
+/// Lines are split at each `|` that is not escaped or quoted as set by [`Quoting`]; the last
+/// column takes the rest of the line.
+#[derive(Debug, Clone, Default)]
+pub struct DelimitedParser {
+    header_line: String,
+    headers: Vec<String>,
+    quoting: Quoting,
+}
+
+/// How a literal `|` is written inside a field of a pipe-delimited log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quoting {
+    /// Escaped with a backslash: `a \| b`.
+    #[default]
+    Backslash,
+    /// A field wrapped in double quotes: `"a | b"`, with `""` for a quote inside.
+    Quotes,
+    /// Every `|` separates fields.
+    None,
+}
+
+impl FromStr for Quoting {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Quoting, String> {
+        match text.to_lowercase().as_str() {
+            "backslash" => Ok(Quoting::Backslash),
+            "quotes" | "quote" => Ok(Quoting::Quotes),
+            "none" => Ok(Quoting::None),
+            _ => Err(format!("unknown quoting `{}` (expected backslash, quotes, or none)", text)),
+        }
+    }
+}
+
+impl DelimitedParser {
+    /// Creates a parser that takes its headers from the first line it sees.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a parser with known headers.
+    pub fn with_headers(header_line: &str) -> Self {
+        let mut parser = Self::new();
+        parser.set_headers(header_line);
+        parser
+    }
+
+    /// Reads literal pipes in fields as `quoting` says.
+    pub fn quoting(mut self, quoting: Quoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// The column names, empty until a header line has been seen.
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    fn set_headers(&mut self, header_line: &str) {
+        self.header_line = header_line.trim().to_string();
+        self.headers = self.header_line.split('|').map(str::to_string).collect();
+    }
+}
+
+impl LogParser for DelimitedParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        if self.headers.is_empty() {
+            self.set_headers(line);
+            return None;
+        }
+        let line = line.trim();
+        // A truncated or rotated file starts over with its header line.
+        if line == self.header_line {
+            return None;
+        }
+        let fields = split_fields(line, self.headers.len(), self.quoting)?;
+        let mut record = LogRecord::new(line);
+        for (header, value) in self.headers.iter().zip(fields) {
+            record.push(header.as_str(), value);
+        }
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn is_header(&self, line: &str) -> bool {
+        line.trim() == self.header_line
+    }
+}
+
+/// Splits `line` into `count` fields at the `|` that are not escaped or quoted, the last
+/// field taking the rest of the line. `None` if the line has fewer fields.
+fn split_fields(line: &str, count: usize, quoting: Quoting) -> Option<Vec<String>> {
+    let mut fields = Vec::with_capacity(count);
+    let mut rest = line;
+    while fields.len() + 1 < count {
+        let (field, after) = next_field(rest, quoting)?;
+        fields.push(field);
+        rest = after;
+    }
+    fields.push(match quoting {
+        Quoting::Backslash => rest.replace("\\|", "|"),
+        Quoting::Quotes => quoted(rest).filter(|(_, after)| after.is_empty()).map_or_else(|| rest.to_string(), |(field, _)| field),
+        Quoting::None => rest.to_string(),
+    });
+    Some(fields)
+}
+
+/// Takes the field at the start of `text`, returning it unescaped along with the text after
+/// the `|` that ends it.
+fn next_field(text: &str, quoting: Quoting) -> Option<(String, &str)> {
+    if quoting == Quoting::Quotes {
+        // A quote that does not end right before a `|` was not meant as quoting.
+        if let Some((field, after)) = quoted(text).and_then(|(field, after)| Some((field, after.strip_prefix('|')?))) {
+            return Some((field, after));
+        }
+    }
+    let mut field = String::new();
+    let mut rest = text;
+    loop {
+        let idx = rest.find('|')?;
+        match rest[..idx].strip_suffix('\\').filter(|_| quoting == Quoting::Backslash) {
+            Some(escaped) => {
+                field.push_str(escaped);
+                field.push('|');
+            }
+            None => {
+                field.push_str(&rest[..idx]);
+                return Some((field, &rest[idx + 1..]));
+            }
+        }
+        rest = &rest[idx + 1..];
+    }
+}
+
+/// Reads a `"..."` field at the start of `text`, returning its content and the text after the
+/// closing quote.
+fn quoted(text: &str) -> Option<(String, &str)> {
+    let mut rest = text.strip_prefix('"')?;
+    let mut field = String::new();
+    loop {
+        let idx = rest.find('"')?;
+        field.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+        match rest.strip_prefix('"') {
+            Some(after) => {
+                field.push('"');
+                rest = after;
+            }
+            None => return Some((field, rest)),
+        }
+    }
+}
+
+/// Sets `key` in the Data column of a record, for continuation lines that add fields.
+fn insert_data(record: &mut LogRecord, key: &str, value: serde_json::Value) {
+    let mut data: serde_json::Map<String, serde_json::Value> = record.get("Data").and_then(|data| serde_json::from_str(data).ok()).unwrap_or_default();
+    data.insert(key.to_string(), value);
+    record.set("Data", serde_json::Value::Object(data).to_string());
+}
+
+/// A field value as a JSON number if it is one, so that `Data.key > N` compares numbers.
+fn number_or_text(text: &str) -> serde_json::Value {
+    if !text.bytes().all(|b| b.is_ascii_digit() || b == b'.' || b == b'-') {
+        return text.into();
+    }
+    match text.parse::<i64>() {
+        Ok(n) => n.into(),
+        Err(_) => text.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map_or_else(|| text.into(), serde_json::Value::Number),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `text` line by line as the inputs do, lines that are not records continuing
+    /// the one before them if `parser` is multi-line.
+    pub(super) fn entries(parser: &mut dyn LogParser, text: &str) -> Vec<LogRecord> {
+        let mut records: Vec<LogRecord> = Vec::new();
+        for line in text.lines() {
+            match (parser.parse(line), records.last_mut()) {
+                (Some(record), _) => records.push(record),
+                (None, Some(last)) if parser.multiline() && !parser.is_header(line) => parser.continue_record(last, line),
+                (None, _) => {}
+            }
+        }
+        records
+    }
+
+    #[test]
+    fn last_field_takes_the_rest() {
+        assert_eq!(split_fields("a|b|c|d", 3, Quoting::None).unwrap(), ["a", "b", "c|d"]);
+        assert_eq!(split_fields("a||", 3, Quoting::None).unwrap(), ["a", "", ""]);
+        assert_eq!(split_fields("a|b", 3, Quoting::None), None);
+    }
+
+    #[test]
+    fn backslash_escapes_a_separator() {
+        assert_eq!(split_fields(r"a \| b|c|d \| e", 3, Quoting::Backslash).unwrap(), ["a | b", "c", "d | e"]);
+        assert_eq!(split_fields(r"a \| b|c", 2, Quoting::None).unwrap(), [r"a \", " b|c"]);
+    }
+
+    #[test]
+    fn quotes_wrap_a_field() {
+        assert_eq!(split_fields(r#""a | ""b"""|c|"d | e""#, 3, Quoting::Quotes).unwrap(), [r#"a | "b""#, "c", "d | e"]);
+        // A quote that does not close the field is text.
+        assert_eq!(split_fields(r#""a" b|c"#, 2, Quoting::Quotes).unwrap(), [r#""a" b"#, "c"]);
+    }
+
+    #[test]
+    fn delimited_parser_skips_repeated_headers() {
+        let mut parser = DelimitedParser::new();
+        assert!(parser.parse("DateTime|Level|Message").is_none());
+        let record = parser.parse("2025-03-01 10:00:00|INFO|started | ok").unwrap();
+        assert_eq!(record.fields().collect::<Vec<_>>(), [("DateTime", "2025-03-01 10:00:00"), ("Level", "INFO"), ("Message", "started | ok")]);
+        assert!(parser.parse("DateTime|Level|Message").is_none());
+        assert!(parser.is_header("DateTime|Level|Message"));
+    }
+
+    #[test]
+    fn numbers_are_told_from_text() {
+        assert_eq!(number_or_text("42"), serde_json::json!(42));
+        assert_eq!(number_or_text("-1.5"), serde_json::json!(-1.5));
+        assert_eq!(number_or_text("1.2.3"), serde_json::json!("1.2.3"));
+        assert_eq!(number_or_text("0x10"), serde_json::json!("0x10"));
+    }
+}
@@ -0,0 +1,122 @@
+use super::LogParser;
+use crate::record::LogRecord;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Level for an HTTP status code: 5xx is an error, 4xx a warning, anything else info.
+pub fn status_level(status: &str) -> &'static str {
+    match status.as_bytes().first() {
+        Some(b'5') => "ERROR",
+        Some(b'4') => "WARNING",
+        Some(b'1') => "DEBUG",
+        _ => "INFO",
+    }
+}
+
+/// Parser for web server access logs in Common Log Format, optionally with the Combined
+/// format's referer and user-agent fields (Apache, Nginx).
+///
+/// Columns are `DateTime`, `Level` (from the status class), `IP`, `Method`, `Path`,
+/// `Status`, `Bytes`, then `Referer` and `UserAgent` for the combined format, and `Data`.
+#[derive(Debug, Clone)]
+pub struct AccessLogParser {
+    regex: Regex,
+    combined: bool,
+}
+
+impl AccessLogParser {
+    /// Parser for `host ident user [time] "request" status bytes`.
+    pub fn common() -> Self {
+        Self::build(false)
+    }
+
+    /// Parser for the common format followed by `"referer" "user-agent"`.
+    pub fn combined() -> Self {
+        Self::build(true)
+    }
+
+    fn build(combined: bool) -> Self {
+        let mut pattern = String::from(r#"^(\S+) (\S+) (\S+) \[([^\]]+)\] "((?:[^"\\]|\\.)*)" (\d{3}|-) (\d+|-)"#);
+        if combined {
+            pattern.push_str(r#" "((?:[^"\\]|\\.)*)" "((?:[^"\\]|\\.)*)""#);
+        }
+        pattern.push_str(r"(?:\s.*)?$");
+        AccessLogParser { regex: Regex::new(&pattern).unwrap(), combined }
+    }
+}
+
+impl LogParser for AccessLogParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim();
+        let caps = self.regex.captures(line)?;
+        let mut request = caps[5].splitn(3, ' ');
+        let (method, path, protocol) = (request.next().unwrap_or(""), request.next().unwrap_or(""), request.next().unwrap_or(""));
+
+        let mut data = Map::new();
+        for (key, value) in [("ident", &caps[2]), ("user", &caps[3]), ("protocol", protocol)] {
+            if !value.is_empty() && value != "-" {
+                data.insert(key.into(), Value::String(value.into()));
+            }
+        }
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", &caps[4]);
+        record.push("Level", status_level(&caps[6]));
+        record.push("IP", &caps[1]);
+        record.push("Method", method);
+        record.push("Path", path);
+        record.push("Status", &caps[6]);
+        record.push("Bytes", &caps[7]);
+        if self.combined {
+            record.push("Referer", &caps[8]);
+            record.push("UserAgent", &caps[9]);
+        }
+        record.push("Data", if data.is_empty() { String::new() } else { Value::Object(data).to_string() });
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_combined_format() {
+        let line = r#"203.0.113.7 - alice [01/Mar/2025:10:00:00 +0000] "GET /api/orders?id=1 HTTP/1.1" 503 1234 "https://example.com/" "curl/8.5.0""#;
+        let record = AccessLogParser::combined().parse(line).unwrap();
+        assert_eq!(record.fields().collect::<Vec<_>>(), [
+            ("DateTime", "01/Mar/2025:10:00:00 +0000"),
+            ("Level", "ERROR"),
+            ("IP", "203.0.113.7"),
+            ("Method", "GET"),
+            ("Path", "/api/orders?id=1"),
+            ("Status", "503"),
+            ("Bytes", "1234"),
+            ("Referer", "https://example.com/"),
+            ("UserAgent", "curl/8.5.0"),
+            ("Data", r#"{"user":"alice","protocol":"HTTP/1.1"}"#),
+        ]);
+    }
+
+    #[test]
+    fn reads_the_common_format_with_missing_fields() {
+        let record = AccessLogParser::common().parse(r#"::1 - - [01/Mar/2025:10:00:00 +0000] "-" 408 -"#).unwrap();
+        assert_eq!((record.level(), record.get("Method"), record.get("Bytes"), record.get("Data")), ("WARNING", Some("-"), Some("-"), Some("")));
+        assert!(record.get("Referer").is_none());
+    }
+
+    #[test]
+    fn rejects_other_lines() {
+        assert!(AccessLogParser::combined().parse(r#"::1 - - [01/Mar/2025:10:00:00 +0000] "GET / HTTP/1.1" 200 5"#).is_none());
+        assert!(AccessLogParser::common().parse("2025-03-01 10:00:00 INFO started").is_none());
+    }
+
+    #[test]
+    fn levels_follow_the_status_class() {
+        assert_eq!(["101", "200", "304", "404", "500", "-"].map(status_level), ["DEBUG", "INFO", "INFO", "WARNING", "ERROR", "INFO"]);
+    }
+}
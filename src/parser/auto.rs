@@ -0,0 +1,90 @@
+use super::{parser_for, LogParser, ParserOptions};
+use crate::record::LogRecord;
+
+/// Formats tried by [`detect_format`], in order of preference when several fit equally well.
+pub const DETECTABLE: &[&str] = &["docker", "cri", "journal", "gelf", "winlog", "oslog", "json", "cef", "leef", "heroku", "syslog", "elb", "cloudfront", "s3", "combined", "clf", "mysql-slow", "postgres", "logfmt", "pipe"];
+
+/// Picks the format that parses the most lines of `sample`, defaulting to `pipe`.
+pub fn detect_format(sample: &[String], options: &ParserOptions) -> &'static str {
+    let lines: Vec<&str> = sample.iter().map(|l| l.as_str()).filter(|l| !l.trim().is_empty()).collect();
+    let mut best = ("pipe", 0);
+    for &format in DETECTABLE {
+        let mut parser = parser_for(format, options).expect("detectable formats exist");
+        // Lines a format skips on purpose, like the count `log show` ends with, fit it too,
+        // and so do the lines of its multi-line entries. The pipe parser takes any first line
+        // for its header, so is scored below.
+        let mut started = false;
+        let mut score = lines.iter().filter(|line| {
+            let parsed = parser.parse(line).is_some();
+            started |= parsed;
+            parsed || (format != "pipe" && parser.is_header(line)) || (started && parser.multiline())
+        }).count();
+        // The pipe parser consumes the header line rather than returning a record for it.
+        if format == "pipe" && lines.first().is_some_and(|l| l.contains('|')) && score > 0 {
+            score += 1;
+        }
+        if score > best.1 {
+            best = (format, score);
+        }
+    }
+    best.0
+}
+
+/// Called with the name of the detected format.
+type DetectCallback = Box<dyn FnMut(&str) + Send>;
+
+/// Parser that decides on a format from the first line it sees, for inputs that cannot be
+/// sampled up front (stdin, pipes).
+pub struct AutoParser {
+    options: ParserOptions,
+    inner: Option<Box<dyn LogParser + Send>>,
+    on_detect: Option<DetectCallback>,
+}
+
+impl AutoParser {
+    /// Creates a parser that has not picked a format yet.
+    pub fn new(options: ParserOptions) -> Self {
+        AutoParser { options, inner: None, on_detect: None }
+    }
+
+    /// Calls `callback` with the format name once it has been detected.
+    pub fn on_detect(mut self, callback: impl FnMut(&str) + Send + 'static) -> Self {
+        self.on_detect = Some(Box::new(callback));
+        self
+    }
+}
+
+impl LogParser for AutoParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        if self.inner.is_none() {
+            if line.trim().is_empty() {
+                return None;
+            }
+            let format = detect_format(&[line.to_string()], &self.options);
+            if let Some(callback) = &mut self.on_detect {
+                callback(format);
+            }
+            self.inner = parser_for(format, &self.options);
+        }
+        self.inner.as_mut()?.parse(line)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        self.inner.as_ref()?.fork()
+    }
+
+    fn is_header(&self, line: &str) -> bool {
+        self.inner.as_ref().is_some_and(|inner| inner.is_header(line))
+    }
+
+    fn multiline(&self) -> bool {
+        self.inner.as_ref().is_some_and(|inner| inner.multiline())
+    }
+
+    fn continue_record(&self, record: &mut LogRecord, line: &str) {
+        match &self.inner {
+            Some(inner) => inner.continue_record(record, line),
+            None => record.append_line(line),
+        }
+    }
+}
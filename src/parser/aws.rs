@@ -0,0 +1,363 @@
+use super::{number_or_text, status_level, LogParser};
+use crate::record::LogRecord;
+use serde_json::{Map, Value};
+
+/// The fields of an Application Load Balancer entry, in order.
+const ALB_FIELDS: [&str; 30] = [
+    "type", "time", "elb", "client", "target", "request_processing_time", "target_processing_time", "response_processing_time",
+    "elb_status_code", "target_status_code", "received_bytes", "sent_bytes", "request", "user_agent", "ssl_cipher",
+    "ssl_protocol", "target_group_arn", "trace_id", "domain_name", "chosen_cert_arn", "matched_rule_priority",
+    "request_creation_time", "actions_executed", "redirect_url", "error_reason", "target_list", "target_status_code_list",
+    "classification", "classification_reason", "conn_trace_id",
+];
+
+/// The fields of a Classic Load Balancer entry, in order.
+const ELB_FIELDS: [&str; 15] = [
+    "time", "elb", "client", "backend", "request_processing_time", "backend_processing_time", "response_processing_time",
+    "elb_status_code", "backend_status_code", "received_bytes", "sent_bytes", "request", "user_agent", "ssl_cipher", "ssl_protocol",
+];
+
+/// The fields of a CloudFront standard log entry when there is no `#Fields:` line.
+const CLOUDFRONT_FIELDS: [&str; 33] = [
+    "date", "time", "x-edge-location", "sc-bytes", "c-ip", "cs-method", "cs(Host)", "cs-uri-stem", "sc-status", "cs(Referer)",
+    "cs(User-Agent)", "cs-uri-query", "cs(Cookie)", "x-edge-result-type", "x-edge-request-id", "x-host-header", "cs-protocol",
+    "cs-bytes", "time-taken", "x-forwarded-for", "ssl-protocol", "ssl-cipher", "x-edge-response-result-type",
+    "cs-protocol-version", "fle-status", "fle-encrypted-fields", "c-port", "time-to-first-byte", "x-edge-detailed-result-type",
+    "sc-content-type", "sc-content-len", "sc-range-start", "sc-range-end",
+];
+
+/// The fields of an S3 server access log entry, in order.
+const S3_FIELDS: [&str; 25] = [
+    "bucket_owner", "bucket", "time", "remote_ip", "requester", "request_id", "operation", "key", "request_uri", "http_status",
+    "error_code", "bytes_sent", "object_size", "total_time", "turn_around_time", "referer", "user_agent", "version_id",
+    "host_id", "signature_version", "cipher_suite", "authentication_type", "host_header", "tls_version", "access_point_arn",
+];
+
+/// The standard columns of an access log entry, with the fields that fill them removed from
+/// the rest, which go into the Data column.
+#[derive(Default)]
+struct Access {
+    time: String,
+    status: String,
+    ip: String,
+    request: String,
+    bytes: String,
+    referer: String,
+    user_agent: String,
+}
+
+impl Access {
+    fn record(self, line: &str, data: Map<String, Value>) -> LogRecord {
+        // `GET https://example.com:443/path?q HTTP/1.1`: load balancers log the whole URL.
+        let mut request = self.request.splitn(3, ' ');
+        let (method, url, protocol) = (request.next().unwrap_or(""), request.next().unwrap_or(""), request.next().unwrap_or(""));
+        let path = url.split_once("://").map_or(url, |(_, rest)| rest.find('/').map_or("/", |idx| &rest[idx..]));
+        let mut data = data;
+        if !protocol.is_empty() {
+            data.insert("protocol".into(), protocol.into());
+        }
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", self.time);
+        record.push("Level", status_level(&self.status));
+        record.push("IP", self.ip);
+        record.push("Method", method);
+        record.push("Path", path);
+        record.push("Status", self.status);
+        record.push("Bytes", self.bytes);
+        record.push("Referer", self.referer);
+        record.push("UserAgent", self.user_agent);
+        record.push("Data", if data.is_empty() { String::new() } else { Value::Object(data).to_string() });
+        record
+    }
+}
+
+/// Parser for AWS Elastic Load Balancing access logs: Application Load Balancer entries,
+/// which start with the request type (`http`, `https`, `h2`, `grpcs`, `ws`, or `wss`),
+/// and Classic Load Balancer entries, which start with the time.
+///
+/// The load balancer's status code gives the Level, so 5xx responses it returned itself
+/// or passed on are errors and 4xx warnings. The other fields, such as `target`,
+/// `target_processing_time` (in seconds), `target_status_code`, `trace_id`, and
+/// `error_reason`, go into the Data column. Columns are `DateTime`, `Level`, `IP`,
+/// `Method`, `Path`, `Status`, `Bytes`, `Referer` (always empty), `UserAgent`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct ElbParser;
+
+impl ElbParser {
+    /// Creates a parser for load balancer access logs.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogParser for ElbParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim();
+        let tokens = tokens(line, ' ');
+        let names: &[&str] = match tokens.first()?.as_str() {
+            "http" | "https" | "h2" | "grpcs" | "ws" | "wss" => &ALB_FIELDS,
+            _ => &ELB_FIELDS,
+        };
+        let mut fields = fields(names, tokens)?;
+        let (ip, port) = take(&mut fields, "client").rsplit_once(':').map(|(ip, port)| (ip.to_string(), port.to_string())).unwrap_or_default();
+        if !port.is_empty() {
+            fields.insert("client_port".into(), number_or_text(&port));
+        }
+        let access = Access {
+            time: take(&mut fields, "time"),
+            status: take(&mut fields, "elb_status_code"),
+            ip,
+            request: take(&mut fields, "request"),
+            bytes: take(&mut fields, "sent_bytes"),
+            user_agent: take(&mut fields, "user_agent"),
+            ..Access::default()
+        };
+        if access.time.is_empty() || !access.time.contains('T') {
+            return None;
+        }
+        Some(access.record(line, fields))
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Parser for Amazon CloudFront standard (access) logs: tab-separated fields, named by the
+/// `#Fields:` line at the top of each file.
+///
+/// The `sc-status` gives the Level. The edge location, result type, `time-taken` (in
+/// seconds), and the other fields go into the Data column, named in snake case, such as
+/// `x_edge_location` and `cs_host`. Columns are `DateTime`, `Level`, `IP`, `Method`,
+/// `Path`, `Status`, `Bytes`, `Referer`, `UserAgent`, and `Data`.
+#[derive(Debug, Clone)]
+pub struct CloudFrontParser {
+    names: Vec<String>,
+}
+
+impl Default for CloudFrontParser {
+    fn default() -> Self {
+        CloudFrontParser { names: CLOUDFRONT_FIELDS.iter().map(|name| name.to_string()).collect() }
+    }
+}
+
+impl CloudFrontParser {
+    /// Creates a parser for CloudFront logs, expecting the fields CloudFront writes by
+    /// default until a `#Fields:` line says otherwise.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LogParser for CloudFrontParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if let Some(names) = line.strip_prefix("#Fields:") {
+            self.names = names.split_whitespace().map(str::to_string).collect();
+            return None;
+        }
+        if line.starts_with('#') || !line.contains('\t') {
+            return None;
+        }
+        let names: Vec<&str> = self.names.iter().map(String::as_str).collect();
+        let mut fields = fields(&names, tokens(line, '\t'))?;
+        let (date, time) = (take(&mut fields, "date"), take(&mut fields, "time"));
+        if date.len() != 10 || !date.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        let query = take(&mut fields, "cs-uri-query");
+        let mut path = take(&mut fields, "cs-uri-stem");
+        if !query.is_empty() {
+            path = format!("{}?{}", path, query);
+        }
+        let method = take(&mut fields, "cs-method");
+        let access = Access {
+            time: format!("{}T{}Z", date, time),
+            status: take(&mut fields, "sc-status"),
+            ip: take(&mut fields, "c-ip"),
+            request: format!("{} {}", method, path),
+            bytes: take(&mut fields, "sc-bytes"),
+            referer: take(&mut fields, "cs(Referer)"),
+            user_agent: take(&mut fields, "cs(User-Agent)").replace("%20", " "),
+        };
+        let data = fields.into_iter().map(|(name, value)| (snake_case(&name), value)).collect();
+        Some(access.record(line, data))
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn is_header(&self, line: &str) -> bool {
+        line.starts_with("#Version:") || line.starts_with("#Fields:")
+    }
+}
+
+/// Parser for Amazon S3 server access logs.
+///
+/// The `http_status` gives the Level. The bucket, operation, key, `error_code`,
+/// `total_time` (in milliseconds), and the other fields go into the Data column. Columns
+/// are `DateTime`, `Level`, `IP`, `Method`, `Path`, `Status`, `Bytes`, `Referer`,
+/// `UserAgent`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct S3AccessParser;
+
+impl S3AccessParser {
+    /// Creates a parser for S3 server access logs.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogParser for S3AccessParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim();
+        let mut fields = fields(&S3_FIELDS, tokens(line, ' '))?;
+        let time = take(&mut fields, "time");
+        let status = take(&mut fields, "http_status");
+        if !line.split(' ').nth(2)?.starts_with('[') || (!status.is_empty() && status.parse::<u16>().is_err()) {
+            return None;
+        }
+        let access = Access {
+            time,
+            status,
+            ip: take(&mut fields, "remote_ip"),
+            request: take(&mut fields, "request_uri"),
+            bytes: take(&mut fields, "bytes_sent"),
+            referer: take(&mut fields, "referer"),
+            user_agent: take(&mut fields, "user_agent"),
+        };
+        Some(access.record(line, fields))
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Splits a line at `separator`, keeping `"quoted"` and `[bracketed]` fields whole and
+/// dropping their quotes and brackets.
+fn tokens(line: &str, separator: char) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        let (token, after) = match rest.chars().next() {
+            Some(open @ ('"' | '[')) => {
+                let close = if open == '"' { '"' } else { ']' };
+                match rest[1..].find(close) {
+                    Some(end) => (&rest[1..end + 1], &rest[end + 2..]),
+                    None => (&rest[1..], ""),
+                }
+            }
+            _ => rest.split_once(separator).unwrap_or((rest, "")),
+        };
+        tokens.push(token.to_string());
+        rest = after.strip_prefix(separator).unwrap_or(after);
+    }
+    tokens
+}
+
+/// Names the tokens of an entry, leaving out empty and `-` values and reading numbers as
+/// such. `None` if there are fewer tokens than the fields that fill the columns need;
+/// newer fields at the end may be missing, and unknown ones are left out.
+fn fields(names: &[&str], tokens: Vec<String>) -> Option<Map<String, Value>> {
+    if tokens.len() < names.len().min(12) {
+        return None;
+    }
+    let mut fields = Map::new();
+    for (name, token) in names.iter().zip(tokens) {
+        if !token.is_empty() && token != "-" {
+            fields.insert(name.to_string(), number_or_text(&token));
+        }
+    }
+    Some(fields)
+}
+
+/// Removes a field, returning its text.
+fn take(fields: &mut Map<String, Value>, name: &str) -> String {
+    match fields.shift_remove(name) {
+        Some(Value::String(text)) => text,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    }
+}
+
+/// A CloudFront field name as a Data key: `cs(User-Agent)` becomes `cs_user_agent`.
+fn snake_case(name: &str) -> String {
+    let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect();
+    name.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(record: &LogRecord) -> Value {
+        serde_json::from_str(record.get("Data").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_application_load_balancer_entries() {
+        let line = r#"https 2018-07-02T22:23:00.186641Z app/my-loadbalancer/50dc6c495c0c9188 192.168.131.39:2817 10.0.0.1:80 0.086 0.048 0.037 502 - 34 366 "GET https://www.example.com:443/api/users?id=7 HTTP/1.1" "curl/7.46.0" ECDHE-RSA-AES128-GCM-SHA256 TLSv1.2 arn:aws:elasticloadbalancing:us-east-2:123456789012:targetgroup/my-targets/73e2d6bc24d8a067 "Root=1-58337281-1d84f3d73c47ec4e58577259" "www.example.com" "-" 1 2018-07-02T22:22:48.364000Z "forward" "-" "Target.ResponseCodeMismatch" "10.0.0.1:80" "-" "-" "-""#;
+        let record = ElbParser::new().parse(line).unwrap();
+        assert_eq!(record.datetime(), "2018-07-02T22:23:00.186641Z");
+        assert_eq!(record.level(), "ERROR");
+        assert_eq!(record.get("IP"), Some("192.168.131.39"));
+        assert_eq!(record.get("Method"), Some("GET"));
+        assert_eq!(record.get("Path"), Some("/api/users?id=7"));
+        assert_eq!(record.get("Bytes"), Some("366"));
+        assert_eq!(record.get("UserAgent"), Some("curl/7.46.0"));
+        let data = data(&record);
+        assert_eq!(data["client_port"], 2817);
+        assert_eq!(data["target_processing_time"], 0.048);
+        assert_eq!(data["error_reason"], "Target.ResponseCodeMismatch");
+        assert_eq!(data["protocol"], "HTTP/1.1");
+        assert!(data.get("target_status_code").is_none());
+    }
+
+    #[test]
+    fn parses_classic_load_balancer_entries() {
+        let line = r#"2015-05-13T23:39:43.945958Z my-loadbalancer 192.168.131.39:2817 10.0.0.1:80 0.000073 0.001048 0.000057 404 404 0 29 "GET http://www.example.com:80/missing HTTP/1.1" "curl/7.38.0" - -"#;
+        let record = ElbParser::new().parse(line).unwrap();
+        assert_eq!(record.level(), "WARNING");
+        assert_eq!(record.get("Path"), Some("/missing"));
+        assert_eq!(data(&record)["backend"], "10.0.0.1:80");
+        assert!(ElbParser::new().parse("GET /index.html 200").is_none());
+    }
+
+    #[test]
+    fn names_cloudfront_fields_by_the_fields_line() {
+        let mut parser = CloudFrontParser::new();
+        assert!(parser.is_header("#Version: 1.0"));
+        assert!(parser.parse("#Fields: date time x-edge-location sc-bytes c-ip cs-method cs(Host) cs-uri-stem sc-status cs(Referer) cs(User-Agent) cs-uri-query").is_none());
+        let record = parser.parse("2019-12-04\t21:02:31\tLAX1\t392\t192.0.2.100\tGET\td111111abcdef8.cloudfront.net\t/index.html\t200\t-\tMozilla/5.0%20(Windows)\tlang=en").unwrap();
+        assert_eq!(record.datetime(), "2019-12-04T21:02:31Z");
+        assert_eq!(record.level(), "INFO");
+        assert_eq!(record.get("Path"), Some("/index.html?lang=en"));
+        assert_eq!(record.get("UserAgent"), Some("Mozilla/5.0 (Windows)"));
+        let data = data(&record);
+        assert_eq!(data["x_edge_location"], "LAX1");
+        assert_eq!(data["cs_host"], "d111111abcdef8.cloudfront.net");
+    }
+
+    #[test]
+    fn parses_s3_server_access_entries() {
+        let line = r#"79a59df900b949e55d96a1e698fbacedfd6e09d98eacf8f8d5218e7cd47ef2be awsexamplebucket1 [06/Feb/2019:00:00:38 +0000] 192.0.2.3 79a59df900b949e55d96a1e698fbacedfd6e09d98eacf8f8d5218e7cd47ef2be 3E57427F3EXAMPLE REST.GET.OBJECT photos/cat.jpg "GET /awsexamplebucket1/photos/cat.jpg HTTP/1.1" 403 AccessDenied 243 - 7 - "-" "S3Console/0.4" - s9lzHYrFp76ZVxRcpX9+5cjAnEH2ROuNkd2BHfIa6UkFVdtjf5mKR3/eTPFvsiP/XV/VLi31234= SigV4 ECDHE-RSA-AES128-GCM-SHA256 AuthHeader awsexamplebucket1.s3.us-west-1.amazonaws.com TLSV1.2 -"#;
+        let record = S3AccessParser::new().parse(line).unwrap();
+        assert_eq!(record.datetime(), "06/Feb/2019:00:00:38 +0000");
+        assert_eq!(record.level(), "WARNING");
+        assert_eq!(record.get("IP"), Some("192.0.2.3"));
+        assert_eq!(record.get("Path"), Some("/awsexamplebucket1/photos/cat.jpg"));
+        let data = data(&record);
+        assert_eq!(data["operation"], "REST.GET.OBJECT");
+        assert_eq!(data["error_code"], "AccessDenied");
+        assert_eq!(data["total_time"], 7);
+    }
+
+    #[test]
+    fn splits_quoted_and_bracketed_tokens() {
+        assert_eq!(tokens(r#"a "b c" [d e] f"#, ' '), ["a", "b c", "d e", "f"]);
+        assert_eq!(snake_case("cs(User-Agent)"), "cs_user_agent");
+    }
+}
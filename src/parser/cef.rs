@@ -0,0 +1,315 @@
+use super::{LogParser, SyslogParser};
+use crate::record::LogRecord;
+use chrono::{DateTime, SecondsFormat};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::sync::LazyLock;
+
+/// Parser for ArcSight Common Event Format lines,
+/// `CEF:Version|Vendor|Product|Version|Signature ID|Name|Severity|Extension`, on their own or
+/// after a syslog header as firewalls and IDSs send them.
+///
+/// The Name becomes the Message, the Product the App, and the 0-10 Severity (or `Low` to
+/// `Very-High`) the Level: INFO up to 3, WARNING up to 6, ERROR up to 8, and CRITICAL above.
+/// The DateTime is the `rt` extension, or else the syslog header's, and the Host the syslog
+/// header's, or else `dvchost`. The vendor, version, signature ID, and severity go into the
+/// Data column with the extension's key-value pairs, such as `src` and `dpt`. Columns are
+/// `DateTime`, `Level`, `Host`, `App`, `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct CefParser {
+    syslog: SyslogParser,
+}
+
+impl CefParser {
+    /// Creates a parser for CEF lines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LogParser for CefParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        static START: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\s)CEF:(\d+)\|").unwrap());
+        let line = line.trim_end();
+        let caps = START.captures(line)?;
+        let (fields, extension) = split_header(&line[caps.get(0)?.end()..], 6)?;
+        let [vendor, product, version, signature, name, severity] = fields.try_into().ok()?;
+        let data = pairs(extension, None);
+
+        let mut header = Map::new();
+        header.insert("cef_version".into(), caps[1].into());
+        header.insert("vendor".into(), vendor.into());
+        header.insert("version".into(), version.into());
+        header.insert("signature_id".into(), signature.into());
+        header.insert("severity".into(), severity.as_str().into());
+        let prefix = (caps.get(0)?.start() > 0).then(|| self.syslog.parse(line)).flatten();
+        Some(record(line, prefix, Columns {
+            time: text(&data, "rt"),
+            level: severity_level(&severity),
+            host: text(&data, "dvchost"),
+            app: product,
+            message: name,
+            data: header.into_iter().chain(data).collect(),
+        }))
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Parser for IBM QRadar Log Event Extended Format lines,
+/// `LEEF:Version|Vendor|Product|Version|Event ID|Attributes`, on their own or after a syslog
+/// header. LEEF 2.0 may name the attribute delimiter before the attributes; it is a tab
+/// otherwise, or spaces when there are no tabs.
+///
+/// The Event ID becomes the Message, the Product the App, and the 1-10 `sev` attribute the
+/// Level as with [`CefParser`]. The DateTime is the `devTime` attribute, or else the syslog
+/// header's, and the Host the syslog header's, or else `identHostName`. The vendor, version,
+/// and attributes go into the Data column. Columns are `DateTime`, `Level`, `Host`, `App`,
+/// `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct LeefParser {
+    syslog: SyslogParser,
+}
+
+impl LeefParser {
+    /// Creates a parser for LEEF 1.0 and 2.0 lines.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LogParser for LeefParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        static START: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\s)LEEF:(\d+(?:\.\d+)?)\|").unwrap());
+        let line = line.trim_end();
+        let caps = START.captures(line)?;
+        let (fields, mut attributes) = split_header(&line[caps.get(0)?.end()..], 4)?;
+        let [vendor, product, version, event] = fields.try_into().ok()?;
+        let mut delimiter = Some('\t');
+        if caps[1].starts_with('2') {
+            // `x09` or `^`; a LEEF 2.0 line may leave the delimiter out.
+            if let Some((named, rest)) = attributes.split_once('|').filter(|(named, _)| !named.contains('=')) {
+                delimiter = match named.strip_prefix(['x', 'X']).or_else(|| named.strip_prefix("0x")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => named.chars().next(),
+                };
+                attributes = rest;
+            }
+        }
+        if delimiter.is_some_and(|d| !attributes.contains(d)) {
+            delimiter = None;
+        }
+        let data = pairs(attributes, delimiter);
+
+        let mut header = Map::new();
+        header.insert("leef_version".into(), caps[1].into());
+        header.insert("vendor".into(), vendor.into());
+        header.insert("version".into(), version.into());
+        let prefix = (caps.get(0)?.start() > 0).then(|| self.syslog.parse(line)).flatten();
+        Some(record(line, prefix, Columns {
+            time: text(&data, "devTime"),
+            level: text(&data, "sev").map(|sev| severity_level(&sev)).unwrap_or_default(),
+            host: text(&data, "identHostName"),
+            app: product,
+            message: event,
+            data: header.into_iter().chain(data).collect(),
+        }))
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// What a CEF or LEEF line says about the standard columns.
+struct Columns {
+    time: Option<String>,
+    level: String,
+    host: Option<String>,
+    app: String,
+    message: String,
+    data: Map<String, Value>,
+}
+
+/// Builds the record, taking the DateTime and Host from the syslog header `prefix`, if any,
+/// when the event does not give them.
+fn record(line: &str, prefix: Option<LogRecord>, columns: Columns) -> LogRecord {
+    let from_prefix = |column: &str| prefix.as_ref().and_then(|p| p.get(column)).filter(|v| !v.is_empty()).map(str::to_string);
+    let time = columns.time.map(|time| event_time(&time)).or_else(|| from_prefix("DateTime")).unwrap_or_default();
+    let host = from_prefix("Host").or(columns.host).unwrap_or_default();
+
+    let mut record = LogRecord::new(line);
+    record.push("DateTime", time);
+    record.push("Level", columns.level);
+    record.push("Host", host);
+    record.push("App", columns.app);
+    record.push("Message", columns.message);
+    record.push("Data", Value::Object(columns.data).to_string());
+    record
+}
+
+/// The text of an extension value.
+fn text(data: &Map<String, Value>, key: &str) -> Option<String> {
+    data.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+/// The time of an `rt` or `devTime` value: milliseconds since the epoch, or a date as the
+/// device wrote it, such as `Oct 11 2025 22:14:15`.
+fn event_time(value: &str) -> String {
+    match value.parse::<i64>().ok().and_then(DateTime::from_timestamp_millis) {
+        Some(time) => time.to_rfc3339_opts(SecondsFormat::Millis, true),
+        None => value.to_string(),
+    }
+}
+
+/// The level for a CEF or LEEF severity.
+fn severity_level(severity: &str) -> String {
+    let level = match severity.trim().to_ascii_lowercase().as_str() {
+        "low" => "INFO",
+        "medium" => "WARNING",
+        "high" => "ERROR",
+        "very-high" => "CRITICAL",
+        number => match number.parse::<u8>() {
+            Ok(0..=3) => "INFO",
+            Ok(4..=6) => "WARNING",
+            Ok(7..=8) => "ERROR",
+            Ok(_) => "CRITICAL",
+            Err(_) => "",
+        },
+    };
+    level.to_string()
+}
+
+/// Splits the first `n` `|`-separated header fields off `text`, unescaping `\|` and `\\`,
+/// and returns them with the rest.
+fn split_header(text: &str, n: usize) -> Option<(Vec<String>, &str)> {
+    let mut fields = Vec::with_capacity(n);
+    let mut field = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped @ ('|' | '\\'))) => field.push(escaped),
+                Some((_, other)) => field.extend(['\\', other]),
+                None => field.push('\\'),
+            },
+            '|' => {
+                fields.push(std::mem::take(&mut field));
+                if fields.len() == n {
+                    return Some((fields, &text[i + 1..]));
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    // The extension is optional, and so is the bar before it.
+    (fields.len() == n - 1).then(|| {
+        fields.push(field);
+        (fields, "")
+    })
+}
+
+/// Reads the `key=value` pairs of an extension: separated by `delimiter`, or where none is
+/// given, each value running up to the space before the next key, as in
+/// `msg=Worm stopped src=10.0.0.1`. `\=`, `\\`, `\n`, and `\r` are unescaped.
+fn pairs(text: &str, delimiter: Option<char>) -> Map<String, Value> {
+    static KEY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\s)([\w.\[\]-]+)=").unwrap());
+    let mut pairs = Map::new();
+    let mut add = |key: &str, value: &str| {
+        pairs.insert(key.to_string(), unescape(value).into());
+    };
+    match delimiter {
+        Some(delimiter) => {
+            for pair in text.split(delimiter) {
+                if let Some((key, value)) = pair.split_once('=') {
+                    add(key.trim(), value);
+                }
+            }
+        }
+        None => {
+            let keys: Vec<_> = KEY.captures_iter(text).map(|caps| (caps.get(0).unwrap().start(), caps.get(1).unwrap())).collect();
+            for (idx, (_, key)) in keys.iter().enumerate() {
+                let end = keys.get(idx + 1).map_or(text.len(), |(start, _)| *start);
+                add(key.as_str(), text[key.end() + 1..end].trim_end());
+            }
+        }
+    }
+    pairs
+}
+
+/// Undoes the escaping of an extension value.
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('r') => text.push('\r'),
+            Some(escaped @ ('=' | '\\' | '|')) => text.push(escaped),
+            Some(other) => text.extend(['\\', other]),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_cef_line_with_its_extension() {
+        let line = r"CEF:0|Security|threatmanager|1.0|100|worm successfully stopped|10|src=10.0.0.1 dst=2.1.2.2 msg=Worm \= stopped\nfor good rt=1760220855000 dvchost=ids1";
+        let record = CefParser::new().parse(line).unwrap();
+        assert_eq!(record.datetime(), "2025-10-11T22:14:15.000Z");
+        assert_eq!(record.level(), "CRITICAL");
+        assert_eq!(record.get("Host"), Some("ids1"));
+        assert_eq!(record.get("App"), Some("threatmanager"));
+        assert_eq!(record.get("Message"), Some("worm successfully stopped"));
+        let data: Value = serde_json::from_str(record.get("Data").unwrap()).unwrap();
+        assert_eq!(data["vendor"], "Security");
+        assert_eq!(data["signature_id"], "100");
+        assert_eq!(data["src"], "10.0.0.1");
+        assert_eq!(data["msg"], "Worm = stopped\nfor good");
+    }
+
+    #[test]
+    fn takes_the_host_from_a_syslog_header() {
+        let line = r"<134>Oct 11 22:14:15 fw01 CEF:0|Vendor|Fire\|wall|2.0|42|Blocked|Medium|dvchost=other";
+        let record = CefParser::new().parse(line).unwrap();
+        assert_eq!(record.get("Host"), Some("fw01"));
+        assert_eq!(record.get("App"), Some("Fire|wall"));
+        assert_eq!(record.level(), "WARNING");
+        assert!(!record.datetime().is_empty());
+        assert!(CefParser::new().parse("CEF:0|too|few|fields").is_none());
+        assert!(CefParser::new().parse("plain text").is_none());
+    }
+
+    #[test]
+    fn maps_severities_to_levels() {
+        let levels: Vec<String> = ["0", "3", "4", "7", "9", "Low", "High", "Very-High", "?"].iter().map(|s| severity_level(s)).collect();
+        assert_eq!(levels, ["INFO", "INFO", "WARNING", "ERROR", "CRITICAL", "INFO", "ERROR", "CRITICAL", ""]);
+    }
+
+    #[test]
+    fn parses_leef_attributes_by_their_delimiter() {
+        let record = LeefParser::new().parse("LEEF:1.0|IBM|QRadar|7.4|login failed|sev=7\tsrc=10.0.0.5\tusrName=bob smith").unwrap();
+        assert_eq!(record.get("Message"), Some("login failed"));
+        assert_eq!(record.level(), "ERROR");
+        let data: Value = serde_json::from_str(record.get("Data").unwrap()).unwrap();
+        assert_eq!(data["usrName"], "bob smith");
+        let record = LeefParser::new().parse("LEEF:2.0|Lancope|StealthWatch|1.0|41|^|src=10.0.1.8^dst=10.0.0.5^sev=5^identHostName=sw1").unwrap();
+        assert_eq!(record.get("Host"), Some("sw1"));
+        assert_eq!(record.level(), "WARNING");
+        let data: Value = serde_json::from_str(record.get("Data").unwrap()).unwrap();
+        assert_eq!((data["src"].as_str(), data["dst"].as_str()), (Some("10.0.1.8"), Some("10.0.0.5")));
+        let record = LeefParser::new().parse("LEEF:2.0|V|P|1|E|x09|a=1\tb=two words").unwrap();
+        assert_eq!(serde_json::from_str::<Value>(record.get("Data").unwrap()).unwrap()["b"], "two words");
+    }
+}
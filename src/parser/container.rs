@@ -0,0 +1,212 @@
+use super::{detect_format, parser_for, LogParser, ParserOptions};
+use crate::{datetime::parse_datetime, record::LogRecord};
+use serde_json::{Map, Value};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Most lines tried to detect the format of the lines a container writes.
+const DETECT_LINES: usize = 50;
+
+/// The on-disk layouts of container logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerLog {
+    /// Docker's `json-file` driver, as in `/var/lib/docker/containers/ID/ID-json.log`:
+    /// `{"log":"text\n","stream":"stdout","time":"2025-10-11T22:14:15.123456789Z"}`. A `log`
+    /// without the newline is part of a longer line.
+    Docker,
+    /// The CRI format kubelet writes to `/var/log/pods/` and `/var/log/containers/`:
+    /// `2025-10-11T22:14:15.123456789Z stdout F text`, where `P` marks part of a longer line.
+    Cri,
+}
+
+/// One line of a container log, unwrapped.
+struct Wrapped<'a> {
+    time: String,
+    stream: String,
+    text: Cow<'a, str>,
+    partial: bool,
+}
+
+/// A line split across entries, pieced together until its last part.
+struct Pending {
+    time: String,
+    text: String,
+    raw: String,
+}
+
+/// Parser for container log files, which wrap each line a container wrote with when and to
+/// which stream it wrote it. Lines longer than the runtime's buffer (16 KiB) come split
+/// into parts, which are joined again before parsing.
+///
+/// The line is parsed by an inner parser: one given for the format, or else one detected
+/// from the first line a format other than plain text fits, so that a startup banner does
+/// not hide the format of what follows; text it does not recognize
+/// becomes the Message as it is, as with
+/// [`TimestampedParser`](super::TimestampedParser). The wrapper's timestamp fills the
+/// DateTime column when the inner parser finds none, and the stream (`stdout` or `stderr`)
+/// is the Stream column.
+pub struct ContainerParser {
+    layout: ContainerLog,
+    inner: Option<Box<dyn LogParser + Send>>,
+    /// Options for the inner parser, while it is still to be detected.
+    detect: Option<ParserOptions>,
+    /// Number of lines tried while detecting, none of which a format fit.
+    tried: usize,
+    /// Parts of split lines, by stream.
+    pending: HashMap<String, Pending>,
+}
+
+impl ContainerParser {
+    /// Creates a parser reading the lines in `layout` with `inner`.
+    pub fn new(layout: ContainerLog, inner: Box<dyn LogParser + Send>) -> Self {
+        ContainerParser { layout, inner: Some(inner), detect: None, tried: 0, pending: HashMap::new() }
+    }
+
+    /// Creates a parser that detects the format of the lines as they come.
+    pub fn detecting(layout: ContainerLog, options: ParserOptions) -> Self {
+        ContainerParser { layout, inner: None, detect: Some(options), tried: 0, pending: HashMap::new() }
+    }
+
+    /// Looks for a format that fits `text`. Plain text fits no format, and would be taken for
+    /// the header of a pipe log, so detection goes on with the next lines until another one
+    /// fits or enough have been tried.
+    fn detect(&mut self, text: &str) {
+        let Some(options) = &self.detect else { return };
+        self.tried += 1;
+        match detect_format(&[text.to_string()], options) {
+            "pipe" if self.tried < DETECT_LINES => return,
+            "pipe" => {}
+            format => self.inner = parser_for(format, options),
+        }
+        self.detect = None;
+    }
+
+    fn decode<'a>(&self, line: &'a str) -> Option<Wrapped<'a>> {
+        match self.layout {
+            ContainerLog::Docker => {
+                let Value::Object(mut object): Value = serde_json::from_str(line.trim()).ok()? else { return None };
+                let Some(Value::String(text)) = object.shift_remove("log") else { return None };
+                let time = string(&mut object, "time")?;
+                let partial = !text.ends_with('\n');
+                let text = text.strip_suffix('\n').map_or_else(|| text.clone(), str::to_string);
+                Some(Wrapped { time, stream: string(&mut object, "stream").unwrap_or_default(), text: text.into(), partial })
+            }
+            ContainerLog::Cri => {
+                let line = line.trim_end_matches(['\r', '\n']);
+                let mut fields = line.splitn(4, ' ');
+                let (time, stream, tag) = (fields.next()?, fields.next()?, fields.next()?);
+                if !matches!(stream, "stdout" | "stderr") || !time.contains('T') || parse_datetime(time).is_none() {
+                    return None;
+                }
+                // The tag may carry more flags after a colon.
+                let partial = match tag.split(':').next()? {
+                    "P" => true,
+                    "F" => false,
+                    _ => return None,
+                };
+                Some(Wrapped { time: time.to_string(), stream: stream.to_string(), text: fields.next().unwrap_or_default().into(), partial })
+            }
+        }
+    }
+}
+
+/// Removes a string field.
+fn string(object: &mut Map<String, Value>, key: &str) -> Option<String> {
+    match object.shift_remove(key)? {
+        Value::String(text) => Some(text),
+        _ => None,
+    }
+}
+
+impl LogParser for ContainerParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let wrapped = self.decode(line)?;
+        let pending = self.pending.remove(&wrapped.stream);
+        if wrapped.partial {
+            let mut pending = pending.unwrap_or(Pending { time: wrapped.time, text: String::new(), raw: String::new() });
+            pending.text.push_str(&wrapped.text);
+            pending.raw.push_str(line);
+            pending.raw.push('\n');
+            self.pending.insert(wrapped.stream, pending);
+            return None;
+        }
+        let (time, text, raw) = match pending {
+            Some(pending) => (pending.time, pending.text + &wrapped.text, pending.raw + line),
+            None => (wrapped.time, wrapped.text.into_owned(), line.to_string()),
+        };
+        if !text.trim().is_empty() {
+            self.detect(&text);
+        }
+        if text.trim().is_empty() || self.inner.as_ref().is_some_and(|inner| inner.is_header(&text)) {
+            return None;
+        }
+
+        let mut record = LogRecord::new(raw);
+        match self.inner.as_mut().and_then(|inner| inner.parse(&text)) {
+            Some(parsed) => {
+                if parsed.get("DateTime").is_none() {
+                    record.push("DateTime", time.as_str());
+                }
+                for (name, value) in parsed.fields() {
+                    record.push(name, if name == "DateTime" && value.is_empty() { &time } else { value });
+                }
+            }
+            None => {
+                record.push("DateTime", time);
+                record.push("Message", text);
+            }
+        }
+        record.push("Stream", wrapped.stream);
+        Some(record)
+    }
+
+    /// The parts of a split line are joined when the last one comes.
+    fn is_header(&self, line: &str) -> bool {
+        self.decode(line).is_some_and(|wrapped| {
+            wrapped.partial || wrapped.text.trim().is_empty() || self.inner.as_ref().is_some_and(|inner| inner.is_header(&wrapped.text))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{tests::entries, JsonParser};
+
+    #[test]
+    fn joins_split_docker_lines() {
+        let log = r#"{"log":"first part, ","stream":"stdout","time":"2025-10-11T22:14:15.1Z"}
+{"log":"oops\n","stream":"stderr","time":"2025-10-11T22:14:15.2Z"}
+{"log":"second part\n","stream":"stdout","time":"2025-10-11T22:14:15.3Z"}
+"#;
+        let mut parser = ContainerParser::detecting(ContainerLog::Docker, ParserOptions::default());
+        assert!(parser.is_header(log.lines().next().unwrap()));
+        let records = entries(&mut parser, log);
+        let messages: Vec<_> = records.iter().map(|record| (record.datetime(), record.get("Message").unwrap(), record.get("Stream").unwrap())).collect();
+        assert_eq!(messages, [("2025-10-11T22:14:15.2Z", "oops", "stderr"), ("2025-10-11T22:14:15.1Z", "first part, second part", "stdout")]);
+        assert_eq!(records[1].raw.lines().count(), 2);
+    }
+
+    #[test]
+    fn detects_the_wrapped_format_after_a_banner() {
+        let log = "\
+2025-10-11T22:14:15.000000001Z stdout F Starting server...
+2025-10-11T22:14:15.000000002Z stdout P {\"level\":\"warn\",
+2025-10-11T22:14:15.000000003Z stdout F \"msg\":\"slow request\",\"took_ms\":900}
+2025-10-11T22:14:15.000000004Z stderr F {\"time\":\"2025-10-11T22:14:16Z\",\"level\":\"error\",\"msg\":\"failed\"}
+";
+        let records = entries(&mut ContainerParser::detecting(ContainerLog::Cri, ParserOptions::default()), log);
+        assert_eq!(records.len(), 3);
+        assert_eq!((records[0].get("Message"), records[0].get("Stream")), (Some("Starting server..."), Some("stdout")));
+        assert_eq!((records[1].datetime(), records[1].level(), records[1].get("Message")), ("2025-10-11T22:14:15.000000002Z", "WARNING", Some("slow request")));
+        assert_eq!((records[2].datetime(), records[2].level(), records[2].get("Stream")), ("2025-10-11T22:14:16Z", "ERROR", Some("stderr")));
+    }
+
+    #[test]
+    fn rejects_lines_in_other_layouts() {
+        let mut parser = ContainerParser::new(ContainerLog::Cri, Box::new(JsonParser::new()));
+        assert!(parser.parse("2025-10-11T22:14:15Z stdin F text").is_none());
+        assert!(parser.parse("2025-10-11T22:14:15Z stdout X text").is_none());
+        assert!(parser.parse(r#"{"log":"text\n","stream":"stdout","time":"2025-10-11T22:14:15Z"}"#).is_none());
+    }
+}
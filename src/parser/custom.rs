@@ -0,0 +1,128 @@
+use super::{LogParser, PatternParser};
+use crate::record::{normalize_level, LogRecord};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat};
+use regex::Regex;
+use serde_derive::Deserialize;
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+/// A format definition file, such as `~/.config/rlog/formats/myapp.toml`:
+///
+/// ```toml
+/// regex = '^(?P<DateTime>\S+ \S+) (?P<Level>[EWID]) \[(?P<App>[^\]]+)\] (?P<Message>.*)$'
+/// time_format = "%d.%m.%Y %H:%M:%S"
+/// multiline = true
+///
+/// [levels]
+/// E = "ERROR"
+/// W = "WARNING"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FormatDefinition {
+    /// The pattern of an entry, whose named groups are the columns, as with `--pattern`.
+    pub regex: String,
+    /// How the DateTime group writes the time, as with `--time-format`.
+    #[serde(default)]
+    pub time_format: Option<String>,
+    /// Level values to level names, such as `E = "ERROR"`; other values are read as usual.
+    #[serde(default)]
+    pub levels: HashMap<String, String>,
+    /// Whether lines that do not match `regex` continue the entry before them.
+    #[serde(default)]
+    pub multiline: bool,
+    /// A pattern of lines that continue the entry before them even if they match `regex`,
+    /// such as `'^\s'`. Implies `multiline`.
+    #[serde(default)]
+    pub continuation: Option<String>,
+}
+
+/// A format defined in a file, compiled.
+#[derive(Debug, Clone)]
+pub struct CustomFormat {
+    /// The name it is selected with: the name of the file, without `.toml`.
+    pub name: String,
+    pattern: PatternParser,
+    time_format: Option<String>,
+    /// Upper-cased values, as the Level column holds them, to level names.
+    levels: HashMap<String, String>,
+    multiline: bool,
+    continuation: Option<Regex>,
+}
+
+impl CustomFormat {
+    /// Compiles a definition, failing on invalid patterns.
+    pub fn new(name: impl Into<String>, definition: FormatDefinition) -> Result<Self, String> {
+        let continuation = definition.continuation.as_deref().map(Regex::new).transpose().map_err(|e| format!("continuation: {}", e))?;
+        Ok(CustomFormat {
+            name: name.into(),
+            pattern: PatternParser::new(&definition.regex).map_err(|e| format!("regex: {}", e))?,
+            time_format: definition.time_format,
+            levels: definition.levels.into_iter().map(|(value, level)| (value.to_uppercase(), normalize_level(&level))).collect(),
+            multiline: definition.multiline || continuation.is_some(),
+            continuation,
+        })
+    }
+}
+
+/// Loads the format definitions (`NAME.toml`) in `dir`, in name order. A missing directory
+/// holds none.
+pub fn load_formats(dir: &Path) -> Result<Vec<Arc<CustomFormat>>, String> {
+    let mut paths: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|entry| Some(entry.ok()?.path())).filter(|path| path.extension().is_some_and(|ext| ext == "toml")).collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    paths.sort();
+    paths.iter().map(|path| {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let definition = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        CustomFormat::new(name, definition).map(Arc::new).map_err(|e| format!("{}: {}", path.display(), e))
+    }).collect()
+}
+
+/// Parser for a [`CustomFormat`]: the columns are the named groups of its regex, as with
+/// [`PatternParser`], with the DateTime read in its time format and written as an ISO 8601
+/// time, and the Level mapped by its level table.
+#[derive(Debug, Clone)]
+pub struct CustomParser {
+    format: Arc<CustomFormat>,
+    pattern: PatternParser,
+}
+
+impl CustomParser {
+    /// Creates a parser for `format`.
+    pub fn new(format: Arc<CustomFormat>) -> Self {
+        CustomParser { pattern: format.pattern.clone(), format }
+    }
+}
+
+impl LogParser for CustomParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        if self.format.continuation.as_ref().is_some_and(|continuation| continuation.is_match(line)) {
+            return None;
+        }
+        let mut record = self.pattern.parse(line)?;
+        if let (Some(format), Some(time)) = (&self.format.time_format, record.get("DateTime")) {
+            let time = match DateTime::parse_from_str(time, format) {
+                Ok(time) => time.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+                Err(_) => match NaiveDateTime::parse_from_str(time, format) {
+                    Ok(time) => time.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+                    Err(_) => time.to_string(),
+                },
+            };
+            record.set("DateTime", time);
+        }
+        if let Some(level) = record.get("Level").and_then(|level| self.format.levels.get(level)) {
+            record.set("Level", level.clone());
+        }
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn multiline(&self) -> bool {
+        self.format.multiline
+    }
+}
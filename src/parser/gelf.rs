@@ -0,0 +1,68 @@
+use super::{decode_pri, scalar_text, LogParser};
+use crate::record::{normalize_level, LogRecord};
+use chrono::{DateTime, SecondsFormat};
+use serde_json::{Map, Value};
+
+/// Additional fields that name the application, tried in order.
+const APP_FIELDS: [&str; 4] = ["_app", "_App", "_application_name", "facility"];
+
+/// Parser for Graylog GELF messages, one JSON object per line, as `rlog listen --gelf-udp`
+/// passes them on or as they are written to files.
+///
+/// `short_message` becomes the Message, `timestamp` (seconds since the epoch) the DateTime,
+/// the syslog `level` number the Level, `host` the Host, and `_app` (or `_application_name`,
+/// or the deprecated `facility`) the App. Additional fields go into the Data column without
+/// their leading underscore, along with `full_message`. Columns are `DateTime`, `Level`,
+/// `Host`, `App`, `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct GelfParser;
+
+impl GelfParser {
+    /// Creates a parser for GELF messages.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogParser for GelfParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim();
+        let Value::Object(mut object) = serde_json::from_str(line).ok()? else { return None };
+        let message = take(&mut object, "short_message")?;
+        object.shift_remove("version");
+        let time = match object.shift_remove("timestamp").as_ref().and_then(Value::as_f64) {
+            Some(seconds) => DateTime::from_timestamp_micros((seconds * 1e6).round() as i64)
+                .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        let level = match object.shift_remove("level") {
+            Some(Value::Number(n)) => n.as_u64().map(|n| decode_pri((n & 7) as u8).1.to_string()).unwrap_or_default(),
+            Some(other) => normalize_level(&scalar_text(&other)),
+            None => String::new(),
+        };
+        let host = take(&mut object, "host").unwrap_or_default();
+        let app = APP_FIELDS.iter().find_map(|key| take(&mut object, key).filter(|app| !app.is_empty())).unwrap_or_default();
+        let data: Map<String, Value> = object.into_iter()
+            .map(|(key, value)| (key.strip_prefix('_').map_or_else(|| key.clone(), str::to_string), value))
+            .collect();
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", time);
+        record.push("Level", level);
+        record.push("Host", host);
+        record.push("App", app);
+        record.push("Message", message);
+        record.push("Data", if data.is_empty() { String::new() } else { Value::Object(data).to_string() });
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Removes a field, returning its text.
+fn take(object: &mut Map<String, Value>, key: &str) -> Option<String> {
+    object.shift_remove(key).map(|value| scalar_text(&value))
+}
@@ -0,0 +1,122 @@
+use super::{logfmt::parse_pairs, number_or_text, status_level, LogParser, SyslogParser};
+use crate::record::LogRecord;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::sync::LazyLock;
+
+/// Parser for Heroku logs, as `heroku logs` prints them,
+/// `2025-10-11T22:14:15.123456+00:00 heroku[router]: at=info method=GET ...`, or as Logplex
+/// sends them to HTTPS and syslog drains, `83 <158>1 2025-10-11T22:14:15+00:00 host heroku
+/// router - at=info ...`, with or without the octet count in front.
+///
+/// The source (`app` or `heroku`) becomes the App column and the process (`web.1`, `router`,
+/// `api`) the Dyno column. The logfmt fields of router lines go into the Data column, with
+/// `status`, `bytes`, and the `connect` and `service` times (in milliseconds) as numbers, so
+/// that `--where 'Data.service > 1000'` finds slow requests; `dyno` there is the dyno that
+/// served the request. Router errors (`at=error`, such as H12 timeouts) and 5xx responses
+/// are errors, and 4xx responses warnings. Other lines are given a level guessed from the
+/// message, as syslog lines without a priority are. Columns are `DateTime`, `Level`, `App`,
+/// `Dyno`, `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct HerokuParser {
+    syslog: SyslogParser,
+}
+
+impl HerokuParser {
+    /// Creates a parser for Heroku logs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LogParser for HerokuParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        static CLI: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\d{4}-\d\d-\d\dT\S+) (app|heroku)\[([^\]]+)\]: ?(.*)$").unwrap());
+        static DRAIN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?:\d+ )?<\d{1,3}>1 (\S+) \S+ (app|heroku) (\S+) - ?(.*)$").unwrap());
+        let line = line.trim_end();
+        let caps = CLI.captures(line).or_else(|| DRAIN.captures(line))?;
+        let (source, dyno, message) = (&caps[2], &caps[3], &caps[4]);
+
+        let mut data = Map::new();
+        let (level, message) = match (source, dyno, parse_pairs(message)) {
+            ("heroku", "router", Some(pairs)) => {
+                for (key, value) in pairs {
+                    let value = match key.as_str() {
+                        "connect" | "service" => number_or_text(value.trim_end_matches("ms")),
+                        "status" | "bytes" => number_or_text(&value),
+                        _ => value.into(),
+                    };
+                    data.insert(key, value);
+                }
+                let text = |key: &str| data.get(key).map(|value| value.as_str().map_or_else(|| value.to_string(), str::to_string)).unwrap_or_default();
+                let level = match text("at").as_str() {
+                    "error" => "ERROR",
+                    _ => status_level(&text("status")),
+                };
+                let request = format!("{} {}", text("method"), text("path"));
+                let message = match data.get("code") {
+                    Some(_) => format!("{} {}: {}", text("code"), text("desc"), request),
+                    None => request,
+                };
+                (level.to_string(), message)
+            }
+            _ => (self.syslog.guess_level(message), message.to_string()),
+        };
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", &caps[1]);
+        record.push("Level", level);
+        record.push("App", source);
+        record.push("Dyno", dyno);
+        record.push("Message", message);
+        record.push("Data", if data.is_empty() { String::new() } else { Value::Object(data).to_string() });
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_router_lines() {
+        let line = r#"2025-10-11T22:14:15.123456+00:00 heroku[router]: at=info method=GET path="/users?page=2" host=app.herokuapp.com request_id=8601b555 fwd="204.204.204.204" dyno=web.1 connect=1ms service=1830ms status=200 bytes=1548 protocol=https"#;
+        let record = HerokuParser::new().parse(line).unwrap();
+        assert_eq!(record.datetime(), "2025-10-11T22:14:15.123456+00:00");
+        assert_eq!(record.level(), "INFO");
+        assert_eq!(record.get("App"), Some("heroku"));
+        assert_eq!(record.get("Dyno"), Some("router"));
+        assert_eq!(record.get("Message"), Some("GET /users?page=2"));
+        let data: Value = serde_json::from_str(record.get("Data").unwrap()).unwrap();
+        assert_eq!((data["service"].as_i64(), data["status"].as_i64(), data["bytes"].as_i64()), (Some(1830), Some(200), Some(1548)));
+        assert_eq!(data["dyno"], "web.1");
+    }
+
+    #[test]
+    fn makes_router_errors_errors() {
+        let line = r#"2025-10-11T22:14:15+00:00 heroku[router]: at=error code=H12 desc="Request timeout" method=POST path="/upload" dyno=web.2 connect=0ms service=30000ms status=503 bytes=0"#;
+        let record = HerokuParser::new().parse(line).unwrap();
+        assert_eq!(record.level(), "ERROR");
+        assert_eq!(record.get("Message"), Some("H12 Request timeout: POST /upload"));
+        let line = "2025-10-11T22:14:15+00:00 heroku[router]: at=info method=GET path=/missing status=404 bytes=0";
+        assert_eq!(HerokuParser::new().parse(line).unwrap().level(), "WARNING");
+    }
+
+    #[test]
+    fn parses_drain_lines_with_or_without_the_octet_count() {
+        for line in ["83 <190>1 2025-10-11T22:14:15+00:00 host app web.1 - Error: connection refused", "<190>1 2025-10-11T22:14:15+00:00 host app web.1 - Error: connection refused"] {
+            let record = HerokuParser::new().parse(line).unwrap();
+            assert_eq!(record.datetime(), "2025-10-11T22:14:15+00:00");
+            assert_eq!(record.get("App"), Some("app"));
+            assert_eq!(record.get("Dyno"), Some("web.1"));
+            assert_eq!(record.get("Message"), Some("Error: connection refused"));
+            assert_eq!(record.level(), "ERROR");
+            assert_eq!(record.get("Data"), Some(""));
+        }
+        assert!(HerokuParser::new().parse("2025-10-11 22:14:15 INFO started").is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use super::{decode_pri, LogParser};
+use crate::record::LogRecord;
+use chrono::{DateTime, SecondsFormat};
+use serde_json::{Map, Value};
+
+/// Parser for systemd journal entries as `journalctl -o json` writes them, one JSON object
+/// per line.
+///
+/// `PRIORITY` becomes the Level, `__REALTIME_TIMESTAMP` the DateTime, `_HOSTNAME` the Host,
+/// and `SYSLOG_IDENTIFIER` (or `_COMM`) the App. The other fields go into the Data column
+/// under their journal names, such as `_SYSTEMD_UNIT` and `_PID`, except the `__` ones that
+/// only locate the entry in the journal. Columns are `DateTime`, `Level`, `Host`, `App`,
+/// `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct JournalParser;
+
+impl JournalParser {
+    /// Creates a parser for `journalctl -o json` output.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogParser for JournalParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim();
+        let Value::Object(mut object) = serde_json::from_str(line).ok()? else { return None };
+        let micros: i64 = object.get("__REALTIME_TIMESTAMP").and_then(Value::as_str)?.parse().ok()?;
+        let time = DateTime::from_timestamp_micros(micros)?.to_rfc3339_opts(SecondsFormat::Micros, true);
+        object.retain(|key, _| !key.starts_with("__"));
+        let level = match take(&mut object, "PRIORITY").parse::<u8>() {
+            Ok(priority) => decode_pri(priority & 7).1.to_string(),
+            Err(_) => String::new(),
+        };
+        let mut app = take(&mut object, "SYSLOG_IDENTIFIER");
+        if app.is_empty() {
+            app = object.get("_COMM").map(text).unwrap_or_default();
+        }
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", time);
+        record.push("Level", level);
+        record.push("Host", take(&mut object, "_HOSTNAME"));
+        record.push("App", app);
+        record.push("Message", take(&mut object, "MESSAGE"));
+        record.push("Data", if object.is_empty() { String::new() } else { Value::Object(object).to_string() });
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Removes a field, returning its text or an empty string.
+fn take(object: &mut Map<String, Value>, key: &str) -> String {
+    object.shift_remove(key).map(|value| text(&value)).unwrap_or_default()
+}
+
+/// The text of a field. Fields that are not valid UTF-8 come as arrays of bytes.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes.iter().filter_map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok())).collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        other => super::scalar_text(other),
+    }
+}
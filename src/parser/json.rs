@@ -0,0 +1,86 @@
+use super::LogParser;
+use crate::record::{normalize_level, LogRecord};
+use serde_json::{Map, Value};
+
+/// Keys tried for each standard column when no explicit mapping is given.
+const DEFAULT_KEYS: [(&str, &[&str]); 3] = [
+    ("DateTime", &["timestamp", "time", "ts", "@timestamp", "datetime", "date"]),
+    ("Level", &["level", "severity", "lvl", "loglevel", "log.level"]),
+    ("Message", &["message", "msg", "@message", "text"]),
+];
+
+/// Parser for JSON Lines logs: one JSON object per line.
+///
+/// Keys are mapped onto the standard `DateTime`, `Level`, and `Message` columns; every
+/// remaining key goes into the `Data` column as a JSON object.
+#[derive(Debug, Clone, Default)]
+pub struct JsonParser {
+    keys: Vec<(String, String)>,
+}
+
+impl JsonParser {
+    /// Creates a parser that recognises common key names (`ts`, `level`, `msg`, ...).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `column` from `key` instead of the default candidates. Dotted keys such as
+    /// `log.level` look into nested objects.
+    pub fn map_key(mut self, column: impl Into<String>, key: impl Into<String>) -> Self {
+        self.keys.push((column.into(), key.into()));
+        self
+    }
+
+    fn keys_for(&self, column: &str) -> Vec<&str> {
+        let mapped: Vec<&str> = self.keys.iter().filter(|(c, _)| c == column).map(|(_, k)| k.as_str()).collect();
+        if !mapped.is_empty() {
+            return mapped;
+        }
+        DEFAULT_KEYS.iter().find(|(c, _)| *c == column).map(|(_, keys)| keys.to_vec()).unwrap_or_default()
+    }
+
+    /// Builds a record from an already decoded object, for formats that map onto JSON.
+    pub(crate) fn record_from(&self, raw: &str, mut object: Map<String, Value>) -> LogRecord {
+        let mut record = LogRecord::new(raw);
+        for (column, _) in DEFAULT_KEYS {
+            let value = self.keys_for(column).into_iter().find_map(|key| take_path(&mut object, key));
+            let text = value.map(|v| scalar_text(&v)).unwrap_or_default();
+            record.push(column, if column == "Level" { normalize_level(&text) } else { text });
+        }
+        let data = if object.is_empty() { String::new() } else { Value::Object(object).to_string() };
+        record.push("Data", data);
+        record
+    }
+}
+
+impl LogParser for JsonParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let Value::Object(object) = serde_json::from_str(line.trim()).ok()? else { return None };
+        Some(self.record_from(line.trim(), object))
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Removes and returns the value at a dotted path, trying the whole key first.
+fn take_path(object: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    if let Some(value) = object.shift_remove(path) {
+        return Some(value);
+    }
+    let (head, rest) = path.split_once('.')?;
+    match object.get_mut(head)? {
+        Value::Object(inner) => take_path(inner, rest),
+        _ => None,
+    }
+}
+
+/// Renders a JSON value as column text, without quotes around strings.
+pub fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
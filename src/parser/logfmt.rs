@@ -0,0 +1,107 @@
+use super::{number_or_text, JsonParser, LogParser};
+use crate::record::LogRecord;
+use serde_json::{Map, Value};
+
+/// Parser for logfmt (`key=value`) logs, as written by Heroku, Grafana, and many Go services.
+///
+/// `ts`, `level`, and `msg` (and their usual synonyms) become the standard columns and the
+/// remaining pairs become the `Data` column, exactly as for [`JsonParser`]. Numeric values
+/// are numbers there, so that `--where 'Data.duration_ms > 500'` compares them as such.
+#[derive(Debug, Clone, Default)]
+pub struct LogfmtParser {
+    columns: JsonParser,
+}
+
+impl LogfmtParser {
+    /// Creates a parser that recognises common key names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `column` from `key` instead of the default candidates.
+    pub fn map_key(mut self, column: impl Into<String>, key: impl Into<String>) -> Self {
+        self.columns = self.columns.map_key(column, key);
+        self
+    }
+}
+
+impl LogParser for LogfmtParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim();
+        let pairs = parse_pairs(line)?;
+        let object: Map<String, Value> = pairs.into_iter().map(|(key, value)| (key, number_or_text(&value))).collect();
+        Some(self.columns.record_from(line, object))
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Splits a logfmt line into pairs, returning `None` if it contains none.
+///
+/// Values may be double-quoted with backslash escapes; a bare key counts as `key=true`.
+pub(crate) fn parse_pairs(line: &str) -> Option<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|&c| c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        if key.is_empty() {
+            break;
+        }
+        if chars.next_if_eq(&'=').is_none() {
+            pairs.push((key, "true".to_string()));
+            continue;
+        }
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        pairs.push((key, value));
+    }
+    // Plain prose has "pairs" that are all bare words; require at least one real assignment.
+    let assigned = line.contains('=');
+    (assigned && !pairs.is_empty()).then_some(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_quoted_and_bare_pairs() {
+        let pairs = parse_pairs(r#"level=info msg="user \"bob\" logged in" retry"#).unwrap();
+        assert_eq!(pairs, [("level".into(), "info".into()), ("msg".into(), r#"user "bob" logged in"#.into()), ("retry".into(), "true".into())]);
+        assert_eq!(parse_pairs("just some words"), None);
+        assert_eq!(parse_pairs(""), None);
+    }
+
+    #[test]
+    fn fills_the_standard_columns() {
+        let record = LogfmtParser::new().parse(r#"ts=2025-03-01T10:00:00Z level=warn msg="disk almost full" free_mb=512 ratio=0.05 host=db1"#).unwrap();
+        assert_eq!(record.datetime(), "2025-03-01T10:00:00Z");
+        assert_eq!(record.level(), "WARNING");
+        assert_eq!(record.get("Message"), Some("disk almost full"));
+        assert_eq!(record.get("Data"), Some(r#"{"free_mb":512,"ratio":0.05,"host":"db1"}"#));
+    }
+
+    #[test]
+    fn maps_other_keys() {
+        let record = LogfmtParser::new().map_key("Message", "event").parse("level=error event=crashed msg=ignored").unwrap();
+        assert_eq!(record.get("Message"), Some("crashed"));
+    }
+}
@@ -0,0 +1,159 @@
+use super::{insert_data, number_or_text, LogParser};
+use crate::record::LogRecord;
+use chrono::NaiveDateTime;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Parser for the MySQL and MariaDB slow query log, whose entries span several lines:
+///
+/// ```text
+/// # Time: 2025-10-11T22:14:15.123456Z
+/// # User@Host: app[app] @ localhost [127.0.0.1]  Id:    42
+/// # Query_time: 2.500000  Lock_time: 0.000100 Rows_sent: 10  Rows_examined: 100000
+/// use shop;
+/// SET timestamp=1760220855;
+/// SELECT * FROM orders
+/// WHERE status = 'open';
+/// ```
+///
+/// The statement becomes the Message. The statistics go into the Data column as numbers
+/// under lower-case names, such as `query_time`, `lock_time`, `rows_sent`, and
+/// `rows_examined`, with the `user`, client `host` and `ip`, connection `id`, and `db`.
+/// Entries that leave out `# Time:`, as older servers do within the same second, take the
+/// time of the one before. Columns are `DateTime`, `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct MysqlSlowParser {
+    /// The time of the last `# Time:` line.
+    time: String,
+    /// Whether the last line was a `# Time:` line, which the `# User@Host:` line continues.
+    after_time: bool,
+}
+
+impl MysqlSlowParser {
+    /// Creates a parser for slow query logs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LogParser for MysqlSlowParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim_end();
+        let after_time = std::mem::take(&mut self.after_time);
+        if let Some(time) = line.strip_prefix("# Time:") {
+            self.time = entry_time(time.trim());
+            self.after_time = true;
+        } else if !line.starts_with("# User@Host:") || after_time {
+            return None;
+        }
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", self.time.clone());
+        record.push("Message", "");
+        record.push("Data", "");
+        if line.starts_with("# User@Host:") {
+            user_host(&mut record, line);
+        }
+        Some(record)
+    }
+
+    fn is_header(&self, line: &str) -> bool {
+        // What the server writes when it opens the log.
+        line.contains(", Version: ") || line.starts_with("Tcp port:") || (line.starts_with("Time ") && line.contains("Id Command"))
+    }
+
+    fn multiline(&self) -> bool {
+        true
+    }
+
+    fn continue_record(&self, record: &mut LogRecord, line: &str) {
+        static STATS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\w+): +(\S+)").unwrap());
+        let line = line.trim_end();
+        if line.starts_with("# User@Host:") {
+            user_host(record, line);
+        } else if let Some(comment) = line.strip_prefix("# ") {
+            for caps in STATS.captures_iter(comment) {
+                insert_data(record, &caps[1].to_lowercase(), number_or_text(&caps[2]));
+            }
+        } else if let Some(db) = line.strip_prefix("use ").and_then(|db| db.strip_suffix(';')) {
+            insert_data(record, "db", db.trim_matches('`').into());
+        } else if !line.starts_with("SET timestamp=") {
+            record.append_line(line);
+            return;
+        }
+        record.raw.push('\n');
+        record.raw.push_str(line);
+    }
+}
+
+/// Adds the user, client, and connection ID of a `# User@Host:` line to the Data column.
+fn user_host(record: &mut LogRecord, line: &str) {
+    static USER_HOST: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^# User@Host: (\S*?)\[[^\]]*\] @ (\S*) ?\[([^\]]*)\](?:\s+Id:\s*(\d+))?").unwrap());
+    let Some(caps) = USER_HOST.captures(line) else { return };
+    for (key, idx) in [("user", 1), ("host", 2), ("ip", 3)] {
+        if !caps[idx].is_empty() {
+            insert_data(record, key, caps[idx].into());
+        }
+    }
+    if let Some(id) = caps.get(4) {
+        insert_data(record, "id", number_or_text(id.as_str()));
+    }
+}
+
+/// The time of a `# Time:` line: ISO 8601 since MySQL 5.7, `YYMMDD HH:MM:SS` before.
+fn entry_time(time: &str) -> String {
+    match NaiveDateTime::parse_from_str(time, "%y%m%d %k:%M:%S") {
+        Ok(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        Err(_) => time.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::tests::entries;
+
+    const LOG: &str = "\
+/usr/sbin/mysqld, Version: 8.0.36 (MySQL Community Server - GPL). started with:
+Tcp port: 3306  Unix socket: /var/run/mysqld/mysqld.sock
+Time                 Id Command    Argument
+# Time: 2025-10-11T22:14:15.123456Z
+# User@Host: app[app] @ localhost [127.0.0.1]  Id:    42
+# Query_time: 2.500000  Lock_time: 0.000100 Rows_sent: 10  Rows_examined: 100000
+use shop;
+SET timestamp=1760220855;
+SELECT * FROM orders
+WHERE status = 'open';
+# User@Host: report[report] @ [10.0.0.5]  Id:    43
+# Query_time: 1.000000  Lock_time: 0.000000 Rows_sent: 1  Rows_examined: 5000
+SET timestamp=1760220855;
+SELECT COUNT(*) FROM users;
+";
+
+    #[test]
+    fn joins_the_lines_of_an_entry() {
+        let records = entries(&mut MysqlSlowParser::new(), LOG);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].datetime(), "2025-10-11T22:14:15.123456Z");
+        assert_eq!(records[0].get("Message"), Some("SELECT * FROM orders\nWHERE status = 'open';"));
+        assert_eq!(
+            records[0].get("Data"),
+            Some(r#"{"user":"app","host":"localhost","ip":"127.0.0.1","id":42,"query_time":2.5,"lock_time":0.0001,"rows_sent":10,"rows_examined":100000,"db":"shop"}"#)
+        );
+        assert_eq!(records[0].raw.lines().count(), 7);
+    }
+
+    #[test]
+    fn entries_without_a_time_take_the_one_before() {
+        let records = entries(&mut MysqlSlowParser::new(), LOG);
+        assert_eq!(records[1].datetime(), "2025-10-11T22:14:15.123456Z");
+        assert_eq!(records[1].get("Message"), Some("SELECT COUNT(*) FROM users;"));
+        assert_eq!(records[1].get("Data"), Some(r#"{"user":"report","ip":"10.0.0.5","id":43,"query_time":1.0,"lock_time":0.0,"rows_sent":1,"rows_examined":5000}"#));
+    }
+
+    #[test]
+    fn reads_old_style_times() {
+        let records = entries(&mut MysqlSlowParser::new(), "# Time: 251011  2:14:15\n# Query_time: 3\nSELECT 1;\n");
+        assert_eq!(records[0].datetime(), "2025-10-11 02:14:15");
+        assert_eq!(records[0].get("Message"), Some("SELECT 1;"));
+    }
+}
@@ -0,0 +1,78 @@
+use super::{scalar_text, LogParser};
+use crate::record::LogRecord;
+use chrono::{DateTime, SecondsFormat};
+use serde_json::{Map, Value};
+
+/// Parser for the macOS unified log as `log stream --style ndjson` and `log show --style
+/// ndjson` write it, one JSON object per entry.
+///
+/// The `messageType` becomes the Level (`Fault` CRITICAL, `Error` ERROR, `Default` NOTICE,
+/// `Info` INFO, and `Debug` DEBUG), `timestamp` the DateTime, the name of the process the
+/// App, and `eventMessage` the Message. The subsystem, category, process and thread IDs,
+/// and the library that logged the entry go into the Data column. Columns are `DateTime`,
+/// `Level`, `App`, `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct OslogParser;
+
+impl OslogParser {
+    /// Creates a parser for `log` ndjson output.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogParser for OslogParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim();
+        let Value::Object(object) = serde_json::from_str(line).ok()? else { return None };
+        let time = object.get("timestamp").and_then(Value::as_str)?;
+        let message = object.get("eventMessage")?;
+        let time = DateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S%.f%z")
+            .map_or_else(|_| time.to_string(), |time| time.to_rfc3339_opts(SecondsFormat::Micros, false));
+        let level = match object.get("messageType").and_then(Value::as_str).unwrap_or_default() {
+            "Fault" => "CRITICAL",
+            "Error" => "ERROR",
+            "Default" => "NOTICE",
+            "Info" => "INFO",
+            "Debug" => "DEBUG",
+            _ => "",
+        };
+        let mut data = Map::new();
+        for key in ["subsystem", "category", "processID", "threadID"] {
+            if let Some(value) = object.get(key).filter(|value| !value.is_null() && value.as_str() != Some("")) {
+                data.insert(key.to_string(), value.clone());
+            }
+        }
+        let sender = file_name(&object, "senderImagePath");
+        if !sender.is_empty() {
+            data.insert("sender".to_string(), sender.into());
+        }
+        if let Some(kind) = object.get("eventType").and_then(Value::as_str).filter(|kind| *kind != "logEvent") {
+            data.insert("eventType".to_string(), kind.into());
+        }
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", time);
+        record.push("Level", level);
+        record.push("App", file_name(&object, "processImagePath"));
+        record.push("Message", scalar_text(message));
+        record.push("Data", if data.is_empty() { String::new() } else { Value::Object(data).to_string() });
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+
+    /// `log stream` starts with a line saying what it filters on, and `log show` ends with
+    /// a count of the entries.
+    fn is_header(&self, line: &str) -> bool {
+        line.starts_with("Filtering the log data") || line.starts_with("{\"count\":")
+    }
+}
+
+/// The last component of a path field, such as the process name of `processImagePath`.
+fn file_name(object: &Map<String, Value>, key: &str) -> String {
+    let path = object.get(key).and_then(Value::as_str).unwrap_or_default();
+    path.rsplit('/').next().unwrap_or_default().to_string()
+}
@@ -0,0 +1,89 @@
+use super::LogParser;
+use crate::record::{normalize_level, LogRecord};
+use regex::Regex;
+
+/// Grok-style building blocks available as `%{NAME}` or `%{NAME:column}` in patterns.
+const GROK: &[(&str, &str)] = &[
+    ("WORD", r"\w+"),
+    ("NOTSPACE", r"\S+"),
+    ("SPACE", r"\s*"),
+    ("DATA", r".*?"),
+    ("GREEDYDATA", r".*"),
+    ("INT", r"[+-]?\d+"),
+    ("NUMBER", r"[+-]?(?:\d+(?:\.\d*)?|\.\d+)"),
+    ("IP", r"(?:\d{1,3}\.){3}\d{1,3}|[0-9A-Fa-f:]*:[0-9A-Fa-f:.]+"),
+    ("HOSTNAME", r"[0-9A-Za-z][0-9A-Za-z.-]*"),
+    ("UUID", r"[0-9A-Fa-f]{8}-(?:[0-9A-Fa-f]{4}-){3}[0-9A-Fa-f]{12}"),
+    ("PATH", r"(?:/[^\s/]*)+"),
+    ("QUOTEDSTRING", r#""(?:[^"\\]|\\.)*""#),
+    ("LOGLEVEL", r"(?i:trace|debug|info|information|notice|warn|warning|error|err|crit|critical|fatal|alert|emerg|emergency)"),
+    ("TIMESTAMP_ISO8601", r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:[.,]\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?"),
+    ("HTTPDATE", r"\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4}"),
+    ("SYSLOGTIMESTAMP", r"\w{3} [ \d]\d \d{2}:\d{2}:\d{2}"),
+];
+
+/// Parser driven by a user-supplied regex whose named capture groups define the columns,
+/// e.g. `^(?P<DateTime>\S+) \[(?P<Level>\w+)\] (?P<Message>.*)$`.
+///
+/// Grok-style `%{NAME:column}` references to the built-in building blocks (`WORD`,
+/// `TIMESTAMP_ISO8601`, `LOGLEVEL`, ...) are expanded first; `%{NAME}` matches without
+/// capturing.
+#[derive(Debug, Clone)]
+pub struct PatternParser {
+    regex: Regex,
+    columns: Vec<String>,
+}
+
+impl PatternParser {
+    /// Compiles `pattern`, failing on invalid regex syntax or unknown grok names.
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let regex = Regex::new(&expand_grok(pattern)?).map_err(|e| e.to_string())?;
+        let columns: Vec<String> = regex.capture_names().flatten().map(str::to_string).collect();
+        if columns.is_empty() {
+            return Err("pattern has no named capture groups".to_string());
+        }
+        Ok(PatternParser { regex, columns })
+    }
+
+    /// The column names, in the order their groups appear in the pattern.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+}
+
+impl LogParser for PatternParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim_end();
+        let caps = self.regex.captures(line)?;
+        let mut record = LogRecord::new(line);
+        for column in &self.columns {
+            let value = caps.name(column).map_or("", |m| m.as_str());
+            record.push(column.as_str(), if column == "Level" { normalize_level(value) } else { value.to_string() });
+        }
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Replaces `%{NAME}` and `%{NAME:column}` with the corresponding regex.
+fn expand_grok(pattern: &str) -> Result<String, String> {
+    let reference = Regex::new(r"%\{(\w+)(?::(\w+))?\}").unwrap();
+    let mut error = None;
+    let expanded = reference.replace_all(pattern, |caps: &regex::Captures| {
+        let Some((_, body)) = GROK.iter().find(|(name, _)| *name == &caps[1]) else {
+            error.get_or_insert_with(|| format!("unknown grok pattern `{}`", &caps[1]));
+            return String::new();
+        };
+        match caps.get(2) {
+            Some(column) => format!("(?P<{}>{})", column.as_str(), body),
+            None => format!("(?:{})", body),
+        }
+    });
+    match error {
+        Some(error) => Err(error),
+        None => Ok(expanded.into_owned()),
+    }
+}
@@ -0,0 +1,188 @@
+use super::{insert_data, LogParser};
+use crate::record::LogRecord;
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::sync::LazyLock;
+
+/// Severities of the lines that add to the entry before them rather than start one.
+const SECONDARY: [&str; 6] = ["DETAIL", "HINT", "QUERY", "CONTEXT", "LOCATION", "STATEMENT"];
+
+/// A line as PostgreSQL writes it to stderr: the timestamp and the rest of the
+/// `log_line_prefix`, then the severity and two spaces.
+static LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4}-\d\d-\d\d[ T]\d\d:\d\d:\d\d(?:\.\d+)?)(?: ([A-Za-z]{1,5}|[+-]\d\d(?::?\d\d)?))?\s+(.*?)\b(DEBUG[1-5]|LOG|INFO|NOTICE|WARNING|ERROR|FATAL|PANIC|DETAIL|HINT|QUERY|CONTEXT|LOCATION|STATEMENT):  (.*)$").unwrap()
+});
+
+/// Parser for PostgreSQL server logs written to stderr with a `log_line_prefix` that starts
+/// with the time (`%m` or `%t`), such as the default `%m [%p] `.
+///
+/// The severity becomes the Level (`LOG` INFO, `DEBUG1` to `DEBUG5` DEBUG, `FATAL` CRITICAL,
+/// and `PANIC` EMERGENCY) and the text after it the Message. The `DETAIL`, `HINT`, `QUERY`,
+/// `CONTEXT`, `LOCATION`, and `STATEMENT` lines that follow, and tab-indented continuation
+/// lines, belong to the same entry; the former go into the Data column under lower-case
+/// names. So do the process ID and the `user`, `db`, `app`, and `client` of the prefix
+/// (written `user=` or `user@db`), the `duration:` of a logged statement as `query_time` in
+/// seconds, and the lock and `lock_time` of a lock wait. Columns are `DateTime`, `Level`,
+/// `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct PostgresParser;
+
+impl PostgresParser {
+    /// Creates a parser for PostgreSQL logs.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogParser for PostgresParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim_end();
+        let caps = LINE.captures(line).filter(|caps| !SECONDARY.contains(&&caps[4]))?;
+        let level = match &caps[4] {
+            "LOG" => "INFO",
+            "FATAL" => "CRITICAL",
+            "PANIC" => "EMERGENCY",
+            debug if debug.starts_with("DEBUG") => "DEBUG",
+            level => level,
+        };
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", entry_time(&caps));
+        record.push("Level", level);
+        record.push("Message", &caps[5]);
+        record.push("Data", "");
+        prefix_fields(&mut record, &caps[3]);
+        message_fields(&mut record, &caps[5]);
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn multiline(&self) -> bool {
+        true
+    }
+
+    fn continue_record(&self, record: &mut LogRecord, line: &str) {
+        let line = line.trim_end();
+        let key = match LINE.captures(line) {
+            Some(caps) if SECONDARY.contains(&&caps[4]) => {
+                insert_data(record, &caps[4].to_lowercase(), caps[5].into());
+                None
+            }
+            // A tab-indented line continues the Message, or the secondary line before it.
+            _ if line.starts_with('\t') => record.raw.lines().rev().find(|line| !line.starts_with('\t'))
+                .and_then(|last| LINE.captures(last))
+                .filter(|caps| SECONDARY.contains(&&caps[4]))
+                .map(|caps| caps[4].to_lowercase()),
+            _ => {
+                record.append_line(line);
+                return;
+            }
+        };
+        match key {
+            Some(key) => {
+                let data: serde_json::Map<String, Value> = record.get("Data").and_then(|data| serde_json::from_str(data).ok()).unwrap_or_default();
+                let text = data.get(&key).and_then(Value::as_str).unwrap_or_default();
+                insert_data(record, &key, format!("{}\n{}", text, line.trim_start()).into());
+            }
+            None if line.starts_with('\t') => {
+                let message = format!("{}\n{}", record.get("Message").unwrap_or_default(), line.trim_start());
+                record.set("Message", message);
+            }
+            None => {}
+        }
+        record.raw.push('\n');
+        record.raw.push_str(line);
+    }
+}
+
+/// The time of a line, with the zone as an offset where it is UTC or numeric. Other zone
+/// abbreviations are ambiguous, and the time is read as local.
+fn entry_time(caps: &Captures) -> String {
+    let time = caps[1].replace('T', " ");
+    match caps.get(2).map(|zone| zone.as_str()) {
+        Some("UTC" | "GMT" | "Z") => format!("{}Z", time),
+        Some(offset) if offset.starts_with(['+', '-']) => {
+            let digits = offset[1..].replace(':', "");
+            format!("{}{}{}:{}", time, &offset[..1], &digits[..2], digits.get(2..).filter(|m| !m.is_empty()).unwrap_or("00"))
+        }
+        _ => time,
+    }
+}
+
+/// Adds the process ID and the session fields of the `log_line_prefix` to the Data column.
+fn prefix_fields(record: &mut LogRecord, prefix: &str) {
+    static PID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[(\d+)(?:-\d+)?\]").unwrap());
+    static NAMED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(user|db|app|client)=([^\s,]+)").unwrap());
+    static USER_DB: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\s)([\w.-]+)@([\w.-]+)(?:\s|$)").unwrap());
+    if let Some(caps) = PID.captures(prefix) {
+        insert_data(record, "pid", caps[1].parse::<u64>().map_or(Value::Null, Value::from));
+    }
+    for caps in NAMED.captures_iter(prefix) {
+        insert_data(record, &caps[1], caps[2].into());
+    }
+    if let Some(caps) = USER_DB.captures(prefix).filter(|_| !NAMED.is_match(prefix)) {
+        insert_data(record, "user", caps[1].into());
+        insert_data(record, "db", caps[2].into());
+    }
+}
+
+/// Adds what the message says about query and lock times to the Data column, in seconds.
+fn message_fields(record: &mut LogRecord, message: &str) {
+    static DURATION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^duration: ([\d.]+) ms").unwrap());
+    static LOCK_WAIT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:still waiting for|acquired) (\w+) on .*? after ([\d.]+) ms").unwrap());
+    let seconds = |ms: &str| ms.parse::<f64>().ok().and_then(|ms| serde_json::Number::from_f64(ms / 1000.0)).map_or(Value::Null, Value::Number);
+    if let Some(caps) = DURATION.captures(message) {
+        insert_data(record, "query_time", seconds(&caps[1]));
+    }
+    if let Some(caps) = LOCK_WAIT.captures(message) {
+        insert_data(record, "lock", caps[1].into());
+        insert_data(record, "lock_time", seconds(&caps[2]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::tests::entries;
+
+    #[test]
+    fn secondary_lines_go_into_data() {
+        let log = "\
+2025-10-11 22:14:15.123 UTC [4242] app@shop ERROR:  duplicate key value violates unique constraint \"orders_pkey\"
+2025-10-11 22:14:15.123 UTC [4242] app@shop DETAIL:  Key (id)=(1) already exists.
+2025-10-11 22:14:15.123 UTC [4242] app@shop STATEMENT:  INSERT INTO orders
+\tVALUES (1);
+2025-10-11 22:14:16.000 UTC [17] LOG:  checkpoint starting: time
+";
+        let records = entries(&mut PostgresParser::new(), log);
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].datetime(), records[0].level()), ("2025-10-11 22:14:15.123Z", "ERROR"));
+        assert_eq!(records[0].get("Message"), Some("duplicate key value violates unique constraint \"orders_pkey\""));
+        assert_eq!(
+            records[0].get("Data"),
+            Some(r#"{"pid":4242,"user":"app","db":"shop","detail":"Key (id)=(1) already exists.","statement":"INSERT INTO orders\nVALUES (1);"}"#)
+        );
+        assert_eq!(records[0].raw.lines().count(), 4);
+        assert_eq!((records[1].level(), records[1].get("Message")), ("INFO", Some("checkpoint starting: time")));
+    }
+
+    #[test]
+    fn tab_indented_lines_continue_the_message() {
+        let log = "2025-10-11 22:14:15 +02 [7] user=app,db=shop LOG:  duration: 1500.5 ms  statement: SELECT *\n\tFROM orders;\n";
+        let records = entries(&mut PostgresParser::new(), log);
+        assert_eq!(records[0].datetime(), "2025-10-11 22:14:15+02:00");
+        assert_eq!(records[0].get("Message"), Some("duration: 1500.5 ms  statement: SELECT *\nFROM orders;"));
+        assert_eq!(records[0].get("Data"), Some(r#"{"pid":7,"user":"app","db":"shop","query_time":1.5005}"#));
+    }
+
+    #[test]
+    fn maps_severities() {
+        let level = |severity: &str| PostgresParser::new().parse(&format!("2025-10-11 22:14:15 UTC [1] {}:  x", severity)).map(|record| record.level().to_string());
+        assert_eq!(level("DEBUG2").as_deref(), Some("DEBUG"));
+        assert_eq!(level("FATAL").as_deref(), Some("CRITICAL"));
+        assert_eq!(level("PANIC").as_deref(), Some("EMERGENCY"));
+        assert_eq!(level("HINT"), None);
+    }
+}
@@ -0,0 +1,162 @@
+use super::LogParser;
+use crate::record::LogRecord;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Syslog severities 0-7, named as in the level palette.
+pub const SEVERITIES: [&str; 8] = ["EMERGENCY", "ALERT", "CRITICAL", "ERROR", "WARNING", "NOTICE", "INFO", "DEBUG"];
+
+/// Syslog facilities 0-23.
+pub const FACILITIES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv", "ftp",
+    "ntp", "security", "console", "solaris-cron", "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7",
+];
+
+/// Splits a PRI value into its facility and severity names.
+pub fn decode_pri(pri: u8) -> (&'static str, &'static str) {
+    let facility = FACILITIES.get((pri >> 3) as usize).copied().unwrap_or("unknown");
+    (facility, SEVERITIES[(pri & 7) as usize])
+}
+
+/// Parser for syslog lines in either RFC 5424 or classic RFC 3164 (BSD) form.
+///
+/// The PRI prefix is decoded into the `Level` column and a `facility` Data field. Lines
+/// without one, as written to `/var/log/syslog`, are given a level guessed from the message.
+/// Columns are `DateTime`, `Level`, `Host`, `App`, `Message`, and `Data`.
+#[derive(Debug, Clone)]
+pub struct SyslogParser {
+    rfc5424: Regex,
+    rfc3164: Regex,
+    level_word: Regex,
+}
+
+impl Default for SyslogParser {
+    fn default() -> Self {
+        SyslogParser {
+            rfc5424: Regex::new(r"^<(\d{1,3})>(\d{1,2}) (\S+) (\S+) (\S+) (\S+) (\S+) (-|(?:\[(?:[^\]\\]|\\.)*\])+)(?: (.*))?$").unwrap(),
+            rfc3164: Regex::new(r"^(?:<(\d{1,3})>)?([A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}|\d{4}-\d{2}-\d{2}T\S+) (\S+) ([^:\[\s]+)(?:\[(\d+)\])?: ?(.*)$").unwrap(),
+            level_word: Regex::new(r"(?i)\b(emerg(?:ency)?|alert|crit(?:ical)?|fatal|err(?:or)?|warn(?:ing)?|notice|debug)\b").unwrap(),
+        }
+    }
+}
+
+impl SyslogParser {
+    /// Creates a parser for both syslog flavours.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn parse_5424(&self, line: &str) -> Option<LogRecord> {
+        let caps = self.rfc5424.captures(line)?;
+        let pri: u8 = caps[1].parse().ok()?;
+        let (facility, severity) = decode_pri(pri);
+        let nil = |i: usize| caps.get(i).map(|m| m.as_str()).filter(|s| *s != "-").unwrap_or("");
+
+        let mut data = Map::new();
+        data.insert("facility".into(), facility.into());
+        for (key, idx) in [("procid", 6), ("msgid", 7)] {
+            if !nil(idx).is_empty() {
+                data.insert(key.into(), nil(idx).into());
+            }
+        }
+        if !nil(8).is_empty() {
+            data.insert("sd".into(), Value::Object(structured_data(nil(8))));
+        }
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", nil(3));
+        record.push("Level", severity);
+        record.push("Host", nil(4));
+        record.push("App", nil(5));
+        record.push("Message", caps.get(9).map_or("", |m| m.as_str().trim_start_matches('\u{feff}')));
+        record.push("Data", Value::Object(data).to_string());
+        Some(record)
+    }
+
+    fn parse_3164(&self, line: &str) -> Option<LogRecord> {
+        let caps = self.rfc3164.captures(line)?;
+        let message = &caps[6];
+        let mut data = Map::new();
+        let level = match caps.get(1).and_then(|m| m.as_str().parse::<u8>().ok()) {
+            Some(pri) => {
+                let (facility, severity) = decode_pri(pri);
+                data.insert("facility".into(), facility.into());
+                severity.to_string()
+            }
+            None => self.guess_level(message),
+        };
+        if let Some(pid) = caps.get(5) {
+            data.insert("procid".into(), pid.as_str().into());
+        }
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", &caps[2]);
+        record.push("Level", level);
+        record.push("Host", &caps[3]);
+        record.push("App", &caps[4]);
+        record.push("Message", message);
+        record.push("Data", if data.is_empty() { String::new() } else { Value::Object(data).to_string() });
+        Some(record)
+    }
+
+    /// Picks a level from the first level-like word in the message, defaulting to INFO.
+    pub(super) fn guess_level(&self, message: &str) -> String {
+        let Some(word) = self.level_word.find(message) else { return "INFO".to_string() };
+        crate::record::normalize_level(match word.as_str().to_lowercase().as_str() {
+            "emerg" => "EMERGENCY",
+            "crit" => "CRITICAL",
+            w => w,
+        })
+    }
+}
+
+impl LogParser for SyslogParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim_end();
+        self.parse_5424(line).or_else(|| self.parse_3164(line))
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Decodes RFC 5424 structured data (`[id key="value" ...]...`) into `{id: {key: value}}`.
+fn structured_data(sd: &str) -> Map<String, Value> {
+    let mut elements = Map::new();
+    let mut rest = sd;
+    while let Some(start) = rest.find('[') {
+        let mut params = Map::new();
+        let body = &rest[start + 1..];
+        let id_end = body.find([' ', ']']).unwrap_or(body.len());
+        let id = &body[..id_end];
+        let mut chars = body[id_end..].char_indices().peekable();
+        let mut consumed = body.len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                ']' => { consumed = id_end + i + 1; break; }
+                ' ' => continue,
+                _ => {
+                    let mut name = c.to_string();
+                    while let Some((_, c)) = chars.next_if(|(_, c)| *c != '=') {
+                        name.push(c);
+                    }
+                    chars.next(); // '='
+                    chars.next(); // opening quote
+                    let mut value = String::new();
+                    while let Some((_, c)) = chars.next() {
+                        match c {
+                            '"' => break,
+                            '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                            c => value.push(c),
+                        }
+                    }
+                    params.insert(name, Value::String(value));
+                }
+            }
+        }
+        elements.insert(id.to_string(), Value::Object(params));
+        rest = &body[consumed.min(body.len())..];
+    }
+    elements
+}
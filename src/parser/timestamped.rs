@@ -0,0 +1,95 @@
+use super::LogParser;
+use crate::{datetime::parse_datetime, record::LogRecord};
+
+/// Parser for lines that start with an RFC 3339 timestamp and a space, as `docker logs
+/// --timestamps` and `kubectl logs --timestamps` write them. The `[pod/NAME/CONTAINER]`
+/// in front that `kubectl logs --prefix` adds becomes the Pod and Container columns.
+/// [`with_stream`](Self::with_stream) reads the name of a log stream after the timestamp,
+/// as `aws logs tail` writes it.
+///
+/// The rest of the line is parsed by an inner parser, and the timestamp fills the DateTime
+/// column when that has none. Text the inner parser does not recognize, such as plain
+/// `println!` output, becomes the Message column as it is.
+pub struct TimestampedParser {
+    inner: Box<dyn LogParser + Send>,
+    stream: bool,
+}
+
+impl TimestampedParser {
+    /// Creates a parser reading what follows the timestamp with `inner`.
+    pub fn new(inner: Box<dyn LogParser + Send>) -> Self {
+        TimestampedParser { inner, stream: false }
+    }
+
+    /// Takes the word after the timestamp as the Stream column.
+    pub fn with_stream(mut self) -> Self {
+        self.stream = true;
+        self
+    }
+}
+
+/// Splits the leading timestamp from the rest of the line.
+fn split(line: &str) -> Option<(&str, &str)> {
+    line.split_once(' ').filter(|(stamp, _)| stamp.contains('T') && parse_datetime(stamp).is_some())
+}
+
+/// Splits a leading `[pod/NAME/CONTAINER] ` into the pod and container names and the rest
+/// of the line.
+fn split_prefix(line: &str) -> Option<((&str, &str), &str)> {
+    let (prefix, rest) = line.strip_prefix("[pod/")?.split_once("] ")?;
+    Some((prefix.split_once('/')?, rest))
+}
+
+impl LogParser for TimestampedParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let (pod, unprefixed) = match split_prefix(line) {
+            Some((pod, rest)) => (Some(pod), rest),
+            None => (None, line),
+        };
+        let Some((stamp, rest)) = split(unprefixed) else { return self.inner.parse(line) };
+        let (stream, rest) = match rest.split_once(' ').filter(|_| self.stream) {
+            Some((stream, rest)) => (Some(stream), rest),
+            None => (None, rest),
+        };
+        let parsed = self.inner.parse(rest);
+        if parsed.is_none() && (rest.trim().is_empty() || self.inner.is_header(rest)) {
+            return None;
+        }
+        let mut record = LogRecord::new(line);
+        match parsed {
+            Some(parsed) => {
+                if parsed.get("DateTime").is_none() {
+                    record.push("DateTime", stamp);
+                }
+                for (name, value) in parsed.fields() {
+                    record.push(name, if name == "DateTime" && value.is_empty() { stamp } else { value });
+                }
+            }
+            None => {
+                record.push("DateTime", stamp);
+                record.push("Message", rest);
+            }
+        }
+        if let Some((pod, container)) = pod {
+            record.push("Pod", pod);
+            record.push("Container", container);
+        }
+        if let Some(stream) = stream {
+            record.push("Stream", stream);
+        }
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(TimestampedParser { inner: self.inner.fork()?, stream: self.stream }))
+    }
+
+    fn is_header(&self, line: &str) -> bool {
+        let line = split_prefix(line).map_or(line, |(_, rest)| rest);
+        split(line).is_some_and(|(_, rest)| {
+            let rest = rest.split_once(' ').filter(|_| self.stream).map_or(rest, |(_, rest)| rest);
+            self.inner.is_header(rest)
+        })
+    }
+}
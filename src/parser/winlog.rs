@@ -0,0 +1,108 @@
+use super::{scalar_text, LogParser};
+use crate::record::LogRecord;
+use serde_json::{Map, Value};
+
+/// The `Keywords` bit of a failed Security audit, such as a failed logon.
+const AUDIT_FAILURE: u64 = 0x0010_0000_0000_0000;
+
+/// Parser for Windows event records as JSON, one per line, as `evtx_dump -o jsonl` writes
+/// them, and `rlog evtx` and `rlog winlog` read them from `.evtx` files:
+/// `{"Event": {"System": {...}, "EventData": {...}}}`.
+///
+/// The event `Level` becomes the Level (CRITICAL, ERROR, WARNING, INFO, or DEBUG; a failed
+/// audit is a WARNING), `TimeCreated` the DateTime, `Computer` the Host, the provider the
+/// App, and `EventID` a column of its own. The Message is the rendered message when the
+/// record carries one, as forwarded events do. The channel, record ID, and the named
+/// fields of the `EventData` (or `UserData`) payload go into the Data column. Columns are
+/// `DateTime`, `Level`, `Host`, `App`, `EventID`, `Message`, and `Data`.
+#[derive(Debug, Clone, Default)]
+pub struct WinlogParser;
+
+impl WinlogParser {
+    /// Creates a parser for Windows event records.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LogParser for WinlogParser {
+    fn parse(&mut self, line: &str) -> Option<LogRecord> {
+        let line = line.trim();
+        let Value::Object(mut object) = serde_json::from_str(line).ok()? else { return None };
+        let Value::Object(mut event) = object.shift_remove("Event")? else { return None };
+        let Some(Value::Object(system)) = event.shift_remove("System") else { return None };
+        let text = |value: Option<&Value>| value.map(|value| scalar_text(content(value))).unwrap_or_default();
+
+        let keywords = text(system.get("Keywords"));
+        let keywords = u64::from_str_radix(keywords.trim_start_matches("0x"), 16).unwrap_or_default();
+        let level = match text(system.get("Level")).parse::<u8>() {
+            _ if keywords & AUDIT_FAILURE != 0 => "WARNING",
+            Ok(1) => "CRITICAL",
+            Ok(2) => "ERROR",
+            Ok(3) => "WARNING",
+            Ok(0 | 4) => "INFO",
+            Ok(_) => "DEBUG",
+            Err(_) => "",
+        };
+        let mut data = Map::new();
+        for (key, field) in [("Channel", "Channel"), ("RecordID", "EventRecordID"), ("Task", "Task"), ("Keywords", "Keywords")] {
+            if let Some(value) = system.get(field).filter(|value| !value.is_null()) {
+                data.insert(key.to_string(), content(value).clone());
+            }
+        }
+        if let Some(user) = system.get("Security").and_then(|security| attribute(security, "UserID")) {
+            data.insert("UserID".to_string(), user.into());
+        }
+        for payload in ["EventData", "UserData"] {
+            if let Some(fields) = event.get(payload) {
+                payload_fields(fields, &mut data);
+            }
+        }
+        let message = event.get("RenderingInfo").and_then(|info| info.get("Message")).map(|m| text(Some(m))).unwrap_or_default();
+
+        let mut record = LogRecord::new(line);
+        record.push("DateTime", system.get("TimeCreated").and_then(|time| attribute(time, "SystemTime")).unwrap_or_default());
+        record.push("Level", level);
+        record.push("Host", text(system.get("Computer")));
+        record.push("App", system.get("Provider").and_then(|provider| attribute(provider, "Name")).unwrap_or_default());
+        record.push("EventID", text(system.get("EventID")));
+        record.push("Message", message);
+        record.push("Data", if data.is_empty() { String::new() } else { Value::Object(data).to_string() });
+        Some(record)
+    }
+
+    fn fork(&self) -> Option<Box<dyn LogParser + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// The text of an XML element with attributes, which comes as `{"#attributes": ..., "#text": ...}`.
+fn content(value: &Value) -> &Value {
+    value.get("#text").unwrap_or(value)
+}
+
+/// An XML attribute of an element.
+fn attribute(element: &Value, name: &str) -> Option<String> {
+    element.get("#attributes")?.get(name).map(scalar_text)
+}
+
+/// Adds the fields of an `EventData` or `UserData` payload, leaving out XML attributes.
+/// Unnamed `<Data>` elements, which come as `{"Data": {"#text": [...]}}`, are numbered.
+fn payload_fields(payload: &Value, data: &mut Map<String, Value>) {
+    let Value::Object(fields) = payload else { return };
+    for (key, value) in fields {
+        match (key.as_str(), content(value)) {
+            ("#attributes", _) | (_, Value::Null) => {}
+            ("Data", Value::Array(values)) => {
+                for (idx, value) in values.iter().enumerate() {
+                    data.insert(format!("Data{}", idx + 1), value.clone());
+                }
+            }
+            // UserData holds a single element named after the event.
+            (_, Value::Object(_)) if fields.len() == 1 => payload_fields(value, data),
+            (_, value) => {
+                data.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
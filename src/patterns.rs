@@ -0,0 +1,49 @@
+use crate::cli::PatternsArgs;
+use rlog::{Drain, LogRecord};
+use std::io::{self, Write};
+
+/// The templates of the matching messages, mined with [`Drain`].
+pub struct Patterns {
+    drain: Drain,
+    total: usize,
+}
+
+impl Patterns {
+    pub fn new(args: &PatternsArgs) -> Self {
+        Patterns { drain: Drain::new(args.similarity), total: 0 }
+    }
+
+    /// Adds the Message column of a record, or its whole line if it has none.
+    pub fn add(&mut self, record: &LogRecord) {
+        self.total += 1;
+        self.drain.add(record.get("Message").unwrap_or(&record.raw));
+    }
+
+    /// Number of records counted.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Writes the `--limit` most frequent templates with their count and share of all
+    /// matching records.
+    pub fn print(&self, args: &PatternsArgs) -> io::Result<()> {
+        let mut clusters: Vec<_> = self.drain.clusters().iter().collect();
+        // Stable, so equally frequent templates stay in the order they were first seen.
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.count));
+        let limit = if args.limit == 0 { clusters.len() } else { args.limit };
+        let share = |count: usize| 100.0 * count as f64 / self.total.max(1) as f64;
+
+        let mut out = io::stdout().lock();
+        writeln!(out, "{:>9}  {:>6}  TEMPLATE", "COUNT", "%")?;
+        for cluster in clusters.iter().take(limit) {
+            writeln!(out, "{:>9}  {:>5.1}%  {}", cluster.count, share(cluster.count), cluster.template())?;
+        }
+        if clusters.len() > limit {
+            let others: usize = clusters[limit..].iter().map(|cluster| cluster.count).sum();
+            let plural = if clusters.len() - limit == 1 { "" } else { "s" };
+            writeln!(out, "{:>9}  {:>5.1}%  ({} other template{})", others, share(others), clusters.len() - limit, plural)?;
+        }
+        let plural = if clusters.len() == 1 { "" } else { "s" };
+        writeln!(out, "{:>9}  {:>5.1}%  {} template{}", self.total, share(self.total), clusters.len(), plural)
+    }
+}
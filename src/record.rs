@@ -0,0 +1,100 @@
+use chrono::{DateTime, FixedOffset};
+use std::sync::Arc;
+
+/// A single parsed log entry: the raw line plus its named columns in header order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogRecord {
+    pub raw: String,
+    /// Label of the input the record was read from, when several inputs are followed.
+    pub source: Option<Arc<str>>,
+    /// 1-based number of the line in its input, if known.
+    pub line: Option<u64>,
+    /// Byte offset of the line in its input.
+    pub offset: Option<u64>,
+    /// The DateTime column as parsed when the record was read, if it could be.
+    pub time: Option<DateTime<FixedOffset>>,
+    /// Whether the line could not be parsed, so the record has no columns but the raw text.
+    pub malformed: bool,
+    fields: Vec<(String, String)>,
+}
+
+impl LogRecord {
+    /// Creates an empty record for the given raw line.
+    pub fn new(raw: impl Into<String>) -> Self {
+        LogRecord { raw: raw.into(), source: None, line: None, offset: None, time: None, malformed: false, fields: Vec::new() }
+    }
+
+    /// Appends a column, keeping insertion order.
+    pub fn push(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.fields.push((name.into(), value.into()));
+    }
+
+    /// Replaces the value of a column, or appends it if the record has none.
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        match self.fields.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.into(),
+            None => self.fields.push((name.to_string(), value.into())),
+        }
+    }
+
+    /// Adds a continuation line, such as a stack trace frame, to the Message column (or to
+    /// Data for records that have it but no Message) and to the raw text.
+    pub fn append_line(&mut self, text: &str) {
+        let column = if self.get("Message").is_none() && self.get("Data").is_some() { "Data" } else { "Message" };
+        let value = match self.get(column) {
+            Some(value) if !value.is_empty() => format!("{}\n{}", value, text),
+            _ => text.to_string(),
+        };
+        self.set(column, value);
+        if !self.raw.is_empty() {
+            self.raw.push('\n');
+        }
+        self.raw.push_str(text);
+    }
+
+    /// Returns the value of a column by header name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Iterates over `(header, value)` pairs in column order.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// The `DateTime` column, or an empty string if absent.
+    pub fn datetime(&self) -> &str {
+        self.get("DateTime").unwrap_or("")
+    }
+
+    /// The `Level` column, or an empty string if absent.
+    pub fn level(&self) -> &str {
+        self.get("Level").unwrap_or("")
+    }
+}
+
+/// Maps common level spellings (`warn`, `err`, `fatal`, syslog and pino/bunyan numbers, ...)
+/// onto the canonical upper-case names used for coloring and filtering. Unknown levels are
+/// upper-cased unchanged.
+pub fn normalize_level(level: &str) -> String {
+    let level = level.trim().to_uppercase();
+    let canonical = match level.as_str() {
+        "TRACE" | "VERBOSE" | "DBG" | "10" | "20" => "DEBUG",
+        "INFORMATION" | "INF" | "30" => "INFO",
+        "WARN" | "WRN" | "40" => "WARNING",
+        "ERR" | "ERRO" | "50" => "ERROR",
+        "FATAL" | "CRIT" | "60" => "CRITICAL",
+        "EMERG" | "PANIC" => "EMERGENCY",
+        _ => return level,
+    };
+    canonical.to_string()
+}
+
+/// Canonical levels from least to most severe.
+pub const SEVERITIES: [&str; 8] = ["DEBUG", "INFO", "NOTICE", "WARNING", "ERROR", "CRITICAL", "ALERT", "EMERGENCY"];
+
+/// Position of a level in [`SEVERITIES`] after normalization, or `None` for unknown levels.
+pub fn severity_rank(level: &str) -> Option<usize> {
+    let level = normalize_level(level);
+    SEVERITIES.iter().position(|l| *l == level)
+}
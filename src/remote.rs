@@ -0,0 +1,126 @@
+use std::{fmt, io::{self, Read}, process::{Child, ChildStdout, Command, Stdio}, str::FromStr, thread, time::Duration};
+
+/// Longest wait between attempts to reconnect.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A log file on another host, `[USER@]HOST:PATH`, followed through `ssh`.
+#[derive(Debug, Clone)]
+pub struct Remote {
+    pub host: String,
+    pub path: String,
+    /// Start with only the last N lines of the file instead of all of it.
+    pub lines: Option<usize>,
+}
+
+impl FromStr for Remote {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value.split_once(':') {
+            Some((host, path)) if !host.is_empty() && !path.is_empty() => Ok(Remote { host: host.to_string(), path: path.to_string(), lines: None }),
+            _ => Err(format!("expected [USER@]HOST:PATH, got `{}`", value)),
+        }
+    }
+}
+
+impl fmt::Display for Remote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.path)
+    }
+}
+
+impl Remote {
+    /// The remote file as a byte stream, see [`RemoteReader`].
+    pub fn reader(&self) -> RemoteReader {
+        RemoteReader { remote: self.clone(), stdout: None, child: None, offset: 0, received: false, backoff: Duration::from_secs(1) }
+    }
+}
+
+/// Reads a remote file by running `tail -F` on its host over `ssh`, which must be able to
+/// log in there. When the connection drops, it reconnects with growing pauses and carries
+/// on from the byte it stopped at; after starting with the last `lines`, the offset is not
+/// known and only lines written after the reconnection are read.
+///
+/// The first connection may ask for a password on the terminal; reconnections do not, so
+/// they need keys or an agent.
+pub struct RemoteReader {
+    remote: Remote,
+    stdout: Option<ChildStdout>,
+    child: Option<Child>,
+    /// Bytes of the file read so far, when reading started at its top.
+    offset: u64,
+    /// Whether anything was read yet, so a first connection that fails is an error.
+    received: bool,
+    backoff: Duration,
+}
+
+impl RemoteReader {
+    fn connect(&mut self) -> io::Result<()> {
+        let start = match (self.received, self.remote.lines) {
+            (false, None) => "-n +1".to_string(),
+            (false, Some(lines)) => format!("-n {}", lines),
+            (true, None) => format!("-c +{}", self.offset + 1),
+            (true, Some(_)) => "-n 0".to_string(),
+        };
+        let quoted = format!("'{}'", self.remote.path.replace('\'', "'\\''"));
+        let mut command = Command::new("ssh");
+        command.args(["-T", "-n", "-o", "ServerAliveInterval=15", "-o", "ServerAliveCountMax=3"]);
+        if self.received {
+            command.args(["-o", "BatchMode=yes"]);
+        }
+        command.arg(&self.remote.host).arg(format!("tail {} -F {}", start, quoted));
+        let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot run ssh: {}", e)))?;
+        self.stdout = child.stdout.take();
+        self.child = Some(child);
+        Ok(())
+    }
+
+    /// Waits for the ssh process that stopped, and tells why.
+    fn disconnected(&mut self) -> String {
+        self.stdout = None;
+        match self.child.take().map(|mut child| child.wait()) {
+            Some(Ok(status)) => format!("ssh {}", status),
+            Some(Err(e)) => e.to_string(),
+            None => "not connected".to_string(),
+        }
+    }
+}
+
+impl Read for RemoteReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.stdout.is_none() {
+                self.connect()?;
+            }
+            let read = self.stdout.as_mut().expect("connected").read(buf);
+            match read {
+                Ok(0) | Err(_) => {
+                    let reason = match read {
+                        Err(e) => format!("{}; {}", e, self.disconnected()),
+                        _ => self.disconnected(),
+                    };
+                    if !self.received {
+                        return Err(io::Error::new(io::ErrorKind::ConnectionRefused, format!("cannot connect ({})", reason)));
+                    }
+                    eprintln!("rlog: lost {} ({}), reconnecting in {}s", self.remote, reason, self.backoff.as_secs());
+                    thread::sleep(self.backoff);
+                    self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                }
+                Ok(n) => {
+                    (self.offset, self.received, self.backoff) = (self.offset + n as u64, true, Duration::from_secs(1));
+                    return Ok(n);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for RemoteReader {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,534 @@
+use crate::{cli::DisplayArgs, config::parse_color, output::OutputFormat};
+use chrono::{Local, TimeDelta};
+use clap::ValueEnum;
+use crossterm::{queue, style::{Attribute, Color, SetAttribute, SetBackgroundColor, SetForegroundColor}};
+use rlog::{parser::scalar_text, record_time, template, DataQuery, LogRecord, Shown};
+use serde_json::Value;
+use std::{borrow::Cow, collections::HashMap, io::{self, Write}};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Names of the built-in themes.
+pub const THEMES: [&str; 3] = ["dark", "light", "colorblind"];
+
+/// Level colors of a built-in theme, from least to most severe.
+pub fn theme(name: &str) -> Option<[(&'static str, Color); 8]> {
+    let rgb = |r, g, b| Color::Rgb { r, g, b };
+    let colors = match name {
+        "dark" => [Color::White, Color::Blue, Color::Cyan, Color::Yellow, Color::Red, Color::Magenta, Color::DarkRed, Color::DarkMagenta],
+        "light" => [Color::DarkGrey, Color::DarkBlue, Color::DarkCyan, Color::DarkYellow, Color::DarkRed, Color::DarkMagenta, Color::Red, Color::Magenta],
+        // Okabe-Ito palette, distinguishable with the common forms of color blindness.
+        "colorblind" => [
+            rgb(153, 153, 153), rgb(0, 114, 178), rgb(86, 180, 233), rgb(230, 159, 0),
+            rgb(213, 94, 0), rgb(204, 121, 167), rgb(240, 228, 66), rgb(0, 158, 115),
+        ],
+        _ => return None,
+    };
+    let levels = rlog::record::SEVERITIES;
+    Some(std::array::from_fn(|idx| (levels[idx], colors[idx])))
+}
+
+/// The color for a level in the active theme.
+pub fn level_color(level: &str, colors: &HashMap<String, Color>) -> Color {
+    colors.get(&level.to_uppercase()).copied().unwrap_or(Color::Reset)
+}
+
+/// Styling applied to rows matching a `--rule`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RowStyle {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub attributes: Vec<Attribute>,
+}
+
+impl RowStyle {
+    /// Parses a style such as `bg:red bold` or `fg:#ff8800,blink`. A bare color sets the
+    /// foreground.
+    pub fn parse(text: &str) -> Result<RowStyle, String> {
+        let mut style = RowStyle::default();
+        for word in text.split([' ', ',']).filter(|w| !w.is_empty()) {
+            let attribute = match word.to_lowercase().as_str() {
+                "bold" => Some(Attribute::Bold),
+                "dim" => Some(Attribute::Dim),
+                "italic" => Some(Attribute::Italic),
+                "underline" => Some(Attribute::Underlined),
+                "blink" => Some(Attribute::SlowBlink),
+                "reverse" => Some(Attribute::Reverse),
+                "strike" => Some(Attribute::CrossedOut),
+                _ => None,
+            };
+            let color = |name: &str| parse_color(name).ok_or_else(|| format!("unknown color `{}`", name));
+            match (attribute, word.split_once(':')) {
+                (Some(attribute), _) => style.attributes.push(attribute),
+                (None, Some(("fg", name))) => style.fg = Some(color(name)?),
+                (None, Some(("bg", name))) => style.bg = Some(color(name)?),
+                (None, _) => style.fg = Some(color(word)?),
+            }
+        }
+        Ok(style)
+    }
+
+    /// Adds `other` on top of this style.
+    fn merge(&mut self, other: &RowStyle) {
+        self.fg = other.fg.or(self.fg);
+        self.bg = other.bg.or(self.bg);
+        self.attributes.extend(&other.attributes);
+    }
+}
+
+/// The combined style of every `--rule` the record matches, in order.
+pub fn rule_style(record: &LogRecord, display: &DisplayArgs) -> RowStyle {
+    let mut style = RowStyle::default();
+    for (expr, rule) in &display.rules {
+        if expr.matches(record) {
+            style.merge(rule);
+        }
+    }
+    style
+}
+
+/// The color for a record's level in the active theme, or for its input with `--color-by-source`.
+pub fn record_color(record: &LogRecord, display: &DisplayArgs) -> Color {
+    match display.color_by_source.then(|| source_color(record, display)).flatten() {
+        Some(color) => color,
+        None => level_color(record.level(), &display.colors),
+    }
+}
+
+/// Colors cycled through to tell sources apart.
+const SOURCE_COLORS: [Color; 6] = [Color::Green, Color::Cyan, Color::Magenta, Color::Yellow, Color::Blue, Color::Red];
+
+/// The color telling the input of a record apart from the others.
+fn source_color(record: &LogRecord, display: &DisplayArgs) -> Option<Color> {
+    let source = record.source.as_ref()?;
+    let idx = display.sources.iter().position(|s| s == source).unwrap_or(0);
+    Some(SOURCE_COLORS[idx % SOURCE_COLORS.len()])
+}
+
+/// The `[source]` prefix and its color, if rows need to be attributed to several inputs and
+/// there is no `Source` column. It is padded to the longest label, so that the columns after
+/// it line up whichever input a row comes from.
+pub fn source_prefix(record: &LogRecord, display: &DisplayArgs) -> Option<(String, Color)> {
+    if (display.sources.len() < 2 && !display.attribute_rows) || display.source_column {
+        return None;
+    }
+    let source = record.source.as_ref()?;
+    let longest = display.sources.iter().map(|source| source.width()).max().unwrap_or(0);
+    let padding = " ".repeat(longest.saturating_sub(source.width()));
+    Some((format!("[{}]{} ", source, padding), source_color(record, display)?))
+}
+
+/// How the DateTime column is shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TimeDisplay {
+    /// As written in the log, or converted with --tz.
+    #[default]
+    Absolute,
+    /// Time elapsed since the entry, e.g. `3m05s ago`.
+    Ago,
+    /// Time since the previous entry shown, e.g. `+1.250s`.
+    Delta,
+}
+
+/// The DateTime of a record as `--time-display` shows it, or `None` to show the column as is.
+/// The first entry shown in `delta` mode keeps its DateTime.
+fn display_time(record: &LogRecord, display: &DisplayArgs) -> Option<String> {
+    let time = record_time(record)?;
+    match display.time_display {
+        TimeDisplay::Absolute => None,
+        TimeDisplay::Ago => Some(format!("{} ago", elapsed(Local::now().fixed_offset() - time, false))),
+        TimeDisplay::Delta => display.previous_time.get().map(|previous| {
+            let delta = time - previous;
+            format!("{}{}", if delta < TimeDelta::zero() { '-' } else { '+' }, elapsed(delta, true))
+        }),
+    }
+}
+
+/// A duration in its two largest units (`2h05m`), or in seconds below a minute, with
+/// milliseconds if `precise`.
+pub fn elapsed(delta: TimeDelta, precise: bool) -> String {
+    let delta = delta.abs();
+    let seconds = delta.num_seconds();
+    match seconds {
+        ..60 if precise => format!("{}.{:03}s", seconds, delta.subsec_millis()),
+        ..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        3600..86400 => format!("{}h{:02}m", seconds / 3600, seconds / 60 % 60),
+        _ => format!("{}d{:02}h", seconds / 86400, seconds / 3600 % 24),
+    }
+}
+
+/// The value of a column picked with `--by` and similar options: a column of the record, or
+/// for `Data.KEY` the value in its JSON Data column. `None` if the record has no such value.
+pub fn column_value(record: &LogRecord, (name, query): &(String, Option<DataQuery>)) -> Option<String> {
+    match query {
+        Some(query) => query.value(record),
+        None => record.get(name).map(str::to_string),
+    }
+}
+
+/// Width of a column when `--width` does not cover it.
+pub fn default_width(header: &str) -> usize {
+    match header {
+        "DateTime" => 20,
+        "Level" => 10,
+        "Message" => 50,
+        "Data" => 30,
+        "Path" | "UserAgent" => 40,
+        "Method" | "Status" | "Bytes" | "Line" => 7,
+        "Offset" => 10,
+        _ => 15,
+    }
+}
+
+/// The columns of a record that are displayed, each paired with its configured width:
+/// the `--columns` selection in order, or else every column with expanded Data keys after
+/// them. The Source, Line, and Offset columns come first and `--data-query` columns last.
+pub fn visible_cells<'a>(record: &'a LogRecord, display: &'a DisplayArgs) -> Vec<(Cow<'a, str>, Cow<'a, str>, usize)> {
+    let needs_data = display.expand_data || !display.data_columns.is_empty() || display.columns.iter().any(|(_, q)| q.is_some());
+    let data: Value = match needs_data {
+        true => record.get("Data").and_then(|d| serde_json::from_str(d).ok()).unwrap_or_default(),
+        false => Value::Null,
+    };
+    let value_of = |query: &DataQuery| Cow::Owned(query.value_in(&data).unwrap_or_default());
+
+    let position = |shown: bool, value: Option<u64>| shown.then(|| value.map_or(String::new(), |n| n.to_string()));
+    let source = display.source_column.then(|| record.source.as_deref().unwrap_or("").to_string());
+    let mut cells: Vec<(Cow<str>, Cow<str>)> = [("Source", source), ("Line", position(display.line_numbers, record.line)), ("Offset", position(display.offsets, record.offset))]
+        .into_iter()
+        .filter_map(|(header, value)| Some((header.into(), value?.into())))
+        .collect();
+    cells.extend(if display.columns.is_empty() {
+        let mut cells: Vec<(Cow<str>, Cow<str>)> = record.fields().map(|(header, value)| (header.into(), value.into())).collect();
+        if let (true, Value::Object(object)) = (display.expand_data, &data) {
+            cells.extend(object.iter().map(|(key, value)| (format!("Data.{}", key).into(), scalar_text(value).into())));
+        }
+        cells
+    } else {
+        display.columns.iter().map(|(header, query)| match query {
+            Some(query) => (header.as_str().into(), value_of(query)),
+            None => (header.as_str().into(), record.get(header).unwrap_or("").into()),
+        }).collect()
+    });
+    cells.extend(display.data_columns.iter().map(|(header, query)| (header.as_str().into(), value_of(query))));
+
+    let structured = display.output != OutputFormat::Table;
+    let show_data = !display.columns.is_empty() || (!display.expand_data && (structured || display.verbose || display.detailed));
+    let cells: Vec<_> = cells.into_iter().enumerate()
+        .filter(|(_, (header, _))| header != "Data" || show_data)
+        .map(|(idx, (header, value))| {
+            let width = display.width.get(idx).copied().unwrap_or_else(|| default_width(&header));
+            let value = match header == "DateTime" {
+                true => display_time(record, display).map_or(value, Cow::Owned),
+                false => value,
+            };
+            (header, value, width)
+        })
+        .collect();
+    match &display.fit {
+        Some(fit) => {
+            let prefix = source_prefix(record, display).map_or(0, |(prefix, _)| prefix.width());
+            fit.borrow_mut().apply(cells, terminal_width().saturating_sub(prefix), !display.wrap)
+        }
+        None => cells,
+    }
+}
+
+/// Width of the terminal, re-read for every row so resizes take effect right away.
+pub fn terminal_width() -> usize {
+    crossterm::terminal::size().ok().filter(|(cols, _)| *cols > 0).map_or(120, |(cols, _)| cols as usize)
+}
+
+/// Column widths sized from the values seen so far and shrunk to fit the terminal.
+#[derive(Debug, Default)]
+pub struct Fit {
+    observed: HashMap<String, usize>,
+}
+
+impl Fit {
+    /// Replaces the widths of `cells` so the row fits in `available` columns, eliding values
+    /// that do not fit with `…` if `elide` is set.
+    fn apply<'a>(&mut self, cells: Vec<(Cow<'a, str>, Cow<'a, str>, usize)>, available: usize, elide: bool) -> Vec<(Cow<'a, str>, Cow<'a, str>, usize)> {
+        let wanted: Vec<usize> = cells.iter().map(|(header, value, _)| {
+            let seen = self.observed.entry(header.to_string()).or_insert(0);
+            *seen = (*seen).max(value.width()).max(1);
+            *seen
+        }).collect();
+        // Each cell is followed by " | ". Short fixed-format columns such as DateTime keep their
+        // width unless the flexible ones cannot fit at all.
+        let total = available.saturating_sub(3 * cells.len());
+        let fixed: Vec<bool> = cells.iter().map(|(header, _, _)| is_fixed(header)).collect();
+        let fixed_width: usize = wanted.iter().zip(&fixed).filter(|(_, f)| **f).map(|(w, _)| w).sum();
+        let flexible: Vec<usize> = wanted.iter().zip(&fixed).filter(|(_, f)| !**f).map(|(w, _)| *w).collect();
+        let (cap, fixed_cap) = match total.checked_sub(fixed_width) {
+            Some(rest) if rest >= 3 * flexible.len() => (water_level(&flexible, rest), usize::MAX),
+            _ => (water_level(&wanted, total), water_level(&wanted, total)),
+        };
+        cells.into_iter().zip(wanted).zip(fixed).map(|(((header, value, _), wanted), fixed)| {
+            let width = wanted.min(if fixed { fixed_cap } else { cap });
+            let value = if elide && value.width() > width { Cow::Owned(self::elide(&value, width)) } else { value };
+            (header, value, width)
+        }).collect()
+    }
+}
+
+/// Columns whose values have a short, regular shape.
+fn is_fixed(header: &str) -> bool {
+    matches!(header, "DateTime" | "Level" | "Status" | "Method" | "Bytes" | "IP" | "Line" | "Offset")
+}
+
+/// The largest width cap under which `wanted` fits in `total`, so only the widest columns
+/// are shrunk. At least 3 so that elided values stay legible.
+fn water_level(wanted: &[usize], total: usize) -> usize {
+    let mut sorted = wanted.to_vec();
+    sorted.sort_unstable();
+    let mut remaining = total;
+    for (idx, &width) in sorted.iter().enumerate() {
+        let left = sorted.len() - idx;
+        if width * left > remaining {
+            return (remaining / left).max(3);
+        }
+        remaining -= width;
+    }
+    usize::MAX
+}
+
+/// Shortens `value` to `width` terminal columns, ending with `…`.
+fn elide(value: &str, width: usize) -> String {
+    let mut text = String::new();
+    let mut used = 0;
+    for c in value.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        used += w;
+        text.push(c);
+    }
+    text.push('…');
+    text
+}
+
+/// The header shown for a column, after `--rename`.
+pub fn label<'a>(header: &'a str, display: &'a DisplayArgs) -> &'a str {
+    display.rename.iter().find(|(from, _)| from == header).map_or(header, |(_, to)| to.as_str())
+}
+
+/// Pads a cell to its display width, right-aligning numbers taken from the Data column and
+/// line numbers and offsets.
+pub fn pad(header: &str, value: &str, width: usize) -> String {
+    let from_data = header.starts_with("Data.") || header.starts_with('.');
+    template::pad(value, width, (from_data && value.parse::<f64>().is_ok()) || header == "Line" || header == "Offset")
+}
+
+/// Writes one record as a colored row, honoring the verbose/detailed Data options, or laid
+/// out by `--format-out`.
+pub fn write_record(out: &mut impl Write, record: &LogRecord, display: &DisplayArgs) -> io::Result<()> {
+    let prefix = source_prefix(record, display);
+    if let Some((prefix, color)) = &prefix {
+        paint(out, *color, display)?;
+        write!(out, "{}", prefix)?;
+    }
+    if record.malformed {
+        return write_raw(out, record, display);
+    }
+    let style = rule_style(record, display);
+    let color = style.fg.unwrap_or_else(|| record_color(record, display));
+    paint(out, color, display)?;
+    if display.colored {
+        if let Some(bg) = style.bg {
+            queue!(out, SetBackgroundColor(bg))?;
+        }
+        for attribute in &style.attributes {
+            queue!(out, SetAttribute(*attribute))?;
+        }
+    }
+
+    let cells = match &display.format_out {
+        Some(template) => {
+            write_highlighted(out, &template.render(record), color, display)?;
+            Vec::new()
+        }
+        None => visible_cells(record, display),
+    };
+    // Multiline values, and with --wrap long ones, continue under their column.
+    let lines: Vec<Vec<String>> = cells.iter()
+        .map(|(header, value, width)| match (display.wrap, header == "Data" && display.detailed) {
+            (_, true) => vec![value.to_string()],
+            (true, false) => value.split('\n').flat_map(|line| wrap(line, *width)).collect(),
+            (false, false) => value.split('\n').map(str::to_string).collect(),
+        })
+        .collect();
+    let rows = lines.iter().map(Vec::len).max().unwrap_or(1);
+    for row in 0..rows {
+        if row > 0 {
+            writeln!(out)?;
+            write!(out, "{}", " ".repeat(prefix.as_ref().map_or(0, |(prefix, _)| prefix.width())))?;
+        }
+        for ((header, _, width), lines) in cells.iter().zip(&lines) {
+            let value = lines.get(row).map_or("", String::as_str);
+            let highlight = header == "Message" || header == "Data" || header.starts_with("Data.");
+            if header == "Data" && display.detailed {
+                if row == 0 {
+                    let text = match serde_json::from_str::<Value>(value) {
+                        Ok(json) => serde_json::to_string_pretty(&json).unwrap(),
+                        Err(_) => value.to_string(),
+                    };
+                    write_highlighted(out, &text, color, display)?;
+                    writeln!(out)?;
+                }
+            } else if highlight {
+                write_highlighted(out, &pad(header, value, *width), color, display)?;
+                write!(out, " | ")?;
+            } else {
+                write!(out, "{} | ", pad(header, value, *width))?;
+            }
+        }
+    }
+
+    paint(out, Color::Reset, display)?;
+    if display.colored && style != RowStyle::default() {
+        queue!(out, SetAttribute(Attribute::Reset))?;
+    }
+    writeln!(out)
+}
+
+/// Writes the raw text of a line that could not be parsed, dimmed.
+fn write_raw(out: &mut impl Write, record: &LogRecord, display: &DisplayArgs) -> io::Result<()> {
+    if display.colored {
+        queue!(out, SetForegroundColor(Color::Reset), SetAttribute(Attribute::Dim))?;
+    }
+    write!(out, "{}", record.raw)?;
+    if display.colored {
+        queue!(out, SetAttribute(Attribute::Reset))?;
+    }
+    writeln!(out)
+}
+
+/// Writes `text` in the row color `base`, coloring `--highlight` matches. Where patterns
+/// overlap, the one given first wins.
+fn write_highlighted(out: &mut impl Write, text: &str, base: Color, display: &DisplayArgs) -> io::Result<()> {
+    if !display.colored || display.highlights.is_empty() {
+        return write!(out, "{}", text);
+    }
+    let mut spans: Vec<(usize, usize, Color)> = Vec::new();
+    for (regex, color) in &display.highlights {
+        for m in regex.find_iter(text).filter(|m| !m.is_empty()) {
+            if !spans.iter().any(|&(start, end, _)| m.start() < end && start < m.end()) {
+                spans.push((m.start(), m.end(), *color));
+            }
+        }
+    }
+    spans.sort_by_key(|&(start, _, _)| start);
+    let mut last = 0;
+    for (start, end, color) in spans {
+        write!(out, "{}", &text[last..start])?;
+        queue!(out, SetForegroundColor(color), SetAttribute(Attribute::Bold))?;
+        write!(out, "{}", &text[start..end])?;
+        queue!(out, SetAttribute(Attribute::NormalIntensity), SetForegroundColor(base))?;
+        last = end;
+    }
+    write!(out, "{}", &text[last..])
+}
+
+/// Splits `text` into lines of at most `width` terminal columns, breaking at spaces where
+/// possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![String::new()];
+    for word in text.split(' ') {
+        let line = lines.last_mut().unwrap();
+        let used = line.width();
+        if used > 0 && used + 1 + word.width() <= width {
+            line.push(' ');
+            line.push_str(word);
+            continue;
+        }
+        if used > 0 {
+            lines.push(String::new());
+        }
+        // Words longer than the column are broken wherever they reach its edge.
+        for c in word.chars() {
+            let line = lines.last_mut().unwrap();
+            if line.width() + c.width().unwrap_or(0) > width {
+                lines.push(String::new());
+            }
+            lines.last_mut().unwrap().push(c);
+        }
+    }
+    lines
+}
+
+/// Switches the foreground color, unless colors are off.
+fn paint(out: &mut impl Write, color: Color, display: &DisplayArgs) -> io::Result<()> {
+    if display.colored { queue!(out, SetForegroundColor(color)) } else { Ok(()) }
+}
+
+/// Writes a bold header row with the labels of the columns `record` is shown with.
+pub fn write_header(out: &mut impl Write, record: &LogRecord, display: &DisplayArgs) -> io::Result<()> {
+    if let Some((prefix, _)) = source_prefix(record, display) {
+        write!(out, "{}", " ".repeat(prefix.width()))?;
+    }
+    if display.colored {
+        queue!(out, SetAttribute(Attribute::Bold), SetAttribute(Attribute::Underlined))?;
+    }
+    let header: Vec<String> = visible_cells(record, display).into_iter()
+        .map(|(header, _, width)| if header == "Data" && display.detailed { header.into_owned() } else { template::pad(label(&header, display), width, false) })
+        .collect();
+    write!(out, "{} |", header.join(" | "))?;
+    if display.colored {
+        queue!(out, SetAttribute(Attribute::Reset))?;
+    }
+    writeln!(out)
+}
+
+/// Writes a record picked by the context window: matches normally, context entries dimmed,
+/// `--` between groups that are not adjacent, and how often a match was repeated.
+pub fn write_shown(out: &mut impl Write, shown: &Shown, display: &DisplayArgs) -> io::Result<()> {
+    match shown {
+        Shown::Match(record) => write_record(out, record, display),
+        Shown::Context(record) if display.colored => {
+            queue!(out, SetAttribute(Attribute::Dim))?;
+            write_record(out, record, display)?;
+            queue!(out, SetAttribute(Attribute::Reset))
+        }
+        Shown::Context(record) => write_record(out, record, display),
+        Shown::Break => writeln!(out, "--"),
+        Shown::Repeated(count) if display.colored => {
+            queue!(out, SetAttribute(Attribute::Dim))?;
+            write!(out, "{}", repeated(*count))?;
+            queue!(out, SetAttribute(Attribute::Reset))?;
+            writeln!(out)
+        }
+        Shown::Repeated(count) => writeln!(out, "{}", repeated(*count)),
+    }
+}
+
+/// The note standing for entries left out by `--max-rate`.
+pub fn suppressed(count: usize) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::new();
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("... suppressed {} entr{} ...", grouped, if count == 1 { "y" } else { "ies" })
+}
+
+/// Writes the [`suppressed`] note as a dimmed row.
+pub fn write_suppressed(out: &mut impl Write, count: usize, display: &DisplayArgs) -> io::Result<()> {
+    if display.colored {
+        queue!(out, SetAttribute(Attribute::Dim))?;
+    }
+    write!(out, "{}", suppressed(count))?;
+    if display.colored {
+        queue!(out, SetAttribute(Attribute::Reset))?;
+    }
+    writeln!(out)
+}
+
+/// The line standing for matches collapsed by `--dedupe`.
+pub fn repeated(count: usize) -> String {
+    format!("  ... repeated {} more time{}", count, if count == 1 { "" } else { "s" })
+}
@@ -0,0 +1,218 @@
+use crate::{channel::{self, Lines}, spawned::curl_headers};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde_json::{json, Value};
+use std::{collections::HashSet, fmt, io::{self, Write}, process::{Command, Stdio}, sync::mpsc::Sender, thread, time::Duration};
+
+/// Hits fetched per request.
+const PAGE_SIZE: usize = 1000;
+
+/// How long a point in time is kept between requests.
+const KEEP_ALIVE: &str = "1m";
+
+/// Pause between searches for new hits when following.
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Longest pause before searching again after a failure.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A search of Elasticsearch or OpenSearch indices, from `rlog es`, whose hits are read as
+/// lines of JSON: the `_source` of each.
+///
+/// Hits are paged through in order of `time_field` with `search_after` in a point in time,
+/// so that the results do not shift under the pages. Following, the search is run again
+/// every few seconds for hits from the time of the last one on.
+#[derive(Debug, Clone)]
+pub struct Search {
+    /// Address of the cluster, such as `http://localhost:9200`.
+    pub url: String,
+    /// Indices to search, such as `logs-*`.
+    pub index: String,
+    /// Query in the Lucene syntax of `query_string`; every document when `None`.
+    pub query: Option<String>,
+    pub time_field: String,
+    /// Headers sent with each request, such as `Authorization: ...`.
+    pub headers: Vec<String>,
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+    /// Start with only the last N hits instead of all of them.
+    pub last: Option<usize>,
+    pub follow: bool,
+}
+
+impl fmt::Display for Search {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.url.trim_end_matches('/'), self.index)
+    }
+}
+
+/// Where the search has got to: the time of the last hit read, in milliseconds, and the hits
+/// already read at that time.
+#[derive(Debug, Default)]
+struct Cursor {
+    time: Option<i64>,
+    seen: HashSet<String>,
+}
+
+impl Search {
+    /// Checks that the cluster can be searched, and reads the hits on a background thread
+    /// from then on.
+    pub fn open(&self) -> io::Result<Lines> {
+        let failed = |e: io::Error| io::Error::new(e.kind(), format!("cannot search {}: {}", self, e));
+        let info = self.request("GET", "", None).map_err(failed)?;
+        let opensearch = info.pointer("/version/distribution").and_then(Value::as_str) == Some("opensearch");
+        let cursor = self.start().map_err(failed)?;
+        let (tx, lines) = channel::lines();
+        let search = self.clone();
+        thread::spawn(move || search.run(cursor, opensearch, tx));
+        Ok(lines)
+    }
+
+    /// Where reading starts: at the time of the Nth last hit with `last`, otherwise at `since`.
+    fn start(&self) -> io::Result<Cursor> {
+        let since = self.since.map(|since| since.timestamp_millis());
+        // Searching for the Nth last hit, or for none, tells whether the query works.
+        let body = json!({ "size": self.last.unwrap_or(0), "query": self.query_body(since), "sort": [self.sort("desc")], "_source": false });
+        let response = self.request("POST", &format!("{}/_search", self.index), Some(&body))?;
+        let time = match self.last {
+            None => since,
+            Some(0) => Some(Utc::now().timestamp_millis()),
+            Some(_) => hits(&response).last().and_then(sort_time).or(since),
+        };
+        Ok(Cursor { time, seen: HashSet::new() })
+    }
+
+    fn run(self, mut cursor: Cursor, opensearch: bool, tx: Sender<Vec<u8>>) {
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            match self.round(&mut cursor, opensearch, &tx) {
+                Ok(true) => backoff = Duration::from_secs(1),
+                Ok(false) => return,
+                Err(e) => {
+                    eprintln!("rlog: {}: {}, searching again in {}s", self, e, backoff.as_secs());
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            }
+            if !self.follow {
+                return;
+            }
+            thread::sleep(FOLLOW_INTERVAL);
+        }
+    }
+
+    /// Sends the hits after `cursor`, page by page in one point in time. Returns `false` once
+    /// nothing reads them any more.
+    fn round(&self, cursor: &mut Cursor, opensearch: bool, tx: &Sender<Vec<u8>>) -> io::Result<bool> {
+        let mut pit = match opensearch {
+            true => self.request("POST", &format!("{}/_search/point_in_time?keep_alive={}", self.index, KEEP_ALIVE), None)?["pit_id"].clone(),
+            false => self.request("POST", &format!("{}/_pit?keep_alive={}", self.index, KEEP_ALIVE), None)?["id"].clone(),
+        };
+        let result = self.pages(&mut pit, cursor, tx);
+        let _ = match opensearch {
+            true => self.request("DELETE", "_search/point_in_time", Some(&json!({ "pit_id": [pit] }))),
+            false => self.request("DELETE", "_pit", Some(&json!({ "id": pit }))),
+        };
+        result
+    }
+
+    fn pages(&self, pit: &mut Value, cursor: &mut Cursor, tx: &Sender<Vec<u8>>) -> io::Result<bool> {
+        let mut after = None;
+        loop {
+            let mut body = json!({
+                "size": PAGE_SIZE,
+                "query": self.query_body(cursor.time),
+                "pit": { "id": pit, "keep_alive": KEEP_ALIVE },
+                "sort": [self.sort("asc")],
+                "track_total_hits": false,
+            });
+            if let Some(after) = after.take() {
+                body["search_after"] = after;
+            }
+            let response = self.request("POST", "_search", Some(&body))?;
+            if let Some(id) = response.get("pit_id") {
+                *pit = id.clone();
+            }
+            let hits = hits(&response);
+            for hit in hits {
+                let (Some(time), Some(id), Some(source)) = (sort_time(hit), hit["_id"].as_str(), hit.get("_source")) else { continue };
+                if cursor.time == Some(time) && !cursor.seen.insert(id.to_string()) {
+                    continue;
+                }
+                if cursor.time != Some(time) {
+                    *cursor = Cursor { time: Some(time), seen: HashSet::from([id.to_string()]) };
+                }
+                let mut line = source.to_string().into_bytes();
+                line.push(b'\n');
+                if tx.send(line).is_err() {
+                    return Ok(false);
+                }
+            }
+            if hits.len() < PAGE_SIZE {
+                return Ok(true);
+            }
+            after = hits.last().map(|hit| hit["sort"].clone());
+        }
+    }
+
+    /// The query for the hits from `from` on, in milliseconds.
+    fn query_body(&self, from: Option<i64>) -> Value {
+        let mut filter = Vec::new();
+        if let Some(query) = &self.query {
+            filter.push(json!({ "query_string": { "query": query } }));
+        }
+        let mut range = json!({ "format": "epoch_millis" });
+        if let Some(from) = from {
+            range["gte"] = from.into();
+        }
+        if let Some(until) = self.until {
+            range["lte"] = until.timestamp_millis().into();
+        }
+        filter.push(json!({ "range": { &self.time_field: range } }));
+        json!({ "bool": { "filter": filter } })
+    }
+
+    /// Sorting on the time field, in milliseconds whether it holds dates or nanosecond dates.
+    fn sort(&self, order: &str) -> Value {
+        json!({ &self.time_field: { "order": order, "numeric_type": "date" } })
+    }
+
+    /// Sends a request through `curl`, returning the response, or the error it reports.
+    fn request(&self, method: &str, path: &str, body: Option<&Value>) -> io::Result<Value> {
+        let url = format!("{}/{}", self.url.trim_end_matches('/'), path);
+        let mut curl = Command::new("curl");
+        curl.args(["--silent", "--show-error", "--request", method, "--config", "-"]);
+        if let Some(body) = body {
+            curl.args(["--header", "Content-Type: application/json", "--data-binary", &body.to_string()]);
+        }
+        let mut child = curl.arg(url).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot run curl: {}", e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(curl_headers(&self.headers).as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr).trim().trim_start_matches("curl: ").to_string();
+            return Err(io::Error::other(message));
+        }
+        let response: Value = serde_json::from_slice(&output.stdout)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the response is not JSON"))?;
+        match response.get("error") {
+            Some(error) => {
+                let reason = error.pointer("/root_cause/0/reason").or_else(|| error.get("reason")).unwrap_or(error);
+                Err(io::Error::other(reason.as_str().map_or_else(|| reason.to_string(), str::to_string)))
+            }
+            None => Ok(response),
+        }
+    }
+}
+
+/// The hits of a search response.
+fn hits(response: &Value) -> &[Value] {
+    response.pointer("/hits/hits").and_then(Value::as_array).map_or(&[], Vec::as_slice)
+}
+
+/// The time a hit is sorted by, in milliseconds.
+fn sort_time(hit: &Value) -> Option<i64> {
+    hit.pointer("/sort/0").and_then(Value::as_i64)
+}
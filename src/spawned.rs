@@ -0,0 +1,156 @@
+use crate::channel::{self, Lines};
+use std::{io::{self, BufRead, BufReader, Read, Write}, process::{Child, Command, Stdio}, sync::mpsc::Sender, thread, time::Duration};
+
+/// Longest pause before a command that keeps ending is run again.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// An input read from what a command prints, such as `docker logs` or `journalctl`: the
+/// lines of its stdout and stderr, as they come.
+#[derive(Debug, Clone)]
+pub struct Spawned {
+    /// Names the input, e.g. after the container.
+    pub label: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// Whether lines start with an RFC 3339 timestamp, see [`rlog::TimestampedParser`].
+    pub timestamped: bool,
+    /// Whether the output may be server-sent events, of which only the `data:` lines are
+    /// read. Other lines are read as they are.
+    pub events: bool,
+    /// Text written to the command's stdin, such as options with secrets in them that should
+    /// not show in the list of processes.
+    pub input: Option<String>,
+    /// Options the command is run again with when it ends, followed by the timestamp of the
+    /// last line read, to carry on from there; e.g. `--since` for `docker logs --follow`,
+    /// which ends when the container stops. Empty to run it again as it was, and `None` if
+    /// the input ends with the command.
+    pub resume: Option<Vec<String>>,
+}
+
+impl Spawned {
+    /// A command whose output is read as plain lines until it ends.
+    pub fn new(label: impl Into<String>, program: &str, args: Vec<String>) -> Self {
+        Spawned { label: label.into(), program: program.to_string(), args, timestamped: false, events: false, input: None, resume: None }
+    }
+
+    /// Runs the command, and reads its output on background threads from then on.
+    pub fn open(&self) -> io::Result<Lines> {
+        let child = self.spawn(&self.args)?;
+        let (tx, lines) = channel::lines();
+        let spawned = self.clone();
+        thread::spawn(move || spawned.supervise(child, tx));
+        Ok(lines)
+    }
+
+    fn spawn(&self, args: &[String]) -> io::Result<Child> {
+        let stdin = if self.input.is_some() { Stdio::piped() } else { Stdio::null() };
+        let mut child = Command::new(&self.program).args(args).stdin(stdin).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => io::Error::new(e.kind(), format!("cannot run {}: it is not installed, or not on the PATH", self.program)),
+                _ => io::Error::new(e.kind(), format!("cannot run {}: {}", self.program, e)),
+            })?;
+        if let (Some(mut stdin), Some(input)) = (child.stdin.take(), self.input.clone()) {
+            thread::spawn(move || stdin.write_all(input.as_bytes()));
+        }
+        Ok(child)
+    }
+
+    /// Passes on the output of each run of the command. With `resume`, runs it again when
+    /// it ends, unless it never printed anything, which means it cannot work at all.
+    fn supervise(self, mut child: Child, tx: Sender<Vec<u8>>) {
+        let (mut backoff, mut last, mut received) = (Duration::from_secs(1), None::<Vec<u8>>, false);
+        loop {
+            if let Some(stderr) = child.stderr.take() {
+                let tx = tx.clone();
+                thread::spawn(move || pump(stderr, &tx, false, None, &mut None));
+            }
+            // Resuming from the timestamp of the last line reads that line again.
+            let repeated = last.clone().filter(|_| self.resume.as_ref().is_some_and(|resume| !resume.is_empty()));
+            let read = child.stdout.take().map_or(0, |stdout| pump(stdout, &tx, self.events, repeated, &mut last));
+            let failure = match child.wait() {
+                Ok(status) if status.success() => String::new(),
+                Ok(status) => format!(" ({})", status),
+                Err(e) => format!(" ({})", e),
+            };
+            received |= read > 0;
+            let Some(resume) = self.resume.as_ref().filter(|_| received) else {
+                if !failure.is_empty() {
+                    eprintln!("rlog: {}: {} ended{}", self.label, self.program, failure);
+                }
+                return;
+            };
+            if read > 0 {
+                backoff = Duration::from_secs(1);
+            }
+            eprintln!("rlog: {}: {} ended{}, running it again in {}s", self.label, self.program, failure, backoff.as_secs());
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            let mut args = self.args.clone();
+            if let Some(stamp) = last.as_deref().and_then(timestamp).filter(|_| !resume.is_empty()) {
+                args.extend(resume.iter().cloned().chain([stamp.to_string()]));
+            }
+            child = match self.spawn(&args) {
+                Ok(child) => child,
+                Err(e) => return eprintln!("rlog: {}: {}", self.label, e),
+            };
+        }
+    }
+}
+
+/// Options for `curl --config -` sending `headers`, so that they do not show in the list of
+/// processes.
+pub fn curl_headers(headers: &[String]) -> String {
+    headers.iter().map(|header| format!("header = \"{}\"\n", header.replace('\\', "\\\\").replace('"', "\\\""))).collect()
+}
+
+/// Sends the lines of `reader` until it ends, keeping the last one in `last`. With `events`,
+/// only the data of server-sent events is sent. Lines up to `repeated` that have its
+/// timestamp are left out, as they were sent before. Returns how many lines were sent.
+fn pump(reader: impl Read, tx: &Sender<Vec<u8>>, events: bool, mut repeated: Option<Vec<u8>>, last: &mut Option<Vec<u8>>) -> usize {
+    let mut reader = BufReader::new(reader);
+    let mut sent = 0;
+    loop {
+        let mut line = Vec::new();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return sent,
+            Ok(_) => {}
+        }
+        if !line.ends_with(b"\n") {
+            line.push(b'\n');
+        }
+        if events {
+            match event_data(&line) {
+                Some(data) => line = data.to_vec(),
+                None => continue,
+            }
+        }
+        if let Some(before) = &repeated {
+            let skip = line == *before || (timestamp(&line).is_some() && timestamp(&line) == timestamp(before));
+            repeated = if line == *before || !skip { None } else { repeated };
+            if skip {
+                continue;
+            }
+        }
+        *last = Some(line.clone());
+        if tx.send(line).is_err() {
+            return sent;
+        }
+        sent += 1;
+    }
+}
+
+/// The timestamp a line starts with, as far as the first space.
+fn timestamp(line: &[u8]) -> Option<&str> {
+    let (stamp, _) = std::str::from_utf8(line).ok()?.split_once(' ')?;
+    Some(stamp)
+}
+
+/// The data of a server-sent event line; `None` for the other fields, comments, and the
+/// blank lines between events. Lines that are not part of an event stream are data as well.
+fn event_data(line: &[u8]) -> Option<&[u8]> {
+    if let Some(data) = line.strip_prefix(b"data:") {
+        return Some(data.strip_prefix(b" ").unwrap_or(data));
+    }
+    let field = [&b"event:"[..], b"id:", b"retry:", b":"].iter().any(|field| line.starts_with(field));
+    (!field && !line.trim_ascii().is_empty()).then_some(line)
+}
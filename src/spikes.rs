@@ -0,0 +1,63 @@
+use std::{collections::VecDeque, time::Instant};
+
+/// Minutes of volume the baseline is the average of.
+const HISTORY: usize = 30;
+
+/// Minutes watched before the baseline is trusted.
+const WARM_UP: usize = 3;
+
+/// Watches the number of matching entries per minute while following, for
+/// `--detect-spikes`: a minute is unusual when its count is `factor` times the average of
+/// the previous minutes, or that many times less.
+pub struct Spikes {
+    factor: f64,
+    start: Instant,
+    /// Minutes since the start of the one being counted.
+    minute: u64,
+    count: usize,
+    /// Counts of the finished minutes, most recent last.
+    history: VecDeque<usize>,
+    /// Whether the minute being counted was reported as a spike.
+    reported: bool,
+}
+
+impl Spikes {
+    pub fn new(factor: f64) -> Self {
+        Spikes { factor, start: Instant::now(), minute: 0, count: 0, history: VecDeque::new(), reported: false }
+    }
+
+    /// Matching entries per minute so far, once enough minutes were watched.
+    fn baseline(&self) -> Option<f64> {
+        (self.history.len() >= WARM_UP).then(|| self.history.iter().sum::<usize>() as f64 / self.history.len() as f64)
+    }
+
+    /// Counts a matching entry, and describes the minute if it just became a spike.
+    pub fn add(&mut self) -> Option<String> {
+        self.count += 1;
+        let baseline = self.baseline()?;
+        if self.reported || (self.count as f64) < self.factor * baseline.max(1.0) {
+            return None;
+        }
+        self.reported = true;
+        Some(format!("spike: {} matching entries this minute, {:.1}x the usual {:.1} a minute", self.count, self.count as f64 / baseline.max(1.0), baseline))
+    }
+
+    /// Ends the minutes that have passed, and describes the first of them if it was a dip.
+    pub fn tick(&mut self) -> Option<String> {
+        let minute = self.start.elapsed().as_secs() / 60;
+        let mut dip = None;
+        while self.minute < minute {
+            // Inputs with fewer than `factor` entries a minute cannot drop by that much.
+            let dipped = |baseline: f64| baseline >= self.factor && self.count as f64 * self.factor <= baseline;
+            if let (None, Some(baseline)) = (&dip, self.baseline().filter(|baseline| dipped(*baseline))) {
+                dip = Some(format!("dip: {} matching entries in the last minute, against the usual {:.1} a minute", self.count, baseline));
+            }
+            self.history.push_back(self.count);
+            if self.history.len() > HISTORY {
+                self.history.pop_front();
+            }
+            (self.minute, self.count, self.reported) = (self.minute + 1, 0, false);
+        }
+        dip
+    }
+}
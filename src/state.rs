@@ -0,0 +1,52 @@
+use rlog::Checkpoint;
+use serde_derive::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::{Path, PathBuf}};
+
+/// The `--state-file`: where each file was read up to, by absolute path, so that a run
+/// started from cron only reads the entries added since the previous one. Like the logtail
+/// offset file or the Filebeat registry, it also records which file (by inode) the offset
+/// belongs to, so that rotation is noticed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    files: BTreeMap<PathBuf, Position>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Position {
+    device: u64,
+    inode: u64,
+    offset: u64,
+}
+
+impl State {
+    /// Reads the state file; a missing one is empty.
+    pub fn load(path: &Path) -> io::Result<State> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(State::default()),
+            Err(e) => Err(io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+        }
+    }
+
+    /// Where `file` was read up to in an earlier run.
+    pub fn get(&self, file: &Path) -> Option<Checkpoint> {
+        let key = file.canonicalize().ok()?;
+        self.files.get(&key).map(|position| Checkpoint { file: (position.device, position.inode), position: position.offset })
+    }
+
+    pub fn set(&mut self, file: &Path, checkpoint: Checkpoint) {
+        if let Ok(key) = file.canonicalize() {
+            let (device, inode) = checkpoint.file;
+            self.files.insert(key, Position { device, inode, offset: checkpoint.position });
+        }
+    }
+
+    /// Writes the state file through a temporary file, so that it is never left half written.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let text = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(&temporary, text + "\n")?;
+        fs::rename(&temporary, path)
+    }
+}
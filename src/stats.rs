@@ -0,0 +1,134 @@
+use crate::{cli::StatsArgs, render::level_color};
+use chrono::{DateTime, NaiveDateTime, TimeDelta};
+use crossterm::{execute, style::{Color, SetForegroundColor}};
+use rlog::{record_time, LogRecord, Zone};
+use std::{collections::{BTreeMap, BTreeSet}, io::{self, Write}};
+
+/// Width of the longest histogram bar.
+const BAR_WIDTH: usize = 40;
+
+/// Most empty buckets filled in between the first and the last one; sparser data shows only
+/// the buckets with entries.
+const MAX_BUCKETS: i64 = 10_000;
+
+/// Matching records counted per level, and with `--bucket` per level in each time bucket.
+pub struct LevelCounts {
+    totals: BTreeMap<String, usize>,
+    /// Bucket length in seconds, from `--bucket`.
+    bucket: Option<i64>,
+    /// Zone whose wall clock the buckets are aligned to.
+    zone: Zone,
+    /// Counts per level, keyed by the start of their bucket.
+    buckets: BTreeMap<NaiveDateTime, BTreeMap<String, usize>>,
+    /// Records without a DateTime to put in a bucket.
+    untimed: usize,
+}
+
+impl LevelCounts {
+    pub fn new(args: &StatsArgs) -> Self {
+        let bucket = args.bucket.map(|bucket| bucket.num_seconds().max(1));
+        LevelCounts { totals: BTreeMap::new(), bucket, zone: args.input.tz.unwrap_or_default(), buckets: BTreeMap::new(), untimed: 0 }
+    }
+
+    pub fn add(&mut self, record: &LogRecord) {
+        let level = record.level().to_uppercase();
+        *self.totals.entry(level.clone()).or_default() += 1;
+        let Some(bucket) = self.bucket else { return };
+        match record_time(record) {
+            Some(time) => {
+                let start = floor(self.zone.convert(&time), bucket);
+                *self.buckets.entry(start).or_default().entry(level).or_default() += 1;
+            }
+            None => self.untimed += 1,
+        }
+    }
+
+    /// Number of records counted.
+    pub fn total(&self) -> usize {
+        self.totals.values().sum()
+    }
+
+    /// Writes one row per level, colored unless `--color` says otherwise, then the total; or
+    /// with `--bucket`, a table of counts per bucket with a histogram bar.
+    pub fn print(&self, args: &StatsArgs) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        if self.bucket.is_some() {
+            return self.print_buckets(&mut out, args);
+        }
+        for (level, count) in &self.totals {
+            if args.colored {
+                execute!(out, SetForegroundColor(level_color(level, &args.colors)))?;
+                write!(out, "{:10} {}", level, count)?;
+                execute!(out, SetForegroundColor(Color::Reset))?;
+                writeln!(out)?;
+            } else {
+                writeln!(out, "{:10} {}", level, count)?;
+            }
+        }
+        writeln!(out, "{:10} {}", "TOTAL", self.total())
+    }
+
+    fn print_buckets(&self, out: &mut impl Write, args: &StatsArgs) -> io::Result<()> {
+        let levels: BTreeSet<&str> = self.totals.keys().map(String::as_str).collect();
+        let widths: Vec<usize> = levels.iter().map(|level| level.len().max(5)).collect();
+        write!(out, "{:19}  {:>7}", "TIME", "TOTAL")?;
+        for (level, width) in levels.iter().zip(&widths) {
+            write!(out, "  {:>width$}", level)?;
+        }
+        writeln!(out)?;
+
+        let peak = self.buckets.values().map(|counts| counts.values().sum::<usize>()).max().unwrap_or(0);
+        let empty = BTreeMap::new();
+        for start in self.starts() {
+            let counts = self.buckets.get(&start).unwrap_or(&empty);
+            let total: usize = counts.values().sum();
+            write!(out, "{}  {:>7}", start.format("%Y-%m-%d %H:%M:%S"), total)?;
+            for (level, width) in levels.iter().zip(&widths) {
+                write!(out, "  {:>width$}", counts.get(*level).copied().unwrap_or(0))?;
+            }
+            write!(out, "  ")?;
+            // Each level gets its share of the bar, in its color.
+            let (mut sum, mut drawn) = (0, 0);
+            for (level, &count) in counts {
+                sum += count;
+                let end = (sum * BAR_WIDTH).div_ceil(peak.max(1));
+                let cells = end - drawn;
+                drawn = end;
+                if args.colored {
+                    execute!(out, SetForegroundColor(level_color(level, &args.colors)))?;
+                    write!(out, "{}", "█".repeat(cells))?;
+                } else {
+                    write!(out, "{}", "#".repeat(cells))?;
+                }
+            }
+            if args.colored {
+                execute!(out, SetForegroundColor(Color::Reset))?;
+            }
+            writeln!(out)?;
+        }
+        if self.untimed > 0 {
+            writeln!(out, "{:19}  {:>7}", "(no time)", self.untimed)?;
+        }
+        writeln!(out, "{:19}  {:>7}", "TOTAL", self.total())
+    }
+
+    /// Starts of the buckets to show: every bucket from the first to the last, unless that
+    /// would be too many.
+    fn starts(&self) -> Vec<NaiveDateTime> {
+        let (Some(bucket), Some(first), Some(last)) = (self.bucket, self.buckets.keys().next(), self.buckets.keys().next_back()) else {
+            return Vec::new();
+        };
+        if (*last - *first).num_seconds() / bucket > MAX_BUCKETS {
+            return self.buckets.keys().copied().collect();
+        }
+        let step = TimeDelta::seconds(bucket);
+        std::iter::successors(Some(*first), |start| Some(*start + step)).take_while(|start| start <= last).collect()
+    }
+}
+
+/// The start of the `bucket`-second interval containing `time`, counted from midnight of
+/// 1970-01-01 on the same wall clock, so that hours and days start on the hour and at midnight.
+pub fn floor(time: NaiveDateTime, bucket: i64) -> NaiveDateTime {
+    let seconds = time.and_utc().timestamp();
+    DateTime::from_timestamp(seconds - seconds.rem_euclid(bucket), 0).map_or(time, |start| start.naive_utc())
+}
@@ -0,0 +1,76 @@
+use crate::render::terminal_width;
+use crossterm::{queue, style::{Attribute, SetAttribute}, terminal::{Clear, ClearType}, cursor::MoveToColumn};
+use rlog::{severity_rank, LogRecord};
+use std::{collections::{BTreeMap, VecDeque}, io::{self, Write}, time::{Duration, Instant}};
+
+/// How far back the line and error rates look.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// The `--status` bar kept below the followed entries: lines per second and errors over the
+/// last minute, and matches per level since the start.
+pub struct StatusLine {
+    start: Instant,
+    /// Matching records per level since the start.
+    levels: BTreeMap<String, usize>,
+    /// Lines read, matches, and errors among them, per poll within the last minute.
+    recent: VecDeque<(Instant, u64, usize, usize)>,
+    /// Matches and errors in the poll being counted.
+    matched: usize,
+    errors: usize,
+    /// Whether the bar is on screen, so it must be cleared before more rows are printed.
+    drawn: bool,
+}
+
+impl StatusLine {
+    pub fn new() -> Self {
+        StatusLine { start: Instant::now(), levels: BTreeMap::new(), recent: VecDeque::new(), matched: 0, errors: 0, drawn: false }
+    }
+
+    /// Counts a matching record.
+    pub fn add(&mut self, record: &LogRecord) {
+        if record.malformed {
+            return;
+        }
+        *self.levels.entry(record.level().to_uppercase()).or_default() += 1;
+        self.matched += 1;
+        if severity_rank(record.level()) >= severity_rank("ERROR") {
+            self.errors += 1;
+        }
+    }
+
+    /// Removes the bar so that rows can be printed where it was.
+    pub fn clear(&mut self) -> io::Result<()> {
+        if self.drawn {
+            let mut stdout = io::stdout().lock();
+            queue!(stdout, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+            stdout.flush()?;
+            self.drawn = false;
+        }
+        Ok(())
+    }
+
+    /// Ends the poll counted since the last call, of which `lines` lines were read, and
+    /// draws the bar on the last line of the terminal, without a line break.
+    pub fn draw(&mut self, lines: u64) -> io::Result<()> {
+        let now = Instant::now();
+        self.recent.push_back((now, lines, self.matched, self.errors));
+        (self.matched, self.errors) = (0, 0);
+        while self.recent.front().is_some_and(|(at, ..)| now.duration_since(*at) > WINDOW) {
+            self.recent.pop_front();
+        }
+        let (lines, matched, errors) = self.recent.iter().fold((0, 0, 0), |(l, m, e), (_, lines, matched, errors)| (l + lines, m + matched, e + errors));
+        let seconds = now.duration_since(self.start).min(WINDOW).as_secs_f64().max(1.0);
+        let share = if matched == 0 { 0.0 } else { 100.0 * errors as f64 / matched as f64 };
+        let levels: Vec<String> = self.levels.iter().map(|(level, count)| format!("{} {}", if level.is_empty() { "-" } else { level }, count)).collect();
+        let text = format!(" {:.1} lines/s | {} errors in last min ({:.1}%) | {} ", lines as f64 / seconds, errors, share, levels.join("  "));
+        let text: String = text.chars().take(terminal_width().saturating_sub(1)).collect();
+
+        let mut stdout = io::stdout().lock();
+        queue!(stdout, MoveToColumn(0), Clear(ClearType::CurrentLine), SetAttribute(Attribute::Reverse))?;
+        write!(stdout, "{}", text)?;
+        queue!(stdout, SetAttribute(Attribute::Reset))?;
+        stdout.flush()?;
+        self.drawn = true;
+        Ok(())
+    }
+}
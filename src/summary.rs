@@ -0,0 +1,47 @@
+use crate::render::elapsed;
+use chrono::{DateTime, FixedOffset};
+use rlog::{record_time, Shown};
+use std::collections::BTreeMap;
+
+/// Counts of what a run showed, printed on stderr at the end with `--summary`.
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// Matching records per level.
+    levels: BTreeMap<String, usize>,
+    matched: usize,
+    /// Earliest and latest DateTime among the matching records.
+    span: Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)>,
+}
+
+impl Summary {
+    /// Counts a shown entry; context entries and unparsed lines are not matches.
+    pub fn add(&mut self, shown: &Shown) {
+        let Shown::Match(record) = shown else { return };
+        if record.malformed {
+            return;
+        }
+        self.matched += 1;
+        *self.levels.entry(record.level().to_uppercase()).or_default() += 1;
+        if let Some(time) = record_time(record) {
+            self.span = Some(match self.span {
+                Some((first, last)) => (first.min(time), last.max(time)),
+                None => (time, time),
+            });
+        }
+    }
+
+    /// Prints the counts on stderr, so they stay out of piped output.
+    pub fn print(&self, (read, malformed): (u64, u64)) {
+        eprintln!("rlog: summary");
+        eprintln!("  {:16} {}", "lines read", read);
+        eprintln!("  {:16} {}", "matched", self.matched);
+        eprintln!("  {:16} {}", "parse failures", malformed);
+        if let Some((first, last)) = self.span {
+            eprintln!("  {:16} {} .. {} ({})", "time span", first.format("%Y-%m-%d %H:%M:%S"), last.format("%Y-%m-%d %H:%M:%S"), elapsed(last - first, false));
+        }
+        for (level, count) in &self.levels {
+            let level = if level.is_empty() { "(no level)" } else { level };
+            eprintln!("  {:16} {}", level, count);
+        }
+    }
+}
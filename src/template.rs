@@ -0,0 +1,181 @@
+use crate::{expr::DataQuery, record::LogRecord};
+use serde_json::Value;
+use std::cell::OnceCell;
+use unicode_width::UnicodeWidthStr;
+
+/// An output layout such as `{DateTime} [{Level}] {Message}{?Data.request_id} ({Data.request_id}){/}`.
+///
+/// `{NAME}` is replaced by a column, or by a key of the JSON Data column for `Data.KEY`.
+/// `{NAME:N}` pads the value to N characters and `{NAME:>N}` right-aligns it.
+/// `{?NAME}...{/}` is only rendered when NAME is non-empty. `{{` and `}}` are literal braces.
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Field(Field, Option<(usize, bool)>),
+    Section(Field, Vec<Part>),
+}
+
+#[derive(Debug, Clone)]
+enum Field {
+    Column(String),
+    Data(DataQuery),
+}
+
+impl Template {
+    /// Parses a template.
+    pub fn parse(source: &str) -> Result<Template, String> {
+        let mut stack: Vec<(Field, Vec<Part>)> = Vec::new();
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = source;
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                text.push(c);
+                rest = &rest[2..];
+                continue;
+            }
+            if c == '}' {
+                return Err("unmatched `}` (write `}}` for a literal brace)".to_string());
+            }
+            if c != '{' {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            let end = rest.find('}').ok_or("unclosed `{`")?;
+            let inner = &rest[1..end];
+            rest = &rest[end + 1..];
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            if let Some(name) = inner.strip_prefix('?') {
+                stack.push((field(name)?, std::mem::take(&mut parts)));
+            } else if inner == "/" {
+                let (field, outer) = stack.pop().ok_or("`{/}` without an open `{?NAME}`")?;
+                let body = std::mem::replace(&mut parts, outer);
+                parts.push(Part::Section(field, body));
+            } else {
+                let (name, spec) = match inner.split_once(':') {
+                    Some((name, spec)) => (name, Some(spec)),
+                    None => (inner, None),
+                };
+                let pad = spec.map(|spec| {
+                    let (digits, right) = match spec.strip_prefix('>') {
+                        Some(digits) => (digits, true),
+                        None => (spec.strip_prefix('<').unwrap_or(spec), false),
+                    };
+                    digits.parse().map(|width| (width, right)).map_err(|_| format!("invalid width `{}` for {}", spec, name))
+                }).transpose()?;
+                parts.push(Part::Field(field(name)?, pad));
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        if !stack.is_empty() {
+            return Err("`{?NAME}` without a closing `{/}`".to_string());
+        }
+        Ok(Template { parts })
+    }
+
+    /// Renders a record.
+    pub fn render(&self, record: &LogRecord) -> String {
+        let data = OnceCell::new();
+        let mut out = String::new();
+        render(&self.parts, record, &data, &mut out);
+        out
+    }
+}
+
+fn field(name: &str) -> Result<Field, String> {
+    let name = name.trim();
+    match name.strip_prefix("Data") {
+        Some(path) if path.starts_with(['.', '[']) => DataQuery::parse(path).map(Field::Data).map_err(|e| e.to_string()),
+        _ if name.is_empty() => Err("empty field name in `{}`".to_string()),
+        _ => Ok(Field::Column(name.to_string())),
+    }
+}
+
+fn value(field: &Field, record: &LogRecord, data: &OnceCell<Value>) -> String {
+    match field {
+        Field::Column(name) => record.get(name).unwrap_or("").to_string(),
+        Field::Data(query) => {
+            let data = data.get_or_init(|| record.get("Data").and_then(|d| serde_json::from_str(d).ok()).unwrap_or_default());
+            query.value_in(data).unwrap_or_default()
+        }
+    }
+}
+
+fn render(parts: &[Part], record: &LogRecord, data: &OnceCell<Value>, out: &mut String) {
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Field(field, None) => out.push_str(&value(field, record, data)),
+            Part::Field(field, Some((width, right))) => out.push_str(&pad(&value(field, record, data), *width, *right)),
+            Part::Section(field, body) => {
+                if !value(field, record, data).is_empty() {
+                    render(body, record, data, out);
+                }
+            }
+        }
+    }
+}
+
+/// Pads `text` with spaces to `width` terminal columns, counting wide characters such as
+/// CJK and emoji as two. Right-aligns when `right` is set.
+pub fn pad(text: &str, width: usize, right: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(text.width()));
+    if right { fill + text } else { text.to_string() + &fill }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> LogRecord {
+        let mut record = LogRecord::new("raw line");
+        record.push("DateTime", "2025-03-01 10:00:00");
+        record.push("Level", "WARNING");
+        record.push("Message", "disk almost full");
+        record.push("Data", r#"{"host":"db1","disk":{"free_mb":512},"tags":["a","b"]}"#);
+        record
+    }
+
+    #[test]
+    fn renders_columns_and_data_keys() {
+        let template = Template::parse("{DateTime} [{Level}] {Message} host={Data.host} free={Data.disk.free_mb} tag={Data.tags[1]}").unwrap();
+        assert_eq!(template.render(&record()), "2025-03-01 10:00:00 [WARNING] disk almost full host=db1 free=512 tag=b");
+        assert_eq!(Template::parse("{Thread}|{Data.missing}|").unwrap().render(&record()), "||");
+    }
+
+    #[test]
+    fn pads_to_the_width() {
+        let template = Template::parse("[{Level:<9}] [{Data.host:>5}] [{Message:4}]").unwrap();
+        assert_eq!(template.render(&record()), "[WARNING  ] [  db1] [disk almost full]");
+        assert_eq!(pad("日本", 6, false), "日本  ");
+        assert_eq!(pad("日本", 6, true), "  日本");
+    }
+
+    #[test]
+    fn renders_sections_only_with_a_value() {
+        let template = Template::parse("{Message}{?Data.host} on {Data.host}{/}{?Thread} in {Thread}{/}").unwrap();
+        assert_eq!(template.render(&record()), "disk almost full on db1");
+        assert_eq!(Template::parse("{{{Level}}}").unwrap().render(&record()), "{WARNING}");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        let error = |source| Template::parse(source).unwrap_err();
+        assert_eq!(error("{Level"), "unclosed `{`");
+        assert_eq!(error("Level}"), "unmatched `}` (write `}}` for a literal brace)");
+        assert_eq!(error("{/}"), "`{/}` without an open `{?NAME}`");
+        assert_eq!(error("{?Level}warn"), "`{?NAME}` without a closing `{/}`");
+        assert_eq!(error("{}"), "empty field name in `{}`");
+        assert_eq!(error("{Level:x}"), "invalid width `x` for Level");
+    }
+}
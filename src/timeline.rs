@@ -0,0 +1,212 @@
+use crate::{cli::TimelineArgs, render::{elapsed, level_color, terminal_width}, stats::floor};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeDelta, Timelike};
+use crossterm::{queue, style::{Color, SetForegroundColor}};
+use rlog::{record_time, LogRecord};
+use std::{collections::BTreeMap, io::{self, Write}};
+
+/// Sparkline marks from the fewest records to the most.
+const MARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Heatmap shades from the fewest records to the most; empty hours stay blank.
+const SHADES: [&str; 4] = ["░░", "▒▒", "▓▓", "██"];
+
+/// Bucket lengths to pick from when `--bucket` is not given, in seconds.
+const NICE_BUCKETS: [i64; 15] = [1, 5, 10, 30, 60, 300, 600, 900, 1800, 3600, 10800, 21600, 43200, 86400, 604800];
+
+/// Width of the row labels.
+const LABEL_WIDTH: usize = 10;
+
+/// The DateTimes of the matching records, in the `--tz` wall clock, with their levels.
+#[derive(Debug, Default)]
+pub struct Timeline {
+    times: Vec<(NaiveDateTime, String)>,
+    /// Matching records without a DateTime.
+    untimed: usize,
+}
+
+impl Timeline {
+    pub fn add(&mut self, record: &LogRecord, args: &TimelineArgs) {
+        match record_time(record) {
+            Some(time) => self.times.push((args.input.tz.unwrap_or_default().convert(&time), record.level().to_uppercase())),
+            None => self.untimed += 1,
+        }
+    }
+
+    /// Number of records counted.
+    pub fn total(&self) -> usize {
+        self.times.len() + self.untimed
+    }
+
+    /// Draws a sparkline (one per level with `--by-level`), or with `--heatmap` a grid of
+    /// days by hours.
+    pub fn print(&self, args: &TimelineArgs) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        let (Some(first), Some(last)) = (self.times.iter().map(|(t, _)| *t).min(), self.times.iter().map(|(t, _)| *t).max()) else {
+            return writeln!(out, "no entries with a DateTime");
+        };
+        if args.heatmap {
+            self.print_heatmap(&mut out, args)?;
+        } else {
+            self.print_sparklines(&mut out, args, first, last)?;
+        }
+        if self.untimed > 0 {
+            writeln!(out, "{} entr{} without a DateTime left out", self.untimed, if self.untimed == 1 { "y" } else { "ies" })?;
+        }
+        Ok(())
+    }
+
+    fn print_sparklines(&self, out: &mut impl Write, args: &TimelineArgs, first: NaiveDateTime, last: NaiveDateTime) -> io::Result<()> {
+        let columns = terminal_width().saturating_sub(LABEL_WIDTH + 1).max(10) as i64;
+        let bucket = args.bucket.map(|b| b.num_seconds().max(1)).unwrap_or_else(|| {
+            let span = (last - first).num_seconds() + 1;
+            NICE_BUCKETS.into_iter().find(|b| (span + b - 1) / b <= columns).unwrap_or(NICE_BUCKETS[NICE_BUCKETS.len() - 1])
+        });
+        let start = floor(first, bucket);
+        let count = ((last - start).num_seconds() / bucket + 1) as usize;
+
+        let mut rows: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        let mut all = vec![0; count];
+        for (time, level) in &self.times {
+            let idx = ((*time - start).num_seconds() / bucket) as usize;
+            all[idx] += 1;
+            if args.by_level {
+                rows.entry(level.as_str()).or_insert_with(|| vec![0; count])[idx] += 1;
+            }
+        }
+        let peak = all.iter().copied().max().unwrap_or(0);
+        writeln!(out, "{} .. {}, {} per mark, at most {} entr{}", start.format("%Y-%m-%d %H:%M:%S"), last.format("%Y-%m-%d %H:%M:%S"), elapsed(TimeDelta::seconds(bucket), false), peak, if peak == 1 { "y" } else { "ies" })?;
+        self.sparkline(out, args, "ALL", &all, peak)?;
+        for (level, counts) in rows {
+            // Each level is scaled on its own, so rare errors still show their shape.
+            let peak = counts.iter().copied().max().unwrap_or(0);
+            self.sparkline(out, args, if level.is_empty() { "-" } else { level }, &counts, peak)?;
+        }
+        Ok(())
+    }
+
+    fn sparkline(&self, out: &mut impl Write, args: &TimelineArgs, label: &str, counts: &[usize], peak: usize) -> io::Result<()> {
+        write!(out, "{:LABEL_WIDTH$} ", label)?;
+        if args.colored && label != "ALL" {
+            queue!(out, SetForegroundColor(level_color(label, &args.colors)))?;
+        }
+        let line: String = counts.iter().map(|&count| step(count, peak, MARKS.len()).map_or(' ', |step| MARKS[step])).collect();
+        write!(out, "{}", line)?;
+        if args.colored {
+            queue!(out, SetForegroundColor(Color::Reset))?;
+        }
+        writeln!(out)
+    }
+
+    fn print_heatmap(&self, out: &mut impl Write, args: &TimelineArgs) -> io::Result<()> {
+        let mut days: BTreeMap<NaiveDate, [usize; 24]> = BTreeMap::new();
+        for (time, _) in &self.times {
+            days.entry(time.date()).or_insert([0; 24])[time.hour() as usize] += 1;
+        }
+        let peak = days.values().flatten().copied().max().unwrap_or(0);
+        write!(out, "{:14}", "")?;
+        for hour in 0..24 {
+            write!(out, "{:<2} ", hour)?;
+        }
+        writeln!(out)?;
+        for (day, hours) in &days {
+            write!(out, "{} {} ", day.format("%Y-%m-%d"), day.weekday())?;
+            for &count in hours {
+                let shade = step(count, peak, SHADES.len()).map_or("  ", |step| SHADES[step]);
+                if args.colored && count > 0 {
+                    queue!(out, SetForegroundColor(heat(count, peak)))?;
+                }
+                write!(out, "{} ", shade)?;
+            }
+            if args.colored {
+                queue!(out, SetForegroundColor(Color::Reset))?;
+            }
+            writeln!(out, " {}", hours.iter().sum::<usize>())?;
+        }
+        writeln!(out, "{:14}at most {} entr{} an hour", "", peak, if peak == 1 { "y" } else { "ies" })
+    }
+}
+
+/// Which of `steps` marks draws `count` records out of at most `peak`: the top one for the
+/// peak itself, none for no records.
+fn step(count: usize, peak: usize, steps: usize) -> Option<usize> {
+    (count > 0).then(|| ((count * steps).div_ceil(peak.max(count)) - 1).min(steps - 1))
+}
+
+/// A color from cool to hot for a heatmap cell.
+fn heat(count: usize, peak: usize) -> Color {
+    match count * 4 / (peak + 1) {
+        0 => Color::Blue,
+        1 => Color::Green,
+        2 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use clap::Parser;
+
+    fn args(options: &[&str]) -> TimelineArgs {
+        match Cli::parse_from(["rlog", "timeline"].iter().chain(options)).command {
+            Some(Command::Timeline(args)) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    fn timeline(entries: &[(&str, &str)], args: &TimelineArgs) -> Timeline {
+        let mut timeline = Timeline::default();
+        for (time, level) in entries {
+            let mut record = LogRecord::new(format!("{} {}", time, level));
+            record.push("DateTime", *time);
+            record.push("Level", *level);
+            timeline.add(&record, args);
+        }
+        timeline
+    }
+
+    #[test]
+    fn the_peak_gets_the_top_mark() {
+        let marks = |counts: &[usize], peak: usize| counts.iter().map(|&count| step(count, peak, MARKS.len()).map_or(' ', |step| MARKS[step])).collect::<String>();
+        assert_eq!(marks(&[1, 0, 1], 1), "█ █");
+        assert_eq!(marks(&[1, 2, 0], 2), "▄█ ");
+        assert_eq!(marks(&[1, 4, 8, 16], 16), "▁▂▄█");
+        assert_eq!(step(2, 2, SHADES.len()), Some(SHADES.len() - 1));
+        assert_eq!(step(1, 100, SHADES.len()), Some(0));
+        assert_eq!(step(0, 5, SHADES.len()), None);
+    }
+
+    #[test]
+    fn counts_records_by_bucket_and_level() {
+        let args = args(&["--bucket", "1m", "--by-level"]);
+        let entries = [("2025-03-01 10:00:05", "ERROR"), ("2025-03-01 10:00:30", "info"), ("2025-03-01 10:01:10", "INFO"), ("2025-03-01 10:03:00", "INFO"), ("soon", "INFO")];
+        let timeline = timeline(&entries, &args);
+        assert_eq!(timeline.total(), 5);
+        let (first, last) = (timeline.times[0].0, timeline.times[3].0);
+        let mut out = Vec::new();
+        timeline.print_sparklines(&mut out, &args, first, last).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), [
+            "2025-03-01 10:00:00 .. 2025-03-01 10:03:00, 1m00s per mark, at most 2 entries",
+            "ALL        █▄ ▄",
+            "ERROR      █   ",
+            "INFO       ██ █",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn lays_out_days_by_hours() {
+        let args = args(&["--heatmap"]);
+        let timeline = timeline(&[("2025-03-01 09:15:00", ""), ("2025-03-01 09:45:00", ""), ("2025-03-02 23:59:59", "")], &args);
+        let mut out = Vec::new();
+        timeline.print_heatmap(&mut out, &args).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].trim_start().starts_with("0  1  2"));
+        assert_eq!(lines[1], format!("2025-03-01 Sat {}██ {} 2", "   ".repeat(9), "   ".repeat(14)));
+        assert_eq!(lines[2], format!("2025-03-02 Sun {}▒▒  1", "   ".repeat(23)));
+        assert_eq!(lines[3].trim_start(), "at most 2 entries an hour");
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{cli::TopArgs, render::column_value};
+use rlog::LogRecord;
+use std::{collections::HashMap, io::{self, Write}};
+
+/// How often each value of the `--by` column occurs among the matching records.
+#[derive(Debug, Default)]
+pub struct Frequencies {
+    counts: HashMap<String, usize>,
+    /// Matching records, including those without the column.
+    total: usize,
+    /// Matching records without the column.
+    missing: usize,
+}
+
+impl Frequencies {
+    pub fn add(&mut self, record: &LogRecord, args: &TopArgs) {
+        self.total += 1;
+        match column_value(record, &args.by) {
+            Some(value) => *self.counts.entry(value).or_default() += 1,
+            None => self.missing += 1,
+        }
+    }
+
+    /// Number of records counted.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Writes the `--limit` most frequent values, most frequent first, with their count and
+    /// share of all matching records.
+    pub fn print(&self, args: &TopArgs) -> io::Result<()> {
+        let mut top: Vec<(&String, &usize)> = self.counts.iter().collect();
+        // Ties are broken by value so the output does not change from run to run.
+        top.sort_by(|(a, m), (b, n)| n.cmp(m).then_with(|| a.cmp(b)));
+        let limit = if args.limit == 0 { top.len() } else { args.limit };
+        let share = |count: usize| 100.0 * count as f64 / self.total.max(1) as f64;
+
+        let mut out = io::stdout().lock();
+        writeln!(out, "{:>9}  {:>6}  {}", "COUNT", "%", args.by.0)?;
+        for (value, &count) in top.iter().take(limit) {
+            writeln!(out, "{:>9}  {:>5.1}%  {}", count, share(count), value)?;
+        }
+        if top.len() > limit {
+            let others: usize = top[limit..].iter().map(|(_, &count)| count).sum();
+            let plural = if top.len() - limit == 1 { "" } else { "s" };
+            writeln!(out, "{:>9}  {:>5.1}%  ({} other value{})", others, share(others), top.len() - limit, plural)?;
+        }
+        if self.missing > 0 {
+            writeln!(out, "{:>9}  {:>5.1}%  (missing)", self.missing, share(self.missing))?;
+        }
+        writeln!(out, "{:>9}  {:>5.1}%  TOTAL", self.total, share(self.total))
+    }
+}
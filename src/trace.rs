@@ -0,0 +1,91 @@
+use crate::record::LogRecord;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+/// The trace and span a record was logged in, as lower-case hex.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32 hex digits.
+    pub trace_id: String,
+    /// 16 hex digits, or empty when only the trace is known.
+    pub span_id: String,
+}
+
+/// Finds the trace context of a record: in columns or Data keys named as OpenTelemetry and
+/// common loggers name them (`trace_id`, `traceId`, `trace.id`, `span_id`, ...), in a W3C
+/// `traceparent` (`00-TRACEID-SPANID-FLAGS`), or failing those, in the text of the line.
+pub fn trace_context(record: &LogRecord) -> Option<TraceContext> {
+    let mut context = TraceContext::default();
+    for (header, value) in record.fields() {
+        if header == "Data" {
+            if let Ok(data) = serde_json::from_str::<Value>(value) {
+                visit(&data, "", &mut context);
+            }
+        } else {
+            take(header, value, &mut context);
+        }
+    }
+    if context.trace_id.is_empty() {
+        context = from_text(&record.raw)?;
+    }
+    Some(context)
+}
+
+/// Looks for trace context keys among the fields of a Data value, with dotted paths for the
+/// nested ones, as in `{"trace": {"id": ...}}`.
+fn visit(value: &Value, path: &str, context: &mut TraceContext) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                visit(value, &path, context);
+            }
+        }
+        Value::String(text) => take(path, text, context),
+        _ => {}
+    }
+}
+
+/// Keeps `value` if `key` names a trace ID, span ID, or traceparent.
+fn take(key: &str, value: &str, context: &mut TraceContext) {
+    let key: String = key.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_ascii_lowercase();
+    let key = key.strip_prefix("otel").unwrap_or(&key);
+    match key {
+        "traceid" | "tracetraceid" if context.trace_id.is_empty() => context.trace_id = hex(value, 32).unwrap_or_default(),
+        "spanid" | "tracespanid" if context.span_id.is_empty() => context.span_id = hex(value, 16).unwrap_or_default(),
+        "traceparent" | "tracecontexttraceparent" => {
+            if let Some(parent) = traceparent(value) {
+                *context = parent;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `value` in lower case if it is `digits` hex digits, not all zero.
+fn hex(value: &str, digits: usize) -> Option<String> {
+    let value = value.trim();
+    let valid = value.len() == digits && value.bytes().all(|b| b.is_ascii_hexdigit()) && value.bytes().any(|b| b != b'0');
+    valid.then(|| value.to_ascii_lowercase())
+}
+
+/// Reads a W3C `traceparent` header value.
+fn traceparent(value: &str) -> Option<TraceContext> {
+    let mut parts = value.trim().split('-');
+    let (_version, trace_id, span_id) = (parts.next()?, parts.next()?, parts.next()?);
+    Some(TraceContext { trace_id: hex(trace_id, 32)?, span_id: hex(span_id, 16).unwrap_or_default() })
+}
+
+/// Finds a traceparent, or `trace_id=`/`span_id=` pairs, in free text.
+fn from_text(text: &str) -> Option<TraceContext> {
+    static PARENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b[0-9a-f]{2}-([0-9a-f]{32})-([0-9a-f]{16})-[0-9a-f]{2}\b").unwrap());
+    static TRACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(?i)\btrace[_.-]?id"?\s*[=:]\s*"?([0-9a-f]{32})\b"#).unwrap());
+    static SPAN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(?i)\bspan[_.-]?id"?\s*[=:]\s*"?([0-9a-f]{16})\b"#).unwrap());
+    if let Some(caps) = PARENT.captures(text) {
+        return traceparent(&format!("00-{}-{}", &caps[1], &caps[2]));
+    }
+    let trace_id = hex(&TRACE.captures(text)?[1], 32)?;
+    let span_id = SPAN.captures(text).and_then(|caps| hex(&caps[1], 16)).unwrap_or_default();
+    Some(TraceContext { trace_id, span_id })
+}
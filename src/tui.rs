@@ -0,0 +1,242 @@
+use crate::{cli::TailArgs, input::Inputs, interrupt, POLL_INTERVAL, render::{label, pad, record_color, rule_style, source_prefix, visible_cells}};
+use crossterm::{style::Attribute, event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEventKind}, execute};
+use ratatui::{layout::{Constraint, Layout}, style::{Modifier, Style}, text::{Line, Span}, widgets::Paragraph, DefaultTerminal, Frame};
+use regex::Regex;
+use rlog::{record_time, Filter, LogRecord, Shown};
+use std::{collections::VecDeque, io, time::{Duration, Instant}};
+use unicode_width::UnicodeWidthStr;
+
+/// State of the interactive viewer.
+struct App {
+    /// Entries in view order, each flagged if it is only context around a match.
+    records: VecDeque<(LogRecord, bool)>,
+    capacity: usize,
+    /// Number of entries between the bottom of the view and the newest entry.
+    scroll: usize,
+    paused: bool,
+    search: Option<Regex>,
+    /// Pattern being typed after `/`, if the search prompt is open.
+    input: Option<String>,
+    quit: bool,
+}
+
+impl App {
+    fn new(capacity: usize) -> Self {
+        App { records: VecDeque::new(), capacity, scroll: 0, paused: false, search: None, input: None, quit: false }
+    }
+
+    fn push(&mut self, shown: Shown) {
+        let entry = match shown {
+            Shown::Match(record) => (record, false),
+            Shown::Context(record) => (record, true),
+            Shown::Break | Shown::Repeated(_) => return,
+        };
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        } else if self.scroll > 0 {
+            // Keep the view still while the user is looking at older entries.
+            self.scroll += 1;
+        }
+        self.records.push_back(entry);
+    }
+
+    fn scroll_up(&mut self, n: usize) {
+        self.scroll = (self.scroll + n).min(self.records.len().saturating_sub(1));
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        self.scroll = self.scroll.saturating_sub(n);
+    }
+
+    /// Moves the view to the next match above (`older`) or below the current bottom entry.
+    fn jump_to_match(&mut self, older: bool) {
+        let Some(search) = &self.search else { return };
+        let len = self.records.len();
+        let current = len.saturating_sub(1 + self.scroll);
+        let matches = |idx: usize| search.is_match(&self.records[idx].0.raw);
+        let found = if older {
+            (0..current).rev().find(|&idx| matches(idx))
+        } else {
+            (current + 1..len).find(|&idx| matches(idx))
+        };
+        if let Some(idx) = found {
+            self.scroll = len - 1 - idx;
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, page: usize) {
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Enter => {
+                    let pattern = self.input.take().unwrap_or_default();
+                    self.search = (!pattern.is_empty()).then(|| {
+                        Regex::new(&pattern).unwrap_or_else(|_| Regex::new(&regex::escape(&pattern)).unwrap())
+                    });
+                    self.jump_to_match(true);
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => { input.pop(); }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Char(' ') | KeyCode::Char('p') => self.paused = !self.paused,
+            KeyCode::Char('/') => self.input = Some(String::new()),
+            KeyCode::Char('n') => self.jump_to_match(true),
+            KeyCode::Char('N') => self.jump_to_match(false),
+            KeyCode::Esc => self.search = None,
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_up(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_down(1),
+            KeyCode::PageUp => self.scroll_up(page),
+            KeyCode::PageDown => self.scroll_down(page),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll_up(usize::MAX / 2),
+            KeyCode::End | KeyCode::Char('G') => self.scroll = 0,
+            _ => {}
+        }
+    }
+
+    /// Splits `text` into spans, highlighting the parts matching the active search.
+    fn highlight<'a>(&self, text: String, base: Style) -> Vec<Span<'a>> {
+        let Some(search) = &self.search else { return vec![Span::styled(text, base)] };
+        let mut spans = Vec::new();
+        let mut last = 0;
+        for m in search.find_iter(&text) {
+            spans.push(Span::styled(text[last..m.start()].to_string(), base));
+            spans.push(Span::styled(m.as_str().to_string(), base.add_modifier(Modifier::REVERSED)));
+            last = m.end();
+        }
+        spans.push(Span::styled(text[last..].to_string(), base));
+        spans
+    }
+
+    fn draw(&self, frame: &mut Frame, args: &TailArgs) {
+        let [header, body, status] = Layout::vertical([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        if let (Some((record, _)), None) = (self.records.iter().rev().find(|(record, _)| !record.malformed), &args.display.format_out) {
+            let labels: String = visible_cells(record, &args.display).into_iter()
+                .map(|(header, _, width)| format!("{} | ", rlog::template::pad(label(&header, &args.display), width, false)))
+                .collect();
+            let indent = source_prefix(record, &args.display).map_or(0, |(prefix, _)| prefix.width());
+            frame.render_widget(Paragraph::new(format!("{:indent$}{}", "", labels)).style(Style::default().add_modifier(Modifier::BOLD)), header);
+        }
+
+        let height = body.height as usize;
+        let end = self.records.len() - self.scroll.min(self.records.len());
+        let start = end.saturating_sub(height);
+        let lines: Vec<Line> = (start..end).map(|idx| {
+            let (record, context) = &self.records[idx];
+            let previous = idx.checked_sub(1).and_then(|prev| record_time(&self.records[prev].0));
+            args.display.previous_time.set(previous);
+            let rule = rule_style(record, &args.display);
+            let mut style = Style::default().fg(rule.fg.unwrap_or_else(|| record_color(record, &args.display)).into());
+            if let Some(bg) = rule.bg {
+                style = style.bg(bg.into());
+            }
+            for attribute in &rule.attributes {
+                style = style.add_modifier(match attribute {
+                    Attribute::Bold => Modifier::BOLD,
+                    Attribute::Dim => Modifier::DIM,
+                    Attribute::Italic => Modifier::ITALIC,
+                    Attribute::Underlined => Modifier::UNDERLINED,
+                    Attribute::SlowBlink => Modifier::SLOW_BLINK,
+                    Attribute::Reverse => Modifier::REVERSED,
+                    _ => Modifier::CROSSED_OUT,
+                });
+            }
+            if *context || record.malformed {
+                style = style.add_modifier(Modifier::DIM);
+            }
+            let row: String = match &args.display.format_out {
+                _ if record.malformed => record.raw.clone(),
+                Some(template) => template.render(record),
+                None => visible_cells(record, &args.display).into_iter()
+                    .map(|(header, value, width)| format!("{} | ", pad(&header, &value, width)))
+                    .collect(),
+            };
+            let mut spans = Vec::new();
+            if let Some((prefix, color)) = source_prefix(record, &args.display) {
+                spans.push(Span::styled(prefix, Style::default().fg(color.into())));
+            }
+            spans.extend(self.highlight(row, style));
+            Line::from(spans)
+        }).collect();
+        frame.render_widget(Paragraph::new(lines), body);
+
+        let text = match &self.input {
+            Some(input) => format!("/{}", input),
+            None => {
+                let state = if self.paused { "PAUSED" } else if self.scroll > 0 { "SCROLLED" } else { "FOLLOWING" };
+                let search = self.search.as_ref().map(|s| format!(" | search: {}", s.as_str())).unwrap_or_default();
+                format!("{} | {} entries{} | q quit, space pause, / search, n/N next/prev", state, self.records.len(), search)
+            }
+        };
+        frame.render_widget(Paragraph::new(text).style(Style::default().add_modifier(Modifier::REVERSED)), status);
+    }
+}
+
+/// Runs the interactive viewer until the user quits. Returns whether any record matched.
+pub fn run(args: &TailArgs, mut inputs: Inputs) -> io::Result<bool> {
+    let mut terminal = ratatui::init();
+    execute!(io::stdout(), EnableMouseCapture)?;
+    let result = event_loop(&mut terminal, args, &mut inputs);
+    execute!(io::stdout(), DisableMouseCapture)?;
+    ratatui::restore();
+    inputs.report();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, args: &TailArgs, inputs: &mut Inputs) -> io::Result<bool> {
+    let filter: Filter = args.filter.to_filter();
+    let mut window = args.display.context_window();
+    let mut app = App::new(args.scrollback.max(1));
+    let mut last_poll: Option<Instant> = None;
+    let mut any = false;
+    match args.history() {
+        Some(count) => inputs.tail(count, &filter)?.into_iter().for_each(|record| {
+            any = true;
+            app.push(Shown::Match(record));
+        }),
+        None => inputs.seek(&filter)?,
+    }
+    if args.no_follow {
+        while let Some(records) = inputs.read_chunk()? {
+            for record in records {
+                let matched = filter.matches(&record);
+                any |= matched;
+                window.push(record, matched).into_iter().for_each(|shown| app.push(shown));
+            }
+        }
+    }
+
+    while !app.quit && !interrupt::requested() {
+        let changed = inputs.changed();
+        if !app.paused && !args.no_follow && (changed || last_poll.is_none_or(|t| t.elapsed() >= POLL_INTERVAL)) {
+            for record in inputs.poll()? {
+                let matched = filter.matches(&record);
+                any |= matched;
+                window.push(record, matched).into_iter().for_each(|shown| app.push(shown));
+            }
+            last_poll = Some(Instant::now());
+        }
+
+        terminal.draw(|frame| app.draw(frame, args))?;
+        let page = terminal.size()?.height.saturating_sub(3) as usize;
+
+        if event::poll(Duration::from_millis(100))? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => app.handle_key(key, page),
+                Event::Mouse(mouse) => match mouse.kind {
+                    MouseEventKind::ScrollUp => app.scroll_up(3),
+                    MouseEventKind::ScrollDown => app.scroll_down(3),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+    Ok(any)
+}
@@ -0,0 +1,86 @@
+//! Picking the records to show: filters, context, repeats, and merging inputs.
+
+use regex::Regex;
+use rlog::{merge_by_datetime, ContextWindow, Dedupe, Expr, FieldMatch, Filter, LogRecord, Shown, TimeBound, Zone};
+
+fn record(time: &str, level: &str, message: &str) -> LogRecord {
+    let mut record = LogRecord::new(format!("{}|{}|{}", time, level, message));
+    record.push("DateTime", time);
+    record.push("Level", level);
+    record.push("Message", message);
+    record
+}
+
+fn records() -> Vec<LogRecord> {
+    vec![
+        record("2025-03-01 10:00:00", "INFO", "server started on port 8080"),
+        record("2025-03-01 10:00:05", "DEBUG", "cache warmed"),
+        record("2025-03-01 10:01:00", "WARNING", "slow request GET /users took 2300ms"),
+        record("2025-03-01 10:02:00", "ERROR", "connection refused by db1"),
+        record("2025-03-01 10:03:00", "ERROR", "connection refused by db1"),
+        record("2025-03-01 10:04:00", "INFO", "request GET /health"),
+    ]
+}
+
+fn messages<'a>(records: impl IntoIterator<Item = &'a LogRecord>) -> Vec<&'a str> {
+    records.into_iter().map(|record| record.get("Message").unwrap()).collect()
+}
+
+#[test]
+fn filters_combine_their_criteria() {
+    let records = records();
+    let shown = |filter: &Filter| messages(records.iter().filter(|record| filter.matches(record)));
+
+    let filter = Filter { min_level: Some("warning".into()), ..Filter::default() };
+    assert_eq!(shown(&filter), ["slow request GET /users took 2300ms", "connection refused by db1", "connection refused by db1"]);
+
+    let filter = Filter { word: Some("get".into()), ignore_case: true, excludes: vec![Regex::new("health").unwrap()], ..Filter::default() };
+    assert_eq!(shown(&filter), ["slow request GET /users took 2300ms"]);
+
+    let filter = Filter {
+        from: Some(TimeBound::parse("2025-03-01 10:00:05", Zone::Local)),
+        to: Some(TimeBound::parse("2025-03-01 10:02:00", Zone::Local)),
+        fields: vec![("Level".into(), FieldMatch::Regex(Regex::new("^(DEBUG|ERROR)$").unwrap()))],
+        ..Filter::default()
+    };
+    assert_eq!(shown(&filter), ["cache warmed", "connection refused by db1"]);
+}
+
+#[test]
+fn expressions_select_records() {
+    let records = records();
+    let expr = Expr::parse(r#"Level >= WARNING && Message =~ "refused|slow" && DateTime < "2025-03-01 10:03""#).unwrap();
+    assert_eq!(messages(records.iter().filter(|record| expr.matches(record))), ["slow request GET /users took 2300ms", "connection refused by db1"]);
+    let filter = Filter { expr: Some(expr), levels: vec!["error".into()], ..Filter::default() };
+    assert_eq!(records.iter().filter(|record| filter.matches(record)).count(), 1);
+    assert!(Expr::parse("Level ==").is_err());
+}
+
+#[test]
+fn context_and_repeats_shape_what_is_shown() {
+    let filter = Filter { levels: vec!["ERROR".into()], ..Filter::default() };
+    let mut window = ContextWindow::new(1, 1);
+    let mut dedupe = Dedupe::new(false);
+    let mut shown: Vec<Shown> = records().into_iter()
+        .flat_map(|record| {
+            let matched = filter.matches(&record);
+            window.push(record, matched)
+        })
+        .flat_map(|shown| dedupe.push(shown))
+        .collect();
+    shown.extend(dedupe.finish());
+    let summary: Vec<String> = shown.iter().map(|shown| match shown {
+        Shown::Match(record) => format!("match {}", record.datetime()),
+        Shown::Context(record) => format!("context {}", record.datetime()),
+        Shown::Break => "--".to_string(),
+        Shown::Repeated(count) => format!("repeated {}", count),
+    }).collect();
+    assert_eq!(summary, ["context 2025-03-01 10:01:00", "match 2025-03-01 10:02:00", "repeated 1", "context 2025-03-01 10:04:00"]);
+}
+
+#[test]
+fn inputs_merge_by_time() {
+    let web = vec![record("2025-03-01T10:00:00Z", "INFO", "web up"), record("2025-03-01T10:00:02Z", "INFO", "web request")];
+    let db = vec![record("2025-03-01T10:00:01Z", "INFO", "db up"), record("2025-03-01T10:00:03Z", "ERROR", "db down")];
+    assert_eq!(messages(&merge_by_datetime(vec![web, db])), ["web up", "db up", "web request", "db down"]);
+}
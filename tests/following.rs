@@ -0,0 +1,90 @@
+//! Reading growing, rotated, and multiple files through the public follower API.
+
+use rlog::{Follower, Line, MultiFollower};
+use std::{fs::{self, OpenOptions}, io::Write, path::Path};
+
+fn append(path: &Path, text: &str) {
+    OpenOptions::new().create(true).append(true).open(path).unwrap().write_all(text.as_bytes()).unwrap();
+}
+
+fn texts(lines: Vec<Line>) -> Vec<String> {
+    lines.into_iter().map(|line| line.text).collect()
+}
+
+#[test]
+fn follows_a_growing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    append(&path, "first\nsecond\n");
+    let mut follower = Follower::open(&path).unwrap();
+    follower.set_count_lines(true);
+    let lines = follower.read_to_end().unwrap();
+    assert_eq!(lines.iter().map(|line| (line.text.as_str(), line.number, line.offset)).collect::<Vec<_>>(), [("first", Some(1), 0), ("second", Some(2), 6)]);
+    assert!(follower.poll().unwrap().is_empty());
+
+    append(&path, "third\nfour");
+    assert_eq!(texts(follower.poll().unwrap()), ["third"]);
+    append(&path, "th\n");
+    assert_eq!(texts(follower.poll().unwrap()), ["fourth"]);
+    assert_eq!(follower.position(), 26);
+}
+
+#[test]
+fn resumes_from_a_checkpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    append(&path, "one\ntwo\n");
+    let mut follower = Follower::open(&path).unwrap();
+    follower.read_to_end().unwrap();
+    let checkpoint = follower.checkpoint().unwrap();
+
+    append(&path, "three\n");
+    let mut follower = Follower::open(&path).unwrap();
+    follower.resume(checkpoint).unwrap();
+    assert_eq!(texts(follower.read_to_end().unwrap()), ["three"]);
+}
+
+#[test]
+fn tails_the_last_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    append(&path, &(1..=100).map(|n| format!("line {}\n", n)).collect::<String>());
+    let mut follower = Follower::open(&path).unwrap();
+    follower.tail(3, |_| Some(true)).unwrap();
+    assert_eq!(texts(follower.read_to_end().unwrap()), ["line 98", "line 99", "line 100"]);
+}
+
+#[test]
+fn reads_readers_and_several_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = (dir.path().join("a.log"), dir.path().join("b.log"));
+    append(&a, "a1\na2\n");
+    append(&b, "b1\n");
+    let mut follower = MultiFollower::open([&a, &b]).unwrap();
+    let stream = follower.add_reader("stream", "s1\ns2\n".as_bytes());
+    assert_eq!(stream, 2);
+    assert_eq!(follower.followers()[1].path(), b);
+
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    while lines.len() < 5 {
+        lines.extend(follower.poll().unwrap().into_iter().map(|(idx, line)| (idx, line.text)));
+    }
+    lines.sort();
+    assert_eq!(lines, [(0, "a1".into()), (0, "a2".into()), (1, "b1".into()), (2, "s1".into()), (2, "s2".into())]);
+}
+
+#[test]
+fn reads_rotated_files_oldest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    fs::write(dir.path().join("app.log.2"), "oldest\n").unwrap();
+    fs::write(dir.path().join("app.log.1"), "older\n").unwrap();
+    append(&path, "current\n");
+    let mut follower = Follower::open(&path).unwrap();
+    assert_eq!(follower.include_rotated().unwrap(), 2);
+    let mut lines = Vec::new();
+    while !follower.is_finished() && lines.len() < 3 {
+        lines.extend(texts(follower.poll().unwrap()));
+    }
+    assert_eq!(lines, ["oldest", "older", "current"]);
+}
@@ -0,0 +1,86 @@
+//! Turning lines into records through the public parser API, as the inputs of the binary do.
+
+use rlog::{build_parser, detect_format, parser_for, LogParser, LogRecord, ParserOptions};
+
+/// Parses `text` line by line, lines that are not records continuing the one before them if
+/// `parser` is multi-line.
+fn records(parser: &mut dyn LogParser, text: &str) -> Vec<LogRecord> {
+    let mut records: Vec<LogRecord> = Vec::new();
+    for line in text.lines() {
+        match (parser.parse(line), records.last_mut()) {
+            (Some(record), _) => records.push(record),
+            (None, Some(last)) if parser.multiline() && !parser.is_header(line) => parser.continue_record(last, line),
+            (None, _) => {}
+        }
+    }
+    records
+}
+
+fn sample(text: &str) -> Vec<String> {
+    text.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn detects_the_format_of_a_sample() {
+    let options = ParserOptions::default();
+    let cases = [
+        ("json", r#"{"time":"2025-03-01T10:00:00Z","level":"info","msg":"started"}"#),
+        ("logfmt", "ts=2025-03-01T10:00:00Z level=info msg=started port=8080"),
+        ("syslog", "<34>Mar  1 10:00:00 web1 sshd[42]: Accepted publickey for bob"),
+        ("combined", r#"10.0.0.1 - - [01/Mar/2025:10:00:00 +0000] "GET / HTTP/1.1" 200 512 "-" "curl/8.0""#),
+        ("cef", "CEF:0|Vendor|Product|1.0|100|Port scan|5|src=10.0.0.1"),
+        ("pipe", "2025-03-01 10:00:00|INFO|started"),
+    ];
+    for (format, line) in cases {
+        assert_eq!(detect_format(&sample(line), &options), format, "{}", line);
+    }
+}
+
+#[test]
+fn parsers_fill_the_standard_columns() {
+    let options = ParserOptions::default();
+    let text = "\
+{\"time\":\"2025-03-01T10:00:00Z\",\"level\":\"warn\",\"msg\":\"disk almost full\",\"free_mb\":512}
+{\"time\":\"2025-03-01T10:00:01Z\",\"level\":\"error\",\"msg\":\"disk full\"}
+not json";
+    let mut parser = parser_for(detect_format(&sample(text), &options), &options).unwrap();
+    let records = records(parser.as_mut(), text);
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].datetime(), "2025-03-01T10:00:00Z");
+    assert_eq!(records[0].level(), "WARNING");
+    assert_eq!(records[0].get("Message"), Some("disk almost full"));
+    assert_eq!(records[0].get("Data"), Some(r#"{"free_mb":512}"#));
+    assert_eq!(records[1].level(), "ERROR");
+}
+
+#[test]
+fn pattern_parsers_take_their_columns_from_the_pattern() {
+    let mut parser = build_parser("auto", Some(r"^%{TIMESTAMP_ISO8601:DateTime} \[%{LOGLEVEL:Level}\] (?P<Message>.*)$"), &ParserOptions::default()).unwrap();
+    let record = parser.parse("2025-03-01 10:00:00 [warn] cache miss").unwrap();
+    assert_eq!(record.fields().collect::<Vec<_>>(), [("DateTime", "2025-03-01 10:00:00"), ("Level", "WARNING"), ("Message", "cache miss")]);
+    assert!(parser.parse("no match").is_none());
+    assert!(build_parser("auto", Some(r"^\S+$"), &ParserOptions::default()).is_err());
+    assert_eq!(build_parser("nope", None, &ParserOptions::default()).err(), Some("unknown format `nope`".to_string()));
+}
+
+#[test]
+fn multiline_entries_take_their_continuation_lines() {
+    let mut parser = parser_for("postgres", &ParserOptions::default()).unwrap();
+    let text = "\
+2025-03-01 10:00:00.123 UTC [42] ERROR:  relation \"users\" does not exist at character 15
+\tSTATEMENT:  SELECT * FROM users
+2025-03-01 10:00:01.000 UTC [43] LOG:  checkpoint starting: time";
+    let records = records(parser.as_mut(), text);
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].level(), "ERROR");
+    assert!(records[0].raw.contains("SELECT * FROM users"));
+    assert_eq!(records[1].level(), "INFO");
+}
+
+#[test]
+fn forked_parsers_parse_alike() {
+    let mut parser = parser_for("logfmt", &ParserOptions::default()).unwrap();
+    let mut fork = parser.fork().unwrap();
+    let line = "level=error msg=\"upstream timed out\" upstream=10.0.0.9";
+    assert_eq!(parser.parse(line), fork.parse(line));
+}